@@ -0,0 +1,43 @@
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use duckdb::Connection;
+use pensieve_rs::binlog::BinlogOperation;
+use pensieve_rs::operation_applier::OperationApplier;
+
+/// INSERTs of `count` rows with a key, text, decimal and timestamp column
+fn inserts(count: usize) -> Vec<BinlogOperation> {
+    (0..count)
+        .map(|id| {
+            let values = [id.to_string(), format!("'title {}'", id), format!("{}.99", id % 100), "'2025-11-08 10:00:00'".to_string()];
+            let values: Vec<&str> = values.iter().map(String::as_str).collect();
+            BinlogOperation::insert("books", &["id", "title", "price", "published_at"], &values).unwrap()
+        })
+        .collect()
+}
+
+fn applier(use_appender: bool) -> OperationApplier {
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch("CREATE TABLE books (id INTEGER PRIMARY KEY, title VARCHAR, price DECIMAL(10, 2), published_at TIMESTAMP);").unwrap();
+    OperationApplier::new(conn).with_appender(use_appender)
+}
+
+/// Batched INSERTs written as multi-row INSERT statements and with DuckDB's Appender
+fn insert_batches(c: &mut Criterion) {
+    let mut group = c.benchmark_group("insert_batches");
+    for count in [1_000, 10_000] {
+        let operations = inserts(count);
+        let refs: Vec<&BinlogOperation> = operations.iter().collect();
+        for (name, use_appender) in [("sql", false), ("appender", true)] {
+            group.bench_with_input(BenchmarkId::new(name, count), &refs, |b, refs| {
+                b.iter_batched(
+                    || applier(use_appender),
+                    |mut applier| applier.apply_operations(refs).unwrap(),
+                    BatchSize::LargeInput,
+                );
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, insert_batches);
+criterion_main!(benches);
@@ -0,0 +1,139 @@
+//! Python bindings: a `pensieve` module with a `Pensieve` class that loads a db_data directory,
+//! navigates it, and returns query results as pyarrow Tables (or pandas DataFrames), handed over
+//! through the Arrow C stream interface without copying.
+
+use pensieve_rs::pensieve::{Pensieve, PensieveConfig};
+use pensieve_rs::snapshot_manager::SnapshotManager;
+use duckdb::arrow::datatypes::SchemaRef;
+use duckdb::arrow::ffi_stream::FFI_ArrowArrayStream;
+use duckdb::arrow::record_batch::{RecordBatch, RecordBatchIterator};
+use pyo3::exceptions::PyRuntimeError;
+use pyo3::prelude::*;
+
+fn py_err(e: Box<dyn std::error::Error>) -> PyErr {
+    PyRuntimeError::new_err(e.to_string())
+}
+
+/// Hands record batches to pyarrow as a Table
+fn to_pyarrow<'py>(py: Python<'py>, schema: SchemaRef, batches: Vec<RecordBatch>) -> PyResult<Bound<'py, PyAny>> {
+    let reader = RecordBatchIterator::new(batches.into_iter().map(Ok), schema);
+    let mut stream = FFI_ArrowArrayStream::new(Box::new(reader));
+    // pyarrow moves the stream out of `stream`, leaving it released
+    let stream_ptr = &mut stream as *mut FFI_ArrowArrayStream as usize;
+    let reader = py.import("pyarrow")?
+        .getattr("RecordBatchReader")?
+        .call_method1("_import_from_c", (stream_ptr,))?;
+    reader.call_method0("read_all")
+}
+
+/// Record batches of a query at the manager's current position
+fn query_batches(manager: &SnapshotManager, sql: &str) -> Result<(SchemaRef, Vec<RecordBatch>), Box<dyn std::error::Error>> {
+    let mut stmt = manager.get_connection().prepare(sql)?;
+    let arrow = stmt.query_arrow([])?;
+    let schema = arrow.get_schema();
+    Ok((schema, arrow.collect()))
+}
+
+/// A snapshot normalised against its binlogs, navigable in time
+#[pyclass(name = "Pensieve", unsendable)]
+struct PyPensieve {
+    inner: Pensieve,
+}
+
+#[pymethods]
+impl PyPensieve {
+    /// Loads the tables and binlogs of `data_dir` (a local path or an s3:// URI) and normalises
+    /// the snapshot taken around `snapshot_timestamp` ("YYMMDD HH:MM:SS"). `database_path` keeps
+    /// the tables in a DuckDB file instead of in memory.
+    #[new]
+    #[pyo3(signature = (data_dir, snapshot_timestamp, window_hours = 1, database_path = None))]
+    fn new(data_dir: &str, snapshot_timestamp: &str, window_hours: i64, database_path: Option<String>) -> PyResult<Self> {
+        let mut config = PensieveConfig::new(data_dir, snapshot_timestamp, window_hours);
+        if let Some(database_path) = database_path {
+            config = config.with_database_path(database_path);
+        }
+        let inner = Pensieve::from_config(&config).map_err(py_err)?;
+        Ok(Self { inner })
+    }
+
+    #[getter]
+    fn position(&self) -> usize {
+        self.inner.get_manager().get_position()
+    }
+
+    /// Timestamp of the operation at the current position
+    #[getter]
+    fn timestamp(&self) -> Option<String> {
+        self.inner.get_manager().get_timestamp().cloned()
+    }
+
+    #[getter]
+    fn operation_count(&self) -> usize {
+        self.inner.get_manager().operation_count()
+    }
+
+    #[getter]
+    fn tables(&self) -> Vec<String> {
+        self.inner.get_table_names().to_vec()
+    }
+
+    fn goto_timestamp(&mut self, timestamp: &str) -> PyResult<()> {
+        self.inner.get_manager_mut().goto_timestamp(timestamp).map_err(py_err)
+    }
+
+    fn goto_position(&mut self, position: usize) -> PyResult<()> {
+        self.inner.get_manager_mut().goto_position(position).map_err(py_err)
+    }
+
+    /// Returns False at the end of the binlog
+    fn step_forward(&mut self) -> PyResult<bool> {
+        self.inner.get_manager_mut().step_forward().map_err(py_err)
+    }
+
+    /// Returns False at the start of the binlog
+    fn step_backward(&mut self) -> PyResult<bool> {
+        self.inner.get_manager_mut().step_backward().map_err(py_err)
+    }
+
+    /// Result of a SQL query at the current position, as a pyarrow Table
+    fn query<'py>(&self, py: Python<'py>, sql: &str) -> PyResult<Bound<'py, PyAny>> {
+        let (schema, batches) = query_batches(self.inner.get_manager(), sql).map_err(py_err)?;
+        to_pyarrow(py, schema, batches)
+    }
+
+    /// Result of a SQL query at the current position, as a pandas DataFrame
+    fn query_df<'py>(&self, py: Python<'py>, sql: &str) -> PyResult<Bound<'py, PyAny>> {
+        self.query(py, sql)?.call_method0("to_pandas")
+    }
+
+    /// Result of a SQL query at `timestamp`, as a pyarrow Table. The position is restored
+    /// afterwards.
+    fn query_at<'py>(&mut self, py: Python<'py>, timestamp: &str, sql: &str) -> PyResult<Bound<'py, PyAny>> {
+        let manager = self.inner.get_manager_mut();
+        let previous_position = manager.get_position();
+        manager.goto_timestamp(timestamp).map_err(py_err)?;
+        let result = query_batches(manager, sql);
+        manager.goto_position(previous_position).map_err(py_err)?;
+        let (schema, batches) = result.map_err(py_err)?;
+        to_pyarrow(py, schema, batches)
+    }
+
+    /// A table at the current position, as a pyarrow Table
+    fn table<'py>(&self, py: Python<'py>, name: &str) -> PyResult<Bound<'py, PyAny>> {
+        let batches = self.inner.get_manager().to_arrow(name).map_err(py_err)?;
+        let schema = batches[0].schema();
+        to_pyarrow(py, schema, batches)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Pensieve(position={}, timestamp={:?}, tables={:?})",
+            self.position(), self.timestamp(), self.tables()
+        )
+    }
+}
+
+#[pymodule]
+fn pensieve(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyPensieve>()
+}
@@ -1,31 +1,24 @@
-use pensieve_rs::pensieve::Pensieve;
-use pensieve_rs::script::{PensieveScript, write_csv};
-use pensieve_rs::script::last_non_null::{run_last_non_null, LastNonNullScript};
+use pensieve_rs::logging::{self, Verbosity};
+use pensieve_rs::script::{run_script, SCRIPTS};
 use std::env;
 
 /// Binary that executes a user-defined script.
 /// You likely want to write your own script and then invoke it using this binary.
 /// Check the script directory for examples of scripts.
-/// TODO: Write a better main(), this will get hard to maintain as the number of scripts increases
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = env::args().collect();
+    let quiet = args.iter().any(|arg| arg == "--quiet");
+    args.retain(|arg| arg != "--quiet");
+    logging::init(if quiet { Verbosity::Quiet } else { Verbosity::Normal });
     
     if args.len() < 2 {
-        eprintln!("Usage: script <script-name> [options]");
+        eprintln!("Usage: script <script-name> [--quiet] [options]");
         eprintln!("Available scripts:");
-        eprintln!("  last-non-null --table <name> --column <name> --output <file.csv>");
-        return Ok(());
-    }
-    
-    let script_name = &args[1];
-    
-    match script_name.as_str() {
-        "last-non-null" => run_last_non_null(&args[2..])?,
-        _ => {
-            eprintln!("Unknown script: {}", script_name);
-            return Ok(());
+        for (name, usage) in SCRIPTS {
+            eprintln!("  {} {}", name, usage);
         }
+        return Ok(());
     }
     
-    Ok(())
+    run_script(&args[1], &args[2..])
 }
@@ -1,5 +1,11 @@
+pub mod binlog_file_marker;
+pub mod binlog_gap;
 pub mod binlog_operation;
 pub mod binlog_timestamp;
+pub mod identifier;
 
-pub use binlog_operation::{BinlogOperation, OperationType};
-pub use binlog_timestamp::BinlogTimestamp;
\ No newline at end of file
+pub use binlog_file_marker::{BinlogFileMarker, BinlogLocation};
+pub use binlog_gap::{BinlogGap, BinlogGapKind, SpansBinlogGapError};
+pub use binlog_operation::{namespaced_table_name, BinlogOperation, OperationType, RowImage, TransactionInfo, NAMESPACE_SEPARATOR};
+pub use binlog_timestamp::{BinlogTimestamp, SourceTimezone, TimestampOptions};
+pub use identifier::{quote_identifier, table_info_query, IdentifierCase};
@@ -0,0 +1,56 @@
+use std::fmt::{Display, Formatter};
+
+/// Where the operations of a binlog file start in the operation stream. Files are told apart by
+/// their Format_description events, and named by the Rotate event before them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BinlogFileMarker {
+    /// Binlog file name, e.g. `mysql-bin.000042`
+    pub file: String,
+    /// Index of the file's first operation (that of the next file's, if it has none)
+    pub first_operation: usize,
+    /// Server version of the file's Format_description event, e.g. `8.0.36`
+    pub server_version: Option<String>,
+    /// File named by the Rotate event the file ends with, None if it doesn't end with one
+    pub rotates_to: Option<String>,
+}
+
+impl BinlogFileMarker {
+    /// Moves the markers to where their operations are once the operations at the (sorted)
+    /// `removed` indices are taken out of the stream
+    pub fn remove_operations(markers: &mut [BinlogFileMarker], removed: &[usize]) {
+        for marker in markers {
+            marker.first_operation -= removed.partition_point(|&idx| idx < marker.first_operation);
+        }
+    }
+
+    /// Whether the file was read from its Format_description event on but doesn't end with a
+    /// Rotate event, as the files MySQL has finished writing do
+    pub fn is_truncated(&self) -> bool {
+        self.server_version.is_some() && self.rotates_to.is_none()
+    }
+
+    /// Name of the binlog file MySQL writes after `file`: its numeric extension plus one
+    pub fn next_file_name(file: &str) -> String {
+        let Some((base, number)) = file.rsplit_once('.') else {
+            return file.to_string();
+        };
+        match number.parse::<u64>() {
+            Ok(n) => format!("{}.{:0width$}", base, n + 1, width = number.len()),
+            Err(_) => file.to_string(),
+        }
+    }
+}
+
+/// Where an operation is in the binlog: its file, and the end_log_pos of its row event, which
+/// `mysqlbinlog --stop-position` takes to replay up to and including it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BinlogLocation {
+    pub file: String,
+    pub offset: u32,
+}
+
+impl Display for BinlogLocation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.file, self.offset)
+    }
+}
@@ -0,0 +1,94 @@
+use std::fmt::{Display, Formatter};
+use crate::binlog::BinlogFileMarker;
+
+/// How two consecutive binlog files fail to follow each other
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BinlogGapKind {
+    /// Files between them were not supplied, so their operations are missing
+    MissingFiles(Vec<String>),
+    /// The second file is numbered before the first, or starts before the first ends, e.g.
+    /// because files were supplied out of order or twice
+    OutOfOrder,
+    /// The first file doesn't end with a Rotate event to the next, so operations at its end may
+    /// be missing, e.g. because it was copied or dumped while still being written
+    Truncated,
+}
+
+/// A break in the continuity of the binlog files the operations came from. Operations before
+/// `position` and from it on are not known to be contiguous, so state and analyses that span it
+/// may be wrong.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BinlogGap {
+    /// Index of the first operation after the gap
+    pub position: usize,
+    pub previous_file: String,
+    pub next_file: String,
+    pub kind: BinlogGapKind,
+    /// Timestamp of the last operation before the gap
+    pub from: Option<String>,
+    /// Timestamp of the first operation after the gap
+    pub to: Option<String>,
+}
+
+impl BinlogGap {
+    /// Files missing between two consecutive files, going by MySQL's numbering of binlog files:
+    /// None if `next` follows `previous`, or if the names aren't numbered alike
+    pub fn between_files(previous: &BinlogFileMarker, next: &BinlogFileMarker) -> Option<BinlogGapKind> {
+        let (previous_base, previous_number) = file_number(&previous.file)?;
+        let (next_base, next_number) = file_number(&next.file)?;
+        if previous_base != next_base || next_number == previous_number + 1 {
+            return None;
+        }
+        if next_number <= previous_number {
+            return Some(BinlogGapKind::OutOfOrder);
+        }
+        let mut missing = vec![BinlogFileMarker::next_file_name(&previous.file)];
+        while missing.len() < (next_number - previous_number - 1) as usize {
+            missing.push(BinlogFileMarker::next_file_name(missing.last().unwrap()));
+        }
+        Some(BinlogGapKind::MissingFiles(missing))
+    }
+
+    /// Whether moving between the two positions applies or undoes operations on both sides of the gap
+    pub fn spans(&self, position_a: usize, position_b: usize) -> bool {
+        position_a.min(position_b) < self.position && self.position <= position_a.max(position_b)
+    }
+}
+
+impl Display for BinlogGap {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            BinlogGapKind::MissingFiles(files) if files.len() == 1 => write!(f, "{} is missing", files[0])?,
+            BinlogGapKind::MissingFiles(files) => write!(f, "{} to {} are missing", files[0], files[files.len() - 1])?,
+            BinlogGapKind::OutOfOrder => write!(f, "{} doesn't follow {}", self.next_file, self.previous_file)?,
+            BinlogGapKind::Truncated => write!(f, "{} ends without rotating to {}", self.previous_file, self.next_file)?,
+        }
+        write!(f, " before position {}", self.position)?;
+        match (&self.from, &self.to) {
+            (Some(from), Some(to)) => write!(f, " ({} to {})", from, to),
+            (Some(from), None) => write!(f, " (after {})", from),
+            (None, Some(to)) => write!(f, " (before {})", to),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+/// Returned by analyses that span a binlog gap once SnapshotManager::set_refuse_binlog_gaps is set
+#[derive(Debug)]
+pub struct SpansBinlogGapError {
+    pub gap: BinlogGap,
+}
+
+impl Display for SpansBinlogGapError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "The positions span a binlog gap: {}", self.gap)
+    }
+}
+
+impl std::error::Error for SpansBinlogGapError {}
+
+/// Base name and number of a binlog file name like `mysql-bin.000042`
+fn file_number(file: &str) -> Option<(&str, u64)> {
+    let (base, number) = file.rsplit_once('.')?;
+    Some((base, number.parse().ok()?))
+}
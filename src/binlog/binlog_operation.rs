@@ -1,4 +1,5 @@
 use std::fmt::{Display, Formatter};
+use crate::binlog::BinlogTimestamp;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum OperationType {
@@ -16,20 +17,207 @@ impl Display for OperationType {
         }
     }
 }
+/// Which columns the binlog logged for an operation (MySQL's binlog_row_image setting)
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum RowImage {
+    /// Every column of the table was logged
+    #[default]
+    Full,
+    /// Only some columns were logged (binlog_row_image=MINIMAL or NOBLOB), and `columns` holds
+    /// only those. `missing_before` lists columns whose before value wasn't logged (e.g. the
+    /// changed columns of a MINIMAL UPDATE, or the non-key columns of a MINIMAL DELETE): their
+    /// before values are NULL placeholders until filled in from the database. Columns a
+    /// ColumnProjection left out of an UPDATE or DELETE are listed too, though not in `columns`:
+    /// their values are gone, so the operation can't be undone exactly.
+    Partial { missing_before: Vec<String> },
+}
+
+impl RowImage {
+    pub fn is_partial(&self) -> bool {
+        matches!(self, RowImage::Partial { .. })
+    }
+
+    /// Columns whose before value is a placeholder
+    pub fn missing_before(&self) -> &[String] {
+        match self {
+            RowImage::Full => &[],
+            RowImage::Partial { missing_before } => missing_before,
+        }
+    }
+
+    /// Whether an operation on `columns` (every loaded column of its table) logged all of their
+    /// before values: a full image, or one missing only columns that weren't loaded
+    pub fn is_complete_for(&self, columns: &[String]) -> bool {
+        match self {
+            RowImage::Full => true,
+            RowImage::Partial { missing_before } => {
+                !missing_before.is_empty() && missing_before.iter().all(|col| !columns.contains(col))
+            }
+        }
+    }
+}
+
+/// The transaction an operation was committed in
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TransactionInfo {
+    /// Xid of the transaction's COMMIT event (transactional engines only)
+    pub xid: Option<u64>,
+    /// sequence_number of the transaction's GTID event, which numbers transactions in commit order
+    /// within a binlog file
+    pub sequence_number: Option<u64>,
+    /// GTID of the transaction (`source_uuid:transaction_id`), if gtid_mode was on
+    pub gtid: Option<String>,
+    /// Id of the server the transaction was executed on; replicated transactions keep the id of
+    /// the server they came from
+    pub server_id: Option<u32>,
+}
+
+/// Separates the database from the table in the DuckDB name of a namespaced table (`shop__books`)
+pub const NAMESPACE_SEPARATOR: &str = "__";
+
+/// DuckDB name of the table `table` of `database` when tables are namespaced by database, for
+/// tables with the same name in several databases
+pub fn namespaced_table_name(database: &str, table: &str) -> String {
+    format!("{}{}{}", database, NAMESPACE_SEPARATOR, table)
+}
+
 #[derive(Debug, Clone)]
 pub struct BinlogOperation {
     pub timestamp: Option<String>,
     pub position: Option<u32>,
     pub operation_type: OperationType,
+    /// DuckDB table the operation applies to: the MySQL table name, or `database__table` if the
+    /// snapshot namespaces the table by database (see mysql_table_name)
     pub table_name: String,
     pub database: String,
     pub columns: Vec<String>,
     pub before_values: Option<Vec<String>>,  // WHERE clause values
     pub after_values: Option<Vec<String>>,   // SET clause values
+    pub row_image: RowImage,
+    pub transaction: TransactionInfo,
 }
 
 impl BinlogOperation {
+    /// INSERT of a row into `table` of the `main` database. Values are SQL literals, as in binlog
+    /// operations (`42`, `'abc'`, `NULL`), one per column.
+    pub fn insert(table: &str, columns: &[&str], values: &[&str]) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::build(OperationType::Insert, table, columns, None, Some(values))
+    }
+
+    /// UPDATE of a row of `table` from the `before` values to the `after` values
+    pub fn update(table: &str, columns: &[&str], before: &[&str], after: &[&str]) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::build(OperationType::Update, table, columns, Some(before), Some(after))
+    }
+
+    /// DELETE of the row of `table` with these values
+    pub fn delete(table: &str, columns: &[&str], values: &[&str]) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::build(OperationType::Delete, table, columns, Some(values), None)
+    }
+
+    /// Binlog timestamp (YYMMDD HH:MM:SS)
+    pub fn with_timestamp(mut self, timestamp: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        BinlogTimestamp::parse(timestamp)?;
+        self.timestamp = Some(timestamp.to_string());
+        Ok(self)
+    }
+
+    /// end_log_pos of the operation's row event
+    pub fn with_position(mut self, position: u32) -> Self {
+        self.position = Some(position);
+        self
+    }
+
+    pub fn with_database(mut self, database: &str) -> Self {
+        self.database = database.to_string();
+        self
+    }
+
+    pub fn with_transaction(mut self, transaction: TransactionInfo) -> Self {
+        self.transaction = transaction;
+        self
+    }
+
+    fn build(
+        operation_type: OperationType,
+        table: &str,
+        columns: &[&str],
+        before: Option<&[&str]>,
+        after: Option<&[&str]>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        if table.is_empty() {
+            return Err(format!("{} needs a table", operation_type).into());
+        }
+        if columns.is_empty() {
+            return Err(format!("{} of {} needs columns", operation_type, table).into());
+        }
+        if let Some(column) = columns.iter().enumerate().find_map(|(idx, column)| columns[..idx].contains(column).then_some(column)) {
+            return Err(format!("{} of {} has column {} twice", operation_type, table, column).into());
+        }
+        for values in [before, after].into_iter().flatten() {
+            if values.len() != columns.len() {
+                return Err(format!("{} of {} has {} columns but {} values", operation_type, table, columns.len(), values.len()).into());
+            }
+        }
+        let to_strings = |values: &[&str]| values.iter().map(|value| value.to_string()).collect();
+        Ok(BinlogOperation {
+            timestamp: None,
+            position: None,
+            operation_type,
+            table_name: table.to_string(),
+            database: "main".to_string(),
+            columns: to_strings(columns),
+            before_values: before.map(to_strings),
+            after_values: after.map(to_strings),
+            row_image: RowImage::Full,
+            transaction: TransactionInfo::default(),
+        })
+    }
+
+    /// Inverts the operation, so that applying it undoes this one.
+    /// Columns whose before value is missing from a partial row image can't be restored, so they
+    /// are left out of the inverted operation.
     pub fn invert(&self) -> Self {
+        let missing_before = self.row_image.missing_before();
+        if missing_before.is_empty() {
+            return self.invert_images();
+        }
+
+        let keep: Vec<bool> = self.columns.iter().map(|col| !missing_before.contains(col)).collect();
+        let retain = |values: &Option<Vec<String>>| values.as_ref().map(|values| {
+            values.iter().zip(&keep).filter(|(_, keep)| **keep).map(|(value, _)| value.clone()).collect()
+        });
+        BinlogOperation {
+            columns: retain(&Some(self.columns.clone())).unwrap_or_default(),
+            before_values: retain(&self.before_values),
+            after_values: retain(&self.after_values),
+            row_image: RowImage::Partial { missing_before: Vec::new() },
+            ..self.clone()
+        }.invert_images()
+    }
+
+    /// The table name in MySQL, without the database namespace of a namespaced DuckDB table
+    pub fn mysql_table_name(&self) -> &str {
+        self.table_name
+            .strip_prefix(self.database.as_str())
+            .and_then(|rest| rest.strip_prefix(NAMESPACE_SEPARATOR))
+            .filter(|table| !self.database.is_empty() && !table.is_empty())
+            .unwrap_or(&self.table_name)
+    }
+
+    /// Columns whose value differs between the before and after images, in column order.
+    /// Every column counts as changed for an INSERT or DELETE.
+    pub fn changed_columns(&self) -> Vec<&str> {
+        match (&self.before_values, &self.after_values) {
+            (Some(before), Some(after)) => self.columns.iter()
+                .zip(before.iter().zip(after.iter()))
+                .filter(|(_, (before, after))| before != after)
+                .map(|(col, _)| col.as_str())
+                .collect(),
+            _ => self.columns.iter().map(|col| col.as_str()).collect(),
+        }
+    }
+
+    fn invert_images(&self) -> Self {
         match self.operation_type {
             OperationType::Insert => {
                 // INSERT → DELETE
@@ -42,6 +230,8 @@ impl BinlogOperation {
                     table_name: self.table_name.clone(),
                     database: self.database.clone(),
                     columns: self.columns.clone(),
+                    row_image: self.row_image.clone(),
+                    transaction: self.transaction.clone(),
                 }
             }
             OperationType::Update => {
@@ -55,6 +245,8 @@ impl BinlogOperation {
                     table_name: self.table_name.clone(),
                     database: self.database.clone(),
                     columns: self.columns.clone(),
+                    row_image: self.row_image.clone(),
+                    transaction: self.transaction.clone(),
                 }
             }
             OperationType::Delete => {
@@ -68,6 +260,8 @@ impl BinlogOperation {
                     table_name: self.table_name.clone(),
                     database: self.database.clone(),
                     columns: self.columns.clone(),
+                    row_image: self.row_image.clone(),
+                    transaction: self.transaction.clone(),
                 }
             }
         }
@@ -77,7 +271,66 @@ impl BinlogOperation {
 impl Display for BinlogOperation {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         let timestamp = self.timestamp.clone().unwrap_or("null".to_string());
-        let position = self.position.clone().unwrap_or(0);
+        let position = self.position.unwrap_or(0);
         write!(f, "{} {} {} {} {}", timestamp, position, self.operation_type, self.database, self.table_name)
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changed_columns() {
+        let update = BinlogOperation {
+            timestamp: None,
+            position: None,
+            operation_type: OperationType::Update,
+            table_name: "books".to_string(),
+            database: "main".to_string(),
+            columns: vec!["id".to_string(), "title".to_string(), "price".to_string()],
+            before_values: Some(vec!["1".to_string(), "'Dune'".to_string(), "10".to_string()]),
+            after_values: Some(vec!["1".to_string(), "'Dune'".to_string(), "12".to_string()]),
+            row_image: RowImage::Full,
+            transaction: TransactionInfo::default(),
+        };
+        assert_eq!(update.changed_columns(), vec!["price"]);
+
+        let round_tripped = update.invert().invert();
+        assert_eq!(round_tripped.changed_columns(), vec!["price"]);
+
+        let delete = BinlogOperation {
+            operation_type: OperationType::Delete,
+            after_values: None,
+            ..update.clone()
+        };
+        assert_eq!(delete.changed_columns(), vec!["id", "title", "price"]);
+
+        let insert = BinlogOperation {
+            operation_type: OperationType::Insert,
+            before_values: None,
+            ..update
+        };
+        assert_eq!(insert.changed_columns(), vec!["id", "title", "price"]);
+    }
+
+    #[test]
+    fn test_constructors_check_values_against_columns() {
+        let update = BinlogOperation::update("books", &["id", "price"], &["1", "10"], &["1", "12"]).unwrap()
+            .with_timestamp("251108 10:00:00").unwrap()
+            .with_position(300);
+        assert_eq!(update.changed_columns(), vec!["price"]);
+        assert_eq!((update.timestamp.as_deref(), update.position, update.database.as_str()), (Some("251108 10:00:00"), Some(300), "main"));
+
+        let insert = BinlogOperation::insert("books", &["id", "title"], &["2", "'Dune'"]).unwrap().with_database("shop");
+        assert_eq!((insert.before_values, insert.after_values), (None, Some(vec!["2".to_string(), "'Dune'".to_string()])));
+        assert_eq!(insert.database, "shop");
+        assert_eq!(BinlogOperation::delete("books", &["id"], &["1"]).unwrap().invert().operation_type, OperationType::Insert);
+
+        let error = BinlogOperation::update("books", &["id", "price"], &["1", "10"], &["1"]).unwrap_err();
+        assert_eq!(error.to_string(), "UPDATE of books has 2 columns but 1 values");
+        assert!(BinlogOperation::insert("books", &["id", "id"], &["1", "2"]).is_err());
+        assert!(BinlogOperation::delete("", &["id"], &["1"]).is_err());
+        assert!(BinlogOperation::insert("books", &["id"], &["1"]).unwrap().with_timestamp("yesterday").is_err());
+    }
 }
\ No newline at end of file
@@ -1,7 +1,9 @@
-use chrono::{NaiveDateTime, Duration, Datelike, Timelike};
+use chrono::{DateTime, FixedOffset, NaiveDateTime, Duration, Offset, TimeZone, Utc};
+use chrono_tz::Tz;
 use std::fmt;
 
-/// Represents a MySQL binlog timestamp in the format "YYMMDD HH:MM:SS"
+/// Represents a MySQL binlog timestamp in the format "YYMMDD HH:MM:SS", optionally with
+/// fractional seconds ("YYMMDD HH:MM:SS.ffffff")
 /// 
 /// This wrapper provides convenient methods for parsing, manipulating,
 /// and formatting timestamps used in MySQL binlog files.
@@ -11,13 +13,20 @@ pub struct BinlogTimestamp {
 }
 
 impl BinlogTimestamp {
-    /// Parse a timestamp string in the format "YYMMDD HH:MM:SS"
+    /// Parse a timestamp string in the format "YYMMDD HH:MM:SS[.ffffff]"
     /// 
     /// # Examples
     /// ```
+    /// use pensieve_rs::binlog::BinlogTimestamp;
     /// let ts = BinlogTimestamp::parse("251108 17:03:00").unwrap();
     /// ```
     pub fn parse(timestamp: &str) -> Result<Self, String> {
+        Self::parse_with_century(timestamp, TimestampOptions::DEFAULT_CENTURY_BASE)
+    }
+
+    /// Parse a timestamp string in the format "YYMMDD HH:MM:SS", where the year is
+    /// `century_base + YY` (e.g. 1900 for binlogs from the 1990s)
+    pub fn parse_with_century(timestamp: &str, century_base: i32) -> Result<Self, String> {
         let parts: Vec<&str> = timestamp.split(' ').collect();
         if parts.len() != 2 {
             return Err(format!("Invalid timestamp format: expected 'YYMMDD HH:MM:SS', got '{}'", timestamp));
@@ -31,7 +40,7 @@ impl BinlogTimestamp {
             return Err(format!("Invalid date format: expected 6 digits (YYMMDD), got '{}'", date_part));
         }
         
-        let year = format!("20{}", &date_part[0..2])
+        let year = century_base + date_part[0..2]
             .parse::<i32>()
             .map_err(|e| format!("Invalid year: {}", e))?;
         let month = date_part[2..4]
@@ -53,15 +62,21 @@ impl BinlogTimestamp {
         let minute = time_components[1]
             .parse::<u32>()
             .map_err(|e| format!("Invalid minute: {}", e))?;
-        let second = time_components[2]
+        let (seconds, fraction) = time_components[2].split_once('.').unwrap_or((time_components[2], ""));
+        let second = seconds
             .parse::<u32>()
             .map_err(|e| format!("Invalid second: {}", e))?;
+        if fraction.len() > 9 || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(format!("Invalid fractional seconds: '{}'", fraction));
+        }
+        // Nanoseconds: the fraction's digits padded to 9
+        let nanosecond = format!("{:0<9}", fraction).parse::<u32>().unwrap_or(0);
         
         // Create NaiveDateTime
         let datetime = NaiveDateTime::new(
             chrono::NaiveDate::from_ymd_opt(year, month, day)
                 .ok_or_else(|| format!("Invalid date: {}-{:02}-{:02}", year, month, day))?,
-            chrono::NaiveTime::from_hms_opt(hour, minute, second)
+            chrono::NaiveTime::from_hms_nano_opt(hour, minute, second, nanosecond)
                 .ok_or_else(|| format!("Invalid time: {:02}:{:02}:{:02}", hour, minute, second))?,
         );
         
@@ -82,23 +97,129 @@ impl BinlogTimestamp {
         }
     }
     
-    /// Convert back to the binlog string format "YYMMDD HH:MM:SS"
+    /// Convert back to the binlog string format "YYMMDD HH:MM:SS", followed by the fractional
+    /// seconds (3, 6 or 9 digits) if there are any
     pub fn to_binlog_format(&self) -> String {
-        format!(
-            "{:02}{:02}{:02} {:02}:{:02}:{:02}",
-            self.datetime.year() % 100,
-            self.datetime.month(),
-            self.datetime.day(),
-            self.datetime.hour(),
-            self.datetime.minute(),
-            self.datetime.second()
-        )
+        self.datetime.format("%y%m%d %H:%M:%S%.f").to_string()
     }
     
     /// Get the underlying NaiveDateTime
     pub fn as_datetime(&self) -> &NaiveDateTime {
         &self.datetime
     }
+
+    /// Converts to UTC, given the UTC offset of the server that wrote the binlog.
+    /// Binlog timestamps are in the server's local time.
+    pub fn to_utc(&self, source_offset: FixedOffset) -> DateTime<Utc> {
+        (self.datetime - source_offset).and_utc()
+    }
+
+    /// Converts a UTC time to the binlog's local time, e.g. to navigate to a time taken from
+    /// application logs
+    pub fn from_utc(datetime: &DateTime<Utc>, source_offset: FixedOffset) -> Self {
+        Self {
+            datetime: datetime.naive_utc() + source_offset,
+        }
+    }
+}
+
+/// Timezone of the server that wrote the binlog
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceTimezone {
+    /// The same UTC offset all year
+    Offset(FixedOffset),
+    /// An IANA zone like Europe/Paris, whose offset changes with daylight saving time
+    Zone(Tz),
+}
+
+impl SourceTimezone {
+    /// UTC offset in effect at a local time. Local times skipped by a DST change take the offset
+    /// from before it; local times repeated by one take the earlier (pre-change) offset.
+    fn offset_at_local(&self, local: &NaiveDateTime) -> FixedOffset {
+        match self {
+            SourceTimezone::Offset(offset) => *offset,
+            SourceTimezone::Zone(zone) => zone.offset_from_local_datetime(local)
+                .earliest()
+                .unwrap_or_else(|| zone.offset_from_utc_datetime(&(*local - Duration::hours(3))))
+                .fix(),
+        }
+    }
+
+    fn offset_at_utc(&self, utc: &NaiveDateTime) -> FixedOffset {
+        match self {
+            SourceTimezone::Offset(offset) => *offset,
+            SourceTimezone::Zone(zone) => zone.offset_from_utc_datetime(utc).fix(),
+        }
+    }
+}
+
+/// How binlog timestamps map to real time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimestampOptions {
+    /// Added to the two-digit binlog year (2000 by default)
+    pub century_base: i32,
+    /// Timezone of the server that wrote the binlog (UTC by default)
+    pub source_timezone: SourceTimezone,
+}
+
+impl Default for TimestampOptions {
+    fn default() -> Self {
+        Self {
+            century_base: Self::DEFAULT_CENTURY_BASE,
+            source_timezone: SourceTimezone::Offset(FixedOffset::east_opt(0).unwrap()),
+        }
+    }
+}
+
+impl TimestampOptions {
+    pub const DEFAULT_CENTURY_BASE: i32 = 2000;
+
+    pub fn with_century_base(mut self, century_base: i32) -> Self {
+        self.century_base = century_base;
+        self
+    }
+
+    pub fn with_source_offset(mut self, source_offset: FixedOffset) -> Self {
+        self.source_timezone = SourceTimezone::Offset(source_offset);
+        self
+    }
+
+    /// Sets the source timezone from an IANA zone such as "Europe/Paris", a UTC offset such as
+    /// "+02:00" or "-0500", or "UTC"
+    pub fn with_source_timezone(mut self, timezone: &str) -> Result<Self, String> {
+        self.source_timezone = match timezone {
+            "UTC" | "Z" => SourceTimezone::Offset(FixedOffset::east_opt(0).unwrap()),
+            _ if timezone.starts_with(['+', '-']) => SourceTimezone::Offset(timezone.parse::<FixedOffset>()
+                .map_err(|e| format!("Invalid timezone '{}': expected a UTC offset like +02:00 ({})", timezone, e))?),
+            _ => SourceTimezone::Zone(timezone.parse::<Tz>()
+                .map_err(|e| format!("Invalid timezone '{}': expected an IANA zone like Europe/Paris ({})", timezone, e))?),
+        };
+        Ok(self)
+    }
+
+    /// Parses a binlog timestamp with these options' century
+    pub fn parse(&self, timestamp: &str) -> Result<BinlogTimestamp, String> {
+        BinlogTimestamp::parse_with_century(timestamp, self.century_base)
+    }
+
+    /// Converts a binlog timestamp to UTC, with these options' century and the source
+    /// timezone's offset at that time
+    pub fn to_utc(&self, timestamp: &str) -> Result<DateTime<Utc>, String> {
+        let timestamp = self.parse(timestamp)?;
+        Ok(timestamp.to_utc(self.source_timezone.offset_at_local(timestamp.as_datetime())))
+    }
+
+    /// Converts a UTC time to the binlog's local time
+    pub fn from_utc(&self, datetime: &DateTime<Utc>) -> BinlogTimestamp {
+        BinlogTimestamp::from_utc(datetime, self.source_timezone.offset_at_utc(&datetime.naive_utc()))
+    }
+
+    /// A binlog timestamp as `YYYY-MM-DD HH:MM:SS` in the server's local time, with these
+    /// options' century; None if it can't be parsed
+    pub fn format(&self, timestamp: &str) -> Option<String> {
+        let timestamp = self.parse(timestamp).ok()?;
+        Some(timestamp.as_datetime().format("%Y-%m-%d %H:%M:%S").to_string())
+    }
 }
 
 impl fmt::Display for BinlogTimestamp {
@@ -116,6 +237,7 @@ impl From<NaiveDateTime> for BinlogTimestamp {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::{Datelike, Timelike};
     
     #[test]
     fn test_parse_valid_timestamp() {
@@ -130,6 +252,17 @@ mod tests {
         assert!(BinlogTimestamp::parse("251108 17:03").is_err());
     }
     
+    #[test]
+    fn test_parse_fractional_seconds() {
+        let ts = BinlogTimestamp::parse("251108 17:03:00.123456").unwrap();
+        assert_eq!(ts.as_datetime().nanosecond(), 123_456_000);
+        assert_eq!(ts.to_binlog_format(), "251108 17:03:00.123456");
+        assert_eq!(BinlogTimestamp::parse("251108 17:03:00.5").unwrap().to_binlog_format(), "251108 17:03:00.500");
+        assert!(BinlogTimestamp::parse("251108 17:03:00").unwrap() < ts);
+        assert!(ts < BinlogTimestamp::parse("251108 17:03:00.2").unwrap());
+        assert!(BinlogTimestamp::parse("251108 17:03:00.12a").is_err());
+    }
+
     #[test]
     fn test_add_hours_within_day() {
         let ts = BinlogTimestamp::parse("251108 10:00:00").unwrap();
@@ -183,6 +316,48 @@ mod tests {
         assert!(ts1 < ts3);
     }
     
+    #[test]
+    fn test_century_base() {
+        let ts = BinlogTimestamp::parse_with_century("991231 23:59:59", 1900).unwrap();
+        assert_eq!(ts.as_datetime().year(), 1999);
+        assert_eq!(ts.to_binlog_format(), "991231 23:59:59");
+        assert_eq!(BinlogTimestamp::parse("991231 23:59:59").unwrap().as_datetime().year(), 2099);
+    }
+
+    #[test]
+    fn test_utc_conversion() {
+        let options = TimestampOptions::default().with_source_timezone("+02:00").unwrap();
+        let utc = options.to_utc("251108 01:30:00").unwrap();
+        assert_eq!(utc.to_rfc3339(), "2025-11-07T23:30:00+00:00");
+        assert_eq!(options.from_utc(&utc), options.parse("251108 01:30:00").unwrap());
+
+        assert!(TimestampOptions::default().with_source_timezone("Mars/Olympus").is_err());
+        let options = TimestampOptions::default().with_source_timezone("-0500").unwrap();
+        assert_eq!(options.source_timezone, SourceTimezone::Offset(FixedOffset::west_opt(5 * 3600).unwrap()));
+    }
+
+    #[test]
+    fn test_utc_conversion_across_dst() {
+        let options = TimestampOptions::default().with_source_timezone("Europe/Paris").unwrap();
+        // CEST (+02:00) in summer, CET (+01:00) in winter
+        assert_eq!(options.to_utc("250710 12:00:00").unwrap().to_rfc3339(), "2025-07-10T10:00:00+00:00");
+        assert_eq!(options.to_utc("251210 12:00:00").unwrap().to_rfc3339(), "2025-12-10T11:00:00+00:00");
+        let summer = options.to_utc("250710 12:00:00").unwrap();
+        assert_eq!(options.from_utc(&summer).to_binlog_format(), "250710 12:00:00");
+
+        // 02:30 was skipped on 2025-03-30, and 02:30 happened twice on 2025-10-26
+        assert_eq!(options.to_utc("250330 02:30:00").unwrap().to_rfc3339(), "2025-03-30T01:30:00+00:00");
+        assert_eq!(options.to_utc("251026 02:30:00").unwrap().to_rfc3339(), "2025-10-26T00:30:00+00:00");
+    }
+
+    #[test]
+    fn test_century_base_applies_to_every_conversion() {
+        let options = TimestampOptions::default().with_century_base(1900);
+        assert_eq!(options.to_utc("991231 23:00:00").unwrap().to_rfc3339(), "1999-12-31T23:00:00+00:00");
+        assert_eq!(options.format("991231 23:00:00").as_deref(), Some("1999-12-31 23:00:00"));
+        assert_eq!(TimestampOptions::default().format("991231 23:00:00").as_deref(), Some("2099-12-31 23:00:00"));
+    }
+
     #[test]
     fn test_display() {
         let ts = BinlogTimestamp::parse("251108 17:03:00").unwrap();
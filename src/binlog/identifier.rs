@@ -0,0 +1,23 @@
+/// How binlog table names are matched to the snapshot's tables
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IdentifierCase {
+    /// Ignoring case, as DuckDB does: operations take the snapshot's spelling of the table name
+    /// (MySQL with lower_case_table_names=1 or 2, or snapshots written with other casing)
+    #[default]
+    Insensitive,
+    /// Exactly, as MySQL with lower_case_table_names=0: row events of tables the snapshot spells
+    /// differently are skipped like those of tables it doesn't have
+    Sensitive,
+}
+
+/// `name` as a double-quoted SQL identifier, so names with uppercase letters, spaces or reserved
+/// words (`order`, `group`) can be used in generated SQL. DuckDB and SQLite both accept it.
+pub fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// DuckDB's `PRAGMA table_info` for `table`. The pragma parses its argument as a possibly
+/// qualified name, so the name is quoted inside the string for names with dots or quotes.
+pub fn table_info_query(table: &str) -> String {
+    format!("PRAGMA table_info('{}')", quote_identifier(table).replace('\'', "''"))
+}
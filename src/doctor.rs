@@ -0,0 +1,365 @@
+use duckdb::Connection;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
+use crate::binlog::{BinlogFileMarker, BinlogGap, BinlogGapKind};
+use crate::parser::binlog_reader;
+use crate::parser::text_binlog_parser::TextBinlogParser;
+use crate::pensieve::Pensieve;
+
+/// Number of binlog lines used to measure parse throughput
+const SAMPLE_LINES: usize = 100_000;
+/// Rough in-memory size of loaded parquet data relative to its compressed size on disk
+const PARQUET_MEMORY_FACTOR: u64 = 4;
+/// Rough in-memory size of parsed operations relative to the text binlog size
+const BINLOG_MEMORY_FACTOR: u64 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Ok,
+    Warning,
+    Failed,
+}
+
+#[derive(Debug, Clone)]
+pub struct DoctorCheck {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+/// Results of all health checks
+#[derive(Debug, Clone, Default)]
+pub struct DoctorReport {
+    pub checks: Vec<DoctorCheck>,
+}
+
+impl DoctorReport {
+    /// True if no check failed (warnings are allowed)
+    pub fn is_ready(&self) -> bool {
+        self.checks.iter().all(|check| check.status != CheckStatus::Failed)
+    }
+
+    fn push(&mut self, name: &str, status: CheckStatus, detail: String) {
+        self.checks.push(DoctorCheck { name: name.to_string(), status, detail });
+    }
+}
+
+impl Display for DoctorReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for check in &self.checks {
+            let status = match check.status {
+                CheckStatus::Ok => "OK",
+                CheckStatus::Warning => "WARN",
+                CheckStatus::Failed => "FAIL",
+            };
+            writeln!(f, "[{:>4}] {}: {}", status, check.name, check.detail)?;
+        }
+        let verdict = if self.is_ready() { "Ready" } else { "Not ready" };
+        write!(f, "{}", verdict)
+    }
+}
+
+/// Checks whether this machine is ready to run Pensieve over the data in `db_data_path`.
+///
+/// Verifies the DuckDB version and parquet extension, compares available memory with a rough
+/// estimate for the discovered dataset, warns about tables without a snapshot or with binlog files
+/// missing between theirs, measures line matching speed on a sample of the binlog,
+/// and checks that `writable_dirs` can be written to.
+pub fn run_checks(db_data_path: &Path, writable_dirs: &[PathBuf]) -> DoctorReport {
+    let mut report = DoctorReport::default();
+
+    check_duckdb(&mut report);
+
+    let (parquet_files, binlog_files) = discover_files(&mut report, db_data_path);
+    check_memory(&mut report, &parquet_files, &binlog_files);
+    if let Some(binlog_file) = binlog_files.first() {
+        check_parse_speed(&mut report, binlog_file);
+    }
+
+    for dir in writable_dirs {
+        check_writable(&mut report, dir);
+    }
+
+    report
+}
+
+fn check_duckdb(report: &mut DoctorReport) {
+    let conn = match Connection::open_in_memory() {
+        Ok(conn) => conn,
+        Err(e) => {
+            report.push("DuckDB", CheckStatus::Failed, format!("Could not open in-memory database: {}", e));
+            return;
+        }
+    };
+
+    match conn.query_row("SELECT version()", [], |row| row.get::<usize, String>(0)) {
+        Ok(version) => report.push("DuckDB", CheckStatus::Ok, format!("version {}", version)),
+        Err(e) => report.push("DuckDB", CheckStatus::Failed, e.to_string()),
+    }
+
+    let parquet_loaded = conn.execute_batch("LOAD parquet;").is_ok();
+    if parquet_loaded {
+        report.push("Parquet extension", CheckStatus::Ok, "available".to_string());
+    } else {
+        report.push("Parquet extension", CheckStatus::Failed, "not available; snapshots cannot be loaded".to_string());
+    }
+}
+
+fn discover_files(report: &mut DoctorReport, db_data_path: &Path) -> (Vec<String>, Vec<String>) {
+    let mut parquet_files = Vec::new();
+    let mut binlog_files = Vec::new();
+
+    let tables = match Pensieve::discover_tables(&db_data_path.to_path_buf()) {
+        Ok(tables) => tables,
+        Err(e) => {
+            report.push("Data discovery", CheckStatus::Failed, e.to_string());
+            return (parquet_files, binlog_files);
+        }
+    };
+
+    for table in &tables {
+        let table_path = db_data_path.join(table);
+        match Pensieve::discover_snapshot_files(&table_path) {
+            Ok(mut files) => parquet_files.append(&mut files),
+            Err(e) => report.push("Data discovery", CheckStatus::Warning, e.to_string()),
+        }
+        match Pensieve::discover_binlog_files(&table_path) {
+            Ok(mut files) => {
+                check_binlog_continuity(report, table, &files);
+                binlog_files.append(&mut files);
+            }
+            Err(e) => report.push("Data discovery", CheckStatus::Warning, e.to_string()),
+        }
+    }
+
+    report.push(
+        "Data discovery",
+        CheckStatus::Ok,
+        format!("{} table(s), {} snapshot file(s), {} binlog file(s)", tables.len(), parquet_files.len(), binlog_files.len()),
+    );
+    (parquet_files, binlog_files)
+}
+
+/// Warns about binlog files missing between a table's files, going by MySQL's numbering
+fn check_binlog_continuity(report: &mut DoctorReport, table: &str, binlog_files: &[String]) {
+    let markers: Vec<BinlogFileMarker> = binlog_files
+        .iter()
+        .map(|file| BinlogFileMarker {
+            file: binlog_reader::binlog_file_name(file).to_string(),
+            first_operation: 0,
+            server_version: None,
+            rotates_to: None,
+        })
+        .collect();
+    let gaps: Vec<String> = markers
+        .windows(2)
+        .filter_map(|pair| match BinlogGap::between_files(&pair[0], &pair[1])? {
+            BinlogGapKind::MissingFiles(files) => Some(files.join(", ")),
+            _ => None,
+        })
+        .collect();
+
+    let name = format!("Binlog continuity ({})", table);
+    if gaps.is_empty() {
+        report.push(&name, CheckStatus::Ok, format!("{} consecutive binlog file(s)", binlog_files.len()));
+    } else {
+        report.push(&name, CheckStatus::Warning, format!("missing {}", gaps.join(", ")));
+    }
+}
+
+fn check_memory(report: &mut DoctorReport, parquet_files: &[String], binlog_files: &[String]) {
+    let total_size = |files: &[String]| -> u64 {
+        files.iter().filter_map(|f| fs::metadata(f).ok()).map(|m| m.len()).sum()
+    };
+    let estimate = total_size(parquet_files) * PARQUET_MEMORY_FACTOR + total_size(binlog_files) * BINLOG_MEMORY_FACTOR;
+
+    match available_memory_bytes() {
+        Some(available) if available >= estimate => report.push(
+            "Memory",
+            CheckStatus::Ok,
+            format!("{} MiB available, roughly {} MiB needed", available / MIB, estimate / MIB),
+        ),
+        Some(available) => report.push(
+            "Memory",
+            CheckStatus::Warning,
+            format!("only {} MiB available, roughly {} MiB needed", available / MIB, estimate / MIB),
+        ),
+        None => report.push(
+            "Memory",
+            CheckStatus::Warning,
+            format!("could not determine available memory, roughly {} MiB needed", estimate / MIB),
+        ),
+    }
+}
+
+const MIB: u64 = 1024 * 1024;
+
+/// Reads MemAvailable from /proc/meminfo (Linux only)
+fn available_memory_bytes() -> Option<u64> {
+    let meminfo = fs::read_to_string("/proc/meminfo").ok()?;
+    let line = meminfo.lines().find(|line| line.starts_with("MemAvailable:"))?;
+    let kib: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kib * 1024)
+}
+
+fn check_parse_speed(report: &mut DoctorReport, binlog_file: &str) {
+    let reader = match binlog_reader::open_binlog(binlog_file, 1024 * 1024) {
+        Ok(reader) => reader,
+        Err(e) => {
+            report.push("Parse speed", CheckStatus::Failed, format!("Could not open {}: {}", binlog_file, e));
+            return;
+        }
+    };
+    let sample: Vec<String> = reader
+        .split(b'\n')
+        .take(SAMPLE_LINES)
+        .filter_map(|line| line.ok())
+        .map(|bytes| String::from_utf8_lossy(&bytes).to_string())
+        .collect();
+
+    let conn = match Connection::open_in_memory() {
+        Ok(conn) => conn,
+        Err(e) => {
+            report.push("Parse speed", CheckStatus::Failed, e.to_string());
+            return;
+        }
+    };
+    let elapsed = TextBinlogParser::new(conn).time_line_matching(&sample);
+    let lines_per_second = sample.len() as f64 / elapsed.as_secs_f64().max(f64::EPSILON);
+
+    let total_size = fs::metadata(binlog_file).map(|m| m.len()).unwrap_or(0);
+    let sample_size: usize = sample.iter().map(|line| line.len() + 1).sum();
+    let estimated_seconds = if sample_size > 0 {
+        elapsed.as_secs_f64() * total_size as f64 / sample_size as f64
+    } else {
+        0.0
+    };
+
+    report.push(
+        "Parse speed",
+        CheckStatus::Ok,
+        format!(
+            "{:.0} lines/s on a {}-line sample of {}; about {:.0}s to match the whole file",
+            lines_per_second, sample.len(), binlog_file, estimated_seconds
+        ),
+    );
+}
+
+fn check_writable(report: &mut DoctorReport, dir: &Path) {
+    let name = format!("Write access ({})", dir.display());
+    if let Err(e) = fs::create_dir_all(dir) {
+        report.push(&name, CheckStatus::Failed, e.to_string());
+        return;
+    }
+
+    let probe = dir.join(format!(".pensieve_doctor_{}", std::process::id()));
+    match fs::write(&probe, b"ok") {
+        Ok(()) => {
+            fs::remove_file(&probe).ok();
+            report.push(&name, CheckStatus::Ok, "writable".to_string());
+        }
+        Err(e) => report.push(&name, CheckStatus::Failed, e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_data_dir(name: &str, files: &[&str]) -> PathBuf {
+        let data_dir = std::env::temp_dir().join(format!("doctor_{}_{}", name, std::process::id()));
+        let table_dir = data_dir.join("books");
+        fs::remove_dir_all(&data_dir).ok();
+        fs::create_dir_all(&table_dir).unwrap();
+        for file in files {
+            fs::write(table_dir.join(file), "").unwrap();
+        }
+        data_dir
+    }
+
+    fn status_of(report: &DoctorReport, name: &str) -> Vec<CheckStatus> {
+        report.checks.iter().filter(|check| check.name == name).map(|check| check.status).collect()
+    }
+
+    #[test]
+    fn test_discovery_warns_about_a_missing_snapshot() {
+        let data_dir = create_data_dir("no_snapshot", &["mysql-bin.000001.sql"]);
+        let mut report = DoctorReport::default();
+        let (parquet_files, binlog_files) = discover_files(&mut report, &data_dir);
+        assert!(parquet_files.is_empty());
+        assert_eq!(binlog_files.len(), 1);
+        assert_eq!(status_of(&report, "Data discovery"), vec![CheckStatus::Warning, CheckStatus::Ok]);
+        assert!(report.checks[0].detail.contains("No parquet"));
+
+        let data_dir = create_data_dir("with_snapshot", &["snapshot.csv", "mysql-bin.000001.sql"]);
+        let mut report = DoctorReport::default();
+        let (parquet_files, _) = discover_files(&mut report, &data_dir);
+        assert_eq!(parquet_files.len(), 1);
+        assert_eq!(status_of(&report, "Data discovery"), vec![CheckStatus::Ok]);
+        fs::remove_dir_all(&data_dir).ok();
+    }
+
+    #[test]
+    fn test_discovery_fails_without_tables() {
+        let mut report = DoctorReport::default();
+        discover_files(&mut report, &std::env::temp_dir().join("doctor_not_there"));
+        assert_eq!(status_of(&report, "Data discovery"), vec![CheckStatus::Failed]);
+        assert!(!report.is_ready());
+    }
+
+    #[test]
+    fn test_continuity_warns_about_a_gap_between_binlog_files() {
+        let data_dir = create_data_dir(
+            "gap",
+            &["snapshot.csv", "mysql-bin.000001.sql", "mysql-bin.000002.sql.gz", "mysql-bin.000005.sql"],
+        );
+        let mut report = DoctorReport::default();
+        discover_files(&mut report, &data_dir);
+        let check = report.checks.iter().find(|check| check.name == "Binlog continuity (books)").unwrap();
+        assert_eq!(check.status, CheckStatus::Warning);
+        assert_eq!(check.detail, "missing mysql-bin.000003, mysql-bin.000004");
+
+        fs::remove_file(data_dir.join("books").join("mysql-bin.000005.sql")).unwrap();
+        let mut report = DoctorReport::default();
+        discover_files(&mut report, &data_dir);
+        assert_eq!(status_of(&report, "Binlog continuity (books)"), vec![CheckStatus::Ok]);
+        fs::remove_dir_all(&data_dir).ok();
+    }
+
+    #[test]
+    fn test_parse_speed_fails_on_an_unreadable_binlog() {
+        let mut report = DoctorReport::default();
+        check_parse_speed(&mut report, "/nonexistent/mysql-bin.000001.sql");
+        assert_eq!(status_of(&report, "Parse speed"), vec![CheckStatus::Failed]);
+
+        let data_dir = create_data_dir("readable", &[]);
+        let binlog_file = data_dir.join("books").join("mysql-bin.000001.sql");
+        fs::write(&binlog_file, "BEGIN\nCOMMIT\n").unwrap();
+        let mut report = DoctorReport::default();
+        check_parse_speed(&mut report, binlog_file.to_str().unwrap());
+        assert_eq!(status_of(&report, "Parse speed"), vec![CheckStatus::Ok]);
+        fs::remove_dir_all(&data_dir).ok();
+    }
+
+    #[test]
+    fn test_writable_check() {
+        let data_dir = create_data_dir("writable", &["not_a_directory"]);
+        let mut report = DoctorReport::default();
+        check_writable(&mut report, &data_dir.join("out"));
+        assert_eq!(report.checks[0].status, CheckStatus::Ok);
+
+        let mut report = DoctorReport::default();
+        check_writable(&mut report, &data_dir.join("books").join("not_a_directory").join("out"));
+        assert_eq!(report.checks[0].status, CheckStatus::Failed);
+        fs::remove_dir_all(&data_dir).ok();
+    }
+
+    #[test]
+    fn test_duckdb_check_passes() {
+        let mut report = DoctorReport::default();
+        check_duckdb(&mut report);
+        assert_eq!(status_of(&report, "DuckDB"), vec![CheckStatus::Ok]);
+    }
+}
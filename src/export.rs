@@ -0,0 +1,5 @@
+pub mod audit_log;
+pub mod binlog_subset;
+pub mod history_export;
+pub mod timeline;
+pub mod undo_script;
\ No newline at end of file
@@ -0,0 +1,277 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use duckdb::params;
+use serde::Serialize;
+use tracing::info;
+use crate::binlog::{BinlogOperation, OperationType, TimestampOptions};
+use crate::snapshot_manager::SnapshotManager;
+
+/// File format of the audit log
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditFormat {
+    Parquet,
+    /// One JSON object per line
+    Jsonl,
+}
+
+impl AuditFormat {
+    /// Picks the format from the output file extension (`.jsonl`/`.json`, anything else is parquet)
+    pub fn from_path(path: &str) -> Self {
+        if path.ends_with(".jsonl") || path.ends_with(".json") {
+            AuditFormat::Jsonl
+        } else {
+            AuditFormat::Parquet
+        }
+    }
+}
+
+/// One changed column of one operation. Serialises to a JSON object with the fields in
+/// AUDIT_COLUMNS order.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AuditRecord {
+    /// Operation timestamp as `YYYY-MM-DD HH:MM:SS`
+    pub timestamp: Option<String>,
+    /// Index of the operation in the operation stream
+    pub position: usize,
+    pub binlog_position: Option<u32>,
+    pub table_name: String,
+    pub operation_type: String,
+    /// Primary key of the row, e.g. `id=1` (first column if the table has no primary key)
+    pub primary_key: String,
+    pub column_name: String,
+    /// Values as text, None for SQL NULL or a missing row image
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+    /// Transaction the operation was committed in (see TransactionInfo)
+    pub xid: Option<u64>,
+    pub sequence_number: Option<u64>,
+    pub gtid: Option<String>,
+    pub server_id: Option<u32>,
+}
+
+/// Columns of the audit log, in output order
+const AUDIT_COLUMNS: [(&str, &str); 13] = [
+    ("timestamp", "TIMESTAMP"),
+    ("position", "BIGINT"),
+    ("binlog_position", "UBIGINT"),
+    ("table_name", "VARCHAR"),
+    ("operation_type", "VARCHAR"),
+    ("primary_key", "VARCHAR"),
+    ("column_name", "VARCHAR"),
+    ("old_value", "VARCHAR"),
+    ("new_value", "VARCHAR"),
+    ("xid", "UBIGINT"),
+    ("sequence_number", "UBIGINT"),
+    ("gtid", "VARCHAR"),
+    ("server_id", "UINTEGER"),
+];
+
+/// Builds the audit trail of the whole operation stream: one record per changed column of every
+/// operation, with the old and new value. Every column of an INSERT or DELETE counts as changed.
+/// The snapshot's position is not changed.
+pub fn audit_records(manager: &SnapshotManager) -> Result<Vec<AuditRecord>, Box<dyn std::error::Error>> {
+    let mut key_columns_by_table: Vec<(String, Vec<String>)> = Vec::new();
+    let mut records = Vec::new();
+
+    manager.for_each_operation(0, manager.operation_count(), |idx, op| {
+        let key_columns = match key_columns_by_table.iter().find(|(table, _)| *table == op.table_name) {
+            Some((_, key_columns)) => key_columns.clone(),
+            None => {
+                let key_columns = manager.primary_key_columns(&op.table_name, &op.columns)?;
+                key_columns_by_table.push((op.table_name.clone(), key_columns.clone()));
+                key_columns
+            }
+        };
+        records.extend(operation_records(idx, op, &key_columns, manager.get_timestamp_options()));
+        Ok(())
+    })?;
+
+    Ok(records)
+}
+
+pub(crate) fn operation_records(idx: usize, op: &BinlogOperation, key_columns: &[String], timestamp_options: &TimestampOptions) -> Vec<AuditRecord> {
+    let value_at = |image: &Option<Vec<String>>, i: usize| {
+        image.as_ref().and_then(|values| values.get(i)).and_then(|value| literal_text(value))
+    };
+    let key_image = match op.operation_type {
+        OperationType::Insert => &op.after_values,
+        OperationType::Update | OperationType::Delete => &op.before_values,
+    };
+    let primary_key = op.columns.iter()
+        .enumerate()
+        .filter(|(_, col)| key_columns.contains(col))
+        .map(|(i, col)| format!("{}={}", col, value_at(key_image, i).unwrap_or_else(|| "NULL".to_string())))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let timestamp = op.timestamp.as_ref().and_then(|ts| timestamp_options.format(ts));
+
+    let changed = op.changed_columns();
+    op.columns.iter()
+        .enumerate()
+        .filter(|(_, col)| changed.contains(&col.as_str()))
+        .map(|(i, col)| AuditRecord {
+            timestamp: timestamp.clone(),
+            position: idx,
+            binlog_position: op.position,
+            table_name: op.table_name.clone(),
+            operation_type: op.operation_type.to_string(),
+            primary_key: primary_key.clone(),
+            column_name: col.clone(),
+            old_value: value_at(&op.before_values, i),
+            new_value: value_at(&op.after_values, i),
+            xid: op.transaction.xid,
+            sequence_number: op.transaction.sequence_number,
+            gtid: op.transaction.gtid.clone(),
+            server_id: op.transaction.server_id,
+        })
+        .collect()
+}
+
+/// Writes the audit trail of the whole operation stream to `output_path`.
+/// Returns the number of records written.
+pub fn export_audit_log(
+    manager: &SnapshotManager,
+    output_path: &str,
+    format: AuditFormat,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let records = audit_records(manager)?;
+    match format {
+        AuditFormat::Parquet => write_parquet(manager, &records, output_path)?,
+        AuditFormat::Jsonl => write_jsonl(&records, output_path)?,
+    }
+    info!("Exported {} audit records to {}", records.len(), output_path);
+    Ok(records.len())
+}
+
+fn write_parquet(manager: &SnapshotManager, records: &[AuditRecord], output_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let conn = manager.get_connection();
+    let column_defs: Vec<String> = AUDIT_COLUMNS.iter()
+        .map(|(name, col_type)| format!("{} {}", name, col_type))
+        .collect();
+    conn.execute_batch(&format!("CREATE OR REPLACE TEMP TABLE audit_log_export ({});", column_defs.join(", ")))?;
+
+    let result = (|| -> Result<(), Box<dyn std::error::Error>> {
+        let mut appender = conn.appender("audit_log_export")?;
+        for record in records {
+            appender.append_row(params![
+                record.timestamp,
+                record.position as i64,
+                record.binlog_position.map(u64::from),
+                record.table_name,
+                record.operation_type,
+                record.primary_key,
+                record.column_name,
+                record.old_value,
+                record.new_value,
+                record.xid,
+                record.sequence_number,
+                record.gtid,
+                record.server_id,
+            ])?;
+        }
+        appender.flush()?;
+        drop(appender);
+        conn.execute_batch(&format!(
+            "COPY audit_log_export TO '{}' (FORMAT PARQUET);",
+            output_path.replace('\'', "''")
+        ))?;
+        Ok(())
+    })();
+    conn.execute_batch("DROP TABLE IF EXISTS audit_log_export;")?;
+    result
+}
+
+pub(crate) fn write_jsonl(records: &[AuditRecord], output_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let mut file = BufWriter::new(File::create(output_path)?);
+    for record in records {
+        serde_json::to_writer(&mut file, record)?;
+        writeln!(file)?;
+    }
+    file.flush()?;
+    Ok(())
+}
+
+/// Text of a SQL literal: quotes removed from strings, None for NULL
+pub(crate) fn literal_text(literal: &str) -> Option<String> {
+    if literal == "NULL" {
+        return None;
+    }
+    match literal.strip_prefix('\'').and_then(|rest| rest.strip_suffix('\'')) {
+        Some(inner) => Some(inner.replace("''", "'")),
+        None => Some(literal.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binlog::TransactionInfo;
+    use crate::test_support::{books_db, operation};
+
+    fn op(operation_type: OperationType, before: Option<[&str; 3]>, after: Option<[&str; 3]>) -> BinlogOperation {
+        BinlogOperation {
+            timestamp: Some("251108 10:00:00".to_string()),
+            position: Some(100),
+            transaction: TransactionInfo { xid: Some(96), server_id: Some(1), ..Default::default() },
+            ..operation(operation_type, "books", &["id", "title", "price"], before.as_ref().map(|v| &v[..]), after.as_ref().map(|v| &v[..]))
+        }
+    }
+
+    fn create_test_manager() -> SnapshotManager {
+        let conn = books_db("id INTEGER PRIMARY KEY, title VARCHAR, price INTEGER", "");
+        let operations = vec![
+            op(OperationType::Insert, None, Some(["1", "'Dune'", "NULL"])),
+            op(OperationType::Update, Some(["1", "'Dune'", "NULL"]), Some(["1", "'It''s Dune'", "12"])),
+            op(OperationType::Delete, Some(["1", "'It''s Dune'", "12"]), None),
+        ];
+        SnapshotManager::new(conn, operations, 0)
+    }
+
+    #[test]
+    fn test_audit_records_list_changed_columns() {
+        let records = audit_records(&create_test_manager()).unwrap();
+
+        assert_eq!(records.len(), 3 + 2 + 3);
+        let update: Vec<(&str, Option<&str>, Option<&str>)> = records.iter()
+            .filter(|record| record.position == 1)
+            .map(|record| (record.column_name.as_str(), record.old_value.as_deref(), record.new_value.as_deref()))
+            .collect();
+        assert_eq!(update, vec![
+            ("title", Some("Dune"), Some("It's Dune")),
+            ("price", None, Some("12")),
+        ]);
+        assert_eq!(records[3].primary_key, "id=1");
+        assert_eq!(records[3].timestamp.as_deref(), Some("2025-11-08 10:00:00"));
+        assert_eq!(records[7].operation_type, "DELETE");
+        assert_eq!(records[7].new_value, None);
+    }
+
+    #[test]
+    fn test_export_audit_log_to_parquet_and_jsonl() {
+        let manager = create_test_manager();
+        let output = std::env::temp_dir().join(format!("audit_log_{}", std::process::id()));
+        let parquet_path = output.with_extension("parquet").to_string_lossy().to_string();
+        let jsonl_path = output.with_extension("jsonl").to_string_lossy().to_string();
+
+        assert_eq!(export_audit_log(&manager, &parquet_path, AuditFormat::from_path(&parquet_path)).unwrap(), 8);
+        let updated_titles: i64 = manager.get_connection().query_row(
+            &format!("SELECT COUNT(*) FROM read_parquet('{}') WHERE column_name = 'title' AND operation_type = 'UPDATE'", parquet_path),
+            [],
+            |row| row.get(0),
+        ).unwrap();
+        assert_eq!(updated_titles, 1);
+
+        export_audit_log(&manager, &jsonl_path, AuditFormat::from_path(&jsonl_path)).unwrap();
+        let jsonl = std::fs::read_to_string(&jsonl_path).unwrap();
+        assert_eq!(jsonl.lines().count(), 8);
+        assert_eq!(
+            jsonl.lines().nth(3).unwrap(),
+            "{\"timestamp\":\"2025-11-08 10:00:00\",\"position\":1,\"binlog_position\":100,\"table_name\":\"books\",\
+             \"operation_type\":\"UPDATE\",\"primary_key\":\"id=1\",\"column_name\":\"title\",\"old_value\":\"Dune\",\
+             \"new_value\":\"It's Dune\",\"xid\":96,\"sequence_number\":null,\"gtid\":null,\"server_id\":1}"
+        );
+
+        std::fs::remove_file(parquet_path).ok();
+        std::fs::remove_file(jsonl_path).ok();
+    }
+}
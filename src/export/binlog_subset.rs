@@ -0,0 +1,276 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use tracing::info;
+use crate::binlog::{BinlogOperation, BinlogTimestamp, OperationType, TransactionInfo};
+use crate::export::audit_log::{self, literal_text};
+use crate::snapshot_manager::SnapshotManager;
+
+/// File format of a binlog subset
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubsetFormat {
+    /// `mysqlbinlog --verbose` text, which Pensieve can parse again
+    Text,
+    /// Audit log records (see export_audit_log), one JSON object per line
+    AuditJsonl,
+}
+
+impl SubsetFormat {
+    /// Picks the format from the output file extension (`.jsonl`/`.json`, anything else is text)
+    pub fn from_path(path: &str) -> Self {
+        if path.ends_with(".jsonl") || path.ends_with(".json") {
+            SubsetFormat::AuditJsonl
+        } else {
+            SubsetFormat::Text
+        }
+    }
+}
+
+/// Which operations a binlog subset keeps. Every filter left unset keeps everything.
+#[derive(Debug, Clone, Default)]
+pub struct BinlogSubset {
+    tables: Vec<String>,
+    from: Option<BinlogTimestamp>,
+    to: Option<BinlogTimestamp>,
+    keys: Vec<String>,
+}
+
+impl BinlogSubset {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_tables(mut self, tables: &[&str]) -> Self {
+        self.tables = tables.iter().map(|table| table.to_string()).collect();
+        self
+    }
+
+    /// Keeps operations with a timestamp between `from` and `to` (YYMMDD HH:MM:SS), inclusive
+    pub fn with_time_range(mut self, from: Option<&str>, to: Option<&str>) -> Result<Self, Box<dyn std::error::Error>> {
+        self.from = from.map(BinlogTimestamp::parse).transpose()?;
+        self.to = to.map(BinlogTimestamp::parse).transpose()?;
+        Ok(self)
+    }
+
+    /// Keeps operations touching a row with one of these primary keys (or first column values), as
+    /// text; composite keys are comma-separated in key column order
+    pub fn with_keys(mut self, keys: &[&str]) -> Self {
+        self.keys = keys.iter().map(|key| key.to_string()).collect();
+        self
+    }
+
+    fn keeps(&self, op: &BinlogOperation, key_columns: &[String]) -> bool {
+        if !self.tables.is_empty() && !self.tables.contains(&op.table_name) {
+            return false;
+        }
+        if self.from.is_some() || self.to.is_some() {
+            let Some(timestamp) = op.timestamp.as_ref().and_then(|ts| BinlogTimestamp::parse(ts).ok()) else {
+                return false;
+            };
+            if self.from.as_ref().is_some_and(|from| timestamp < *from) || self.to.as_ref().is_some_and(|to| timestamp > *to) {
+                return false;
+            }
+        }
+        if self.keys.is_empty() {
+            return true;
+        }
+        [&op.before_values, &op.after_values].into_iter().flatten().any(|values| {
+            let key = key_columns.iter()
+                .map(|key| {
+                    let idx = op.columns.iter().position(|column| column == key)?;
+                    values.get(idx).and_then(|value| literal_text(value))
+                })
+                .collect::<Option<Vec<_>>>();
+            key.is_some_and(|key| self.keys.contains(&key.join(",")))
+        })
+    }
+}
+
+/// Writes the operations kept by `subset` to `output_path`, so that a huge binlog can be shared
+/// or archived in reduced form. Returns the number of operations written.
+///
+/// The text format numbers columns (`@1`, `@2`, ...) by their position in the snapshot table and
+/// keeps transactions, timestamps, positions and Xids/GTIDs. The snapshot's position is not changed.
+pub fn export_binlog_subset(
+    manager: &SnapshotManager,
+    subset: &BinlogSubset,
+    output_path: &str,
+    format: SubsetFormat,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let mut table_columns: HashMap<String, (Vec<String>, Vec<String>)> = HashMap::new();
+    let mut kept = Vec::new();
+    manager.for_each_operation(0, manager.operation_count(), |idx, op| {
+        if !table_columns.contains_key(&op.table_name) {
+            let columns = manager.table_columns(&op.table_name)?;
+            let key_columns = manager.primary_key_columns(&op.table_name, &columns)?;
+            table_columns.insert(op.table_name.clone(), (columns, key_columns));
+        }
+        if subset.keeps(op, &table_columns[&op.table_name].1) {
+            kept.push((idx, op.clone()));
+        }
+        Ok(())
+    })?;
+    let kept: Vec<(usize, &BinlogOperation)> = kept.iter().map(|(idx, op)| (*idx, op)).collect();
+
+    match format {
+        SubsetFormat::Text => {
+            let mut writer = BufWriter::new(File::create(output_path)?);
+            write_text(&mut writer, &kept, &table_columns)?;
+            writer.flush()?;
+        }
+        SubsetFormat::AuditJsonl => {
+            let records: Vec<_> = kept.iter()
+                .flat_map(|(idx, op)| audit_log::operation_records(*idx, op, &table_columns[&op.table_name].1, manager.get_timestamp_options()))
+                .collect();
+            audit_log::write_jsonl(&records, output_path)?;
+        }
+    }
+    info!("Wrote {} of {} operations to {}", kept.len(), manager.operation_count(), output_path);
+    Ok(kept.len())
+}
+
+fn write_text(
+    writer: &mut impl Write,
+    operations: &[(usize, &BinlogOperation)],
+    table_columns: &HashMap<String, (Vec<String>, Vec<String>)>,
+) -> std::io::Result<()> {
+    writeln!(writer, "# Binlog subset written by pensieve {}", env!("CARGO_PKG_VERSION"))?;
+    let mut current: Option<&TransactionInfo> = None;
+    for (i, (_, op)) in operations.iter().enumerate() {
+        let header = event_header(op);
+        if current != Some(&op.transaction) {
+            // The original last_committed isn't kept, so each transaction is written as depending on the
+            // one before it, which replays the subset serially
+            if let Some(sequence_number) = op.transaction.sequence_number {
+                writeln!(
+                    writer,
+                    "{}\tGTID\tlast_committed={}\tsequence_number={}",
+                    header,
+                    sequence_number.saturating_sub(1),
+                    sequence_number
+                )?;
+            }
+            if let Some(gtid) = &op.transaction.gtid {
+                writeln!(writer, "SET @@SESSION.GTID_NEXT= '{}'/*!*/;", gtid)?;
+            }
+            writeln!(writer, "{}\nBEGIN", header)?;
+            current = Some(&op.transaction);
+        }
+
+        let (verb, images) = match op.operation_type {
+            OperationType::Insert => ("INSERT INTO", [("SET", &op.after_values), ("", &None)]),
+            OperationType::Update => ("UPDATE", [("WHERE", &op.before_values), ("SET", &op.after_values)]),
+            OperationType::Delete => ("DELETE FROM", [("WHERE", &op.before_values), ("", &None)]),
+        };
+        let columns = &table_columns[&op.table_name].0;
+        writeln!(writer, "{}\n### {} `{}`.`{}`", header, verb, op.database, op.mysql_table_name())?;
+        for (heading, values) in images {
+            let Some(values) = values else {
+                continue;
+            };
+            writeln!(writer, "### {}", heading)?;
+            for (column, value) in op.columns.iter().zip(values) {
+                let missing = heading == "WHERE" && op.row_image.missing_before().contains(column);
+                if let Some(number) = columns.iter().position(|c| c == column).filter(|_| !missing) {
+                    writeln!(writer, "###   @{}={}", number + 1, binlog_value(value))?;
+                }
+            }
+        }
+
+        let last_of_transaction = operations.get(i + 1).is_none_or(|(_, next)| next.transaction != op.transaction);
+        if last_of_transaction {
+            if let Some(xid) = op.transaction.xid {
+                writeln!(writer, "{}\tXid = {}", header, xid)?;
+            }
+            writeln!(writer, "COMMIT/*!*/;")?;
+            current = None;
+        }
+    }
+    Ok(())
+}
+
+fn event_header(op: &BinlogOperation) -> String {
+    let mut header = format!("#{}", op.timestamp.as_deref().unwrap_or("700101 00:00:00"));
+    if let Some(server_id) = op.transaction.server_id {
+        header.push_str(&format!(" server id {}", server_id));
+    }
+    if let Some(position) = op.position {
+        header.push_str(&format!("  end_log_pos {}", position));
+    }
+    header
+}
+
+/// A SQL literal as mysqlbinlog prints it: strings quoted, with quotes, backslashes and control
+/// characters escaped as `\xNN`; blobs (`unhex('..')`) as escaped bytes
+fn binlog_value(literal: &str) -> String {
+    if let Some(hex) = literal.strip_prefix("unhex('").and_then(|rest| rest.strip_suffix("')")) {
+        let escaped: String = hex.as_bytes()
+            .chunks(2)
+            .map(|pair| format!("\\x{}", String::from_utf8_lossy(pair).to_lowercase()))
+            .collect();
+        return format!("'{}'", escaped);
+    }
+    if !literal.starts_with('\'') {
+        return literal.to_string();
+    }
+    let text = literal_text(literal).unwrap_or_default();
+    let escaped: String = text.chars()
+        .map(|c| match c {
+            '\'' | '\\' => format!("\\x{:02x}", c as u32),
+            c if c.is_control() && (c as u32) < 0x80 => format!("\\x{:02x}", c as u32),
+            c => c.to_string(),
+        })
+        .collect();
+    format!("'{}'", escaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use duckdb::Connection;
+    use crate::parser::text_binlog_parser::TextBinlogParser;
+    use crate::test_support::{books_db, operation};
+
+    fn op(operation_type: OperationType, xid: u64, minute: u32, before: Option<[&str; 2]>, after: Option<[&str; 2]>) -> BinlogOperation {
+        BinlogOperation {
+            timestamp: Some(format!("251108 10:{:02}:00", minute)),
+            position: Some(1000 + minute),
+            database: "shop".to_string(),
+            transaction: TransactionInfo { xid: Some(xid), sequence_number: Some(xid), server_id: Some(1), ..Default::default() },
+            ..operation(operation_type, "books", &["id", "title"], before.as_ref().map(|v| &v[..]), after.as_ref().map(|v| &v[..]))
+        }
+    }
+
+    #[test]
+    fn test_subset_round_trips_through_text_parser() {
+        let conn = books_db("id INTEGER PRIMARY KEY, title VARCHAR", "");
+        let operations = vec![
+            op(OperationType::Insert, 7, 0, None, Some(["1", "'O''Brien\\'"])),
+            op(OperationType::Insert, 7, 0, None, Some(["2", "'Emma'"])),
+            op(OperationType::Update, 8, 5, Some(["1", "'O''Brien\\'"]), Some(["1", "'Dune'"])),
+            op(OperationType::Delete, 9, 30, Some(["1", "'Dune'"]), None),
+        ];
+        let manager = SnapshotManager::new(conn, operations, 0);
+        let path = std::env::temp_dir().join(format!("pensieve_subset_{}.sql", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        let subset = BinlogSubset::new()
+            .with_tables(&["books"])
+            .with_time_range(None, Some("251108 10:10:00")).unwrap()
+            .with_keys(&["1"]);
+        assert_eq!(export_binlog_subset(&manager, &subset, path, SubsetFormat::from_path(path)).unwrap(), 2);
+
+        let text = std::fs::read_to_string(path).unwrap();
+        assert!(text.contains("\tGTID\tlast_committed=7\tsequence_number=8\n"));
+
+        let parser_conn = Connection::open_in_memory().unwrap();
+        parser_conn.execute_batch("CREATE TABLE books (id INTEGER PRIMARY KEY, title VARCHAR);").unwrap();
+        let parsed = TextBinlogParser::new(parser_conn).parse_file(path).unwrap();
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0].after_values, Some(vec!["1".to_string(), "'O''Brien\\'".to_string()]));
+        assert_eq!((parsed[1].operation_type.clone(), parsed[1].position, parsed[1].transaction.xid), (OperationType::Update, Some(1005), Some(8)));
+        assert_eq!(parsed[1].transaction.sequence_number, Some(8));
+        assert_eq!(parsed[1].timestamp.as_deref(), Some("251108 10:05:00"));
+        std::fs::remove_file(path).ok();
+    }
+}
@@ -0,0 +1,341 @@
+use std::fs;
+use std::path::Path;
+use duckdb::Connection;
+use tracing::info;
+use crate::binlog::{quote_identifier, BinlogOperation, OperationType, TimestampOptions};
+use crate::operation_applier::{StateStore, DEFAULT_BATCH_SIZE};
+use crate::snapshot_manager::SnapshotManager;
+
+/// Warehouse dialect used for the schema DDL file written next to the exported history
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchemaDialect {
+    ClickHouse,
+    BigQuery,
+}
+
+impl std::str::FromStr for SchemaDialect {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "clickhouse" => Ok(SchemaDialect::ClickHouse),
+            "bigquery" => Ok(SchemaDialect::BigQuery),
+            _ => Err(format!("Unknown schema dialect {} (expected clickhouse or bigquery)", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct HistoryExportOptions {
+    /// If set, a `<table>_history.sql` file with a CREATE TABLE statement for this dialect is written
+    /// to the output directory
+    pub ddl_dialect: Option<SchemaDialect>,
+}
+
+#[derive(Debug, Clone)]
+pub struct HistoryExportSummary {
+    pub rows_exported: usize,
+    pub ddl_path: Option<String>,
+}
+
+/// Columns appended to every history row, after the table's own columns
+const HISTORY_COLUMNS: [(&str, &str); 4] = [
+    ("_op_type", "VARCHAR"),
+    ("_position", "BIGINT"),
+    ("_binlog_position", "UBIGINT"),
+    ("_timestamp", "TIMESTAMP"),
+];
+
+/// Exports the full change history of a table as parquet, partitioned Hive-style by day
+/// (`output_dir/dt=YYYY-MM-DD/*.parquet`), so it can be bulk-loaded into a warehouse.
+///
+/// Each operation becomes one row holding the row image it produced (the deleted image for DELETEs),
+/// plus the operation type, its index in the operation stream, its binlog position and its timestamp.
+/// The snapshot's position is not changed.
+pub fn export_history(
+    manager: &SnapshotManager,
+    table: &str,
+    output_dir: &str,
+    options: &HistoryExportOptions,
+) -> Result<HistoryExportSummary, Box<dyn std::error::Error>> {
+    manager.check_continuity(0, manager.operation_count())?;
+    let conn = manager.get_connection();
+    let schema = conn.table_schema(table)?;
+    if schema.is_empty() {
+        return Err(format!("Table {} not found", table).into());
+    }
+
+    let history_table = format!("{}__history_export", table);
+    let mut column_defs: Vec<String> = schema.iter()
+        .map(|(name, col_type)| format!("{} {}", quote_identifier(name), col_type))
+        .collect();
+    column_defs.extend(HISTORY_COLUMNS.iter().map(|(name, col_type)| format!("{} {}", name, col_type)));
+    conn.execute_batch(&format!(
+        "CREATE OR REPLACE TEMP TABLE {} ({});",
+        quote_identifier(&history_table),
+        column_defs.join(", ")
+    ))?;
+
+    let result = fill_and_copy(manager, table, &schema, &history_table, output_dir);
+    conn.execute_batch(&format!("DROP TABLE IF EXISTS {};", quote_identifier(&history_table)))?;
+    let rows_exported = result?;
+
+    let ddl_path = match options.ddl_dialect {
+        Some(dialect) => {
+            let path = Path::new(output_dir).join(format!("{}_history.sql", table));
+            fs::write(&path, generate_ddl(table, &schema, dialect))?;
+            Some(path.to_string_lossy().to_string())
+        }
+        None => None,
+    };
+
+    info!("Exported {} history rows for {} to {}", rows_exported, table, output_dir);
+    Ok(HistoryExportSummary { rows_exported, ddl_path })
+}
+
+fn fill_and_copy(
+    manager: &SnapshotManager,
+    table: &str,
+    schema: &[(String, String)],
+    history_table: &str,
+    output_dir: &str,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let conn = manager.get_connection();
+    let mut column_types: Vec<String> = schema.iter().map(|(_, col_type)| col_type.clone()).collect();
+    column_types.extend(HISTORY_COLUMNS.iter().map(|(_, col_type)| col_type.to_string()));
+    let mut pending: Vec<Vec<String>> = Vec::with_capacity(DEFAULT_BATCH_SIZE);
+    let mut rows_exported = 0;
+
+    manager.for_each_operation(0, manager.operation_count(), |idx, op| {
+        if op.table_name == table {
+            pending.push(history_row(schema, idx, op, manager.get_timestamp_options()));
+            rows_exported += 1;
+            if pending.len() >= DEFAULT_BATCH_SIZE {
+                append_history_rows(conn, history_table, &column_types, &pending)?;
+                pending.clear();
+            }
+        }
+        Ok(())
+    })?;
+    append_history_rows(conn, history_table, &column_types, &pending)?;
+
+    fs::create_dir_all(output_dir)?;
+    conn.execute_batch(&format!(
+        "COPY (SELECT *, CAST(_timestamp AS DATE) AS dt FROM {}) TO '{}' \
+         (FORMAT PARQUET, PARTITION_BY (dt), OVERWRITE_OR_IGNORE true);",
+        quote_identifier(history_table),
+        output_dir.replace('\'', "''")
+    ))?;
+
+    Ok(rows_exported)
+}
+
+/// Appends history rows through the Appender, falling back to an INSERT statement for batches holding
+/// literals the Appender can't take
+fn append_history_rows(
+    conn: &Connection,
+    history_table: &str,
+    column_types: &[String],
+    rows: &[Vec<String>],
+) -> Result<(), Box<dyn std::error::Error>> {
+    if rows.is_empty() || conn.append_rows(history_table, column_types, rows)? {
+        return Ok(());
+    }
+    let values: Vec<String> = rows.iter().map(|row| format!("({})", row.join(", "))).collect();
+    conn.execute_batch(&format!("INSERT INTO {} VALUES {};", quote_identifier(history_table), values.join(", ")))?;
+    Ok(())
+}
+
+/// The history row for an operation as SQL literals, in the history table's column order
+fn history_row(schema: &[(String, String)], idx: usize, op: &BinlogOperation, timestamp_options: &TimestampOptions) -> Vec<String> {
+    let row_image = match op.operation_type {
+        OperationType::Insert | OperationType::Update => op.after_values.as_ref(),
+        OperationType::Delete => op.before_values.as_ref(),
+    };
+    let mut values: Vec<String> = schema.iter()
+        .map(|(name, _)| {
+            op.columns.iter().position(|column| column == name)
+                .and_then(|i| row_image?.get(i).cloned())
+                .unwrap_or_else(|| "NULL".to_string())
+        })
+        .collect();
+
+    let timestamp = op.timestamp.as_ref()
+        .and_then(|ts| timestamp_options.format(ts))
+        .map(|ts| format!("'{}'", ts))
+        .unwrap_or_else(|| "NULL".to_string());
+
+    values.push(format!("'{}'", op.operation_type));
+    values.push(idx.to_string());
+    values.push(op.position.map(|p| p.to_string()).unwrap_or_else(|| "NULL".to_string()));
+    values.push(timestamp);
+    values
+}
+
+/// `name` as an identifier quoted with backticks, which ClickHouse and BigQuery both accept
+fn backquote(name: &str) -> String {
+    format!("`{}`", name.replace('`', "\\`"))
+}
+
+/// Generates a CREATE TABLE statement for the exported history in the given warehouse dialect
+pub fn generate_ddl(table: &str, schema: &[(String, String)], dialect: SchemaDialect) -> String {
+    let mut columns: Vec<(String, String)> = schema.to_vec();
+    columns.extend(HISTORY_COLUMNS.iter().map(|(name, col_type)| (name.to_string(), col_type.to_string())));
+    columns.push(("dt".to_string(), "DATE".to_string()));
+
+    let column_defs: Vec<String> = columns.iter()
+        .map(|(name, duckdb_type)| match dialect {
+            SchemaDialect::ClickHouse => {
+                let col_type = clickhouse_type(duckdb_type);
+                if name == "dt" {
+                    format!("    {} {}", backquote(name), col_type)
+                } else {
+                    format!("    {} Nullable({})", backquote(name), col_type)
+                }
+            }
+            SchemaDialect::BigQuery => format!("    {} {}", backquote(name), bigquery_type(duckdb_type)),
+        })
+        .collect();
+
+    match dialect {
+        SchemaDialect::ClickHouse => format!(
+            "CREATE TABLE {} (\n{}\n)\nENGINE = MergeTree\nPARTITION BY dt\nORDER BY (dt, _position);\n",
+            backquote(&format!("{}_history", table)),
+            column_defs.join(",\n")
+        ),
+        SchemaDialect::BigQuery => format!(
+            "CREATE TABLE {} (\n{}\n)\nPARTITION BY dt;\n",
+            backquote(&format!("{}_history", table)),
+            column_defs.join(",\n")
+        ),
+    }
+}
+
+fn clickhouse_type(duckdb_type: &str) -> String {
+    let upper = duckdb_type.to_uppercase();
+    if let Some(precision) = upper.strip_prefix("DECIMAL") {
+        return format!("Decimal{}", precision);
+    }
+    match upper.as_str() {
+        "TINYINT" => "Int8",
+        "SMALLINT" => "Int16",
+        "INTEGER" => "Int32",
+        "BIGINT" => "Int64",
+        "HUGEINT" => "Int128",
+        "UTINYINT" => "UInt8",
+        "USMALLINT" => "UInt16",
+        "UINTEGER" => "UInt32",
+        "UBIGINT" => "UInt64",
+        "FLOAT" => "Float32",
+        "DOUBLE" => "Float64",
+        "BOOLEAN" => "Bool",
+        "DATE" => "Date32",
+        "TIMESTAMP" => "DateTime64(6)",
+        _ => "String",
+    }.to_string()
+}
+
+fn bigquery_type(duckdb_type: &str) -> String {
+    let upper = duckdb_type.to_uppercase();
+    if let Some(precision) = upper.strip_prefix("DECIMAL") {
+        return format!("NUMERIC{}", precision);
+    }
+    match upper.as_str() {
+        "TINYINT" | "SMALLINT" | "INTEGER" | "BIGINT" | "UTINYINT" | "USMALLINT" | "UINTEGER" => "INT64",
+        "HUGEINT" | "UBIGINT" => "BIGNUMERIC",
+        "FLOAT" | "DOUBLE" => "FLOAT64",
+        "BOOLEAN" => "BOOL",
+        "DATE" => "DATE",
+        "TIMESTAMP" => "DATETIME",
+        "BLOB" => "BYTES",
+        _ => "STRING",
+    }.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{books_db, operation};
+
+    fn op(operation_type: OperationType, timestamp: &str, before: Option<Vec<&str>>, after: Option<Vec<&str>>) -> BinlogOperation {
+        BinlogOperation {
+            timestamp: Some(timestamp.to_string()),
+            position: Some(100),
+            ..operation(operation_type, "books", &["id", "title"], before.as_deref(), after.as_deref())
+        }
+    }
+
+    #[test]
+    fn test_export_history_writes_hive_partitions() {
+        let conn = books_db("id INTEGER, title VARCHAR", "");
+        let operations = vec![
+            op(OperationType::Insert, "251108 10:00:00", None, Some(vec!["1", "'Dune'"])),
+            op(OperationType::Update, "251109 10:00:00", Some(vec!["1", "'Dune'"]), Some(vec!["1", "'Dune II'"])),
+            op(OperationType::Delete, "251109 11:00:00", Some(vec!["1", "'Dune II'"]), None),
+        ];
+        let manager = SnapshotManager::new(conn, operations, 0);
+
+        let output_dir = std::env::temp_dir().join(format!("history_export_{}", std::process::id()));
+        let output_dir = output_dir.to_str().unwrap();
+        let options = HistoryExportOptions { ddl_dialect: Some(SchemaDialect::ClickHouse) };
+        let summary = export_history(&manager, "books", output_dir, &options).unwrap();
+
+        assert_eq!(summary.rows_exported, 3);
+        assert!(Path::new(output_dir).join("dt=2025-11-08").is_dir());
+        assert!(Path::new(output_dir).join("dt=2025-11-09").is_dir());
+
+        let count: i64 = manager.get_connection().query_row(
+            &format!("SELECT COUNT(*) FROM read_parquet('{}/dt=2025-11-09/*.parquet') WHERE title = 'Dune II'", output_dir),
+            [],
+            |row| row.get(0),
+        ).unwrap();
+        assert_eq!(count, 2);
+
+        let ddl = fs::read_to_string(summary.ddl_path.unwrap()).unwrap();
+        assert!(ddl.contains("CREATE TABLE `books_history`"));
+        assert!(ddl.contains("`title` Nullable(String)"));
+
+        fs::remove_dir_all(output_dir).ok();
+    }
+
+    #[test]
+    fn test_export_history_falls_back_to_inserts_and_quotes_the_output_dir() {
+        let conn = books_db("id INTEGER, cover BLOB", "");
+        let operations = vec![
+            BinlogOperation {
+                timestamp: Some("251108 10:00:00".to_string()),
+                ..operation(OperationType::Insert, "books", &["id", "cover"], None, Some(&["1", "unhex('CAFE')"]))
+            },
+        ];
+        let manager = SnapshotManager::new(conn, operations, 0);
+
+        let output_dir = std::env::temp_dir().join(format!("history_export_o'brien_{}", std::process::id()));
+        let output_dir = output_dir.to_str().unwrap();
+        let summary = export_history(&manager, "books", output_dir, &HistoryExportOptions::default()).unwrap();
+
+        assert_eq!(summary.rows_exported, 1);
+        assert!(summary.ddl_path.is_none());
+        let cover: String = manager.get_connection().query_row(
+            &format!("SELECT hex(cover) FROM read_parquet('{}/dt=2025-11-08/*.parquet')", output_dir.replace('\'', "''")),
+            [],
+            |row| row.get(0),
+        ).unwrap();
+        assert_eq!(cover, "CAFE");
+
+        fs::remove_dir_all(output_dir).ok();
+    }
+
+    #[test]
+    fn test_generate_bigquery_ddl() {
+        let schema = vec![
+            ("id".to_string(), "INTEGER".to_string()),
+            ("price".to_string(), "DECIMAL(10,2)".to_string()),
+        ];
+        let ddl = generate_ddl("books", &schema, SchemaDialect::BigQuery);
+
+        assert!(ddl.contains("`id` INT64"));
+        assert!(ddl.contains("`price` NUMERIC(10,2)"));
+        assert!(ddl.contains("`_timestamp` DATETIME"));
+        assert!(ddl.ends_with("PARTITION BY dt;\n"));
+    }
+}
@@ -0,0 +1,208 @@
+use std::collections::BTreeMap;
+use std::fs;
+use chrono::{DurationRound, TimeDelta};
+use serde_json::{json, Value as JsonValue};
+use tracing::info;
+use crate::snapshot_manager::SnapshotManager;
+
+/// File format of the timeline
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimelineFormat {
+    Json,
+    /// A self-contained HTML page drawing the timeline
+    Html,
+}
+
+impl TimelineFormat {
+    /// Picks the format from the output file extension (`.html`/`.htm`, anything else is JSON)
+    pub fn from_path(path: &str) -> Self {
+        if path.ends_with(".html") || path.ends_with(".htm") {
+            TimelineFormat::Html
+        } else {
+            TimelineFormat::Json
+        }
+    }
+}
+
+/// Timeline of the operation stream, for choosing where to navigate:
+///
+/// - `buckets`: `{"minute", "table", "operations", "first_position"}` for every table and minute
+///   with operations, in time and then table order; `first_position` is where to go to see the
+///   minute's first change
+/// - `anchor`: `{"position", "timestamp"}` of the position the snapshot was anchored to, if given
+/// - `bookmarks`: `{"name", "position", "timestamp"}` in name order
+///
+/// plus `tables`, `operation_count` and the current `position`. Timestamps are
+/// `YYYY-MM-DD HH:MM:SS`. Operations without a timestamp are left out of the buckets.
+pub fn timeline(manager: &SnapshotManager, anchor_position: Option<usize>) -> Result<JsonValue, Box<dyn std::error::Error>> {
+    let mut buckets: BTreeMap<(String, String), (usize, usize)> = BTreeMap::new();
+    manager.for_each_operation(0, manager.operation_count(), |position, op| {
+        let Some(timestamp) = op.timestamp.as_ref().and_then(|ts| manager.get_timestamp_options().parse(ts).ok()) else {
+            return Ok(());
+        };
+        let minute = timestamp.as_datetime().duration_trunc(TimeDelta::minutes(1))?;
+        let bucket = buckets.entry((minute.format("%Y-%m-%d %H:%M").to_string(), op.table_name.clone())).or_insert((0, position));
+        bucket.0 += 1;
+        Ok(())
+    })?;
+
+    let mut tables: Vec<&str> = buckets.keys().map(|(_, table)| table.as_str()).collect();
+    tables.sort();
+    tables.dedup();
+    let bookmarks: Vec<JsonValue> = manager.get_bookmarks().into_iter()
+        .map(|(name, bookmark)| json!({
+            "name": name,
+            "position": bookmark.position,
+            "timestamp": bookmark.timestamp.as_deref().and_then(|ts| manager.get_timestamp_options().format(ts)),
+        }))
+        .collect();
+    let anchor = anchor_position.map(|position| json!({
+        "position": position,
+        "timestamp": manager.get_operation(position)
+            .and_then(|op| op.timestamp.as_deref())
+            .and_then(|ts| manager.get_timestamp_options().format(ts)),
+    }));
+
+    Ok(json!({
+        "operation_count": manager.operation_count(),
+        "position": manager.get_position(),
+        "anchor": anchor,
+        "tables": tables,
+        "bookmarks": bookmarks,
+        "buckets": buckets.iter()
+            .map(|((minute, table), (operations, first_position))| json!({
+                "minute": minute,
+                "table": table,
+                "operations": operations,
+                "first_position": first_position,
+            }))
+            .collect::<Vec<_>>(),
+    }))
+}
+
+/// Writes the timeline (see timeline) as JSON, or as an HTML report with one lane per table
+/// where hovering a minute shows its operation count and the position to navigate to.
+/// Returns the number of buckets.
+pub fn export_timeline(
+    manager: &SnapshotManager,
+    anchor_position: Option<usize>,
+    output_path: &str,
+    format: TimelineFormat,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let timeline = timeline(manager, anchor_position)?;
+    let bucket_count = timeline["buckets"].as_array().map_or(0, Vec::len);
+    match format {
+        TimelineFormat::Json => fs::write(output_path, serde_json::to_string_pretty(&timeline)?)?,
+        // "</" would end the script element the timeline is embedded in
+        TimelineFormat::Html => fs::write(output_path, HTML_REPORT.replace("{{TIMELINE}}", &timeline.to_string().replace("</", "<\\/")))?,
+    }
+    info!("Exported a timeline of {} bucket(s) to {}", bucket_count, output_path);
+    Ok(bucket_count)
+}
+
+const HTML_REPORT: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Pensieve timeline</title>
+<style>
+body { font-family: sans-serif; margin: 1em; }
+svg text { font-size: 11px; }
+#details { margin-top: 1em; font-family: monospace; }
+</style>
+</head>
+<body>
+<h1>Pensieve timeline</h1>
+<p id="summary"></p>
+<svg id="timeline"></svg>
+<div id="details">Hover or click a minute to see where to navigate.</div>
+<script>
+const timeline = {{TIMELINE}};
+const minute = text => Date.parse(text.slice(0, 16).replace(" ", "T") + ":00Z") / 60000;
+const label = 140, width = 1000, lane = 24, lanesTop = 30;
+const minutes = timeline.buckets.map(b => minute(b.minute));
+const markers = [timeline.anchor, ...timeline.bookmarks].filter(m => m && m.timestamp);
+markers.forEach(m => minutes.push(minute(m.timestamp)));
+const start = Math.min(...minutes), end = Math.max(...minutes) + 1;
+const x = m => label + (m - start) / (end - start) * width;
+const peak = Math.max(1, ...timeline.buckets.map(b => b.operations));
+const svg = document.getElementById("timeline");
+const details = document.getElementById("details");
+svg.setAttribute("width", label + width + 20);
+svg.setAttribute("height", lanesTop + lane * timeline.tables.length + 10);
+const add = (tag, attributes, text) => {
+  const element = document.createElementNS("http://www.w3.org/2000/svg", tag);
+  Object.entries(attributes).forEach(([name, value]) => element.setAttribute(name, value));
+  if (text !== undefined) element.textContent = text;
+  svg.appendChild(element);
+  return element;
+};
+document.getElementById("summary").textContent = `${timeline.operation_count} operations, current position ${timeline.position}`;
+timeline.tables.forEach((table, i) => add("text", { x: 0, y: lanesTop + i * lane + 16 }, table));
+timeline.buckets.forEach(b => {
+  const row = timeline.tables.indexOf(b.table);
+  const bar = add("rect", {
+    x: x(minute(b.minute)), y: lanesTop + row * lane + 2,
+    width: Math.max(2, width / (end - start)), height: lane - 4,
+    fill: "steelblue", "fill-opacity": 0.2 + 0.8 * b.operations / peak,
+  });
+  const text = `${b.table} ${b.minute}: ${b.operations} operation(s), first at position ${b.first_position}`;
+  add("title", {}, text);
+  bar.appendChild(svg.lastChild);
+  bar.addEventListener("mouseover", () => details.textContent = text);
+  bar.addEventListener("click", () => details.textContent = `${text}. Navigate with {"position": ${b.first_position}} (POST /goto)`);
+});
+markers.forEach(m => {
+  const color = m === timeline.anchor ? "crimson" : "darkorange";
+  const name = m === timeline.anchor ? "anchor" : m.name;
+  add("line", { x1: x(minute(m.timestamp)), x2: x(minute(m.timestamp)), y1: 14, y2: lanesTop + lane * timeline.tables.length, stroke: color });
+  add("text", { x: x(minute(m.timestamp)) + 2, y: 12, fill: color }, `${name} (${m.position})`);
+});
+</script>
+</body>
+</html>
+"#;
+
+#[cfg(test)]
+mod tests {
+    use duckdb::Connection;
+    use crate::binlog::{BinlogOperation, OperationType};
+    use super::*;
+    use crate::test_support::operation;
+
+    fn insert(table: &str, timestamp: &str) -> BinlogOperation {
+        BinlogOperation { timestamp: Some(timestamp.to_string()), ..operation(OperationType::Insert, table, &["id"], None, Some(&["1"])) }
+    }
+
+    #[test]
+    fn test_timeline_buckets_operations_per_table_and_minute() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE books (id INTEGER); CREATE TABLE authors (id INTEGER);").unwrap();
+        let operations = vec![
+            insert("books", "251108 10:00:05"),
+            insert("authors", "251108 10:00:30"),
+            insert("books", "251108 10:00:59"),
+            insert("books", "251108 10:02:00"),
+        ];
+        let mut manager = SnapshotManager::new(conn, operations, 0);
+        manager.goto_position(3).unwrap();
+        manager.bookmark("spike");
+
+        let timeline = timeline(&manager, Some(0)).unwrap();
+        assert_eq!(timeline["tables"], json!(["authors", "books"]));
+        assert_eq!(timeline["buckets"], json!([
+            { "minute": "2025-11-08 10:00", "table": "authors", "operations": 1, "first_position": 1 },
+            { "minute": "2025-11-08 10:00", "table": "books", "operations": 2, "first_position": 0 },
+            { "minute": "2025-11-08 10:02", "table": "books", "operations": 1, "first_position": 3 },
+        ]));
+        assert_eq!(timeline["anchor"], json!({ "position": 0, "timestamp": "2025-11-08 10:00:05" }));
+        assert_eq!(timeline["bookmarks"], json!([{ "name": "spike", "position": 3, "timestamp": "2025-11-08 10:02:00" }]));
+
+        let path = std::env::temp_dir().join(format!("timeline_{}.html", std::process::id()));
+        let path = path.to_str().unwrap();
+        assert_eq!(export_timeline(&manager, Some(0), path, TimelineFormat::from_path(path)).unwrap(), 3);
+        let html = fs::read_to_string(path).unwrap();
+        assert!(html.contains(r#""first_position":3"#) && !html.contains("{{TIMELINE}}"));
+        fs::remove_file(path).ok();
+    }
+}
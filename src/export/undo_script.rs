@@ -0,0 +1,220 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use tracing::info;
+use crate::binlog::{BinlogOperation, OperationType};
+use crate::snapshot_manager::SnapshotManager;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct UndoScriptSummary {
+    pub statements: usize,
+    /// Timestamps of the first and last undone operations
+    pub first_timestamp: Option<String>,
+    pub last_timestamp: Option<String>,
+}
+
+/// Writes a MySQL script that undoes the operations at positions `from..=to`: their inverses, latest
+/// first, in one transaction. Applied to a MySQL instance in the state after `to`, it brings the
+/// rows back to the state before `from`, e.g. to undo a bad deploy.
+///
+/// Rows are identified by the snapshot table's primary key (or its first column) when the row image
+/// holds it, and by every logged column otherwise. Fails without writing anything if an operation
+/// can't be undone exactly: a DELETE whose row image didn't log every column can't be re-inserted,
+/// and redacted or decoded columns don't hold the values MySQL has. The snapshot's position is
+/// not changed.
+pub fn export_undo_script(
+    manager: &SnapshotManager,
+    from: usize,
+    to: usize,
+    output_path: &str,
+) -> Result<UndoScriptSummary, Box<dyn std::error::Error>> {
+    if from > to || to >= manager.operation_count() {
+        return Err(format!("Invalid position range {}..={} for {} operations", from, to, manager.operation_count()).into());
+    }
+    let operations = manager.get_operations_range(from, to + 1)?;
+
+    let mut statements = Vec::with_capacity(operations.len());
+    for (offset, op) in operations.iter().enumerate().rev() {
+        let position = from + offset;
+        // A DELETE's inverse can't re-insert columns that weren't logged, and an UPDATE's inverse
+        // can't restore them (or columns a projection left out)
+        if !op.row_image.missing_before().is_empty() {
+            return Err(format!(
+                "Can't undo the {} at position {}: columns {:?} of {} weren't logged or loaded",
+                op.operation_type, position, op.row_image.missing_before(), op.table_name
+            ).into());
+        }
+        if let Some(column) = op.columns.iter().find(|column| {
+            manager.get_redactions().redaction(&op.table_name, column).is_some()
+                || manager.get_decoded_columns().iter().any(|(table, decoded)| *table == op.table_name && decoded == *column)
+        }) {
+            return Err(format!(
+                "Can't undo the operation at position {}: {}.{} is redacted or decoded, so it doesn't hold MySQL's values",
+                position, op.table_name, column
+            ).into());
+        }
+        let inverted = op.invert();
+        let columns = manager.table_columns(&inverted.table_name)?;
+        let key_columns = manager.primary_key_columns(&inverted.table_name, &columns)?;
+        let statement = mysql_statement(&inverted, &key_columns)
+            .map_err(|e| format!("Can't undo the operation at position {}: {}", position, e))?;
+        statements.push((position, op.timestamp.as_deref(), statement));
+    }
+
+    let mut writer = BufWriter::new(File::create(output_path)?);
+    writeln!(writer, "-- Undo script for operations {} to {}, generated by pensieve {}", from, to, env!("CARGO_PKG_VERSION"))?;
+    writeln!(writer, "-- Apply to a database in the state after operation {}", to)?;
+    writeln!(writer, "START TRANSACTION;")?;
+    for (position, timestamp, statement) in &statements {
+        writeln!(writer, "-- position {} ({})", position, timestamp.unwrap_or("no timestamp"))?;
+        writeln!(writer, "{}", statement)?;
+    }
+    writeln!(writer, "COMMIT;")?;
+    writer.flush()?;
+
+    info!("Wrote {} undo statement(s) to {}", statements.len(), output_path);
+    Ok(UndoScriptSummary {
+        statements: statements.len(),
+        first_timestamp: operations.first().and_then(|op| op.timestamp.clone()),
+        last_timestamp: operations.last().and_then(|op| op.timestamp.clone()),
+    })
+}
+
+/// MySQL statement applying `op` to exactly one row. Fails for UPDATEs that don't change any
+/// column, which would have an empty SET clause.
+fn mysql_statement(op: &BinlogOperation, key_columns: &[String]) -> Result<String, String> {
+    let table = format!("`{}`.`{}`", op.database, op.mysql_table_name());
+    Ok(match op.operation_type {
+        OperationType::Insert => {
+            let columns: Vec<String> = op.columns.iter().map(|col| format!("`{}`", col)).collect();
+            format!(
+                "INSERT INTO {} ({}) VALUES ({});",
+                table,
+                columns.join(", "),
+                op.after_values.as_deref().unwrap_or_default().iter().map(|value| mysql_literal(value)).collect::<Vec<_>>().join(", ")
+            )
+        }
+        OperationType::Update => {
+            let changed = op.changed_columns();
+            let set_parts: Vec<String> = op.columns.iter()
+                .zip(op.after_values.as_deref().unwrap_or_default())
+                .filter(|(col, _)| changed.contains(&col.as_str()))
+                .map(|(col, value)| format!("`{}` = {}", col, mysql_literal(value)))
+                .collect();
+            if set_parts.is_empty() {
+                return Err(format!("the UPDATE of {} doesn't change any logged column", op.table_name));
+            }
+            format!("UPDATE {} SET {} WHERE {} LIMIT 1;", table, set_parts.join(", "), row_filter(op, key_columns))
+        }
+        OperationType::Delete => format!("DELETE FROM {} WHERE {} LIMIT 1;", table, row_filter(op, key_columns)),
+    })
+}
+
+/// Predicates on the before image: the key columns if it holds all of them, every column otherwise
+fn row_filter(op: &BinlogOperation, key_columns: &[String]) -> String {
+    let before = op.before_values.as_deref().unwrap_or_default();
+    let has_key = !key_columns.is_empty() && key_columns.iter().all(|key| op.columns.contains(key));
+    let parts: Vec<String> = op.columns.iter()
+        .zip(before)
+        .filter(|(col, _)| !has_key || key_columns.contains(col))
+        .map(|(col, value)| if value == "NULL" {
+            format!("`{}` IS NULL", col)
+        } else {
+            format!("`{}` = {}", col, mysql_literal(value))
+        })
+        .collect();
+    parts.join(" AND ")
+}
+
+/// A SQL literal as MySQL reads it: backslashes in strings are escapes in MySQL's default
+/// sql_mode, but literal characters in the standard SQL of the operations' values
+fn mysql_literal(value: &str) -> String {
+    value.replace('\\', "\\\\")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use duckdb::Connection;
+    use crate::binlog::RowImage;
+    use crate::loader::redaction::{Redaction, Redactions};
+    use crate::test_support::{books_db, operation};
+
+    fn op(operation_type: OperationType, before: Option<[&str; 3]>, after: Option<[&str; 3]>) -> BinlogOperation {
+        BinlogOperation {
+            timestamp: Some("251108 10:00:00".to_string()),
+            database: "shop".to_string(),
+            ..operation(operation_type, "books", &["id", "title", "price"], before.as_ref().map(|v| &v[..]), after.as_ref().map(|v| &v[..]))
+        }
+    }
+
+    #[test]
+    fn test_undo_script_inverts_latest_first() {
+        let conn = books_db("id INTEGER PRIMARY KEY, title VARCHAR, price INTEGER", "");
+        let mut operations = vec![
+            op(OperationType::Insert, None, Some(["1", "'Dune'", "10"])),
+            op(OperationType::Update, Some(["1", "'Dune'", "10"]), Some(["1", "'Dune'", "12"])),
+            op(OperationType::Delete, Some(["2", "NULL", "5"]), None),
+        ];
+        let manager = SnapshotManager::new(conn, operations.clone(), 0);
+
+        let path = std::env::temp_dir().join(format!("pensieve_undo_{}.sql", std::process::id()));
+        let path = path.to_str().unwrap();
+        let summary = export_undo_script(&manager, 1, 2, path).unwrap();
+        assert_eq!(summary.statements, 2);
+
+        let script = std::fs::read_to_string(path).unwrap();
+        let statements: Vec<&str> = script.lines().filter(|line| !line.starts_with("--")).collect();
+        assert_eq!(statements, vec![
+            "START TRANSACTION;",
+            "INSERT INTO `shop`.`books` (`id`, `title`, `price`) VALUES (2, NULL, 5);",
+            "UPDATE `shop`.`books` SET `price` = 10 WHERE `id` = 1 LIMIT 1;",
+            "COMMIT;",
+        ]);
+        assert!(export_undo_script(&manager, 2, 1, path).is_err());
+
+        let mut manager = SnapshotManager::new(Connection::open_in_memory().unwrap(), operations.clone(), 0);
+        manager.set_redactions(Redactions::new().with_column("books", "title", Redaction::Hash));
+        assert!(export_undo_script(&manager, 1, 2, path).is_err());
+        manager.set_redactions(Redactions::new());
+        manager.set_decoded_columns(vec![("books".to_string(), "price".to_string())]);
+        assert!(export_undo_script(&manager, 1, 2, path).is_err());
+
+        let mut projected = operations.clone();
+        projected[1].row_image = RowImage::Partial { missing_before: vec!["blurb".to_string()] };
+        let manager = SnapshotManager::new(Connection::open_in_memory().unwrap(), projected, 0);
+        assert!(export_undo_script(&manager, 1, 1, path).is_err());
+
+        operations[2].row_image = RowImage::Partial { missing_before: vec!["title".to_string()] };
+        let conn = books_db("id INTEGER PRIMARY KEY, title VARCHAR, price INTEGER", "");
+        let manager = SnapshotManager::new(conn, operations, 0);
+        assert!(export_undo_script(&manager, 0, 2, path).is_err());
+
+        std::fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn test_minimal_update_is_refused() {
+        let conn = books_db("id INTEGER PRIMARY KEY, title VARCHAR, price INTEGER", "");
+        // binlog_row_image=MINIMAL: the before image only holds the key
+        let mut update = op(OperationType::Update, Some(["1", "NULL", "NULL"]), Some(["1", "'Dune'", "12"]));
+        update.row_image = RowImage::Partial { missing_before: vec!["title".to_string(), "price".to_string()] };
+        let manager = SnapshotManager::new(conn, vec![update], 0);
+
+        let path = std::env::temp_dir().join(format!("pensieve_undo_minimal_{}.sql", std::process::id()));
+        let error = export_undo_script(&manager, 0, 0, path.to_str().unwrap()).unwrap_err();
+        assert!(error.to_string().contains("UPDATE at position 0"));
+        assert!(!path.exists());
+
+        let unchanged = op(OperationType::Update, Some(["1", "'Dune'", "12"]), Some(["1", "'Dune'", "12"]));
+        assert!(mysql_statement(&unchanged, &[]).is_err());
+    }
+
+    #[test]
+    fn test_backslashes_are_escaped_for_mysql() {
+        let update = op(OperationType::Update, Some(["1", r"'C:\new'", "12"]), Some(["1", r"'C:\old'", "10"]));
+        assert_eq!(
+            mysql_statement(&update, &[]).unwrap(),
+            r"UPDATE `shop`.`books` SET `title` = 'C:\\old', `price` = 10 WHERE `id` = 1 AND `title` = 'C:\\new' AND `price` = 12 LIMIT 1;"
+        );
+    }
+}
@@ -6,6 +6,9 @@ pub mod snapshot_manager;
 pub mod snapshot_normaliser;
 pub mod binlog;
 pub mod script;
+pub mod export;
+pub mod doctor;
+pub mod logging;
 
 
 
@@ -13,3 +16,7 @@ pub mod script;
 
 
 
+pub mod server;
+
+#[cfg(test)]
+mod test_support;
@@ -1 +1,7 @@
-pub mod parquet_loader;
\ No newline at end of file
+pub mod binlog_only_tables;
+pub mod column_decoding;
+pub mod column_projection;
+pub mod parquet_loader;
+pub mod redaction;
+pub mod remote_files;
+pub mod resource_limits;
\ No newline at end of file
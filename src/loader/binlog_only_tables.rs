@@ -0,0 +1,162 @@
+use duckdb::Connection;
+use std::collections::BTreeMap;
+use std::io::BufRead;
+use std::path::Path;
+use tracing::info;
+use crate::binlog::{quote_identifier, BinlogOperation};
+use crate::operation_applier::OperationApplier;
+use crate::parser::{binlog_reader, line_tokenizer};
+
+/// Creates empty tables for tables that have no snapshot, only binlog operations, so that they
+/// can be reconstructed from scratch by replaying the binlog.
+///
+/// Existing tables of that name are dropped. `ddl_file` (CREATE TABLE statements) is run first;
+/// tables it doesn't create get one column per value of their first INSERT in the binlog, named
+/// `col1`, `col2`, ... and typed BIGINT, DOUBLE or VARCHAR after the value.
+pub fn create_binlog_only_tables(
+    conn: &Connection,
+    tables: &[String],
+    binlog_files: &[String],
+    ddl_file: Option<&Path>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    for table in tables {
+        conn.execute_batch(&format!("DROP TABLE IF EXISTS main.{};", quote_identifier(table)))?;
+    }
+    if let Some(ddl_file) = ddl_file {
+        conn.execute_batch(&std::fs::read_to_string(ddl_file)?)
+            .map_err(|e| format!("Failed to run {:?}: {}", ddl_file, e))?;
+    }
+
+    let mut missing = Vec::new();
+    for table in tables {
+        let exists: bool = conn.query_row(
+            "SELECT COUNT(*) > 0 FROM duckdb_tables() WHERE schema_name = 'main' AND table_name = ?",
+            [table],
+            |row| row.get(0),
+        )?;
+        if !exists {
+            missing.push(table.as_str());
+        }
+    }
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    let inserts = first_inserts(binlog_files, &missing)?;
+    for table in missing {
+        let Some(values) = inserts.get(table) else {
+            return Err(format!("{} has no INSERT in the binlog to take its columns from; create it in a DDL file", table).into());
+        };
+        let columns: Vec<String> = values.iter()
+            .enumerate()
+            .map(|(i, value)| format!("col{} {}", i + 1, literal_type(value)))
+            .collect();
+        info!("Creating binlog-only table {} ({})", table, columns.join(", "));
+        conn.execute_batch(&format!("CREATE TABLE main.{} ({});", quote_identifier(table), columns.join(", ")))?;
+    }
+    Ok(())
+}
+
+/// Brings binlog-only tables to their state at `position` by replaying every operation on them
+/// up to it
+pub fn reconstruct_binlog_only_tables(
+    conn: Connection,
+    tables: &[String],
+    operations: &[BinlogOperation],
+    position: usize,
+    use_appender: bool,
+) -> Result<Connection, Box<dyn std::error::Error>> {
+    let mut applier = OperationApplier::new(conn).with_appender(use_appender);
+    for table in tables {
+        applier.get_connection().execute_batch(&format!("DELETE FROM main.{};", quote_identifier(table)))?;
+    }
+    let replayed: Vec<&BinlogOperation> = operations.iter()
+        .take(position + 1)
+        .filter(|op| tables.contains(&op.table_name))
+        .collect();
+    applier.apply_operations(&replayed)?;
+    Ok(applier.into_connection())
+}
+
+/// Raw values of the first INSERT into each of `tables`, in column order
+fn first_inserts(binlog_files: &[String], tables: &[&str]) -> Result<BTreeMap<String, Vec<String>>, Box<dyn std::error::Error>> {
+    let mut inserts: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for file in binlog_files {
+        let mut inserting: Option<String> = None;
+        for line in binlog_reader::open_binlog(file, 1024 * 1024)?.lines() {
+            let line = line?;
+            if let Some(table_path) = line.strip_prefix("### INSERT INTO ") {
+                let table = table_path.trim().rsplit('.').next().unwrap_or_default().trim_matches('`');
+                inserting = (tables.contains(&table) && !inserts.contains_key(table)).then(|| table.to_string());
+                if let Some(table) = &inserting {
+                    inserts.insert(table.clone(), Vec::new());
+                }
+            } else if let Some(table) = &inserting {
+                if let Some((_, value)) = line_tokenizer::column_value(&line) {
+                    inserts.get_mut(table).unwrap().push(value.to_string());
+                } else if !line.starts_with("###") {
+                    inserting = None;
+                    if inserts.len() == tables.len() {
+                        return Ok(inserts);
+                    }
+                }
+            }
+        }
+    }
+    Ok(inserts)
+}
+
+/// Column type for a raw binlog value: unsigned values are followed by their signed reading,
+/// e.g. `-1 (4294967295)`
+fn literal_type(value: &str) -> &'static str {
+    let value = value.split_whitespace().next().unwrap_or_default();
+    if value.parse::<i64>().is_ok() {
+        "BIGINT"
+    } else if !value.starts_with('\'') && value != "NULL" && value.parse::<f64>().is_ok() {
+        "DOUBLE"
+    } else {
+        "VARCHAR"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_creates_tables_from_ddl_or_first_insert() {
+        let dir = std::env::temp_dir().join(format!("pensieve_binlog_only_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let binlog = dir.join("mysql-bin.000001.sql");
+        std::fs::write(&binlog, "\
+#251020 19:43:32 server id 1  end_log_pos 2000
+### INSERT INTO `shop`.`events`
+### SET
+###   @1=4
+###   @2='login'
+###   @3=12.5
+# at 2000
+### INSERT INTO `shop`.`audit`
+### SET
+###   @1=1
+").unwrap();
+        let ddl = dir.join("schema.sql");
+        std::fs::write(&ddl, "CREATE TABLE audit (id INTEGER PRIMARY KEY, note VARCHAR);").unwrap();
+
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE events (stale INTEGER);").unwrap();
+        let tables = vec!["events".to_string(), "audit".to_string()];
+        create_binlog_only_tables(&conn, &tables, &[binlog.to_string_lossy().to_string()], Some(&ddl)).unwrap();
+
+        let columns_of = |table: &str| -> Vec<String> {
+            let sql = format!("SELECT column_name || ' ' || data_type FROM duckdb_columns() WHERE table_name = '{table}'");
+            let mut stmt = conn.prepare(&sql).unwrap();
+            stmt.query_map([], |row| row.get(0)).unwrap().map(|column| column.unwrap()).collect()
+        };
+        assert_eq!(columns_of("events"), vec!["col1 BIGINT", "col2 VARCHAR", "col3 DOUBLE"]);
+        assert_eq!(columns_of("audit"), vec!["id INTEGER", "note VARCHAR"]);
+
+        assert!(create_binlog_only_tables(&conn, &["missing".to_string()], &[binlog.to_string_lossy().to_string()], None).is_err());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,68 @@
+use duckdb::Connection;
+use duckdb::params;
+use tracing::info;
+use crate::binlog::quote_identifier;
+use crate::parser::value_decoder::ColumnDecoder;
+
+/// Decodes the snapshot's values of columns with a ColumnDecoder, so they match the decoded
+/// values the parser stores in operations. The columns become VARCHAR. Each distinct value is
+/// decoded once. Columns the snapshot doesn't have are skipped.
+pub fn decode_snapshot_columns(conn: &Connection, decoders: &[(String, String, ColumnDecoder)]) -> Result<(), Box<dyn std::error::Error>> {
+    for (table, column, decoder) in decoders {
+        let (quoted_table, quoted_column) = (quote_identifier(table), quote_identifier(column));
+        let column_type: Option<String> = conn.query_row(
+            "SELECT data_type FROM duckdb_columns() WHERE schema_name = 'main' AND table_name = ? AND column_name = ?",
+            [table, column],
+            |row| row.get(0),
+        ).ok();
+        let Some(column_type) = column_type else {
+            continue;
+        };
+        if column_type != "VARCHAR" {
+            conn.execute_batch(&format!("ALTER TABLE main.{quoted_table} ALTER COLUMN {quoted_column} TYPE VARCHAR;"))?;
+        }
+
+        let encoded: Vec<String> = conn
+            .prepare(&format!("SELECT DISTINCT {quoted_column} FROM main.{quoted_table} WHERE {quoted_column} IS NOT NULL"))?
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
+        info!("Decoding {} distinct values of {}.{}", encoded.len(), table, column);
+
+        conn.execute_batch("CREATE OR REPLACE TEMP TABLE pensieve_decoded (encoded VARCHAR, decoded VARCHAR);")?;
+        {
+            let mut appender = conn.appender("pensieve_decoded")?;
+            for value in &encoded {
+                appender.append_row(params![value, decoder.decode(value)])?;
+            }
+        }
+        conn.execute_batch(&format!(
+            "UPDATE main.{quoted_table} AS t SET {quoted_column} = d.decoded FROM pensieve_decoded d WHERE t.{quoted_column} = d.encoded;
+             DROP TABLE pensieve_decoded;"
+        ))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decodes_each_value_of_the_column() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE events (id INTEGER, payload VARCHAR);
+                            INSERT INTO events VALUES (1, 'abc'), (2, NULL), (3, 'abc'), (4, 'xyz');").unwrap();
+        let decoders = vec![
+            ("events".to_string(), "payload".to_string(), ColumnDecoder::new(|text| text.to_uppercase())),
+            ("missing".to_string(), "payload".to_string(), ColumnDecoder::new(|text| text.to_string())),
+        ];
+
+        decode_snapshot_columns(&conn, &decoders).unwrap();
+
+        let payloads: Vec<Option<String>> = conn.prepare("SELECT payload FROM events ORDER BY id").unwrap()
+            .query_map([], |row| row.get(0)).unwrap()
+            .map(|row| row.unwrap())
+            .collect();
+        assert_eq!(payloads, vec![Some("ABC".to_string()), None, Some("ABC".to_string()), Some("XYZ".to_string())]);
+    }
+}
@@ -0,0 +1,113 @@
+use std::collections::BTreeMap;
+use crate::binlog::{quote_identifier, BinlogOperation, OperationType, RowImage};
+
+/// Columns to load for wide tables, to shrink the snapshot database when scripts only need a few
+/// of them. Tables without a projection are loaded whole.
+///
+/// Binlog row events still carry every column; the parser matches them against the table's full
+/// column list and then drops the values of columns left out. Rows are identified by the loaded
+/// columns only, so a projection should keep the primary key.
+#[derive(Debug, Clone, Default)]
+pub struct ColumnProjection {
+    tables: BTreeMap<String, Vec<String>>,
+}
+
+impl ColumnProjection {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads only these columns of `table`, in this order
+    pub fn with_table(mut self, table: &str, columns: &[&str]) -> Self {
+        self.tables.insert(table.to_string(), columns.iter().map(|c| c.to_string()).collect());
+        self
+    }
+
+    /// Columns loaded for `table`, if it is projected
+    pub fn columns(&self, table: &str) -> Option<&Vec<String>> {
+        self.tables.get(table)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tables.is_empty()
+    }
+
+    /// SELECT list loading `table`: its projected columns, or `*`
+    pub fn select_list(&self, table: &str) -> String {
+        match self.columns(table) {
+            Some(columns) => columns.iter().map(|column| quote_identifier(column)).collect::<Vec<_>>().join(", "),
+            None => "*".to_string(),
+        }
+    }
+
+    /// Drops the values of columns the projection leaves out of the operation's table. They stay
+    /// listed in the row image's missing before values of UPDATEs and DELETEs, since the
+    /// operation no longer holds the whole row it changed.
+    pub fn project(&self, op: &mut BinlogOperation) {
+        let Some(keep) = self.columns(&op.table_name) else {
+            return;
+        };
+        let kept: Vec<bool> = op.columns.iter().map(|col| keep.contains(col)).collect();
+        let dropped: Vec<String> = op.columns.iter().filter(|col| !keep.contains(col)).cloned().collect();
+        let retain = |values: &mut Vec<String>| {
+            let mut kept = kept.iter();
+            values.retain(|_| *kept.next().unwrap_or(&false));
+        };
+        retain(&mut op.columns);
+        if let Some(values) = &mut op.before_values {
+            retain(values);
+        }
+        if let Some(values) = &mut op.after_values {
+            retain(values);
+        }
+        if let RowImage::Partial { missing_before } = &mut op.row_image {
+            missing_before.retain(|col| keep.contains(col));
+            // The image may be partial only in columns that aren't loaded
+            let complete = match op.operation_type {
+                OperationType::Insert => op.columns.len() == keep.len(),
+                _ => missing_before.is_empty(),
+            };
+            if complete {
+                op.row_image = RowImage::Full;
+            }
+        }
+        if op.operation_type != OperationType::Insert && !dropped.is_empty() {
+            let mut missing_before = op.row_image.missing_before().to_vec();
+            missing_before.extend(dropped);
+            op.row_image = RowImage::Partial { missing_before };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binlog::TransactionInfo;
+
+    #[test]
+    fn test_project_drops_unloaded_columns() {
+        let projection = ColumnProjection::new().with_table("books", &["id", "price"]);
+        assert_eq!(projection.select_list("books"), r#""id", "price""#);
+        assert_eq!(projection.select_list("authors"), "*");
+
+        let mut op = BinlogOperation {
+            timestamp: None,
+            position: None,
+            operation_type: OperationType::Update,
+            table_name: "books".to_string(),
+            database: "main".to_string(),
+            columns: vec!["id".to_string(), "blurb".to_string(), "price".to_string()],
+            before_values: Some(vec!["1".to_string(), "'long'".to_string(), "10".to_string()]),
+            after_values: Some(vec!["1".to_string(), "'longer'".to_string(), "12".to_string()]),
+            row_image: RowImage::Partial { missing_before: vec!["blurb".to_string()] },
+            transaction: TransactionInfo::default(),
+        };
+        projection.project(&mut op);
+
+        assert_eq!(op.columns, vec!["id", "price"]);
+        assert_eq!(op.before_values, Some(vec!["1".to_string(), "10".to_string()]));
+        assert_eq!(op.after_values, Some(vec!["1".to_string(), "12".to_string()]));
+        assert_eq!(op.row_image, RowImage::Partial { missing_before: vec!["blurb".to_string()] });
+        assert!(op.row_image.is_complete_for(&op.columns));
+    }
+}
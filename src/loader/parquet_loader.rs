@@ -1,4 +1,15 @@
-use duckdb::{Connection, Result};
+use duckdb::{Connection, OptionalExt, Result};
+use std::path::Path;
+use tracing::{debug, info};
+use crate::binlog::quote_identifier;
+use crate::loader::column_projection::ColumnProjection;
+use crate::loader::redaction::{Redaction, Redactions};
+use crate::loader::remote_files;
+use crate::loader::resource_limits::ResourceLimits;
+use crate::parser::operation_cache;
+
+/// Schema holding the untouched parquet snapshot of each table in a file-backed database
+const SNAPSHOT_SCHEMA: &str = "pensieve_snapshot";
 
 #[derive(Debug, Clone)]
 pub enum ParquetLoadError {
@@ -17,21 +28,338 @@ impl std::fmt::Display for ParquetLoadError {
 
 impl std::error::Error for ParquetLoadError {}
 
+/// File format of a table's snapshot files, chosen by file extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotFormat {
+    Parquet,
+    /// CSV with a header row, read with DuckDB's read_csv (delimiter and types are detected)
+    Csv,
+    /// One JSON object per line, read with DuckDB's read_json
+    Jsonl,
+}
+
+impl SnapshotFormat {
+    /// Formats in order of preference, for table directories holding files of several formats
+    pub const ALL: [SnapshotFormat; 3] = [SnapshotFormat::Parquet, SnapshotFormat::Csv, SnapshotFormat::Jsonl];
+
+    pub fn extensions(&self) -> &'static [&'static str] {
+        match self {
+            SnapshotFormat::Parquet => &["parquet"],
+            SnapshotFormat::Csv => &["csv"],
+            SnapshotFormat::Jsonl => &["jsonl", "ndjson"],
+        }
+    }
+
+    pub fn from_path(path: &str) -> Option<Self> {
+        let extension = path.rsplit_once('.')?.1.to_ascii_lowercase();
+        Self::ALL.into_iter().find(|format| format.extensions().contains(&extension.as_str()))
+    }
+
+    /// DuckDB table function reading the files. Files in hive partition directories
+    /// (`dt=2025-11-08/part-0.parquet`) get a column per partition key.
+    fn reader(&self, file_paths: &[&str]) -> String {
+        let (function, mut options) = match self {
+            SnapshotFormat::Parquet => ("read_parquet", String::new()),
+            SnapshotFormat::Csv => ("read_csv", ", header = true".to_string()),
+            SnapshotFormat::Jsonl => ("read_json", ", format = 'newline_delimited'".to_string()),
+        };
+        if is_hive_partitioned(file_paths) {
+            options.push_str(", hive_partitioning = true");
+        }
+        format!("{function}([{}]{options})", files_list(file_paths))
+    }
+}
+
+/// The snapshot files of a table to load: those of the most preferred format among `files`.
+/// Files of other formats (or that aren't snapshot files) are left out, sorted by name.
+pub fn select_snapshot_files(files: &[String]) -> Option<(SnapshotFormat, Vec<String>)> {
+    SnapshotFormat::ALL.into_iter().find_map(|format| {
+        let mut selected: Vec<String> = files.iter()
+            .filter(|file| SnapshotFormat::from_path(file) == Some(format))
+            .cloned()
+            .collect();
+        selected.sort();
+        (!selected.is_empty()).then_some((format, selected))
+    })
+}
+
 pub fn load_table_from_parquet_files(table_name: &str, parquet_file_paths: &[&str]) -> Result<Connection, ParquetLoadError> {
+    load_table_from_files(table_name, parquet_file_paths, SnapshotFormat::Parquet)
+}
+
+/// Loads a table from CSV files with a header row
+pub fn load_table_from_csv_files(table_name: &str, csv_file_paths: &[&str]) -> Result<Connection, ParquetLoadError> {
+    load_table_from_files(table_name, csv_file_paths, SnapshotFormat::Csv)
+}
+
+/// Loads a table from newline-delimited JSON files
+pub fn load_table_from_jsonl_files(table_name: &str, jsonl_file_paths: &[&str]) -> Result<Connection, ParquetLoadError> {
+    load_table_from_files(table_name, jsonl_file_paths, SnapshotFormat::Jsonl)
+}
+
+fn load_table_from_files(table_name: &str, file_paths: &[&str], format: SnapshotFormat) -> Result<Connection, ParquetLoadError> {
+    let conn = Connection::open_in_memory()
+        .map_err(|e| ParquetLoadError::ConnectionError(e.to_string()))?;
+    create_table_from_files(&conn, &quote_identifier(table_name), &format.reader(file_paths), "*")?;
+    Ok(conn)
+}
+
+/// Loads several tables into one in-memory connection, so they can be joined.
+/// Each table is read according to the extension of its files (parquet, CSV or JSONL).
+/// Files can be object store URIs (e.g. `s3://...`), which DuckDB reads directly.
+/// Projected tables only get their projected columns, and redacted columns are redacted as
+/// they are read. The limits are set before loading.
+pub fn load_tables_from_parquet_files(
+    tables: &[(String, Vec<String>)],
+    projection: &ColumnProjection,
+    redactions: &Redactions,
+    limits: &ResourceLimits,
+) -> Result<Connection, ParquetLoadError> {
     let conn = Connection::open_in_memory()
         .map_err(|e| ParquetLoadError::ConnectionError(e.to_string()))?;
-    let files_list = parquet_file_paths
+    limits.apply(&conn).map_err(|e| ParquetLoadError::ConnectionError(e.to_string()))?;
+    prepare_remote_access(&conn, tables)?;
+    for (table_name, parquet_file_paths) in tables {
+        let paths: Vec<&str> = parquet_file_paths.iter().map(|s| s.as_str()).collect();
+        let source = redacted_source(&conn, table_name, &paths, redactions)?;
+        create_table_from_files(&conn, &quote_identifier(table_name), &source, &projection.select_list(table_name))?;
+    }
+    Ok(conn)
+}
+
+/// Loads several tables into a DuckDB database file (created if missing), for snapshots that
+/// don't fit in memory.
+///
+/// The parquet data of each table is kept in the `pensieve_snapshot` schema, and the table itself is
+/// recreated from that copy, because navigation modifies the table. Each copy is recorded in the
+/// `pensieve_snapshot.manifest` table with the size and hash of every file it was read from and its
+/// row count. With `reuse_existing`, a table whose files are unchanged and whose copy still has
+/// the recorded row count is restored from the copy instead of being read from parquet again.
+/// The copy holds every column; a projection only applies to the table recreated from it.
+/// Redaction applies to the copy, which is loaded again when the table's redactions change.
+pub fn load_tables_into_database_file(
+    database_path: &Path,
+    tables: &[(String, Vec<String>)],
+    reuse_existing: bool,
+    projection: &ColumnProjection,
+    redactions: &Redactions,
+    limits: &ResourceLimits,
+) -> Result<Connection, ParquetLoadError> {
+    let conn = Connection::open(database_path)
+        .map_err(|e| ParquetLoadError::ConnectionError(e.to_string()))?;
+    limits.apply(&conn).map_err(|e| ParquetLoadError::ConnectionError(e.to_string()))?;
+    prepare_remote_access(&conn, tables)?;
+
+    let execute = |sql: &str| conn.execute_batch(sql).map_err(|e| ParquetLoadError::ExecutionError(e.to_string()));
+    // loaded_files only recorded file names; databases that have it are loaded again once
+    execute(&format!(
+        "CREATE SCHEMA IF NOT EXISTS {SNAPSHOT_SCHEMA};
+         DROP TABLE IF EXISTS {SNAPSHOT_SCHEMA}.loaded_files;
+         CREATE TABLE IF NOT EXISTS {SNAPSHOT_SCHEMA}.manifest
+             (table_name VARCHAR PRIMARY KEY, files VARCHAR, file_hashes VARCHAR, row_count BIGINT);"
+    ))?;
+
+    for (table_name, parquet_file_paths) in tables {
+        let files = parquet_file_paths.join("\n");
+        let mut file_hashes = file_hashes(&conn, parquet_file_paths)?;
+        for (_, column, redaction) in redactions.columns().filter(|(table, _, _)| table == table_name) {
+            file_hashes.push_str(&format!("\nredacted {}: {:?}", column, redaction));
+        }
+        // Hashes made with another salt don't match the binlog's
+        if redactions.columns().any(|(table, _, redaction)| table == table_name && *redaction == Redaction::Hash) {
+            file_hashes.push_str(&format!("\nsalt {}", redactions.salt_digest()));
+        }
+
+        if reuse_existing && copy_matches_manifest(&conn, table_name, &files, &file_hashes)? {
+            info!("Reusing {} from {:?}", table_name, database_path);
+        } else {
+            let paths: Vec<&str> = parquet_file_paths.iter().map(|s| s.as_str()).collect();
+            execute(&format!("DROP TABLE IF EXISTS {SNAPSHOT_SCHEMA}.{};", quote_identifier(table_name)))?;
+            let source = redacted_source(&conn, table_name, &paths, redactions)?;
+            create_table_from_files(&conn, &format!("{SNAPSHOT_SCHEMA}.{}", quote_identifier(table_name)), &source, "*")?;
+            let row_count = copy_row_count(&conn, table_name)?;
+            conn.execute(
+                &format!("INSERT OR REPLACE INTO {SNAPSHOT_SCHEMA}.manifest VALUES (?, ?, ?, ?)"),
+                duckdb::params![table_name, files, file_hashes, row_count],
+            ).map_err(|e| ParquetLoadError::ExecutionError(e.to_string()))?;
+        }
+
+        let quoted = quote_identifier(table_name);
+        execute(&format!(
+            "CREATE OR REPLACE TABLE main.{quoted} AS SELECT {} FROM {SNAPSHOT_SCHEMA}.{quoted};",
+            projection.select_list(table_name)
+        ))?;
+    }
+
+    Ok(conn)
+}
+
+/// `size:hash` of every file, one per line. Remote files aren't downloaded to be hashed, so they
+/// are identified by `size:last_modified` from the object store instead.
+fn file_hashes(conn: &Connection, file_paths: &[String]) -> Result<String, ParquetLoadError> {
+    let hashes = file_paths.iter()
+        .map(|path| {
+            if remote_files::is_remote(path) {
+                let (size, last_modified) = remote_files::object_version(conn, path)
+                    .map_err(|e| ParquetLoadError::ExecutionError(format!("Failed to read the metadata of {}: {}", path, e)))?;
+                return Ok(format!("{}:{}", size, last_modified.unwrap_or_default()));
+            }
+            let (size, hash) = operation_cache::hash_file(path)
+                .map_err(|e| ParquetLoadError::ExecutionError(format!("Failed to hash {}: {}", path, e)))?;
+            Ok(format!("{}:{:016x}", size, hash))
+        })
+        .collect::<Result<Vec<_>, ParquetLoadError>>()?;
+    Ok(hashes.join("\n"))
+}
+
+/// Whether the manifest records the table as loaded from these files, and its copy in the
+/// database still has the rows it was loaded with
+fn copy_matches_manifest(conn: &Connection, table_name: &str, files: &str, file_hashes: &str) -> Result<bool, ParquetLoadError> {
+    let recorded: Option<(String, String, i64)> = conn.query_row(
+        &format!("SELECT files, file_hashes, row_count FROM {SNAPSHOT_SCHEMA}.manifest WHERE table_name = ?"),
+        [table_name],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    ).optional().map_err(|e| ParquetLoadError::ExecutionError(e.to_string()))?;
+
+    let Some((recorded_files, recorded_hashes, recorded_rows)) = recorded else {
+        return Ok(false);
+    };
+    if recorded_files != files || recorded_hashes != file_hashes {
+        info!("Snapshot files of {} changed since they were loaded", table_name);
+        return Ok(false);
+    }
+    // Missing if the database was changed outside Pensieve
+    let row_count = copy_row_count(conn, table_name).ok();
+    if row_count != Some(recorded_rows) {
+        info!("Loaded copy of {} has {:?} rows, not the {} it was loaded with", table_name, row_count, recorded_rows);
+        return Ok(false);
+    }
+    Ok(true)
+}
+
+fn copy_row_count(conn: &Connection, table_name: &str) -> Result<i64, ParquetLoadError> {
+    conn.query_row(&format!("SELECT COUNT(*) FROM {SNAPSHOT_SCHEMA}.{}", quote_identifier(table_name)), [], |row| row.get(0))
+        .map_err(|e| ParquetLoadError::ExecutionError(e.to_string()))
+}
+
+fn prepare_remote_access(conn: &Connection, tables: &[(String, Vec<String>)]) -> Result<(), ParquetLoadError> {
+    if tables.iter().flat_map(|(_, files)| files).any(|file| remote_files::is_remote(file)) {
+        remote_files::prepare_connection(conn)
+            .map_err(|e| ParquetLoadError::ConnectionError(e.to_string()))?;
+    }
+    Ok(())
+}
+
+/// Format of a table's files, which must all have the same one
+fn format_of(file_paths: &[&str]) -> Result<SnapshotFormat, ParquetLoadError> {
+    let format = file_paths.first().and_then(|path| SnapshotFormat::from_path(path)).unwrap_or(SnapshotFormat::Parquet);
+    if let Some(other) = file_paths.iter().find(|path| SnapshotFormat::from_path(path).unwrap_or(SnapshotFormat::Parquet) != format) {
+        return Err(ParquetLoadError::ExecutionError(format!("{} is not a {:?} file like {}", other, format, file_paths[0])));
+    }
+    Ok(format)
+}
+
+/// Every column of a table's snapshot files, in file order, whatever was loaded of them
+pub fn snapshot_columns(conn: &Connection, file_paths: &[String]) -> Result<Vec<String>, ParquetLoadError> {
+    let paths: Vec<&str> = file_paths.iter().map(|s| s.as_str()).collect();
+    let sql = format!("DESCRIBE SELECT * FROM {};", format_of(&paths)?.reader(&paths));
+    let mut stmt = conn.prepare(&sql).map_err(|e| ParquetLoadError::ExecutionError(e.to_string()))?;
+    stmt.query_map([], |row| row.get::<usize, String>(0))
+        .and_then(|rows| rows.collect())
+        .map_err(|e| ParquetLoadError::ExecutionError(e.to_string()))
+}
+
+/// Adds the rows of a table's snapshot files (parquet, CSV or JSONL) to an existing table, matching
+/// `columns` by name and casting them to the table's types. The files are redacted like those of
+/// `redacted_table`.
+pub fn insert_from_snapshot_files(
+    conn: &Connection,
+    table_name: &str,
+    columns: &[String],
+    file_paths: &[String],
+    redacted_table: &str,
+    redactions: &Redactions,
+) -> Result<usize, ParquetLoadError> {
+    let paths: Vec<&str> = file_paths.iter().map(|s| s.as_str()).collect();
+    let columns = columns.iter().map(|column| quote_identifier(column)).collect::<Vec<_>>().join(", ");
+    let sql = format!(
+        "INSERT INTO {} ({columns}) SELECT {columns} FROM {};",
+        quote_identifier(table_name),
+        redacted_source(conn, redacted_table, &paths, redactions)?
+    );
+    debug!("{sql}");
+    conn.execute(&sql, []).map_err(|e| ParquetLoadError::ExecutionError(e.to_string()))
+}
+
+/// Replaces the rows of a loaded table with those of its snapshot files, keeping the table itself
+/// (types, constraints). `projection` and `redactions` must be those the table was loaded with.
+pub fn reload_table_from_files(
+    conn: &Connection,
+    table_name: &str,
+    file_paths: &[String],
+    projection: &ColumnProjection,
+    redactions: &Redactions,
+) -> Result<(), ParquetLoadError> {
+    let paths: Vec<&str> = file_paths.iter().map(|s| s.as_str()).collect();
+    let quoted = quote_identifier(table_name);
+    let sql = format!(
+        "DELETE FROM {quoted}; INSERT INTO {quoted} SELECT {} FROM {};",
+        projection.select_list(table_name),
+        redacted_source(conn, table_name, &paths, redactions)?
+    );
+    debug!("{sql}");
+    conn.execute_batch(&sql).map_err(|e| ParquetLoadError::ExecutionError(e.to_string()))
+}
+
+/// Number of rows in a table's snapshot files. Parquet files answer from their metadata.
+pub fn snapshot_row_count(conn: &Connection, file_paths: &[String]) -> Result<usize, ParquetLoadError> {
+    let paths: Vec<&str> = file_paths.iter().map(|s| s.as_str()).collect();
+    let sql = format!("SELECT COUNT(*) FROM {};", format_of(&paths)?.reader(&paths));
+    conn.query_row(&sql, [], |row| row.get::<usize, i64>(0))
+        .map(|count| count as usize)
+        .map_err(|e| ParquetLoadError::ExecutionError(e.to_string()))
+}
+
+/// Whether the files are in `key=value` directories
+fn is_hive_partitioned(file_paths: &[&str]) -> bool {
+    file_paths.iter().any(|path| {
+        Path::new(path).parent()
+            .and_then(|directory| directory.file_name())
+            .is_some_and(|name| name.to_string_lossy().split_once('=').is_some_and(|(key, _)| !key.is_empty()))
+    })
+}
+
+fn files_list(file_paths: &[&str]) -> String {
+    file_paths
         .iter()
         .map(|path| format!("'{}'", path))
         .collect::<Vec<_>>()
-        .join(", ");
-    let sql = format!("CREATE TABLE {table_name} AS SELECT * FROM read_parquet([{files_list}]);");
-    println!("{sql}");
+        .join(", ")
+}
+
+/// What a table's files are read from: their reader, or a subquery redacting its columns
+fn redacted_source(conn: &Connection, table_name: &str, file_paths: &[&str], redactions: &Redactions) -> Result<String, ParquetLoadError> {
+    let reader = format_of(file_paths)?.reader(file_paths);
+    if !redactions.has_table(table_name) {
+        return Ok(reader);
+    }
+    let file_paths: Vec<String> = file_paths.iter().map(|path| path.to_string()).collect();
+    let columns = snapshot_columns(conn, &file_paths)?;
+    Ok(format!("(SELECT {} FROM {reader})", redactions.select_list(table_name, &columns)))
+}
+
+fn create_table_from_files(
+    conn: &Connection,
+    table_name: &str,
+    source: &str,
+    select_list: &str,
+) -> Result<(), ParquetLoadError> {
+    let sql = format!("CREATE TABLE {table_name} AS SELECT {select_list} FROM {source};");
+    debug!("{sql}");
     conn.execute(&sql, [])
         .map_err(|e| ParquetLoadError::ExecutionError(e.to_string()))?;
 
-    Ok(conn)
-
+    Ok(())
 }
 
 /// Creates an in-memory DuckDB connection and executes a series of SQL statements.
@@ -42,7 +370,7 @@ pub fn load_table_from_sql(sql_statements: Vec<&str>) -> Result<Connection, Parq
         .map_err(|e| ParquetLoadError::ConnectionError(e.to_string()))?;
     
     for (i, stmt) in sql_statements.iter().enumerate() {
-        println!("Executing SQL statement {}: {}", i + 1, stmt);
+        debug!("Executing SQL statement {}: {}", i + 1, stmt);
         conn.execute(stmt, [])
             .map_err(|e| ParquetLoadError::ExecutionError(format!("Failed on statement {}: {} - Error: {}", i + 1, stmt, e)))?;
     }
@@ -52,7 +380,11 @@ pub fn load_table_from_sql(sql_statements: Vec<&str>) -> Result<Connection, Parq
 
 #[cfg(test)]
 mod tests {
-    use crate::loader::parquet_loader::{load_table_from_parquet_files, load_table_from_sql};
+    use crate::loader::column_projection::ColumnProjection;
+    use crate::loader::redaction::Redactions;
+    use crate::loader::resource_limits::ResourceLimits;
+    use crate::loader::remote_files;
+    use crate::loader::parquet_loader::{file_hashes, load_table_from_csv_files, load_table_from_jsonl_files, load_table_from_parquet_files, load_table_from_sql, load_tables_into_database_file, select_snapshot_files, snapshot_columns, SnapshotFormat};
 
     #[test]
     fn loads_sample_table() {
@@ -71,6 +403,101 @@ mod tests {
         assert_eq!(row0.get(0), Ok(1));
     }
 
+    #[test]
+    fn loads_csv_and_jsonl_tables() {
+        let dir = std::env::temp_dir().join(format!("pensieve_text_snapshot_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let csv = dir.join("books.csv");
+        let jsonl = dir.join("books.jsonl");
+        std::fs::write(&csv, "id,title,price\n1,Dune,9.5\n2,\"Emma, Vol. 1\",12\n").unwrap();
+        std::fs::write(&jsonl, "{\"id\": 1, \"title\": \"Dune\", \"price\": 9.5}\n{\"id\": 2, \"title\": \"Emma, Vol. 1\", \"price\": 12}\n").unwrap();
+
+        for conn in [
+            load_table_from_csv_files("books", &[csv.to_str().unwrap()]).unwrap(),
+            load_table_from_jsonl_files("books", &[jsonl.to_str().unwrap()]).unwrap(),
+        ] {
+            let (title, price): (String, f64) = conn.query_row("SELECT title, price FROM books WHERE id = 2", [], |row| Ok((row.get(0)?, row.get(1)?))).unwrap();
+            assert_eq!((title.as_str(), price), ("Emma, Vol. 1", 12.0));
+        }
+
+        let files = vec![jsonl.to_string_lossy().to_string(), csv.to_string_lossy().to_string(), "notes.txt".to_string()];
+        assert_eq!(select_snapshot_files(&files), Some((SnapshotFormat::Csv, vec![csv.to_string_lossy().to_string()])));
+        assert_eq!(select_snapshot_files(&files[2..]), None);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn reuses_snapshot_in_database_file() {
+        let database_path = std::env::temp_dir().join(format!("pensieve_loader_{}.duckdb", std::process::id()));
+        std::fs::remove_file(&database_path).ok();
+        let tables = vec![("test_table".to_string(), vec!["./test_data/test_table_1.parquet".to_string()])];
+
+        let conn = load_tables_into_database_file(&database_path, &tables, true, &ColumnProjection::new(), &Redactions::new(), &ResourceLimits::new()).unwrap();
+        // Navigation changes the table; the next load must start from the snapshot again
+        conn.execute_batch("DELETE FROM test_table;").unwrap();
+        drop(conn);
+
+        let projection = ColumnProjection::new().with_table("test_table", &["title", "year"]);
+        let conn = load_tables_into_database_file(&database_path, &tables, true, &projection, &Redactions::new(), &ResourceLimits::new()).unwrap();
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM test_table", [], |row| row.get(0)).unwrap();
+        assert_eq!(count, 3);
+        let loaded: (i64, i64) = conn.query_row("SELECT COUNT(*), SUM(row_count) FROM pensieve_snapshot.manifest", [], |row| Ok((row.get(0)?, row.get(1)?))).unwrap();
+        assert_eq!(loaded, (1, 3));
+        let columns: i64 = conn.query_row("SELECT COUNT(*) FROM duckdb_columns() WHERE table_name = 'test_table' AND schema_name = 'main'", [], |row| row.get(0)).unwrap();
+        assert_eq!(columns, 2);
+        assert_eq!(snapshot_columns(&conn, &tables[0].1).unwrap(), vec!["title", "developer", "year"]);
+
+        drop(conn);
+        std::fs::remove_file(&database_path).ok();
+        std::fs::remove_file(database_path.with_extension("duckdb.wal")).ok();
+    }
+
+    #[test]
+    fn reloads_snapshot_when_files_or_copy_change() {
+        let dir = std::env::temp_dir().join(format!("pensieve_manifest_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let database_path = dir.join("snapshot.duckdb");
+        let file = dir.join("books.parquet").to_string_lossy().to_string();
+        let write_snapshot = |rows: usize| duckdb::Connection::open_in_memory().unwrap()
+            .execute_batch(&format!("COPY (SELECT range AS id FROM range({rows})) TO '{file}' (FORMAT PARQUET);"))
+            .unwrap();
+        let tables = vec![("books".to_string(), vec![file.clone()])];
+        let load = || {
+            let conn = load_tables_into_database_file(&database_path, &tables, true, &ColumnProjection::new(), &Redactions::new(), &ResourceLimits::new()).unwrap();
+            let count: i64 = conn.query_row("SELECT COUNT(*) FROM books", [], |row| row.get(0)).unwrap();
+            (conn, count)
+        };
+
+        write_snapshot(3);
+        let (conn, _) = load();
+        conn.execute_batch("DELETE FROM pensieve_snapshot.books WHERE id = 0;").unwrap();
+        drop(conn);
+        assert_eq!(load().1, 3);
+
+        write_snapshot(5);
+        assert_eq!(load().1, 5);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn fingerprints_remote_files_by_object_metadata() {
+        let conn = duckdb::Connection::open_in_memory().unwrap();
+        // Without httpfs the object's metadata can't be read, which must not pass for an unchanged file
+        conn.execute_batch("SET autoinstall_known_extensions = false; SET autoload_known_extensions = false;").unwrap();
+        assert!(file_hashes(&conn, &["s3://bucket/db_data/books/part-01.parquet".to_string()]).is_err());
+
+        let file = std::env::temp_dir().join(format!("pensieve_object_version_{}.parquet", std::process::id()));
+        let file = file.to_string_lossy().to_string();
+        std::fs::write(&file, "abc").unwrap();
+        let first = remote_files::object_version(&conn, &file).unwrap();
+        assert_eq!(first.0, 3);
+        assert!(first.1.is_some());
+        std::fs::write(&file, "abcdef").unwrap();
+        assert_ne!(remote_files::object_version(&conn, &file).unwrap(), first);
+        std::fs::remove_file(&file).ok();
+    }
+
     #[test]
     fn test_load_table_from_sql_creates_and_populates_table() {
         let sql_statements = vec![
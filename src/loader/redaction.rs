@@ -0,0 +1,170 @@
+use std::collections::BTreeMap;
+use sha2::{Digest, Sha256};
+use crate::binlog::{quote_identifier, BinlogOperation};
+
+/// How a redacted column's values are replaced
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Redaction {
+    /// Lowercase hex SHA-256 of the salt followed by the value, so equal values stay equal
+    Hash,
+    /// NULL
+    Null,
+    /// The value's first characters
+    Truncate(usize),
+}
+
+/// Columns whose values never reach Pensieve as they are, e.g. PII. Snapshot files are redacted
+/// as they are loaded and binlog values as they are parsed, so the database, operations, caches
+/// and everything exported from them only hold redacted values. Redacted columns are VARCHAR.
+///
+/// Values are redacted in their text form, which matches between snapshot and binlog for text,
+/// numbers and timestamps, but not for binary values.
+#[derive(Debug, Clone, Default)]
+pub struct Redactions {
+    columns: BTreeMap<(String, String), Redaction>,
+    salt: String,
+}
+
+impl Redactions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_column(mut self, table: &str, column: &str, redaction: Redaction) -> Self {
+        self.columns.insert((table.to_string(), column.to_string()), redaction);
+        self
+    }
+
+    /// Prefixed to values before hashing, so hashes can't be looked up in precomputed tables
+    pub fn with_salt(mut self, salt: &str) -> Self {
+        self.salt = salt.to_string();
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.columns.is_empty()
+    }
+
+    /// Lowercase hex SHA-256 of the salt, to tell whether values were hashed with the same salt
+    /// without recording it
+    pub fn salt_digest(&self) -> String {
+        format!("{:x}", Sha256::digest(&self.salt))
+    }
+
+    pub fn redaction(&self, table: &str, column: &str) -> Option<&Redaction> {
+        self.columns.get(&(table.to_string(), column.to_string()))
+    }
+
+    /// Redacted (table, column) pairs, in name order
+    pub fn columns(&self) -> impl Iterator<Item = (&str, &str, &Redaction)> {
+        self.columns.iter().map(|((table, column), redaction)| (table.as_str(), column.as_str(), redaction))
+    }
+
+    pub fn has_table(&self, table: &str) -> bool {
+        self.columns.keys().any(|(redacted, _)| redacted == table)
+    }
+
+    /// SELECT list of every column of `table`, with the redacted ones among `columns` replaced
+    pub fn select_list(&self, table: &str, columns: &[String]) -> String {
+        let replaced: Vec<String> = columns.iter()
+            .filter_map(|column| {
+                let quoted = quote_identifier(column);
+                let expression = match self.redaction(table, column)? {
+                    Redaction::Hash => format!("sha256({} || CAST({quoted} AS VARCHAR))", quote(&self.salt)),
+                    Redaction::Null => "CAST(NULL AS VARCHAR)".to_string(),
+                    Redaction::Truncate(length) => format!("left(CAST({quoted} AS VARCHAR), {length})"),
+                };
+                Some(format!("{expression} AS {quoted}"))
+            })
+            .collect();
+        if replaced.is_empty() {
+            return "*".to_string();
+        }
+        format!("* REPLACE ({})", replaced.join(", "))
+    }
+
+    /// Redacts the operation's values of redacted columns
+    pub fn redact(&self, op: &mut BinlogOperation) {
+        if !self.has_table(&op.table_name) {
+            return;
+        }
+        for (idx, column) in op.columns.iter().enumerate() {
+            let Some(redaction) = self.redaction(&op.table_name, column) else {
+                continue;
+            };
+            for values in [&mut op.before_values, &mut op.after_values].into_iter().flatten() {
+                if let Some(value) = values.get_mut(idx) {
+                    *value = self.redact_literal(redaction, value);
+                }
+            }
+        }
+    }
+
+    /// A SQL literal redacted like the snapshot's values are, NULL staying NULL
+    fn redact_literal(&self, redaction: &Redaction, literal: &str) -> String {
+        if literal == "NULL" {
+            return literal.to_string();
+        }
+        let text = match literal.strip_prefix('\'').and_then(|rest| rest.strip_suffix('\'')) {
+            Some(quoted) => quoted.replace("''", "'"),
+            None => literal.to_string(),
+        };
+        match redaction {
+            Redaction::Hash => quote(&format!("{:x}", Sha256::digest(format!("{}{}", self.salt, text)))),
+            Redaction::Null => "NULL".to_string(),
+            Redaction::Truncate(length) => quote(&text.chars().take(*length).collect::<String>()),
+        }
+    }
+}
+
+fn quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use duckdb::Connection;
+    use crate::binlog::{OperationType, RowImage, TransactionInfo};
+    use super::*;
+
+    #[test]
+    fn test_binlog_values_are_redacted_like_the_snapshot() {
+        let redactions = Redactions::new()
+            .with_salt("pepper")
+            .with_column("users", "email", Redaction::Hash)
+            .with_column("users", "phone", Redaction::Null)
+            .with_column("users", "name", Redaction::Truncate(2))
+            .with_column("users", "zip", Redaction::Hash);
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE source (id INTEGER, email VARCHAR, phone VARCHAR, name VARCHAR, zip INTEGER);
+                            INSERT INTO source VALUES (1, 'o''brien@example.com', '555', 'Ödön', 90210), (2, NULL, NULL, NULL, NULL);").unwrap();
+        let columns: Vec<String> = ["id", "email", "phone", "name", "zip"].iter().map(|c| c.to_string()).collect();
+        let snapshot: Vec<Vec<Option<String>>> = conn
+            .prepare(&format!("SELECT {} FROM source ORDER BY id", redactions.select_list("users", &columns))).unwrap()
+            .query_map([], |row| (1..5).map(|idx| row.get(idx)).collect()).unwrap()
+            .map(|row| row.unwrap())
+            .collect();
+
+        let mut op = BinlogOperation {
+            timestamp: None,
+            position: None,
+            operation_type: OperationType::Update,
+            table_name: "users".to_string(),
+            database: "main".to_string(),
+            columns,
+            before_values: Some(vec!["1".into(), "'o''brien@example.com'".into(), "'555'".into(), "'Ödön'".into(), "90210".into()]),
+            after_values: Some(vec!["2".into(), "NULL".into(), "NULL".into(), "NULL".into(), "NULL".into()]),
+            row_image: RowImage::Full,
+            transaction: TransactionInfo::default(),
+        };
+        redactions.redact(&mut op);
+
+        let as_literal = |value: &Option<String>| value.as_deref().map(quote).unwrap_or_else(|| "NULL".to_string());
+        for (values, row) in [op.before_values.unwrap(), op.after_values.unwrap()].iter().zip(&snapshot) {
+            assert_eq!(values[1..], row.iter().map(as_literal).collect::<Vec<_>>()[..]);
+        }
+        assert_eq!(snapshot[0][0].as_ref().map(String::len), Some(64));
+        assert_eq!(snapshot[0][1..3], [None, Some("Öd".to_string())]);
+        assert_eq!(redactions.select_list("books", &["id".to_string()]), "*");
+    }
+}
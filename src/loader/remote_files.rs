@@ -0,0 +1,210 @@
+use duckdb::Connection;
+use duckdb::types::ValueRef;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use tracing::info;
+use crate::loader::parquet_loader::{self, SnapshotFormat};
+use crate::parser::binlog_reader;
+
+/// URI schemes DuckDB's httpfs extension can read from
+const REMOTE_SCHEMES: [&str; 5] = ["s3://", "s3a://", "gcs://", "gs://", "r2://"];
+
+/// True if the path is an object store URI (e.g. `s3://bucket/db_data`) rather than a local path
+pub fn is_remote(path: &str) -> bool {
+    REMOTE_SCHEMES.iter().any(|scheme| path.starts_with(scheme))
+}
+
+/// Loads DuckDB's httpfs extension and configures S3 access from the usual AWS environment
+/// variables (AWS_REGION, AWS_ACCESS_KEY_ID, AWS_SECRET_ACCESS_KEY, AWS_SESSION_TOKEN, and
+/// AWS_ENDPOINT_URL for S3-compatible stores). Unset variables are left at DuckDB's defaults.
+pub fn prepare_connection(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    conn.execute_batch("INSTALL httpfs; LOAD httpfs;")?;
+
+    let settings = [
+        ("s3_region", "AWS_REGION"),
+        ("s3_access_key_id", "AWS_ACCESS_KEY_ID"),
+        ("s3_secret_access_key", "AWS_SECRET_ACCESS_KEY"),
+        ("s3_session_token", "AWS_SESSION_TOKEN"),
+        ("s3_endpoint", "AWS_ENDPOINT_URL"),
+    ];
+    for (setting, variable) in settings {
+        if let Ok(value) = std::env::var(variable) {
+            // DuckDB expects the endpoint without a scheme
+            let value = value.trim_start_matches("https://").trim_start_matches("http://").to_string();
+            conn.execute_batch(&format!("SET {} = '{}';", setting, value.replace('\'', "''")))?;
+        }
+    }
+    Ok(())
+}
+
+/// Tables, parquet files and binlog files found under a remote db_data root
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RemoteDiscovery {
+    /// (table, snapshot file URIs) in table name order. DuckDB reads these directly from the store.
+    pub table_parquet_files: Vec<(String, Vec<String>)>,
+    /// (table, binlog URIs) in table name order
+    pub table_binlog_files: Vec<(String, Vec<String>)>,
+}
+
+/// Lists `<root>/<table>/*.parquet` (or `.csv`/`.jsonl`, see SnapshotFormat) and `<root>/<table>/*.sql`
+/// (or `.sql.gz`/`.sql.zst`), following the same layout as a local db_data directory
+pub fn discover(conn: &Connection, root: &str) -> Result<RemoteDiscovery, Box<dyn std::error::Error>> {
+    let root = root.trim_end_matches('/');
+    let mut snapshot_files = Vec::new();
+    for format in SnapshotFormat::ALL {
+        for extension in format.extensions() {
+            snapshot_files.extend(glob(conn, &format!("{}/*/*.{}", root, extension))?);
+            // Partitioned exports, e.g. <table>/dt=2025-11-08/part-0.parquet
+            snapshot_files.extend(glob(conn, &format!("{}/*/*/**/*.{}", root, extension))?);
+        }
+    }
+    snapshot_files.sort();
+    snapshot_files.dedup();
+    let binlog_files: Vec<String> = glob(conn, &format!("{}/*/*.sql*", root))?
+        .into_iter()
+        .filter(|file| binlog_reader::is_binlog_file(Path::new(file)))
+        .collect();
+
+    let discovery = RemoteDiscovery {
+        table_parquet_files: group_by_table(root, &snapshot_files).into_iter()
+            .filter_map(|(table, files)| Some((table, parquet_loader::select_snapshot_files(&files)?.1)))
+            .collect(),
+        table_binlog_files: group_by_table(root, &binlog_files),
+    };
+    if discovery.table_parquet_files.is_empty() {
+        return Err(format!("No parquet, CSV or JSONL files found under {}", root).into());
+    }
+    Ok(discovery)
+}
+
+/// Downloads a remote file into `cache_dir`, keeping its file name, under a directory named after
+/// the URI and the object's size and last modified time. Files already in the cache for the same
+/// object version are not downloaded again.
+pub fn download(conn: &Connection, uri: &str, cache_dir: &Path) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let file_name = uri.rsplit('/').next().unwrap_or(uri);
+    let (remote_size, last_modified) = object_version(conn, uri)?;
+    let version = Sha256::digest(format!("{}\n{}\n{}", uri, remote_size, last_modified.unwrap_or_default()));
+    let version_dir = cache_dir.join(format!("{:x}", version).get(..16).unwrap_or_default());
+    let local_path = version_dir.join(file_name);
+    if fs::metadata(&local_path).is_ok_and(|m| m.len() as i64 == remote_size) {
+        return Ok(local_path);
+    }
+
+    info!("Downloading {} to {:?}", uri, local_path);
+    fs::create_dir_all(&version_dir)?;
+    // Written under another name first, so an interrupted download is never taken for a cached file
+    let partial_path = version_dir.join(format!("{}.part", file_name));
+    {
+        let mut stmt = conn.prepare("SELECT content FROM read_blob(?)")?;
+        let mut rows = stmt.query([uri])?;
+        let row = rows.next()?.ok_or_else(|| format!("{} not found", uri))?;
+        // Written straight from DuckDB's buffer rather than copied out of it first
+        let ValueRef::Blob(content) = row.get_ref(0)? else {
+            return Err(format!("read_blob returned no content for {}", uri).into());
+        };
+        let mut file = BufWriter::new(fs::File::create(&partial_path)?);
+        file.write_all(content)?;
+        file.flush()?;
+    }
+    fs::rename(&partial_path, &local_path)?;
+    Ok(local_path)
+}
+
+/// Size and last modified time of a remote object, read from its metadata: its content isn't
+/// fetched, as it isn't selected
+pub fn object_version(conn: &Connection, uri: &str) -> Result<(i64, Option<String>), duckdb::Error> {
+    conn.query_row(
+        "SELECT size, CAST(last_modified AS VARCHAR) FROM read_blob(?)",
+        [uri],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )
+}
+
+fn glob(conn: &Connection, pattern: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let mut stmt = conn.prepare("SELECT file FROM glob(?) ORDER BY file")?;
+    let files = stmt
+        .query_map([pattern], |row| row.get::<usize, String>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(files)
+}
+
+/// Groups `<root>/<table>/<file>` URIs by table, sorting files by name
+fn group_by_table(root: &str, files: &[String]) -> Vec<(String, Vec<String>)> {
+    let mut by_table: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for file in files {
+        let Some(relative) = file.strip_prefix(root).map(|rest| rest.trim_start_matches('/')) else {
+            continue;
+        };
+        if let Some((table, _)) = relative.split_once('/') {
+            by_table.entry(table.to_string()).or_default().push(file.clone());
+        }
+    }
+    by_table.into_iter()
+        .map(|(table, mut files)| {
+            files.sort();
+            (table, files)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_remote() {
+        assert!(is_remote("s3://bucket/db_data"));
+        assert!(is_remote("gs://bucket/db_data"));
+        assert!(!is_remote("/home/me/db_data"));
+        assert!(!is_remote("db_data"));
+    }
+
+    #[test]
+    fn test_group_by_table() {
+        let files = vec![
+            "s3://bucket/db_data/books/part-02.parquet".to_string(),
+            "s3://bucket/db_data/authors/part-01.parquet".to_string(),
+            "s3://bucket/db_data/books/part-01.parquet".to_string(),
+        ];
+
+        assert_eq!(group_by_table("s3://bucket/db_data", &files), vec![
+            ("authors".to_string(), vec!["s3://bucket/db_data/authors/part-01.parquet".to_string()]),
+            ("books".to_string(), vec![
+                "s3://bucket/db_data/books/part-01.parquet".to_string(),
+                "s3://bucket/db_data/books/part-02.parquet".to_string(),
+            ]),
+        ]);
+    }
+
+    #[test]
+    fn test_download_caches_per_uri_and_version() {
+        let conn = Connection::open_in_memory().unwrap();
+        let root = std::env::temp_dir().join(format!("pensieve_download_{}", std::process::id()));
+        let cache_dir = root.join("cache");
+        for table in ["books", "authors"] {
+            fs::create_dir_all(root.join(table)).unwrap();
+            fs::write(root.join(table).join("binlog.sql"), format!("-- {}\n", table)).unwrap();
+        }
+        let uri = |table: &str| root.join(table).join("binlog.sql").to_string_lossy().to_string();
+
+        let books = download(&conn, &uri("books"), &cache_dir).unwrap();
+        let authors = download(&conn, &uri("authors"), &cache_dir).unwrap();
+        assert_ne!(books, authors);
+        assert_eq!(fs::read_to_string(&books).unwrap(), "-- books\n");
+        assert_eq!(fs::read_to_string(&authors).unwrap(), "-- authors\n");
+        assert_eq!(download(&conn, &uri("books"), &cache_dir).unwrap(), books);
+
+        // Same size, newer object
+        fs::write(root.join("books").join("binlog.sql"), "-- BOOKS\n").unwrap();
+        let file = fs::File::options().write(true).open(root.join("books").join("binlog.sql")).unwrap();
+        file.set_modified(std::time::SystemTime::now() + std::time::Duration::from_secs(60)).unwrap();
+        let updated = download(&conn, &uri("books"), &cache_dir).unwrap();
+        assert_ne!(updated, books);
+        assert_eq!(fs::read_to_string(&updated).unwrap(), "-- BOOKS\n");
+
+        fs::remove_dir_all(&root).ok();
+    }
+}
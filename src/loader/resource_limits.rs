@@ -0,0 +1,64 @@
+use duckdb::Connection;
+
+/// Caps on what the DuckDB connection may use, so that loading and navigating a large snapshot
+/// leaves room for other jobs on the same machine. Unset limits keep DuckDB's defaults (80% of
+/// memory and one thread per core).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResourceLimits {
+    /// DuckDB memory limit, e.g. `4GB` or `512MiB`. Larger operations spill to the temporary
+    /// directory instead.
+    pub memory_limit: Option<String>,
+    /// Threads DuckDB and the binlog parser may use
+    pub max_threads: Option<usize>,
+}
+
+impl ResourceLimits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_memory_limit(mut self, memory_limit: &str) -> Self {
+        self.memory_limit = Some(memory_limit.to_string());
+        self
+    }
+
+    pub fn with_max_threads(mut self, max_threads: usize) -> Self {
+        self.max_threads = Some(max_threads.max(1));
+        self
+    }
+
+    /// Sets the limits on the connection (`memory_limit` and `threads` settings)
+    pub fn apply(&self, conn: &Connection) -> duckdb::Result<()> {
+        if let Some(memory_limit) = &self.memory_limit {
+            conn.execute_batch(&format!("SET memory_limit = '{}';", memory_limit.replace('\'', "''")))?;
+        }
+        if let Some(max_threads) = self.max_threads {
+            conn.execute_batch(&format!("SET threads = {};", max_threads))?;
+        }
+        Ok(())
+    }
+
+    /// `available` capped at max_threads
+    pub fn threads(&self, available: usize) -> usize {
+        self.max_threads.map_or(available, |max_threads| available.min(max_threads))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limits_are_set_on_connection() {
+        let conn = Connection::open_in_memory().unwrap();
+        let limits = ResourceLimits::new().with_memory_limit("256MB").with_max_threads(2);
+        limits.apply(&conn).unwrap();
+
+        let threads: i64 = conn.query_row("SELECT current_setting('threads')", [], |row| row.get(0)).unwrap();
+        let memory_limit: String = conn.query_row("SELECT current_setting('memory_limit')", [], |row| row.get(0)).unwrap();
+        assert_eq!(threads, 2);
+        assert!(memory_limit.contains("MiB") || memory_limit.contains("MB"), "{}", memory_limit);
+        assert_eq!(limits.threads(8), 2);
+        assert!(ResourceLimits::new().with_memory_limit("lots").apply(&conn).is_err());
+    }
+}
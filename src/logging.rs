@@ -0,0 +1,41 @@
+use tracing_subscriber::EnvFilter;
+use tracing_subscriber::fmt::format::FmtSpan;
+
+/// How much the library reports while it works. Everything goes to stderr, so stdout stays
+/// free for script output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Verbosity {
+    /// Warnings only, no phase timings
+    Quiet,
+    /// Progress messages, and the time each phase (parse, normalise, ...) took
+    #[default]
+    Normal,
+    /// Also per-table, per-navigation and per-line-count events
+    Verbose,
+}
+
+impl Verbosity {
+    fn default_filter(self) -> &'static str {
+        match self {
+            Verbosity::Quiet => "warn",
+            Verbosity::Normal => "info",
+            Verbosity::Verbose => "debug",
+        }
+    }
+}
+
+/// Installs a stderr subscriber for Pensieve's tracing events and spans. `RUST_LOG` overrides the
+/// level implied by `verbosity`. Does nothing if the application already installed a subscriber.
+pub fn init(verbosity: Verbosity) {
+    let filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new(verbosity.default_filter()));
+    let span_events = match verbosity {
+        Verbosity::Quiet => FmtSpan::NONE,
+        _ => FmtSpan::CLOSE,
+    };
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
+        .with_span_events(span_events)
+        .try_init();
+}
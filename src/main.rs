@@ -1,11 +1,466 @@
+use clap::{Args, Parser, Subcommand};
+use duckdb::types::Value;
+use pensieve_rs::doctor;
+use pensieve_rs::logging::{self, Verbosity};
+use pensieve_rs::export::audit_log::{export_audit_log, AuditFormat};
+use pensieve_rs::export::binlog_subset::{export_binlog_subset, BinlogSubset, SubsetFormat};
+use pensieve_rs::export::history_export::{export_history, HistoryExportOptions, SchemaDialect};
+use pensieve_rs::export::timeline::{export_timeline, TimelineFormat};
+use pensieve_rs::export::undo_script::export_undo_script;
+use pensieve_rs::parser::binlog_profile::profile_binlogs;
 use pensieve_rs::pensieve::Pensieve;
+use pensieve_rs::script;
+use pensieve_rs::server;
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(name = "pensieve", about = "Point-in-time snapshots of a MySQL database from a parquet snapshot and its binlogs")]
+struct Cli {
+    #[command(flatten)]
+    snapshot: SnapshotArgs,
+
+    /// Only report warnings and errors
+    #[arg(long, short, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Also report per-table and per-navigation details
+    #[arg(long, short, global = true)]
+    verbose: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+/// Where the data is and which snapshot to build. Accepted by every subcommand.
+#[derive(Args)]
+struct SnapshotArgs {
+    /// db_data directory (local path or object store URI)
+    #[arg(long, global = true, default_value = "db_data")]
+    data_dir: String,
+
+    /// Approximate timestamp of the parquet snapshot (YYMMDD HH:MM:SS)
+    #[arg(long, global = true)]
+    snapshot_ts: Option<String>,
+
+    /// Hours around the snapshot timestamp to search for the snapshot's binlog position
+    #[arg(long, global = true, default_value_t = 6)]
+    window: i64,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Load the snapshot and binlogs and print a summary
+    Load,
+    /// Load and normalise the snapshot, optionally writing what the normaliser did
+    Normalise {
+        /// Write one line per operation the normaliser considered (TSV)
+        #[arg(long)]
+        decision_log: Option<String>,
+        /// Write the SQL statements the normaliser applied
+        #[arg(long)]
+        sql_script: Option<String>,
+    },
+    /// Move the snapshot and print where it ends up
+    Step {
+        /// Operations to step, negative to step backwards
+        #[arg(long, allow_hyphen_values = true, conflicts_with = "to")]
+        count: Option<i64>,
+        /// Go to the last operation at or before this timestamp (YYMMDD HH:MM:SS)
+        #[arg(long)]
+        to: Option<String>,
+    },
+    /// Run a SQL query and print the rows tab-separated
+    Query {
+        /// Timestamp to query at (YYMMDD HH:MM:SS); defaults to the snapshot
+        #[arg(long)]
+        at: Option<String>,
+        sql: String,
+    },
+    /// Compare a table between two timestamps
+    Diff {
+        #[arg(long)]
+        table: String,
+        #[arg(long)]
+        from: String,
+        #[arg(long)]
+        to: String,
+        /// Print the changes as a JSON ChangeSet
+        #[arg(long)]
+        json: bool,
+    },
+    /// Compare the state at a timestamp with a reference dump taken then (db_data layout)
+    Verify {
+        #[arg(long)]
+        reference: PathBuf,
+        #[arg(long)]
+        at: String,
+        /// Also print every differing row
+        #[arg(long)]
+        rows: bool,
+    },
+    /// Summarise binlog files without loading the snapshot: tables, operations per hour, largest transactions
+    ProfileBinlog {
+        /// Binlog files to profile; those in --data-dir if none are given
+        files: Vec<String>,
+        /// Number of largest transactions to list
+        #[arg(long, default_value_t = 10)]
+        top: usize,
+    },
+    /// Export the change history
+    Export {
+        #[command(subcommand)]
+        kind: ExportCommand,
+    },
+    /// Run a bundled script
+    Scripts {
+        #[command(subcommand)]
+        command: ScriptsCommand,
+    },
+    /// Serve the snapshot over an HTTP JSON API for navigation and queries
+    Serve {
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        listen: String,
+        /// Serve on an address other than a loopback one, letting any client that can reach it
+        /// query and change the snapshot without authentication
+        #[arg(long)]
+        allow_remote: bool,
+    },
+    /// Check that the machine and data are ready before a long run
+    Doctor {
+        /// Directory that must be writable; the temp directory if none are given
+        #[arg(long)]
+        writable_dir: Vec<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ExportCommand {
+    /// Audit trail of every change (parquet, or JSON lines for .jsonl)
+    Audit {
+        #[arg(long)]
+        output: String,
+    },
+    /// Full change history of a table, as day-partitioned parquet
+    History {
+        #[arg(long)]
+        table: String,
+        #[arg(long)]
+        output_dir: String,
+        /// Also write a CREATE TABLE statement for the history in this warehouse dialect (clickhouse, bigquery)
+        #[arg(long)]
+        ddl: Option<SchemaDialect>,
+    },
+    /// Filtered copy of the parsed binlog (mysqlbinlog text, or audit JSON lines for .jsonl)
+    Subset {
+        #[arg(long)]
+        output: String,
+        /// Only operations on these tables
+        #[arg(long)]
+        table: Vec<String>,
+        /// Only operations at or after this timestamp (YYMMDD HH:MM:SS)
+        #[arg(long)]
+        from: Option<String>,
+        /// Only operations at or before this timestamp (YYMMDD HH:MM:SS)
+        #[arg(long)]
+        to: Option<String>,
+        /// Only operations touching rows with this primary key (comma-separated if composite)
+        #[arg(long)]
+        key: Vec<String>,
+    },
+    /// Operations per table per minute, with the anchor and bookmarks (JSON, or an HTML report for .html)
+    Timeline {
+        #[arg(long)]
+        output: String,
+    },
+    /// MySQL script undoing the operations between two positions, latest first
+    Undo {
+        #[arg(long)]
+        from: usize,
+        #[arg(long)]
+        to: usize,
+        #[arg(long)]
+        output: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ScriptsCommand {
+    /// List the bundled scripts
+    List,
+    /// Run a script by name, passing the remaining arguments to it
+    Run {
+        name: String,
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+}
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let snapshot_timestamp = "251108 17:03:00";
-    let window_hours = 6;
-    
-    let pensieve = Pensieve::new(snapshot_timestamp, window_hours)?;
+    let cli = Cli::parse();
+    logging::init(match (cli.quiet, cli.verbose) {
+        (true, _) => Verbosity::Quiet,
+        (_, true) => Verbosity::Verbose,
+        _ => Verbosity::Normal,
+    });
+    let snapshot = &cli.snapshot;
+
+    match cli.command {
+        Command::Doctor { writable_dir } => run_doctor(&snapshot.data_dir, writable_dir),
+        Command::Scripts { command: ScriptsCommand::List } => {
+            for (name, usage) in script::SCRIPTS {
+                println!("{} {}", name, usage);
+            }
+            Ok(())
+        }
+        Command::Scripts { command: ScriptsCommand::Run { name, args } } => {
+            script::run_script(&name, &script_args(snapshot, args))
+        }
+        Command::Load => {
+            let pensieve = load(snapshot)?;
+            println!("Tables: {}", pensieve.get_table_names().join(", "));
+            println!("Operations: {}", pensieve.get_manager().operation_count());
+            print_position(&pensieve);
+            Ok(())
+        }
+        Command::Normalise { decision_log, sql_script } => {
+            let pensieve = load(snapshot)?;
+            let report = pensieve.get_normalisation_report();
+            println!("Normaliser considered {} operation(s)", report.decisions.len());
+            if let Some(path) = decision_log {
+                report.write_decision_log(&path)?;
+                println!("Decision log written to {}", path);
+            }
+            if let Some(path) = sql_script {
+                report.write_sql_script(&path)?;
+                println!("SQL script written to {}", path);
+            }
+            print_position(&pensieve);
+            Ok(())
+        }
+        Command::Step { count, to } => {
+            let mut pensieve = load(snapshot)?;
+            let manager = pensieve.get_manager_mut();
+            match (count, to) {
+                (_, Some(timestamp)) => manager.goto_timestamp(&timestamp)?,
+                (Some(count), None) if count < 0 => {
+                    manager.step_backward_by(count.unsigned_abs() as usize)?;
+                }
+                (count, None) => {
+                    manager.step_forward_by(count.unwrap_or(1) as usize)?;
+                }
+            }
+            print_position(&pensieve);
+            Ok(())
+        }
+        Command::Query { at, sql } => {
+            let mut pensieve = load(snapshot)?;
+            let rows = match at {
+                Some(timestamp) => pensieve.query_at(&timestamp, &sql)?,
+                None => pensieve.get_manager().query(&sql)?,
+            };
+            for row in rows {
+                println!("{}", row.iter().map(format_value).collect::<Vec<_>>().join("\t"));
+            }
+            Ok(())
+        }
+        Command::Diff { table, from, to, json } => {
+            let mut pensieve = load(snapshot)?;
+            let manager = pensieve.get_manager_mut();
+            manager.goto_timestamp(&from)?;
+            let position_a = manager.get_position();
+            manager.goto_timestamp(&to)?;
+            let position_b = manager.get_position();
+            let diff = manager.diff(position_a, position_b, &table)?;
+            if json {
+                println!("{}", diff.to_change_set().to_json()?);
+                return Ok(());
+            }
+
+            println!("{}", diff.columns.join("\t"));
+            for row in &diff.inserted {
+                println!("+ {}", row.iter().map(format_value).collect::<Vec<_>>().join("\t"));
+            }
+            for row in &diff.deleted {
+                println!("- {}", row.iter().map(format_value).collect::<Vec<_>>().join("\t"));
+            }
+            for (before, after) in &diff.modified {
+                println!("< {}", before.iter().map(format_value).collect::<Vec<_>>().join("\t"));
+                println!("> {}", after.iter().map(format_value).collect::<Vec<_>>().join("\t"));
+            }
+            Ok(())
+        }
+        Command::Verify { reference, at, rows } => {
+            let mut pensieve = load(snapshot)?;
+            let report = pensieve.verify_against_dump(&at, &reference)?;
+            println!("Verified at position {} ({:?})", report.position, report.timestamp);
+            println!("table\treference\treconstructed\tmissing\textra\tmismatched");
+            for table in &report.tables {
+                println!(
+                    "{}\t{}\t{}\t{}\t{}\t{}",
+                    table.table, table.reference_rows, table.reconstructed_rows,
+                    table.missing.len(), table.extra.len(), table.mismatched.len()
+                );
+                if rows {
+                    for row in &table.missing {
+                        println!("- {}", row.iter().map(format_value).collect::<Vec<_>>().join("\t"));
+                    }
+                    for row in &table.extra {
+                        println!("+ {}", row.iter().map(format_value).collect::<Vec<_>>().join("\t"));
+                    }
+                    for (expected, actual) in &table.mismatched {
+                        println!("< {}", expected.iter().map(format_value).collect::<Vec<_>>().join("\t"));
+                        println!("> {}", actual.iter().map(format_value).collect::<Vec<_>>().join("\t"));
+                    }
+                }
+            }
+            println!("Accuracy: {:.4}%", report.accuracy() * 100.0);
+            Ok(())
+        }
+        Command::ProfileBinlog { files, top } => {
+            let files = if files.is_empty() {
+                Pensieve::binlog_files(std::path::Path::new(&snapshot.data_dir))?
+            } else {
+                files
+            };
+            let profile = profile_binlogs(&files, top)?;
+            println!(
+                "{} file(s), {} transaction(s), {} operation(s) from {} to {}",
+                profile.files, profile.transactions, profile.operations,
+                profile.first_timestamp.as_deref().unwrap_or("-"), profile.last_timestamp.as_deref().unwrap_or("-")
+            );
+            println!("\ntable\toperations");
+            for (table, operations) in profile.operations_per_table() {
+                println!("{}\t{}", table, operations);
+            }
+            println!("\ntable\thour\toperation\tcount");
+            for (table, hours) in &profile.hourly {
+                for (hour, counts) in hours {
+                    for (operation_type, count) in counts {
+                        println!("{}\t{}\t{}\t{}", table, hour, operation_type, count);
+                    }
+                }
+            }
+            println!("\noperations\ttimestamp\tfile\tend_log_pos\ttables");
+            for transaction in &profile.largest_transactions {
+                println!(
+                    "{}\t{}\t{}\t{}\t{}",
+                    transaction.operations,
+                    transaction.timestamp.as_deref().unwrap_or("-"),
+                    transaction.file,
+                    transaction.position.map(|p| p.to_string()).unwrap_or_else(|| "-".to_string()),
+                    transaction.tables.join(",")
+                );
+            }
+            Ok(())
+        }
+        Command::Serve { listen, allow_remote } => {
+            let mut pensieve = load(snapshot)?;
+            print_position(&pensieve);
+            server::serve(pensieve.get_manager_mut(), &listen, allow_remote)
+        }
+        Command::Export { kind: ExportCommand::Audit { output } } => {
+            let pensieve = load(snapshot)?;
+            let count = export_audit_log(pensieve.get_manager(), &output, AuditFormat::from_path(&output))?;
+            println!("Wrote {} audit record(s) to {}", count, output);
+            Ok(())
+        }
+        Command::Export { kind: ExportCommand::History { table, output_dir, ddl } } => {
+            let pensieve = load(snapshot)?;
+            let options = HistoryExportOptions { ddl_dialect: ddl };
+            let summary = export_history(pensieve.get_manager(), &table, &output_dir, &options)?;
+            println!("Wrote {} history row(s) to {}", summary.rows_exported, output_dir);
+            if let Some(ddl_path) = summary.ddl_path {
+                println!("Wrote the table definition to {}", ddl_path);
+            }
+            Ok(())
+        }
+        Command::Export { kind: ExportCommand::Subset { output, table, from, to, key } } => {
+            let pensieve = load(snapshot)?;
+            let subset = BinlogSubset::new()
+                .with_tables(&table.iter().map(|table| table.as_str()).collect::<Vec<_>>())
+                .with_time_range(from.as_deref(), to.as_deref())?
+                .with_keys(&key.iter().map(|key| key.as_str()).collect::<Vec<_>>());
+            let count = export_binlog_subset(pensieve.get_manager(), &subset, &output, SubsetFormat::from_path(&output))?;
+            println!("Wrote {} operation(s) to {}", count, output);
+            Ok(())
+        }
+        Command::Export { kind: ExportCommand::Timeline { output } } => {
+            let pensieve = load(snapshot)?;
+            let anchor = pensieve.get_normalisation_report().tx_zero_idx;
+            let count = export_timeline(pensieve.get_manager(), anchor, &output, TimelineFormat::from_path(&output))?;
+            println!("Wrote a timeline of {} bucket(s) to {}", count, output);
+            Ok(())
+        }
+        Command::Export { kind: ExportCommand::Undo { from, to, output } } => {
+            let pensieve = load(snapshot)?;
+            let summary = export_undo_script(pensieve.get_manager(), from, to, &output)?;
+            println!("Wrote {} undo statement(s) to {}", summary.statements, output);
+            Ok(())
+        }
+    }
+}
+
+fn load(snapshot: &SnapshotArgs) -> Result<Pensieve, Box<dyn std::error::Error>> {
+    let snapshot_ts = snapshot.snapshot_ts.as_deref().ok_or("--snapshot-ts is required")?;
+    Pensieve::from_data_dir(&snapshot.data_dir, snapshot_ts, snapshot.window)
+}
+
+/// Forwards the global snapshot flags to a script, unless the script's own arguments set them
+fn script_args(snapshot: &SnapshotArgs, args: Vec<String>) -> Vec<String> {
+    let mut forwarded = vec!["--data-dir".to_string(), snapshot.data_dir.clone()];
+    if let Some(snapshot_ts) = &snapshot.snapshot_ts {
+        forwarded.extend(["--timestamp".to_string(), snapshot_ts.clone()]);
+    }
+    forwarded.extend(["--window".to_string(), snapshot.window.to_string()]);
+    // Flags after the script name are the script's own, so --snapshot-ts there means --timestamp.
+    // Scripts take the last value of a repeated flag, so the script's own arguments win.
+    forwarded.extend(args.into_iter().map(|arg| match arg.as_str() {
+        "--snapshot-ts" => "--timestamp".to_string(),
+        _ => arg,
+    }));
+    forwarded
+}
+
+fn print_position(pensieve: &Pensieve) {
+    println!(
+        "Position: {} ({})",
+        pensieve.get_manager().get_position(),
+        pensieve.get_manager().get_timestamp().map(|ts| ts.as_str()).unwrap_or("no timestamp"),
+    );
+}
+
+fn format_value(value: &Value) -> String {
+    match value {
+        Value::Null => "NULL".to_string(),
+        Value::Boolean(b) => b.to_string(),
+        Value::TinyInt(n) => n.to_string(),
+        Value::SmallInt(n) => n.to_string(),
+        Value::Int(n) => n.to_string(),
+        Value::BigInt(n) => n.to_string(),
+        Value::HugeInt(n) => n.to_string(),
+        Value::UTinyInt(n) => n.to_string(),
+        Value::USmallInt(n) => n.to_string(),
+        Value::UInt(n) => n.to_string(),
+        Value::UBigInt(n) => n.to_string(),
+        Value::Float(n) => n.to_string(),
+        Value::Double(n) => n.to_string(),
+        Value::Decimal(n) => n.to_string(),
+        Value::Text(s) => s.clone(),
+        other => format!("{:?}", other),
+    }
+}
+
+fn run_doctor(data_dir: &str, mut writable_dirs: Vec<PathBuf>) -> Result<(), Box<dyn std::error::Error>> {
+    if writable_dirs.is_empty() {
+        writable_dirs.push(std::env::temp_dir());
+    }
 
+    let report = doctor::run_checks(&PathBuf::from(data_dir), &writable_dirs);
+    println!("{}", report);
 
+    if !report.is_ready() {
+        std::process::exit(1);
+    }
     Ok(())
 }
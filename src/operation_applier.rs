@@ -1,28 +1,192 @@
 use duckdb::Connection;
 use std::collections::HashMap;
-use crate::binlog::{BinlogOperation, OperationType};
+use tracing::warn;
+use crate::binlog::{quote_identifier, BinlogOperation, OperationType, RowImage};
+use crate::parser::value_normaliser::ValueNormaliser;
 
-/// Handles applying binlog operations to a DuckDB connection
-pub struct OperationApplier {
-    conn: Connection,
+pub mod batch_apply;
+pub mod row_comparator;
+#[cfg(feature = "sqlite")]
+pub mod sqlite_store;
+pub mod state_store;
+pub use batch_apply::DEFAULT_BATCH_SIZE;
+pub use row_comparator::{ComparedColumn, Comparison, RowComparator};
+pub use state_store::StateStore;
+
+/// What to do with an UPDATE or DELETE whose before-image doesn't match the current row, and
+/// which wasn't already applied either. This usually means the snapshot and binlog disagree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ApplyPolicy {
+    /// Skip the operation silently
+    #[default]
+    Skip,
+    /// Fail with an error describing the operation
+    Error,
+    /// Execute the operation's SQL anyway (verbatim mode). The statement still filters on the
+    /// before-image, so it may not change any row.
+    ForceApply,
+    /// Skip the operation and print a warning
+    LogAndSkip,
+}
+
+/// What to do with an UPDATE or DELETE whose before-image matches more than one row, e.g. a
+/// table without a primary key holding duplicate rows. MySQL changed a single row, so this
+/// usually means the table's key assumptions are wrong.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MultiMatchPolicy {
+    /// Change every matching row
+    #[default]
+    ApplyToAll,
+    /// Skip the operation
+    Skip,
+    /// Fail with an error describing the operation
+    Error,
+}
+
+/// What apply_operation_with_outcome did with an operation, and why
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApplyOutcome {
+    Applied,
+    /// Applied although the before-image didn't match (ApplyPolicy::ForceApply)
+    ForceApplied,
+    /// Skipped because the table already reflects the operation
+    AlreadyApplied,
+    /// Skipped because the row matches neither the before-image nor the after-image
+    Mismatch,
+    /// Skipped because the before-image matches more than one row (MultiMatchPolicy::Skip)
+    MultipleMatches,
+}
+
+impl ApplyOutcome {
+    pub fn applied(&self) -> bool {
+        matches!(self, ApplyOutcome::Applied | ApplyOutcome::ForceApplied)
+    }
+}
+
+impl std::fmt::Display for ApplyOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let reason = match self {
+            ApplyOutcome::Applied => "applied",
+            ApplyOutcome::ForceApplied => "applied despite before-image mismatch",
+            ApplyOutcome::AlreadyApplied => "already applied",
+            ApplyOutcome::Mismatch => "before-image mismatch",
+            ApplyOutcome::MultipleMatches => "before-image matches several rows",
+        };
+        write!(f, "{}", reason)
+    }
+}
+
+/// Result of comparing an operation with the current state of its row
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RowState {
+    /// The operation would change the table
+    Applicable,
+    /// The table already reflects the operation
+    AlreadyApplied,
+    /// The row matches neither the before-image nor the after-image
+    Mismatch,
+}
+
+/// Handles applying binlog operations to a database, DuckDB unless another StateStore is given
+pub struct OperationApplier<S: StateStore = Connection> {
+    conn: S,
     schema_cache: HashMap<String, Vec<String>>,
     type_cache: HashMap<String, Vec<String>>,
+    policy: ApplyPolicy,
+    // Before-image mismatches per table
+    mismatch_counts: HashMap<String, usize>,
+    multi_match_policy: MultiMatchPolicy,
+    // Operations whose before-image matched several rows, per table
+    multi_match_counts: HashMap<String, usize>,
+    value_normaliser: ValueNormaliser,
+    // Tables whose rows are looked up by primary key and compared with their own RowComparator
+    comparators: HashMap<String, Box<dyn RowComparator>>,
+    key_cache: HashMap<String, Vec<String>>,
+    // Most INSERTs apply_operations combines into one statement
+    batch_size: usize,
+    // Batched INSERTs go through StateStore::append_rows when they can
+    use_appender: bool,
 }
 
-impl OperationApplier {
-    pub fn new(conn: Connection) -> Self {
+impl<S: StateStore> OperationApplier<S> {
+    pub fn new(conn: S) -> Self {
         Self {
             conn,
             schema_cache: HashMap::new(),
             type_cache: HashMap::new(),
+            policy: ApplyPolicy::default(),
+            mismatch_counts: HashMap::new(),
+            multi_match_policy: MultiMatchPolicy::default(),
+            multi_match_counts: HashMap::new(),
+            value_normaliser: ValueNormaliser::new(),
+            comparators: HashMap::new(),
+            key_cache: HashMap::new(),
+            batch_size: DEFAULT_BATCH_SIZE,
+            use_appender: false,
         }
     }
 
-    pub fn get_connection(&self) -> &Connection {
+    pub fn with_policy(mut self, policy: ApplyPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn get_policy(&self) -> ApplyPolicy {
+        self.policy
+    }
+
+    pub fn set_policy(&mut self, policy: ApplyPolicy) {
+        self.policy = policy;
+    }
+
+    pub fn with_multi_match_policy(mut self, policy: MultiMatchPolicy) -> Self {
+        self.multi_match_policy = policy;
+        self
+    }
+
+    pub fn get_multi_match_policy(&self) -> MultiMatchPolicy {
+        self.multi_match_policy
+    }
+
+    pub fn set_multi_match_policy(&mut self, policy: MultiMatchPolicy) {
+        self.multi_match_policy = policy;
+    }
+
+    pub fn with_comparator(mut self, table: &str, comparator: impl RowComparator + 'static) -> Self {
+        self.set_comparator(table, comparator);
+        self
+    }
+
+    /// Compare rows of `table` with `comparator` when deciding whether an operation applies.
+    /// The table's rows are then looked up by primary key instead of by every value of the row
+    /// image, and UPDATEs and DELETEs filter on the key. Applying an operation on the table fails
+    /// if it has no declared primary key, since filtering on any other column could change
+    /// every row sharing its value.
+    pub fn set_comparator(&mut self, table: &str, comparator: impl RowComparator + 'static) {
+        self.comparators.insert(table.to_string(), Box::new(comparator));
+    }
+
+    /// Go back to comparing rows of `table` value by value
+    pub fn clear_comparator(&mut self, table: &str) {
+        self.comparators.remove(table);
+    }
+
+    /// Number of before-image mismatches seen per table, whatever the policy
+    pub fn get_mismatch_counts(&self) -> &HashMap<String, usize> {
+        &self.mismatch_counts
+    }
+
+    /// Number of UPDATEs and DELETEs whose before-image matched several rows, per table, whatever
+    /// the policy
+    pub fn get_multi_match_counts(&self) -> &HashMap<String, usize> {
+        &self.multi_match_counts
+    }
+
+    pub fn get_connection(&self) -> &S {
         &self.conn
     }
 
-    pub fn into_connection(self) -> Connection {
+    pub fn into_connection(self) -> S {
         self.conn
     }
 
@@ -32,28 +196,12 @@ impl OperationApplier {
             return (cols.clone(), types.clone());
         }
 
-        let query = format!("PRAGMA table_info('{}')", table_name);
-        let Ok(mut stmt) = self.conn.prepare(&query) else {
-            return (Vec::new(), Vec::new())
-        };
-
-        let Ok(rows) = stmt.query_map([], |row| {
-            let name: String = row.get(1)?;
-            let col_type: String = row.get(2)?;
-            Ok((name, col_type))
-        }) else {
-            return (Vec::new(), Vec::new());
+        // Not cached for missing tables, which may be created later
+        let schema = match self.conn.table_schema(table_name) {
+            Ok(schema) if !schema.is_empty() => schema,
+            _ => return (Vec::new(), Vec::new()),
         };
-
-        let mut columns = Vec::new();
-        let mut types = Vec::new();
-
-        for row in rows {
-            if let Ok((name, col_type)) = row {
-                columns.push(name);
-                types.push(col_type);
-            }
-        }
+        let (columns, types): (Vec<String>, Vec<String>) = schema.into_iter().unzip();
 
         self.schema_cache.insert(table_name.to_string(), columns.clone());
         self.type_cache.insert(table_name.to_string(), types.clone());
@@ -61,62 +209,99 @@ impl OperationApplier {
         (columns, types)
     }
 
+    /// JSON columns of a table. Their text in the database may differ from the binlog's in key order
+    /// and whitespace, so statements write them in canonical form and don't filter on them; they
+    /// are compared after parsing instead (see ValueNormaliser).
+    fn json_columns(&mut self, table_name: &str) -> Vec<String> {
+        let (columns, types) = self.get_table_schema(table_name);
+        columns.into_iter()
+            .zip(types)
+            .filter(|(_, column_type)| column_type.eq_ignore_ascii_case("JSON"))
+            .map(|(column, _)| column)
+            .collect()
+    }
+
+    /// `op` with the values of its JSON columns in canonical form
+    fn canonical_json(&self, op: &BinlogOperation, json_columns: &[String]) -> BinlogOperation {
+        let canonical = |values: &Option<Vec<String>>| values.as_ref().map(|values| {
+            op.columns.iter()
+                .zip(values)
+                .map(|(col, value)| if json_columns.contains(col) {
+                    self.value_normaliser.normalise(value, "JSON")
+                } else {
+                    value.clone()
+                })
+                .collect()
+        });
+        BinlogOperation {
+            before_values: canonical(&op.before_values),
+            after_values: canonical(&op.after_values),
+            ..op.clone()
+        }
+    }
+
     /// Generate SQL statement from a binlog operation
-    pub fn generate_sql(&self, op: &BinlogOperation) -> String {
+    pub fn generate_sql(&mut self, op: &BinlogOperation) -> String {
+        let json_columns = self.json_columns(&op.table_name);
+        if !json_columns.is_empty() {
+            let op = self.canonical_json(op, &json_columns);
+            let unfiltered = [op.row_image.missing_before(), json_columns.as_slice()].concat();
+            let where_parts = op.before_values.as_ref()
+                .map(|before| Self::where_parts(&op.columns, before, &unfiltered))
+                .unwrap_or_default();
+            return Self::statement(&op, &where_parts);
+        }
+        let where_parts = op.before_values.as_ref()
+            .map(|before| Self::where_parts(&op.columns, before, op.row_image.missing_before()))
+            .unwrap_or_default();
+        Self::statement(op, &where_parts)
+    }
+
+    /// SQL statement for `op`, with UPDATEs and DELETEs filtering on `where_parts`
+    fn statement(op: &BinlogOperation, where_parts: &[String]) -> String {
         match op.operation_type {
             OperationType::Insert => {
                 let vals = op.after_values.as_ref().unwrap();
                 format!(
                     "INSERT INTO {} ({}) VALUES ({});",
-                    op.table_name,
-                    op.columns.join(", "),
+                    quote_identifier(&op.table_name),
+                    quoted_columns(&op.columns),
                     vals.join(", ")
                 )
             }
             OperationType::Update => {
-                let before = op.before_values.as_ref().unwrap();
                 let after = op.after_values.as_ref().unwrap();
                 
+                // Only set the columns that change; a no-op update still sets every column
+                let changed = op.changed_columns();
                 let set_parts: Vec<String> = op.columns.iter()
                     .zip(after.iter())
-                    .map(|(col, val)| format!("{} = {}", col, val))
-                    .collect();
-                    
-                let where_parts: Vec<String> = op.columns.iter()
-                    .zip(before.iter())
-                    .filter(|(_, val)| *val != "NULL")
-                    .map(|(col, val)| format!("{} = {}", col, val))
+                    .filter(|(col, _)| changed.is_empty() || changed.contains(&col.as_str()))
+                    .map(|(col, val)| format!("{} = {}", quote_identifier(col), val))
                     .collect();
                 
                 if where_parts.is_empty() {
                     format!(
                         "UPDATE {} SET {};",
-                        op.table_name,
+                        quote_identifier(&op.table_name),
                         set_parts.join(", ")
                     )
                 } else {
                     format!(
                         "UPDATE {} SET {} WHERE {};",
-                        op.table_name,
+                        quote_identifier(&op.table_name),
                         set_parts.join(", "),
                         where_parts.join(" AND ")
                     )
                 }
             }
             OperationType::Delete => {
-                let before = op.before_values.as_ref().unwrap();
-                let where_parts: Vec<String> = op.columns.iter()
-                    .zip(before.iter())
-                    .filter(|(_, val)| *val != "NULL")
-                    .map(|(col, val)| format!("{} = {}", col, val))
-                    .collect();
-                
                 if where_parts.is_empty() {
-                    format!("DELETE FROM {};", op.table_name)
+                    format!("DELETE FROM {};", quote_identifier(&op.table_name))
                 } else {
                     format!(
                         "DELETE FROM {} WHERE {};",
-                        op.table_name,
+                        quote_identifier(&op.table_name),
                         where_parts.join(" AND ")
                     )
                 }
@@ -124,6 +309,20 @@ impl OperationApplier {
         }
     }
 
+    /// WHERE predicates matching a row image: `col IS NULL` for NULL values, `col = value` otherwise.
+    /// Columns in `unlogged` have placeholder values (see RowImage::Partial) and are left out.
+    fn where_parts(columns: &[String], values: &[String], unlogged: &[String]) -> Vec<String> {
+        columns.iter()
+            .zip(values.iter())
+            .filter(|(col, _)| !unlogged.contains(col))
+            .map(|(col, val)| if val == "NULL" {
+                format!("{} IS NULL", quote_identifier(col))
+            } else {
+                format!("{} = {}", quote_identifier(col), val)
+            })
+            .collect()
+    }
+
     /// Fetch the current row from database matching the identifying values
     pub fn fetch_current_row(
         &mut self,
@@ -131,51 +330,127 @@ impl OperationApplier {
         columns: &[String],
         identifying_values: &[String],
     ) -> Result<Option<Vec<String>>, Box<dyn std::error::Error>> {
-        let where_parts: Vec<String> = columns.iter()
-            .zip(identifying_values.iter())
-            .filter(|(_, val)| *val != "NULL")
-            .map(|(col, val)| format!("{} = {}", col, val))
+        self.fetch_row(table, columns, identifying_values, &[])
+    }
+
+    /// Same as fetch_current_row, without matching on the `unlogged` columns
+    fn fetch_row(
+        &mut self,
+        table: &str,
+        columns: &[String],
+        identifying_values: &[String],
+        unlogged: &[String],
+    ) -> Result<Option<Vec<String>>, Box<dyn std::error::Error>> {
+        let unfiltered = [unlogged, self.json_columns(table).as_slice()].concat();
+        let where_parts = Self::where_parts(columns, identifying_values, &unfiltered);
+        self.select_row(table, columns, &where_parts)
+    }
+
+    /// Primary key columns of a table with a comparator. Fails if it has no declared primary key.
+    fn key_columns(&mut self, table: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        if let Some(key_columns) = self.key_cache.get(table) {
+            return Ok(key_columns.clone());
+        }
+        let key_columns = self.conn.primary_key_columns(table)?;
+        if key_columns.is_empty() {
+            return Err(format!("Table {} has a comparator but no primary key to look its rows up by", table).into());
+        }
+        self.key_cache.insert(table.to_string(), key_columns.clone());
+        Ok(key_columns)
+    }
+
+    /// WHERE predicates on the key values of a row image, for tables with a comparator.
+    /// Empty if the image doesn't hold every key column.
+    fn key_where_parts(&mut self, op: &BinlogOperation, image: &[String]) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let key_columns = self.key_columns(&op.table_name)?;
+        let key_values: Option<Vec<String>> = key_columns.iter()
+            .map(|key| op.columns.iter().position(|col| col == key).map(|idx| image[idx].clone()))
             .collect();
-        
+        Ok(key_values
+            .map(|key_values| Self::where_parts(&key_columns, &key_values, op.row_image.missing_before()))
+            .unwrap_or_default())
+    }
+
+    /// The current row to compare with a row image of `op`: the row matching every logged value,
+    /// or the row with the image's key if the table has a comparator
+    fn fetch_for_image(&mut self, op: &BinlogOperation, image: &[String], unlogged: &[String]) -> Result<Option<Vec<String>>, Box<dyn std::error::Error>> {
+        if self.comparators.contains_key(&op.table_name) {
+            let where_parts = self.key_where_parts(op, image)?;
+            return self.select_row(&op.table_name, &op.columns, &where_parts);
+        }
+        self.fetch_row(&op.table_name, &op.columns, image, unlogged)
+    }
+
+    /// Fetch every column of the row with the given key values, in table column order
+    pub fn fetch_row_by_key(
+        &mut self,
+        table: &str,
+        key_columns: &[String],
+        key_values: &[String],
+    ) -> Result<Option<Vec<String>>, Box<dyn std::error::Error>> {
+        let (table_columns, _) = self.get_table_schema(table);
+        let where_parts = Self::where_parts(key_columns, key_values, &[]);
+        self.select_row(table, &table_columns, &where_parts)
+    }
+
+    /// Selects `columns` of the first row matching `where_parts`, as SQL literals. Rows are taken in
+    /// insertion order, so the same row is picked every time when several match.
+    fn select_row(
+        &mut self,
+        table: &str,
+        columns: &[String],
+        where_parts: &[String],
+    ) -> Result<Option<Vec<String>>, Box<dyn std::error::Error>> {
         if where_parts.is_empty() {
             return Ok(None);
         }
         
-        let (_, types) = self.get_table_schema(table);
-        if types.is_empty() {
+        let (table_columns, table_types) = self.get_table_schema(table);
+        if table_types.is_empty() {
             return Ok(None);
         }
+        // Operations with a partial row image only hold some of the table's columns
+        let types: Vec<String> = columns.iter()
+            .map(|col| table_columns.iter().position(|c| c == col)
+                .map(|idx| table_types[idx].clone())
+                .unwrap_or_default())
+            .collect();
         
+        // Blobs are read as hex, since their bytes needn't be text
         let select_parts: Vec<String> = columns.iter()
-            .map(|col| format!("CAST({} AS VARCHAR)", col))
+            .zip(types.iter())
+            .map(|(col, col_type)| if col_type == "BLOB" {
+                format!("hex({})", quote_identifier(col))
+            } else {
+                format!("CAST({} AS VARCHAR)", quote_identifier(col))
+            })
             .collect();
         
         let query = format!(
-            "SELECT {} FROM {} WHERE {} LIMIT 1",
+            "SELECT {} FROM {} WHERE {} ORDER BY rowid LIMIT 1",
             select_parts.join(", "),
-            table,
+            quote_identifier(table),
             where_parts.join(" AND ")
         );
         
-        let mut stmt = match self.conn.prepare(&query) {
-            Ok(s) => s,
-            Err(_) => return Ok(None),
+        // A column the table doesn't have makes the query fail, and means no row matches
+        let Ok(row) = self.conn.query_row(&query) else {
+            return Ok(None);
         };
         
-        let mut rows = stmt.query([])?;
-        
-        if let Some(row) = rows.next()? {
+        if let Some(row) = row {
             let mut values = Vec::new();
             
-            for i in 0..columns.len() {
+            for (i, string_val) in row.into_iter().enumerate() {
                 let col_type = types.get(i).map(|s| s.as_str()).unwrap_or("");
-                let string_val: Option<String> = row.get(i)?;
                 
                 let value = match string_val {
                     Some(v) => {
-                        if col_type.contains("VARCHAR") || col_type.contains("TEXT") || col_type.contains("CHAR")
-                            || col_type.contains("TIMESTAMP") || col_type.contains("DATE") {
-                            format!("'{}'", v)
+                        if col_type == "BLOB" {
+                            format!("unhex('{}')", v)
+                        } else if col_type.contains("VARCHAR") || col_type.contains("TEXT") || col_type.contains("CHAR")
+                            || col_type.contains("TIMESTAMP") || col_type.contains("DATE") || col_type == "JSON" {
+                            format!("'{}'", v.replace('\'', "''"))
                         } else if col_type.contains("BOOL") {
                             if v == "true" || v == "t" {
                                 "1".to_string()
@@ -200,40 +475,209 @@ impl OperationApplier {
         }
     }
 
+    /// Fills in the before values a partial row image didn't log, from the row currently matching
+    /// the logged ones. Columns that weren't loaded stay listed as missing. Returns None if nothing
+    /// can be filled in or no row matches.
+    pub fn complete_row_image(&mut self, op: &BinlogOperation) -> Result<Option<BinlogOperation>, Box<dyn std::error::Error>> {
+        let missing_before = op.row_image.missing_before();
+        let Some(before_vals) = op.before_values.as_ref() else {
+            return Ok(None);
+        };
+        if op.row_image.is_complete_for(&op.columns) || missing_before.is_empty() {
+            return Ok(None);
+        }
+        let Some(current) = self.fetch_row(&op.table_name, &op.columns, before_vals, missing_before)? else {
+            return Ok(None);
+        };
+
+        let before_vals = op.columns.iter()
+            .zip(before_vals.iter().zip(current))
+            .map(|(col, (logged, current))| if missing_before.contains(col) { current } else { logged.clone() })
+            .collect();
+        Ok(Some(BinlogOperation {
+            before_values: Some(before_vals),
+            row_image: RowImage::Partial {
+                missing_before: missing_before.iter().filter(|col| !op.columns.contains(col)).cloned().collect(),
+            },
+            ..op.clone()
+        }))
+    }
+
     /// Check if an operation should be applied based on current database state
     /// If not, the operation can be safely skipped
     pub fn should_apply(&mut self, op: &BinlogOperation) -> Result<bool, Box<dyn std::error::Error>> {
+        Ok(self.row_state(op)? == RowState::Applicable)
+    }
+
+    /// Whether a row read with fetch_for_image holds the values of a row image of `op`, comparing
+    /// each value through its column's DuckDB type, or with the table's comparator.
+    /// Columns in `unlogged` are only left out by comparators.
+    fn matches_image(&mut self, op: &BinlogOperation, current: &[String], image: &[String], unlogged: &[String]) -> Result<bool, Box<dyn std::error::Error>> {
+        if current == image {
+            return Ok(true);
+        }
+        if current.len() != image.len() {
+            return Ok(false);
+        }
+        let (table_columns, table_types) = self.get_table_schema(&op.table_name);
+        let column_type = |col: &String| table_columns.iter().position(|c| c == col)
+            .map(|idx| table_types[idx].as_str())
+            .unwrap_or("");
+
+        if self.comparators.contains_key(&op.table_name) {
+            let key_columns = self.key_columns(&op.table_name)?;
+            let logged: Vec<usize> = (0..op.columns.len()).filter(|&idx| !unlogged.contains(&op.columns[idx])).collect();
+            let columns: Vec<ComparedColumn> = logged.iter()
+                .map(|&idx| ComparedColumn {
+                    name: &op.columns[idx],
+                    column_type: column_type(&op.columns[idx]),
+                    is_key: key_columns.contains(&op.columns[idx]),
+                })
+                .collect();
+            let current: Vec<String> = logged.iter().map(|&idx| current[idx].clone()).collect();
+            let image: Vec<String> = logged.iter().map(|&idx| image[idx].clone()).collect();
+            return Ok(self.comparators[&op.table_name].rows_match(&columns, &current, &image));
+        }
+
+        Ok(op.columns.iter()
+            .zip(current.iter().zip(image.iter()))
+            .all(|(col, (current, logged))| self.value_normaliser.values_equal(current, logged, column_type(col))))
+    }
+
+    fn row_state(&mut self, op: &BinlogOperation) -> Result<RowState, Box<dyn std::error::Error>> {
         match op.operation_type {
             OperationType::Insert => {
                 let after_vals = op.after_values.as_ref().unwrap();
-                let current = self.fetch_current_row(&op.table_name, &op.columns, after_vals)?;
-                
-                match current {
-                    None => Ok(true),
-                    Some(current_vals) => Ok(&current_vals != after_vals)
+                match self.fetch_for_image(op, after_vals, &[])? {
+                    Some(current_vals) if self.matches_image(op, &current_vals, after_vals, &[])? => Ok(RowState::AlreadyApplied),
+                    _ => Ok(RowState::Applicable),
                 }
             }
-            OperationType::Update | OperationType::Delete => {
+            OperationType::Update => {
                 let before_vals = op.before_values.as_ref().unwrap();
-                let current = self.fetch_current_row(&op.table_name, &op.columns, before_vals)?;
-                
-                match current {
-                    None => Ok(false),
-                    Some(current_vals) => Ok(&current_vals == before_vals),
+                let unlogged = op.row_image.missing_before();
+                if let Some(current_vals) = self.fetch_for_image(op, before_vals, unlogged)?
+                    && self.matches_image(op, &current_vals, before_vals, unlogged)? {
+                    return Ok(RowState::Applicable);
+                }
+                let after_vals = op.after_values.as_ref().unwrap();
+                match self.fetch_for_image(op, after_vals, &[])? {
+                    Some(current_vals) if self.matches_image(op, &current_vals, after_vals, &[])? => Ok(RowState::AlreadyApplied),
+                    _ => Ok(RowState::Mismatch),
+                }
+            }
+            OperationType::Delete => {
+                let before_vals = op.before_values.as_ref().unwrap();
+                let unlogged = op.row_image.missing_before();
+                match self.fetch_for_image(op, before_vals, unlogged)? {
+                    None => Ok(RowState::AlreadyApplied),
+                    Some(current_vals) if self.matches_image(op, &current_vals, before_vals, unlogged)? => Ok(RowState::Applicable),
+                    Some(_) => Ok(RowState::Mismatch),
                 }
             }
         }
     }
 
-    /// Apply an operation conditionally (only if it would actually make a change to the table)
+    /// Apply an operation conditionally (only if it would actually make a change to the table).
+    /// Missing before values of a partial row image are filled in from the database first.
+    /// Before-image mismatches are counted and handled according to the applier's ApplyPolicy.
     pub fn apply_operation_conditionally(&mut self, op: &BinlogOperation) -> Result<bool, Box<dyn std::error::Error>> {
-        if self.should_apply(op)? {
-            let sql = self.generate_sql(op);
-            self.conn.execute(&sql, [])?;
-            Ok(true)
-        } else {
-            Ok(false)
+        Ok(self.apply_operation_with_outcome(op)?.applied())
+    }
+
+    /// Same as apply_operation_conditionally, but tells why an operation was skipped
+    pub fn apply_operation_with_outcome(&mut self, op: &BinlogOperation) -> Result<ApplyOutcome, Box<dyn std::error::Error>> {
+        Ok(self.apply_operation_with_row_count(op)?.0)
+    }
+
+    /// Same as apply_operation_with_outcome, and also returns the number of rows the statement
+    /// changed: a forced statement may not change any
+    pub fn apply_operation_with_row_count(&mut self, op: &BinlogOperation) -> Result<(ApplyOutcome, usize), Box<dyn std::error::Error>> {
+        let completed = self.complete_row_image(op)?;
+        let op = completed.as_ref().unwrap_or(op);
+
+        let outcome = match self.row_state(op)? {
+            RowState::Applicable => ApplyOutcome::Applied,
+            RowState::AlreadyApplied => return Ok((ApplyOutcome::AlreadyApplied, 0)),
+            RowState::Mismatch => {
+                *self.mismatch_counts.entry(op.table_name.clone()).or_default() += 1;
+                match self.policy {
+                    ApplyPolicy::Skip => return Ok((ApplyOutcome::Mismatch, 0)),
+                    ApplyPolicy::LogAndSkip => {
+                        warn!("Skipping operation whose before-image doesn't match: {}", op);
+                        return Ok((ApplyOutcome::Mismatch, 0));
+                    }
+                    ApplyPolicy::Error => {
+                        return Err(format!("Before-image doesn't match current row: {}", op).into());
+                    }
+                    ApplyPolicy::ForceApply => ApplyOutcome::ForceApplied,
+                }
+            }
+        };
+
+        // With ApplyToAll, several matches show in the number of rows the statement changed
+        if self.multi_match_policy != MultiMatchPolicy::ApplyToAll && self.matching_rows(op)? > 1 {
+            *self.multi_match_counts.entry(op.table_name.clone()).or_default() += 1;
+            if self.multi_match_policy == MultiMatchPolicy::Error {
+                return Err(format!("Before-image matches several rows: {}", op).into());
+            }
+            return Ok((ApplyOutcome::MultipleMatches, 0));
+        }
+
+        let rows_changed = self.execute_operation(op)?;
+        if rows_changed > 1 && op.operation_type != OperationType::Insert {
+            *self.multi_match_counts.entry(op.table_name.clone()).or_default() += 1;
+        }
+        Ok((outcome, rows_changed))
+    }
+
+    /// Number of rows the statement for an UPDATE or DELETE would change, counting up to 2
+    fn matching_rows(&mut self, op: &BinlogOperation) -> Result<usize, Box<dyn std::error::Error>> {
+        let where_parts = self.row_filter(op)?;
+        if op.operation_type == OperationType::Insert || where_parts.is_empty() {
+            return Ok(1);
+        }
+        let count = self.conn.query_row(&format!(
+            "SELECT CAST(COUNT(*) AS VARCHAR) FROM (SELECT 1 FROM {} WHERE {} LIMIT 2)",
+            quote_identifier(&op.table_name),
+            where_parts.join(" AND ")
+        ))?;
+        Ok(count.into_iter().flatten().flatten().next().and_then(|count| count.parse().ok()).unwrap_or(0))
+    }
+
+    /// WHERE predicates of the statement execute_operation runs for an UPDATE or DELETE: the key
+    /// for tables with a comparator, every logged value otherwise
+    fn row_filter(&mut self, op: &BinlogOperation) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let Some(before) = &op.before_values else {
+            return Ok(Vec::new());
+        };
+        if self.comparators.contains_key(&op.table_name) {
+            let where_parts = self.key_where_parts(op, before)?;
+            // Without the key in the image, filtering on the logged values is still safer
+            // than an unfiltered statement
+            if !where_parts.is_empty() {
+                return Ok(where_parts);
+            }
         }
+        let unfiltered = [op.row_image.missing_before(), self.json_columns(&op.table_name).as_slice()].concat();
+        Ok(Self::where_parts(&op.columns, before, &unfiltered))
+    }
+
+    /// Executes the operation's statement without checking the current row first, returning the
+    /// number of rows changed. Used to replay the exact inverse of an operation known to be applied.
+    pub fn execute_operation(&mut self, op: &BinlogOperation) -> Result<usize, Box<dyn std::error::Error>> {
+        let where_parts = self.row_filter(op)?;
+        let json_columns = self.json_columns(&op.table_name);
+        let sql = if json_columns.is_empty() {
+            Self::statement(op, &where_parts)
+        } else {
+            Self::statement(&self.canonical_json(op, &json_columns), &where_parts)
+        };
+        self.conn.execute(&sql)
     }
 }
 
+/// Quoted column names, comma-separated
+fn quoted_columns(columns: &[String]) -> String {
+    columns.iter().map(|column| quote_identifier(column)).collect::<Vec<_>>().join(", ")
+}
@@ -0,0 +1,233 @@
+use crate::binlog::{quote_identifier, BinlogOperation, OperationType};
+use crate::operation_applier::{quoted_columns, ApplyOutcome, OperationApplier, RowState, StateStore};
+
+/// Most INSERTs apply_operations combines into one statement by default
+pub const DEFAULT_BATCH_SIZE: usize = 256;
+
+impl<S: StateStore> OperationApplier<S> {
+    /// Applies operations in order like apply_operation_with_outcome, running consecutive INSERTs
+    /// into the same table and columns as multi-row statements of up to the batch size.
+    /// The INSERTs are still checked one by one, and one that could match a pending INSERT is
+    /// only checked once the pending ones are in the table, so the outcomes are those of applying
+    /// the operations one at a time.
+    pub fn apply_operations(&mut self, ops: &[&BinlogOperation]) -> Result<Vec<ApplyOutcome>, Box<dyn std::error::Error>> {
+        let mut outcomes = Vec::with_capacity(ops.len());
+        let mut pending: Vec<&BinlogOperation> = Vec::new();
+
+        for &op in ops {
+            let joins_batch = op.operation_type == OperationType::Insert
+                && pending.len() < self.batch_size
+                && pending.first().is_none_or(|first| first.table_name == op.table_name && first.columns == op.columns);
+            if !joins_batch || self.could_match_pending(op, &pending)? {
+                self.flush_inserts(&mut pending)?;
+            }
+
+            if op.operation_type != OperationType::Insert {
+                outcomes.push(self.apply_operation_with_outcome(op)?);
+                continue;
+            }
+            match self.row_state(op)? {
+                RowState::AlreadyApplied => outcomes.push(ApplyOutcome::AlreadyApplied),
+                _ => {
+                    pending.push(op);
+                    outcomes.push(ApplyOutcome::Applied);
+                }
+            }
+        }
+        self.flush_inserts(&mut pending)?;
+
+        Ok(outcomes)
+    }
+
+    pub fn get_batch_size(&self) -> usize {
+        self.batch_size
+    }
+
+    /// Most INSERTs apply_operations combines into one statement; 1 applies them one at a time
+    pub fn set_batch_size(&mut self, batch_size: usize) {
+        self.batch_size = batch_size.max(1);
+    }
+
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.set_batch_size(batch_size);
+        self
+    }
+
+    pub fn get_use_appender(&self) -> bool {
+        self.use_appender
+    }
+
+    /// Inserts batches with the store's bulk path (DuckDB's Appender) instead of multi-row
+    /// statements, when the INSERTs hold every column of the table and no JSON. Off by default.
+    pub fn set_use_appender(&mut self, use_appender: bool) {
+        self.use_appender = use_appender;
+    }
+
+    pub fn with_appender(mut self, use_appender: bool) -> Self {
+        self.set_use_appender(use_appender);
+        self
+    }
+
+    /// Whether the INSERT's row could be found by looking up a pending INSERT's row: every value
+    /// equal, or the key for tables with a comparator
+    fn could_match_pending(&mut self, op: &BinlogOperation, pending: &[&BinlogOperation]) -> Result<bool, Box<dyn std::error::Error>> {
+        if pending.is_empty() {
+            return Ok(false);
+        }
+        let compared: Vec<usize> = if self.comparators.contains_key(&op.table_name) {
+            let key_columns = self.key_columns(&op.table_name)?;
+            (0..op.columns.len()).filter(|&idx| key_columns.contains(&op.columns[idx])).collect()
+        } else {
+            (0..op.columns.len()).collect()
+        };
+        let (table_columns, table_types) = self.get_table_schema(&op.table_name);
+        let column_type = |idx: usize| table_columns.iter().position(|c| *c == op.columns[idx])
+            .map(|position| table_types[position].as_str())
+            .unwrap_or("");
+
+        let after = op.after_values.as_deref().unwrap_or_default();
+        Ok(pending.iter().any(|other| {
+            let other = other.after_values.as_deref().unwrap_or_default();
+            compared.iter().all(|&idx| match (after.get(idx), other.get(idx)) {
+                (Some(value), Some(other)) => self.value_normaliser.values_equal(value, other, column_type(idx)),
+                _ => true,
+            })
+        }))
+    }
+
+    /// Inserts the pending rows with one statement, or the Appender. If that fails, they are
+    /// inserted one at a time, so the rows before the failing one are in the table, as without
+    /// batching.
+    fn flush_inserts(&mut self, pending: &mut Vec<&BinlogOperation>) -> Result<(), Box<dyn std::error::Error>> {
+        if self.use_appender && pending.len() > 1 && self.append_pending(pending)? {
+            pending.clear();
+            return Ok(());
+        }
+        match pending.as_slice() {
+            [] => return Ok(()),
+            [op] => {
+                self.execute_operation(op)?;
+            }
+            [first, ..] => {
+                let json_columns = self.json_columns(&first.table_name);
+                let rows: Vec<String> = pending.iter()
+                    .map(|op| {
+                        let values = if json_columns.is_empty() {
+                            op.after_values.clone()
+                        } else {
+                            self.canonical_json(op, &json_columns).after_values
+                        };
+                        format!("({})", values.unwrap_or_default().join(", "))
+                    })
+                    .collect();
+                let sql = format!("INSERT INTO {} ({}) VALUES {};", quote_identifier(&first.table_name), quoted_columns(&first.columns), rows.join(", "));
+                if self.conn.execute(&sql).is_err() {
+                    for op in pending.iter() {
+                        self.execute_operation(op)?;
+                    }
+                }
+            }
+        }
+        pending.clear();
+        Ok(())
+    }
+
+    /// Appends the pending rows in table column order, false if none of them were appended
+    fn append_pending(&mut self, pending: &[&BinlogOperation]) -> Result<bool, Box<dyn std::error::Error>> {
+        let table = &pending[0].table_name;
+        let (table_columns, table_types) = self.get_table_schema(table);
+        if table_columns.is_empty() || table_types.iter().any(|column_type| column_type.eq_ignore_ascii_case("JSON")) {
+            return Ok(false);
+        }
+        // Columns the INSERTs leave out take their defaults, which only SQL fills in
+        let Some(order) = table_columns.iter()
+            .map(|column| pending[0].columns.iter().position(|c| c == column))
+            .collect::<Option<Vec<usize>>>() else {
+            return Ok(false);
+        };
+        let rows: Option<Vec<Vec<String>>> = pending.iter()
+            .map(|op| {
+                let values = op.after_values.as_ref()?;
+                order.iter().map(|&idx| values.get(idx).cloned()).collect()
+            })
+            .collect();
+        let Some(rows) = rows else {
+            return Ok(false);
+        };
+        self.conn.append_rows(table, &table_types, &rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use duckdb::Connection;
+    use super::*;
+    use crate::test_support::operation;
+
+    fn op(operation_type: OperationType, table: &str, before: Option<&[&str]>, after: Option<&[&str]>) -> BinlogOperation {
+        BinlogOperation { timestamp: Some("251108 10:00:00".to_string()), ..operation(operation_type, table, &["id", "title"], before, after) }
+    }
+
+    fn rows(conn: &Connection, table: &str) -> Vec<(i64, String)> {
+        let mut stmt = conn.prepare(&format!("SELECT id, title FROM {} ORDER BY rowid", table)).unwrap();
+        stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?))).unwrap().map(|row| row.unwrap()).collect()
+    }
+
+    #[test]
+    fn test_batched_apply_matches_applying_one_at_a_time() {
+        let ops = vec![
+            op(OperationType::Insert, "books", None, Some(&["1", "'Dune'"])),
+            op(OperationType::Insert, "books", None, Some(&["2", "'Emma'"])),
+            // Already applied by the pending INSERT before it
+            op(OperationType::Insert, "books", None, Some(&["2.0", "'Emma'"])),
+            op(OperationType::Insert, "authors", None, Some(&["1", "'Herbert'"])),
+            op(OperationType::Insert, "books", None, Some(&["3", "'Ulysses'"])),
+            op(OperationType::Update, "books", Some(&["3", "'Ulysses'"]), Some(&["3", "'Ulysses!'"])),
+            op(OperationType::Insert, "books", None, Some(&["4", "'Beloved'"])),
+            op(OperationType::Delete, "books", Some(&["1", "'Dune'"]), None),
+            // Already in the snapshot
+            op(OperationType::Insert, "books", None, Some(&["7", "'Walden'"])),
+        ];
+        let refs: Vec<&BinlogOperation> = ops.iter().collect();
+        let setup = "CREATE TABLE books (id INTEGER, title VARCHAR); CREATE TABLE authors (id INTEGER, title VARCHAR);
+                     INSERT INTO books VALUES (7, 'Walden');";
+
+        let mut one_at_a_time = OperationApplier::new(Connection::open_in_memory().unwrap());
+        one_at_a_time.get_connection().execute_batch(setup).unwrap();
+        let expected: Vec<ApplyOutcome> = ops.iter().map(|op| one_at_a_time.apply_operation_with_outcome(op).unwrap()).collect();
+
+        for (batch_size, use_appender) in [(1, false), (2, false), (DEFAULT_BATCH_SIZE, false), (DEFAULT_BATCH_SIZE, true)] {
+            let mut batched = OperationApplier::new(Connection::open_in_memory().unwrap())
+                .with_batch_size(batch_size)
+                .with_appender(use_appender);
+            batched.get_connection().execute_batch(setup).unwrap();
+
+            assert_eq!(batched.apply_operations(&refs).unwrap(), expected);
+            for table in ["books", "authors"] {
+                assert_eq!(rows(batched.get_connection(), table), rows(one_at_a_time.get_connection(), table));
+            }
+        }
+    }
+
+    #[test]
+    fn test_batch_failing_midway_inserts_each_row_once() {
+        let mut ops = vec![
+            op(OperationType::Insert, "books", None, Some(&["1", "12.50"])),
+            op(OperationType::Insert, "books", None, Some(&["2", "'twelve'"])),
+            op(OperationType::Insert, "books", None, Some(&["3", "3"])),
+        ];
+        for op in &mut ops {
+            op.columns = vec!["id".to_string(), "price".to_string()];
+        }
+        let refs: Vec<&BinlogOperation> = ops.iter().collect();
+
+        for use_appender in [false, true] {
+            let mut applier = OperationApplier::new(Connection::open_in_memory().unwrap()).with_appender(use_appender);
+            applier.get_connection().execute_batch("CREATE TABLE books (id INTEGER, price DECIMAL(10,2));").unwrap();
+
+            assert!(applier.apply_operations(&refs).is_err());
+            let count: i64 = applier.get_connection().query_row("SELECT COUNT(*) FROM books", [], |row| row.get(0)).unwrap();
+            assert_eq!(count, 1);
+        }
+    }
+}
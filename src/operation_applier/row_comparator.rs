@@ -0,0 +1,97 @@
+use crate::parser::value_normaliser::ValueNormaliser;
+
+/// One column of a row being compared with a row image
+#[derive(Debug, Clone, Copy)]
+pub struct ComparedColumn<'a> {
+    pub name: &'a str,
+    /// DuckDB type of the column, empty if the table doesn't have it
+    pub column_type: &'a str,
+    /// Whether the column is part of the table's primary key
+    pub is_key: bool,
+}
+
+/// Decides whether the current state of a row holds the values of a row image, which is how
+/// OperationApplier tells applicable, already applied and mismatching operations apart.
+/// Values are SQL literals, `NULL` for NULL. Columns a partial row image didn't log are left out.
+pub trait RowComparator: Send {
+    fn rows_match(&self, columns: &[ComparedColumn], current: &[String], image: &[String]) -> bool;
+}
+
+/// Built-in comparison strategies
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    /// Values must be equal once normalised for their column type (quoting, decimal scale,
+    /// booleans). This is how rows are compared when a table has no comparator.
+    Exact,
+    /// Like Exact, but numbers match if they differ by at most epsilon
+    NumericTolerant(f64),
+    /// Like Exact, but strings match ignoring case and surrounding whitespace, as with a
+    /// case-insensitive collation
+    CaseInsensitive,
+    /// Only the primary key columns have to match
+    PrimaryKeyOnly,
+}
+
+/// Shared by every comparison, which runs for each row an operation touches
+static NORMALISER: ValueNormaliser = ValueNormaliser;
+
+impl RowComparator for Comparison {
+    fn rows_match(&self, columns: &[ComparedColumn], current: &[String], image: &[String]) -> bool {
+        current.len() == image.len() && columns.iter()
+            .zip(current.iter().zip(image))
+            .all(|(column, (current, logged))| {
+                if NORMALISER.values_equal(current, logged, column.column_type) {
+                    return true;
+                }
+                match self {
+                    Comparison::Exact => false,
+                    Comparison::NumericTolerant(epsilon) => match (parse_number(current), parse_number(logged)) {
+                        (Some(current), Some(logged)) => (current - logged).abs() <= *epsilon,
+                        _ => false,
+                    },
+                    Comparison::CaseInsensitive => match (fold_case(current), fold_case(logged)) {
+                        (Some(current), Some(logged)) => current == logged,
+                        _ => false,
+                    },
+                    Comparison::PrimaryKeyOnly => !column.is_key,
+                }
+            })
+    }
+}
+
+fn parse_number(value: &str) -> Option<f64> {
+    value.trim_matches('\'').trim().parse().ok()
+}
+
+fn fold_case(value: &str) -> Option<String> {
+    let inner = value.strip_prefix('\'')?.strip_suffix('\'')?;
+    Some(inner.trim().to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_builtin_comparisons() {
+        let columns = [
+            ComparedColumn { name: "id", column_type: "INTEGER", is_key: true },
+            ComparedColumn { name: "price", column_type: "DOUBLE", is_key: false },
+            ComparedColumn { name: "title", column_type: "VARCHAR", is_key: false },
+        ];
+        let current = values(&["1", "9.99", "'Dune'"]);
+
+        assert!(Comparison::Exact.rows_match(&columns, &current, &values(&["1", "9.99", "'Dune'"])));
+        assert!(!Comparison::Exact.rows_match(&columns, &current, &values(&["1", "9.990001", "'Dune'"])));
+        assert!(Comparison::NumericTolerant(0.001).rows_match(&columns, &current, &values(&["1", "9.990001", "'Dune'"])));
+        assert!(!Comparison::NumericTolerant(0.001).rows_match(&columns, &current, &values(&["1", "9.9", "'Dune'"])));
+        assert!(Comparison::CaseInsensitive.rows_match(&columns, &current, &values(&["1", "9.99", "'DUNE '"])));
+        assert!(!Comparison::CaseInsensitive.rows_match(&columns, &current, &values(&["1", "9.99", "'Dunes'"])));
+        assert!(Comparison::PrimaryKeyOnly.rows_match(&columns, &current, &values(&["1", "5", "'Emma'"])));
+        assert!(!Comparison::PrimaryKeyOnly.rows_match(&columns, &current, &values(&["2", "9.99", "'Dune'"])));
+    }
+}
@@ -0,0 +1,68 @@
+use rusqlite::Connection;
+use crate::operation_applier::StateStore;
+
+/// SQLite backend, for environments where DuckDB's native build is a problem and for lighter tests.
+/// Only the OperationApplier runs on it: SnapshotManager and the loaders need DuckDB.
+impl StateStore for Connection {
+    fn execute(&self, sql: &str) -> Result<usize, Box<dyn std::error::Error>> {
+        Ok(Connection::execute(self, sql, [])?)
+    }
+
+    fn query_row(&self, sql: &str) -> Result<Option<Vec<Option<String>>>, Box<dyn std::error::Error>> {
+        let mut stmt = self.prepare(sql)?;
+        let column_count = stmt.column_count();
+        let mut rows = stmt.query([])?;
+        let Some(row) = rows.next()? else {
+            return Ok(None);
+        };
+        let values = (0..column_count)
+            .map(|i| row.get::<usize, Option<String>>(i))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Some(values))
+    }
+
+    fn table_schema(&self, table: &str) -> Result<Vec<(String, String)>, Box<dyn std::error::Error>> {
+        let mut stmt = self.prepare("SELECT name, type FROM pragma_table_info(?1) ORDER BY cid")?;
+        let columns = stmt
+            .query_map([table], |row| Ok((row.get::<usize, String>(0)?, row.get::<usize, String>(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(columns)
+    }
+
+    fn primary_key_columns(&self, table: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let mut stmt = self.prepare("SELECT name FROM pragma_table_info(?1) WHERE pk > 0 ORDER BY pk")?;
+        let key_columns = stmt
+            .query_map([table], |row| row.get::<usize, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(key_columns)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rusqlite::Connection;
+    use crate::binlog::{BinlogOperation, OperationType};
+    use crate::operation_applier::{ApplyOutcome, OperationApplier};
+    use crate::test_support::operation;
+
+    fn op(operation_type: OperationType, before: Option<[&str; 3]>, after: Option<[&str; 3]>) -> BinlogOperation {
+        operation(operation_type, "books", &["id", "title", "price"], before.as_ref().map(|v| &v[..]), after.as_ref().map(|v| &v[..]))
+    }
+
+    #[test]
+    fn test_applier_runs_on_sqlite() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE books (id INTEGER PRIMARY KEY, title VARCHAR, price DOUBLE);").unwrap();
+        let mut applier = OperationApplier::new(conn);
+
+        let insert = op(OperationType::Insert, None, Some(["1", "'Dune'", "9.5"]));
+        let update = op(OperationType::Update, Some(["1", "'Dune'", "9.5"]), Some(["1", "NULL", "12.0"]));
+        assert_eq!(applier.apply_operation_with_outcome(&insert).unwrap(), ApplyOutcome::Applied);
+        assert_eq!(applier.apply_operation_with_outcome(&insert).unwrap(), ApplyOutcome::AlreadyApplied);
+        assert_eq!(applier.apply_operation_with_outcome(&update).unwrap(), ApplyOutcome::Applied);
+        assert_eq!(applier.apply_operation_with_outcome(&update.invert()).unwrap(), ApplyOutcome::Applied);
+
+        let row = applier.fetch_row_by_key("books", &["id".to_string()], &["1".to_string()]).unwrap();
+        assert_eq!(row, Some(vec!["1".to_string(), "'Dune'".to_string(), "9.5".to_string()]));
+    }
+}
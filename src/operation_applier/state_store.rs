@@ -0,0 +1,189 @@
+use duckdb::Connection;
+use duckdb::appender_params_from_iter;
+use duckdb::types::Value;
+use tracing::debug;
+use crate::binlog::table_info_query;
+
+/// The database an OperationApplier applies operations to. Implemented for DuckDB's Connection,
+/// and for rusqlite's with the `sqlite` feature.
+///
+/// Statements are generated as plain SQL with quoted identifiers (`INSERT INTO "t" ("a", "b")
+/// VALUES (...)`, `CAST("a" AS VARCHAR)`, `"a" IS NULL`), which both engines accept.
+pub trait StateStore {
+    /// Executes a statement, returning the number of rows it changed
+    fn execute(&self, sql: &str) -> Result<usize, Box<dyn std::error::Error>>;
+
+    /// First row of a query whose columns are all text, None for NULL values; None if the query
+    /// returns no row
+    fn query_row(&self, sql: &str) -> Result<Option<Vec<Option<String>>>, Box<dyn std::error::Error>>;
+
+    /// (column name, declared type) of each column of a table, in column order; empty if there is
+    /// no such table
+    fn table_schema(&self, table: &str) -> Result<Vec<(String, String)>, Box<dyn std::error::Error>>;
+
+    /// Column names of a table, in column order; empty if there is no such table
+    fn table_columns(&self, table: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        Ok(self.table_schema(table)?.into_iter().map(|(name, _)| name).collect())
+    }
+
+    /// Primary key columns of a table, in key order; empty if it has no primary key
+    fn primary_key_columns(&self, table: &str) -> Result<Vec<String>, Box<dyn std::error::Error>>;
+
+    /// Inserts rows of SQL literals, in table column order, without going through SQL. Returns
+    /// false without inserting anything if the store can't, can't convert a value to its
+    /// column's type (`column_types`) or fails to insert a row, so the caller falls back to
+    /// INSERT statements. Errors only if the store is left in an unknown state.
+    fn append_rows(&self, _table: &str, _column_types: &[String], _rows: &[Vec<String>]) -> Result<bool, Box<dyn std::error::Error>> {
+        Ok(false)
+    }
+}
+
+impl StateStore for Connection {
+    fn execute(&self, sql: &str) -> Result<usize, Box<dyn std::error::Error>> {
+        Ok(Connection::execute(self, sql, [])?)
+    }
+
+    fn query_row(&self, sql: &str) -> Result<Option<Vec<Option<String>>>, Box<dyn std::error::Error>> {
+        let mut stmt = self.prepare(sql)?;
+        let mut rows = stmt.query([])?;
+        let column_count = rows.as_ref().map(|stmt| stmt.column_count()).unwrap_or(0);
+        let Some(row) = rows.next()? else {
+            return Ok(None);
+        };
+        let values = (0..column_count)
+            .map(|i| row.get::<usize, Option<String>>(i))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Some(values))
+    }
+
+    fn table_schema(&self, table: &str) -> Result<Vec<(String, String)>, Box<dyn std::error::Error>> {
+        // The pragma fails for missing tables
+        let exists: i64 = Connection::query_row(
+            self,
+            "SELECT COUNT(*) FROM duckdb_tables() WHERE schema_name = 'main' AND table_name = ?",
+            [table],
+            |row| row.get(0),
+        )?;
+        if exists == 0 {
+            return Ok(Vec::new());
+        }
+        let mut stmt = self.prepare(&table_info_query(table))?;
+        let columns = stmt
+            .query_map([], |row| Ok((row.get::<usize, String>(1)?, row.get::<usize, String>(2)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(columns)
+    }
+
+    fn primary_key_columns(&self, table: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let mut stmt = self.prepare(
+            "SELECT unnest(constraint_column_names) FROM duckdb_constraints() \
+             WHERE table_name = ? AND constraint_type = 'PRIMARY KEY'"
+        )?;
+        let key_columns = stmt
+            .query_map([table], |row| row.get::<usize, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(key_columns)
+    }
+
+    /// Uses DuckDB's Appender, which casts text values to types like DECIMAL and TIMESTAMP itself.
+    /// An Appender flushes the rows appended so far when it's dropped, even after a failed row,
+    /// so the rows are appended in a transaction that is rolled back if any of them fails. Inside
+    /// a caller's transaction there is nothing to roll back to, so nothing is appended.
+    fn append_rows(&self, table: &str, column_types: &[String], rows: &[Vec<String>]) -> Result<bool, Box<dyn std::error::Error>> {
+        let typed: Option<Vec<Vec<Value>>> = rows.iter()
+            .map(|row| row.iter().zip(column_types).map(|(value, column_type)| typed_value(value, column_type)).collect())
+            .collect();
+        let Some(typed) = typed else {
+            return Ok(false);
+        };
+        if self.execute_batch("BEGIN TRANSACTION;").is_err() {
+            return Ok(false);
+        }
+        let append = || -> duckdb::Result<()> {
+            let mut appender = self.appender(table)?;
+            for row in typed {
+                appender.append_row(appender_params_from_iter(row))?;
+            }
+            appender.flush()
+        };
+        match append() {
+            Ok(()) => {
+                self.execute_batch("COMMIT;")?;
+                Ok(true)
+            }
+            Err(e) => {
+                debug!("Appending to {} failed, falling back to INSERT statements: {}", table, e);
+                self.execute_batch("ROLLBACK;")?;
+                Ok(false)
+            }
+        }
+    }
+}
+
+/// An SQL literal as a value of its column's DuckDB type, None for literals that need SQL to
+/// evaluate (`unhex(...)`) or types the Appender fast path leaves to INSERT statements
+fn typed_value(literal: &str, column_type: &str) -> Option<Value> {
+    if literal == "NULL" {
+        return Some(Value::Null);
+    }
+    let text = || literal.strip_prefix('\'')?.strip_suffix('\'').map(|inner| inner.replace("''", "'"));
+    let column_type = column_type.to_ascii_uppercase();
+    match column_type.as_str() {
+        "TINYINT" | "SMALLINT" | "INTEGER" | "BIGINT" | "UTINYINT" | "USMALLINT" | "UINTEGER" => {
+            literal.parse().ok().map(Value::BigInt)
+        }
+        "UBIGINT" => literal.parse().ok().map(Value::UBigInt),
+        "FLOAT" | "DOUBLE" | "REAL" => literal.parse().ok().map(Value::Double),
+        "BOOLEAN" => match literal {
+            "1" | "true" | "TRUE" => Some(Value::Boolean(true)),
+            "0" | "false" | "FALSE" => Some(Value::Boolean(false)),
+            _ => None,
+        },
+        "VARCHAR" | "TEXT" => text().map(Value::Text),
+        _ if column_type.starts_with("DECIMAL") || column_type.starts_with("TIMESTAMP")
+            || column_type == "DATE" || column_type == "TIME" => {
+            text().or_else(|| literal.parse::<f64>().is_ok().then(|| literal.to_string())).map(Value::Text)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_append_rows_converts_literals_through_column_types() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE books (id INTEGER, title VARCHAR, price DECIMAL(10,2), published DATE, in_print BOOLEAN)").unwrap();
+        let types: Vec<String> = conn.table_schema("books").unwrap().into_iter().map(|(_, column_type)| column_type).collect();
+        let row = |values: &[&str]| values.iter().map(|v| v.to_string()).collect::<Vec<String>>();
+
+        let rows = vec![row(&["1", "'O''Brien'", "12.50", "'2025-11-08'", "1"]), row(&["2", "NULL", "3", "NULL", "0"])];
+        assert!(conn.append_rows("books", &types, &rows).unwrap());
+        assert!(!conn.append_rows("books", &types, &[row(&["3", "unhex('00')", "1", "NULL", "1"])]).unwrap());
+
+        let text: Vec<Option<String>> = StateStore::query_row(&conn, "SELECT CAST(title AS VARCHAR), CAST(price AS VARCHAR), CAST(published AS VARCHAR), CAST(in_print AS VARCHAR) FROM books WHERE id = 1").unwrap().unwrap();
+        assert_eq!(text, vec![Some("O'Brien".to_string()), Some("12.50".to_string()), Some("2025-11-08".to_string()), Some("true".to_string())]);
+        assert_eq!(StateStore::query_row(&conn, "SELECT CAST(COUNT(*) AS VARCHAR) FROM books").unwrap(), Some(vec![Some("2".to_string())]));
+    }
+
+    #[test]
+    fn test_failed_append_leaves_no_rows() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE books (id INTEGER, price DECIMAL(10,2))").unwrap();
+        let types = vec!["INTEGER".to_string(), "DECIMAL(10,2)".to_string()];
+        let row = |values: &[&str]| values.iter().map(|v| v.to_string()).collect::<Vec<String>>();
+        let count = || StateStore::query_row(&conn, "SELECT CAST(COUNT(*) AS VARCHAR) FROM books").unwrap();
+
+        // The second row's price can't be cast, after the first row was appended
+        let rows = vec![row(&["1", "12.50"]), row(&["2", "'twelve'"]), row(&["3", "1"])];
+        assert!(!conn.append_rows("books", &types, &rows).unwrap());
+        assert_eq!(count(), Some(vec![Some("0".to_string())]));
+
+        // Nothing is appended inside a caller's transaction
+        conn.execute_batch("BEGIN TRANSACTION;").unwrap();
+        assert!(!conn.append_rows("books", &types, &[row(&["1", "12.50"])]).unwrap());
+        conn.execute_batch("ROLLBACK;").unwrap();
+    }
+}
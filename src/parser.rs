@@ -1,2 +1,17 @@
+pub mod binlog_dialect;
+pub mod binlog_profile;
+pub mod binlog_reader;
+pub mod column_mapping;
+pub mod line_tokenizer;
+pub mod live_tail;
+pub mod operation_cache;
+pub mod operation_validation;
+pub mod parse_diagnostics;
+pub mod replace_fusion;
+pub mod schema_history;
 pub mod sql_binlog_parser;
-pub mod text_binlog_parser;
\ No newline at end of file
+pub mod table_filter;
+pub mod text_binlog_parser;
+pub mod timestamp_backfill;
+pub mod value_decoder;
+pub mod value_normaliser;
\ No newline at end of file
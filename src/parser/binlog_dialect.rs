@@ -0,0 +1,47 @@
+use std::io::BufRead;
+use crate::parser::{binlog_reader, line_tokenizer};
+
+/// Lines read from the start of a file to detect its dialect. The format description event
+/// naming the server version comes first.
+const DETECTION_LINES: usize = 200;
+
+/// Flavour of `mysqlbinlog --verbose` text. MariaDB's differs in its GTID events, starts
+/// transactions with `START TRANSACTION` and prints the statement of each row event as
+/// `#Q>` lines (ANNOTATE_ROWS).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BinlogDialect {
+    #[default]
+    MySql,
+    MariaDb,
+}
+
+impl BinlogDialect {
+    /// Dialect of the lines at the start of a binlog: MariaDB if the server version or a GTID
+    /// or ANNOTATE_ROWS event says so, MySQL otherwise
+    pub fn detect<'a>(lines: impl IntoIterator<Item = &'a str>) -> Self {
+        let mariadb = lines.into_iter().take(DETECTION_LINES).any(|line| {
+            line.starts_with('#')
+                && (line.contains("-MariaDB") || line.contains("Annotate_rows") || line_tokenizer::mariadb_gtid(line).is_some())
+        });
+        if mariadb { BinlogDialect::MariaDb } else { BinlogDialect::MySql }
+    }
+
+    pub fn detect_file(filepath: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let lines: Vec<String> = binlog_reader::open_binlog(filepath, 64 * 1024)?
+            .split(b'\n')
+            .take(DETECTION_LINES)
+            .map(|line| line.map(|bytes| String::from_utf8_lossy(&bytes).to_string()))
+            .collect::<Result<_, _>>()?;
+        Ok(Self::detect(lines.iter().map(|line| line.as_str())))
+    }
+
+    /// Whether the line starts a transaction
+    pub fn is_begin(&self, line: &str) -> bool {
+        line.starts_with("BEGIN") || *self == BinlogDialect::MariaDb && line.starts_with("START TRANSACTION")
+    }
+
+    /// Whether the line is part of a MariaDB ANNOTATE_ROWS event's statement
+    pub fn is_annotation(&self, line: &str) -> bool {
+        *self == BinlogDialect::MariaDb && line.starts_with("#Q>")
+    }
+}
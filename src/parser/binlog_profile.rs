@@ -0,0 +1,209 @@
+use std::collections::BTreeMap;
+use std::io::BufRead;
+use tracing::info;
+use crate::binlog::{BinlogTimestamp, OperationType};
+use crate::parser::binlog_reader;
+use crate::parser::line_tokenizer;
+
+/// Size of one committed transaction, for BinlogProfile::largest_transactions
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransactionSize {
+    pub file: String,
+    /// Timestamp of the transaction's last event
+    pub timestamp: Option<String>,
+    /// end_log_pos of the transaction's last event
+    pub position: Option<u32>,
+    pub gtid: Option<String>,
+    pub operations: usize,
+    /// Tables the transaction changed, as `database.table`
+    pub tables: Vec<String>,
+}
+
+/// What a set of binlog files contains, gathered by scanning their lines without building
+/// operations or opening a database. Only committed row changes are counted, like the parser.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BinlogProfile {
+    pub files: usize,
+    /// Timestamps of the first and last events
+    pub first_timestamp: Option<String>,
+    pub last_timestamp: Option<String>,
+    pub transactions: usize,
+    pub operations: usize,
+    /// Operations per table (`database.table`), per hour (`YYMMDD HH:00`), per operation type
+    pub hourly: BTreeMap<String, BTreeMap<String, BTreeMap<String, usize>>>,
+    /// The transactions with the most operations, largest first
+    pub largest_transactions: Vec<TransactionSize>,
+}
+
+impl BinlogProfile {
+    /// Tables touched, with their operation counts
+    pub fn operations_per_table(&self) -> BTreeMap<&str, usize> {
+        self.hourly.iter()
+            .map(|(table, hours)| (table.as_str(), hours.values().flat_map(|counts| counts.values()).sum()))
+            .collect()
+    }
+}
+
+/// Profiles text binlog files (plain or compressed), keeping the `top_transactions` largest
+/// transactions. Quick enough to run before deciding whether, and how, to load a snapshot.
+pub fn profile_binlogs(files: &[String], top_transactions: usize) -> Result<BinlogProfile, Box<dyn std::error::Error>> {
+    let mut profile = BinlogProfile { files: files.len(), ..BinlogProfile::default() };
+    for file in files {
+        let reader = binlog_reader::open_binlog(file, 10 * 1024 * 1024)?;
+        profile_reader(&mut profile, file, reader, top_transactions)?;
+        info!("Profiled {}: {} operation(s) so far", file, profile.operations);
+    }
+    Ok(profile)
+}
+
+fn profile_reader<R: BufRead>(
+    profile: &mut BinlogProfile,
+    file: &str,
+    reader: R,
+    top_transactions: usize,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut timestamp: Option<String> = None;
+    let mut position: Option<u32> = None;
+    let mut gtid: Option<String> = None;
+    // (table, hour, operation type) of each row change of the open transaction
+    let mut pending: Vec<(String, String, OperationType)> = Vec::new();
+
+    for line in reader.split(b'\n') {
+        let line = String::from_utf8_lossy(&line?).to_string();
+
+        if let Some((date, time)) = line_tokenizer::event_timestamp(&line) {
+            let event_timestamp = format!("{} {}", date, time);
+            if profile.first_timestamp.is_none() {
+                profile.first_timestamp = Some(event_timestamp.clone());
+            }
+            profile.last_timestamp = Some(event_timestamp.clone());
+            timestamp = Some(event_timestamp);
+            position = line_tokenizer::end_log_pos(&line).or(position);
+            if let Some((mariadb_gtid, _)) = line_tokenizer::mariadb_gtid(&line) {
+                gtid = Some(mariadb_gtid.to_string());
+            }
+            continue;
+        }
+        if let Some(next) = line_tokenizer::gtid_next(&line) {
+            gtid = Some(next.to_string());
+        } else if line.starts_with("BEGIN") || line.starts_with("START TRANSACTION") || line.starts_with("ROLLBACK") {
+            pending.clear();
+        } else if line.starts_with("COMMIT") {
+            if !pending.is_empty() {
+                let transaction = TransactionSize {
+                    file: file.to_string(),
+                    timestamp: timestamp.clone(),
+                    position,
+                    gtid: gtid.clone(),
+                    operations: pending.len(),
+                    tables: transaction_tables(&pending),
+                };
+                record_transaction(profile, transaction, top_transactions);
+                for (table, hour, operation_type) in pending.drain(..) {
+                    *profile.hourly.entry(table).or_default()
+                        .entry(hour).or_default()
+                        .entry(operation_type.to_string()).or_default() += 1;
+                    profile.operations += 1;
+                }
+            }
+            gtid = None;
+        } else if let Some((operation_type, table)) = row_change(&line) {
+            let hour = timestamp.as_deref()
+                .and_then(|ts| BinlogTimestamp::parse(ts).ok())
+                .map(|ts| ts.as_datetime().format("%y%m%d %H:00").to_string())
+                .unwrap_or_else(|| "unknown".to_string());
+            pending.push((table, hour, operation_type));
+        }
+    }
+    Ok(())
+}
+
+/// Operation type and `database.table` of a row image header, like ``### UPDATE `main`.`books` ``
+fn row_change(line: &str) -> Option<(OperationType, String)> {
+    let (operation_type, table_path) = if let Some(rest) = line.strip_prefix("### INSERT INTO") {
+        (OperationType::Insert, rest)
+    } else if let Some(rest) = line.strip_prefix("### UPDATE") {
+        (OperationType::Update, rest)
+    } else if let Some(rest) = line.strip_prefix("### DELETE FROM") {
+        (OperationType::Delete, rest)
+    } else {
+        return None;
+    };
+    Some((operation_type, table_path.trim().replace('`', "")))
+}
+
+fn transaction_tables(pending: &[(String, String, OperationType)]) -> Vec<String> {
+    let mut tables: Vec<String> = pending.iter().map(|(table, _, _)| table.clone()).collect();
+    tables.sort();
+    tables.dedup();
+    tables
+}
+
+fn record_transaction(profile: &mut BinlogProfile, transaction: TransactionSize, top_transactions: usize) {
+    profile.transactions += 1;
+    let largest = &mut profile.largest_transactions;
+    let index = largest.partition_point(|t| t.operations >= transaction.operations);
+    if index < top_transactions {
+        largest.insert(index, transaction);
+        largest.truncate(top_transactions);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BINLOG: &str = "\
+#251108 10:59:58 server id 1  end_log_pos 100 CRC32 0x1 \tGTID\tlast_committed=0\tsequence_number=1
+SET @@SESSION.GTID_NEXT= 'uuid:1'/*!*/;
+BEGIN
+#251108 10:59:59 server id 1  end_log_pos 200 CRC32 0x1 \tWrite_rows: table id 1 flags: STMT_END_F
+### INSERT INTO `shop`.`books`
+### SET
+###   @1=1
+COMMIT/*!*/;
+BEGIN
+#251108 11:00:01 server id 1  end_log_pos 300 CRC32 0x1 \tUpdate_rows: table id 1 flags: STMT_END_F
+### UPDATE `shop`.`books`
+### WHERE
+###   @1=1
+### SET
+###   @1=2
+### DELETE FROM `shop`.`authors`
+### WHERE
+###   @1=7
+#251108 11:00:02 server id 1  end_log_pos 400 CRC32 0x1 \tXid = 5
+COMMIT/*!*/;
+BEGIN
+### DELETE FROM `shop`.`books`
+### WHERE
+###   @1=2
+ROLLBACK/*!*/;
+";
+
+    #[test]
+    fn test_profile_counts_committed_changes() {
+        let path = std::env::temp_dir().join(format!("pensieve_profile_{}.sql", std::process::id()));
+        std::fs::write(&path, BINLOG).unwrap();
+        let file = path.to_string_lossy().to_string();
+
+        let profile = profile_binlogs(std::slice::from_ref(&file), 1).unwrap();
+
+        assert_eq!((profile.files, profile.transactions, profile.operations), (1, 2, 3));
+        assert_eq!(profile.first_timestamp.as_deref(), Some("251108 10:59:58"));
+        assert_eq!(profile.last_timestamp.as_deref(), Some("251108 11:00:02"));
+        assert_eq!(profile.operations_per_table(), BTreeMap::from([("shop.authors", 1), ("shop.books", 2)]));
+        assert_eq!(profile.hourly["shop.books"]["251108 10:00"]["INSERT"], 1);
+        assert_eq!(profile.hourly["shop.books"]["251108 11:00"]["UPDATE"], 1);
+        assert_eq!(profile.largest_transactions, vec![TransactionSize {
+            file,
+            timestamp: Some("251108 11:00:02".to_string()),
+            position: Some(400),
+            gtid: None,
+            operations: 2,
+            tables: vec!["shop.authors".to_string(), "shop.books".to_string()],
+        }]);
+
+        std::fs::remove_file(path).ok();
+    }
+}
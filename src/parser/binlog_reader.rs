@@ -0,0 +1,80 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+/// File name suffixes of text binlogs: plain, gzipped or zstd-compressed
+pub const BINLOG_SUFFIXES: [&str; 3] = [".sql", ".sql.gz", ".sql.zst"];
+
+/// How a binlog file is compressed, going by its file name
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinlogCompression {
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl BinlogCompression {
+    pub fn from_path(path: &str) -> Self {
+        if path.ends_with(".gz") {
+            BinlogCompression::Gzip
+        } else if path.ends_with(".zst") {
+            BinlogCompression::Zstd
+        } else {
+            BinlogCompression::None
+        }
+    }
+}
+
+/// Returns true if the file name is that of a text binlog (see BINLOG_SUFFIXES)
+pub fn is_binlog_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| BINLOG_SUFFIXES.iter().any(|suffix| name.ends_with(suffix)))
+}
+
+/// Name of the binlog file a text file was dumped from: `mysql-bin.000042` for `mysql-bin.000042.sql.gz`
+pub fn binlog_file_name(filepath: &str) -> &str {
+    let name = Path::new(filepath).file_name().and_then(|name| name.to_str()).unwrap_or(filepath);
+    BINLOG_SUFFIXES.iter().find_map(|suffix| name.strip_suffix(suffix)).unwrap_or(name)
+}
+
+/// Opens a binlog file for reading line by line, decompressing it on the fly if it is
+/// gzipped or zstd-compressed
+pub fn open_binlog(path: &str, capacity: usize) -> io::Result<Box<dyn BufRead + Send>> {
+    let file = File::open(path)?;
+    Ok(match BinlogCompression::from_path(path) {
+        BinlogCompression::None => Box::new(BufReader::with_capacity(capacity, file)),
+        BinlogCompression::Gzip => Box::new(BufReader::with_capacity(capacity, flate2::read::MultiGzDecoder::new(file))),
+        BinlogCompression::Zstd => Box::new(BufReader::with_capacity(capacity, zstd::Decoder::new(file)?)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_open_binlog_decompresses() {
+        let content = "BEGIN\n### INSERT INTO `main`.`books`\nCOMMIT\n";
+        let dir = std::env::temp_dir();
+        let gz_path = dir.join(format!("binlog_reader_{}.sql.gz", std::process::id()));
+        let zst_path = dir.join(format!("binlog_reader_{}.sql.zst", std::process::id()));
+
+        let mut encoder = flate2::write::GzEncoder::new(File::create(&gz_path).unwrap(), flate2::Compression::default());
+        encoder.write_all(content.as_bytes()).unwrap();
+        encoder.finish().unwrap();
+        std::fs::write(&zst_path, zstd::encode_all(content.as_bytes(), 0).unwrap()).unwrap();
+
+        for path in [&gz_path, &zst_path] {
+            assert!(is_binlog_file(path));
+            let lines: Vec<String> = open_binlog(path.to_str().unwrap(), 1024).unwrap()
+                .lines()
+                .map(|line| line.unwrap())
+                .collect();
+            assert_eq!(lines, vec!["BEGIN", "### INSERT INTO `main`.`books`", "COMMIT"]);
+            std::fs::remove_file(path).ok();
+        }
+        assert!(!is_binlog_file(Path::new("books.parquet")));
+    }
+}
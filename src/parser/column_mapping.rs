@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+use std::path::Path;
+use duckdb::Connection;
+use crate::operation_applier::StateStore;
+use crate::loader::column_projection::ColumnProjection;
+
+/// Name of the per-table mapping file, looked up in each table's db_data directory
+pub const COLUMN_MAPPING_FILE: &str = "column_mapping.json";
+
+/// Maps binlog column positions onto snapshot column names, for tables whose parquet export
+/// renamed or reordered columns.
+///
+/// Binlog row events only carry column numbers (`@1`, `@2`, ...), which are normally matched
+/// against the table's DuckDB schema in order. A mapping replaces that schema for its table with
+/// the binlog's own column order, renamed to the parquet names.
+///
+/// Mapping files are JSON:
+///
+/// ```json
+/// {
+///   "binlog_columns": ["id", "user_id", "name"],
+///   "rename": { "user_id": "userId" }
+/// }
+/// ```
+///
+/// `binlog_columns` lists every column of the MySQL table in binlog order; `rename` maps binlog
+/// names to parquet names where they differ.
+#[derive(Debug, Clone, Default)]
+pub struct ColumnMapping {
+    tables: HashMap<String, Vec<String>>,
+}
+
+impl ColumnMapping {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maps the binlog columns of `table`, in order, onto these snapshot column names
+    pub fn with_table(mut self, table: &str, columns: &[&str]) -> Self {
+        self.tables.insert(table.to_string(), columns.iter().map(|c| c.to_string()).collect());
+        self
+    }
+
+    /// Adds the mapping for `table` from the contents of a mapping file
+    pub fn with_table_json(mut self, table: &str, json: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let mapping: serde_json::Value = serde_json::from_str(json)?;
+
+        let binlog_columns = mapping.get("binlog_columns")
+            .and_then(|columns| columns.as_array())
+            .ok_or_else(|| format!("Column mapping for {} has no binlog_columns array", table))?;
+        let empty = serde_json::Map::new();
+        let rename = match mapping.get("rename") {
+            Some(rename) => rename.as_object()
+                .ok_or_else(|| format!("Column mapping for {}: rename must be an object", table))?,
+            None => &empty,
+        };
+
+        let mut columns = Vec::with_capacity(binlog_columns.len());
+        for column in binlog_columns {
+            let name = column.as_str()
+                .ok_or_else(|| format!("Column mapping for {}: binlog_columns must be strings", table))?;
+            let renamed = match rename.get(name) {
+                Some(renamed) => renamed.as_str()
+                    .ok_or_else(|| format!("Column mapping for {}: rename of {} must be a string", table, name))?,
+                None => name,
+            };
+            columns.push(renamed.to_string());
+        }
+        if let Some(unknown) = rename.keys().find(|name| !binlog_columns.iter().any(|c| c.as_str() == Some(name.as_str()))) {
+            return Err(format!("Column mapping for {} renames {}, which isn't in binlog_columns", table, unknown).into());
+        }
+
+        self.tables.insert(table.to_string(), columns);
+        Ok(self)
+    }
+
+    /// Adds the mapping in `table_dir`'s mapping file, if it has one
+    pub fn with_table_dir(self, table: &str, table_dir: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let path = table_dir.join(COLUMN_MAPPING_FILE);
+        if !path.exists() {
+            return Ok(self);
+        }
+        let json = std::fs::read_to_string(&path)?;
+        self.with_table_json(table, &json)
+            .map_err(|e| format!("{}: {}", path.display(), e).into())
+    }
+
+    /// Snapshot column names for each binlog column of `table`, if it is mapped
+    pub fn columns(&self, table: &str) -> Option<&Vec<String>> {
+        self.tables.get(table)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tables.is_empty()
+    }
+
+    /// Checks that every mapped column exists in the snapshot, except those left out by the projection
+    pub fn validate(&self, conn: &Connection, projection: &ColumnProjection) -> Result<(), Box<dyn std::error::Error>> {
+        for (table, columns) in &self.tables {
+            let loaded = |column: &&String| projection.columns(table).is_none_or(|projected| projected.contains(column));
+            let snapshot_columns = conn.table_columns(table)?;
+            if snapshot_columns.is_empty() {
+                return Err(format!("Column mapping for {}: no such table in the snapshot", table).into());
+            }
+            if let Some(missing) = columns.iter().filter(loaded).find(|column| !snapshot_columns.contains(column)) {
+                return Err(format!("Column mapping for {}: snapshot has no column {}", table, missing).into());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mapping_file_renames_binlog_columns() {
+        let mapping = ColumnMapping::new().with_table_json(
+            "users",
+            r#"{"binlog_columns": ["id", "user_id", "name"], "rename": {"user_id": "userId"}}"#,
+        ).unwrap();
+        assert_eq!(mapping.columns("users").unwrap(), &vec!["id", "userId", "name"]);
+        assert!(mapping.columns("books").is_none());
+
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE users (name VARCHAR, id INTEGER, userId INTEGER);").unwrap();
+        assert!(mapping.validate(&conn, &ColumnProjection::new()).is_ok());
+
+        assert!(ColumnMapping::new().with_table_json("users", r#"{"binlog_columns": ["id"], "rename": {"uid": "x"}}"#).is_err());
+        let missing = ColumnMapping::new().with_table("users", &["id", "user_id"]);
+        assert!(missing.validate(&conn, &ColumnProjection::new()).is_err());
+        assert!(missing.validate(&conn, &ColumnProjection::new().with_table("users", &["id"])).is_ok());
+    }
+}
@@ -0,0 +1,204 @@
+/// Column number and raw value of a row image line, like `###   @3='abc'`.
+/// Same as `^###\s+@(\d+)=(.*)$`.
+pub fn column_value(line: &str) -> Option<(&str, &str)> {
+    let rest = line.strip_prefix("###")?;
+    let rest = skip_whitespace(rest, 1)?;
+    let rest = rest.strip_prefix('@')?;
+    let (number, rest) = split_digits(rest, 1, usize::MAX)?;
+    let value = rest.strip_prefix('=')?;
+    // `.` doesn't match a newline
+    if value.contains('\n') {
+        return None;
+    }
+    Some((number, value))
+}
+
+/// Date and time of an event header line, like `#251020 19:43:32 server id 1 ...`, with the
+/// fractional seconds if there are any. Same as `^#(\d{6})\s+(\d{1,2}:\d{2}:\d{2}(?:\.\d{1,6})?)`.
+pub fn event_timestamp(line: &str) -> Option<(&str, &str)> {
+    let rest = line.strip_prefix('#')?;
+    let (date, rest) = split_digits(rest, 6, 6)?;
+    let time_start = skip_whitespace(rest, 1)?;
+
+    let (_, rest) = split_digits(time_start, 1, 2)?;
+    let rest = rest.strip_prefix(':')?;
+    let (_, rest) = split_digits(rest, 2, 2)?;
+    let rest = rest.strip_prefix(':')?;
+    let seconds = rest.get(..2).filter(|s| s.bytes().all(|b| b.is_ascii_digit()))?;
+    let fraction = rest[2..].strip_prefix('.')
+        .and_then(|fraction| split_digits(fraction, 1, 6))
+        .map_or(0, |(digits, _)| digits.len() + 1);
+    let time_len = time_start.len() - rest.len() + seconds.len() + fraction;
+    Some((date, &time_start[..time_len]))
+}
+
+/// Value of the first `end_log_pos <digits>` in the line, if it fits in a u32.
+/// Same as `end_log_pos\s+(\d+)` followed by a u32 parse.
+pub fn end_log_pos(line: &str) -> Option<u32> {
+    const MARKER: &str = "end_log_pos";
+    let mut search = line;
+    while let Some(idx) = search.find(MARKER) {
+        let rest = &search[idx + MARKER.len()..];
+        if let Some((digits, _)) = skip_whitespace(rest, 1).and_then(|rest| split_digits(rest, 1, usize::MAX)) {
+            return digits.parse().ok();
+        }
+        search = rest;
+    }
+    None
+}
+
+/// Server id of an event header line, like `#251020 19:43:32 server id 123  end_log_pos 1000`
+pub fn server_id(line: &str) -> Option<u32> {
+    header_number(line, "server id", 1)
+}
+
+/// Xid of a COMMIT event header line, like `#251020 19:43:32 server id 1 ... Xid = 96`
+pub fn xid(line: &str) -> Option<u64> {
+    header_number(line, "Xid =", 1)
+}
+
+/// sequence_number of a GTID (or Anonymous_GTID) event header line, like
+/// `#251111  1:40:46 server id 1 ... GTID last_committed=0 sequence_number=1 rbr_only=yes`
+pub fn gtid_sequence_number(line: &str) -> Option<u64> {
+    header_number(line, "sequence_number=", 0)
+}
+
+/// GTID set by a `SET @@SESSION.GTID_NEXT= 'uuid:23'/*!*/;` line, None for ANONYMOUS and AUTOMATIC
+pub fn gtid_next(line: &str) -> Option<&str> {
+    let rest = line.strip_prefix("SET @@SESSION.GTID_NEXT=")?.trim_start().strip_prefix('\'')?;
+    let gtid = &rest[..rest.find('\'')?];
+    (gtid != "ANONYMOUS" && gtid != "AUTOMATIC").then_some(gtid)
+}
+
+/// GTID (`domain-server-sequence`) and sequence number of a MariaDB GTID event header line, like
+/// `#251020 19:43:32 server id 1  end_log_pos 342 CRC32 0x1  GTID 0-1-23 trans`
+pub fn mariadb_gtid(line: &str) -> Option<(&str, u64)> {
+    event_timestamp(line)?;
+    let start = line.find("GTID ")? + "GTID ".len();
+    let gtid = line[start..].split_whitespace().next()?;
+    let parts: Vec<&str> = gtid.split('-').collect();
+    let [domain, server, sequence] = parts[..] else {
+        return None;
+    };
+    if ![domain, server].iter().all(|part| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit())) {
+        return None;
+    }
+    Some((gtid, sequence.parse().ok()?))
+}
+
+/// Database selected by a `use `shop`/*!*/;` line, which precedes statements run in it
+pub fn use_database(line: &str) -> Option<&str> {
+    let rest = line.strip_prefix("use `")?;
+    Some(&rest[..rest.find('`')?])
+}
+
+/// File a Rotate event header line switches to, like
+/// `#251111  1:50:12 server id 1  end_log_pos 158 CRC32 0x1 \tRotate to binlog.000002  pos: 4`
+pub fn rotate_target(line: &str) -> Option<&str> {
+    event_timestamp(line)?;
+    let start = line.find("Rotate to ")? + "Rotate to ".len();
+    line[start..].split_whitespace().next()
+}
+
+/// Server version of a Format_description event header line, like
+/// `#251111  1:10:48 server id 1  end_log_pos 127 CRC32 0x1 \tStart: binlog v 4, server v 9.3.0 created ...`
+pub fn format_description_version(line: &str) -> Option<&str> {
+    event_timestamp(line)?;
+    let rest = &line[line.find("Start: binlog v ")?..];
+    let start = rest.find("server v ")? + "server v ".len();
+    rest[start..].split_whitespace().next()
+}
+
+/// Whether the line is the header of a row event (`Write_rows`, `Update_rows`, `Delete_rows`, and
+/// their `_v1` and `Partial_update_rows` variants)
+pub fn is_row_event(line: &str) -> bool {
+    event_timestamp(line).is_some()
+        && ["Write_rows", "Update_rows", "Delete_rows", "Partial_update_rows"].iter().any(|event| line.contains(event))
+}
+
+/// Number following the first `marker` of an event header line (one starting with a timestamp),
+/// after at least `min_space` whitespace characters
+fn header_number<T: std::str::FromStr>(line: &str, marker: &str, min_space: usize) -> Option<T> {
+    event_timestamp(line)?;
+    let rest = &line[line.find(marker)? + marker.len()..];
+    let (digits, _) = split_digits(skip_whitespace(rest, min_space)?, 1, usize::MAX)?;
+    digits.parse().ok()
+}
+
+/// Skips at least `min` leading whitespace characters
+fn skip_whitespace(s: &str, min: usize) -> Option<&str> {
+    let trimmed = s.trim_start();
+    (s.len() - trimmed.len() >= min).then_some(trimmed)
+}
+
+/// Splits off between `min` and `max` leading ASCII digits (greedy)
+fn split_digits(s: &str, min: usize, max: usize) -> Option<(&str, &str)> {
+    let count = s.bytes().take(max).take_while(|b| b.is_ascii_digit()).count();
+    (count >= min).then(|| s.split_at(count))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use regex::Regex;
+
+    const LINES: [&str; 17] = [
+        "###   @1=1",
+        "###   @12='O''Brien' /* VARSTRING(255) meta=255 nullable=1 is_null=0 */",
+        "### @3=",
+        "###@1=1",
+        "###   @=1",
+        "###   @1 = 1",
+        "### SET",
+        "#251020 19:43:32 server id 123  end_log_pos 1000 CRC32 0x1",
+        "#251020  9:43:32 server id 123  end_log_pos 4294967296",
+        "#251020 19:43:32.123456 server id 123  end_log_pos 1000",
+        "#251020 19:43",
+        "#25102 19:43:32",
+        "#251020 19:43:321",
+        "# at 4 end_log_posX end_log_pos 77",
+        "end_log_pos12",
+        "BEGIN",
+        "",
+    ];
+
+    #[test]
+    fn test_matches_regex_behaviour() {
+        let column_value_regex = Regex::new(r"^###\s+@(\d+)=(.*)$").unwrap();
+        let timestamp_regex = Regex::new(r"^#(\d{6})\s+(\d{1,2}:\d{2}:\d{2}(?:\.\d{1,6})?)").unwrap();
+        let position_regex = Regex::new(r"end_log_pos\s+(\d+)").unwrap();
+
+        for line in LINES {
+            let expected = column_value_regex.captures(line)
+                .map(|c| (c.get(1).unwrap().as_str(), c.get(2).unwrap().as_str()));
+            assert_eq!(column_value(line), expected, "column value of {:?}", line);
+
+            let expected = timestamp_regex.captures(line)
+                .map(|c| (c.get(1).unwrap().as_str(), c.get(2).unwrap().as_str()));
+            assert_eq!(event_timestamp(line), expected, "timestamp of {:?}", line);
+
+            let expected = position_regex.captures(line).and_then(|c| c[1].parse::<u32>().ok());
+            assert_eq!(end_log_pos(line), expected, "position of {:?}", line);
+        }
+    }
+
+    #[test]
+    fn test_transaction_fields() {
+        let gtid_event = "#251111  1:40:46 server id 7  end_log_pos 235 CRC32 0x52854b4b \tGTID\tlast_committed=0\tsequence_number=12\trbr_only=yes";
+        let commit_event = "#251111  1:40:46 server id 7  end_log_pos 406 CRC32 0xc092f87f \tXid = 96";
+        assert_eq!(server_id(gtid_event), Some(7));
+        assert_eq!(gtid_sequence_number(gtid_event), Some(12));
+        assert_eq!(xid(gtid_event), None);
+        assert_eq!(xid(commit_event), Some(96));
+        assert_eq!(xid("###   @1='Xid = 96'"), None);
+        assert_eq!(gtid_next("SET @@SESSION.GTID_NEXT= '3e11fa47-71ca-11e1-9e33-c80aa9429562:23'/*!*/;"), Some("3e11fa47-71ca-11e1-9e33-c80aa9429562:23"));
+        assert_eq!(gtid_next("SET @@SESSION.GTID_NEXT= 'ANONYMOUS'/*!*/;"), None);
+        assert!(is_row_event("#251111  1:40:46 server id 7  end_log_pos 520 CRC32 0x1b2c3d4e \tWrite_rows: table id 108 flags: STMT_END_F"));
+        assert!(!is_row_event(commit_event));
+        assert_eq!(mariadb_gtid("#251111  1:40:46 server id 7  end_log_pos 342 CRC32 0x1 \tGTID 0-7-23 trans"), Some(("0-7-23", 23)));
+        assert_eq!(mariadb_gtid(gtid_event), None);
+        assert_eq!(rotate_target("#251111  1:50:12 server id 7  end_log_pos 158 CRC32 0x1 \tRotate to binlog.000002  pos: 4"), Some("binlog.000002"));
+        assert_eq!(format_description_version("#251111  1:10:48 server id 1  end_log_pos 127 CRC32 0x1 \tStart: binlog v 4, server v 9.3.0 created 251111  1:10:48 at startup"), Some("9.3.0"));
+        assert_eq!(rotate_target("###   @1='Rotate to binlog.000002'"), None);
+    }
+}
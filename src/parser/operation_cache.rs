@@ -0,0 +1,365 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::PathBuf;
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use crate::binlog::{BinlogFileMarker, BinlogOperation, OperationType, RowImage, TransactionInfo};
+
+/// Suffix of the cache file written next to a binlog file (`mysql-bin.000101.sql.pensieve-cache`)
+pub const CACHE_SUFFIX: &str = ".pensieve-cache";
+
+/// Bump whenever the cache layout or the operations the parser produces change, so that caches
+/// written by older versions are parsed again
+const CACHE_FORMAT_VERSION: u32 = 5;
+
+const CACHE_MAGIC: &str = "pensieve-operation-cache";
+
+/// Field standing in for None; values are escaped, so a literal `\N` can't be mistaken for it
+const NONE_FIELD: &str = "\\N";
+
+/// What a cached parse of a binlog file is valid for: the file's contents, and the parser settings
+/// and table schemas that decided which operations came out of it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheKey {
+    pub file_size: u64,
+    pub file_hash: u64,
+    pub parser_fingerprint: u64,
+}
+
+impl CacheKey {
+    /// Hashes the binlog file's contents (compressed files are hashed as they are on disk)
+    pub fn for_file(binlog_file: &str, parser_fingerprint: u64) -> io::Result<Self> {
+        let (file_size, file_hash) = hash_file(binlog_file)?;
+        Ok(Self { file_size, file_hash, parser_fingerprint })
+    }
+
+    fn header(&self) -> String {
+        format!(
+            "{}\t{}\t{}\t{}\t{:016x}\t{:016x}",
+            CACHE_MAGIC,
+            CACHE_FORMAT_VERSION,
+            env!("CARGO_PKG_VERSION"),
+            self.file_size,
+            self.file_hash,
+            self.parser_fingerprint
+        )
+    }
+}
+
+pub const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+/// Size and FNV-1a hash of a file's contents
+pub fn hash_file(path: &str) -> io::Result<(u64, u64)> {
+    let mut reader = BufReader::with_capacity(1024 * 1024, File::open(path)?);
+    let mut file_size = 0;
+    let mut file_hash = FNV_OFFSET_BASIS;
+    loop {
+        let buffer = reader.fill_buf()?;
+        if buffer.is_empty() {
+            break;
+        }
+        file_hash = fnv1a(file_hash, buffer);
+        file_size += buffer.len() as u64;
+        let consumed = buffer.len();
+        reader.consume(consumed);
+    }
+    Ok((file_size, file_hash))
+}
+
+/// 64-bit FNV-1a, used instead of std's DefaultHasher because its output must not change
+/// between builds
+pub fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    for &byte in bytes {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
+
+/// Operations parsed from a binlog file, with the column list of every table altered in it (as of
+/// the end of the file), which parsing the next file carries over, and the binlog files it holds
+#[derive(Debug, Clone, Default)]
+pub struct CachedParse {
+    pub operations: Vec<BinlogOperation>,
+    pub schemas: Vec<(String, Vec<String>)>,
+    pub file_markers: Vec<BinlogFileMarker>,
+}
+
+pub fn cache_path(binlog_file: &str) -> PathBuf {
+    PathBuf::from(format!("{}{}", binlog_file, CACHE_SUFFIX))
+}
+
+/// Reads the cached parse of a binlog file. Returns None if there is no cache, or it was written
+/// for other file contents, parser settings or Pensieve version, or can't be read.
+pub fn load(binlog_file: &str, key: &CacheKey) -> Option<CachedParse> {
+    let file = File::open(cache_path(binlog_file)).ok()?;
+    let mut lines = BufReader::new(GzDecoder::new(file)).lines();
+    if lines.next()?.ok()? != key.header() {
+        return None;
+    }
+
+    let mut cached = CachedParse::default();
+    for line in lines {
+        let line = line.ok()?;
+        let mut fields = line.split('\t');
+        match fields.next()? {
+            "schema" => {
+                let table = unescape(fields.next()?);
+                cached.schemas.push((table, fields.map(unescape).collect()));
+            }
+            "file" => cached.file_markers.push(BinlogFileMarker {
+                file: unescape(fields.next()?),
+                first_operation: fields.next()?.parse().ok()?,
+                server_version: optional(fields.next()?),
+                rotates_to: optional(fields.next()?),
+            }),
+            "op" => cached.operations.push(read_operation(&mut fields)?),
+            _ => return None,
+        }
+    }
+    Some(cached)
+}
+
+/// Writes the parse of a binlog file to its cache file, replacing any previous cache
+pub fn store(binlog_file: &str, key: &CacheKey, cached: &CachedParse) -> io::Result<()> {
+    let path = cache_path(binlog_file);
+    // Written under a temporary name first, so an interrupted write never leaves a truncated cache
+    let partial_path = PathBuf::from(format!("{}.partial", path.display()));
+    let mut writer = BufWriter::new(GzEncoder::new(File::create(&partial_path)?, Compression::fast()));
+
+    writeln!(writer, "{}", key.header())?;
+    for (table, columns) in &cached.schemas {
+        let fields: Vec<String> = std::iter::once(table).chain(columns).map(|field| escape(field)).collect();
+        writeln!(writer, "schema\t{}", fields.join("\t"))?;
+    }
+    for marker in &cached.file_markers {
+        let server_version = marker.server_version.as_deref().map(escape).unwrap_or_else(|| NONE_FIELD.to_string());
+        let rotates_to = marker.rotates_to.as_deref().map(escape).unwrap_or_else(|| NONE_FIELD.to_string());
+        writeln!(writer, "file\t{}\t{}\t{}\t{}", escape(&marker.file), marker.first_operation, server_version, rotates_to)?;
+    }
+    for op in &cached.operations {
+        writeln!(writer, "op\t{}", write_operation(op).join("\t"))?;
+    }
+    writer.into_inner().map_err(|e| e.into_error())?.finish()?;
+
+    std::fs::rename(partial_path, path)
+}
+
+/// An operation as one line of text (without the line break), in the cache's format
+pub fn encode_operation(op: &BinlogOperation) -> String {
+    write_operation(op).join("\t")
+}
+
+/// Reads an operation written by encode_operation. None if the line isn't one.
+pub fn decode_operation(line: &str) -> Option<BinlogOperation> {
+    read_operation(&mut line.split('\t'))
+}
+
+/// Fields: type, timestamp, position, database, table, column count, columns, before image,
+/// after image, xid, sequence number, GTID, server id, row image. Each image is `-` if missing,
+/// or `+` followed by one value per column.
+fn write_operation(op: &BinlogOperation) -> Vec<String> {
+    let mut fields = vec![
+        op.operation_type.to_string(),
+        op.timestamp.as_deref().map(escape).unwrap_or_else(|| NONE_FIELD.to_string()),
+        optional_field(op.position),
+        escape(&op.database),
+        escape(&op.table_name),
+        op.columns.len().to_string(),
+    ];
+    fields.extend(op.columns.iter().map(|col| escape(col)));
+    for image in [&op.before_values, &op.after_values] {
+        match image {
+            Some(values) => {
+                fields.push("+".to_string());
+                fields.extend(values.iter().map(|value| escape(value)));
+            }
+            None => fields.push("-".to_string()),
+        }
+    }
+    let transaction = &op.transaction;
+    fields.push(optional_field(transaction.xid));
+    fields.push(optional_field(transaction.sequence_number));
+    fields.push(transaction.gtid.as_deref().map(escape).unwrap_or_else(|| NONE_FIELD.to_string()));
+    fields.push(optional_field(transaction.server_id));
+    match &op.row_image {
+        RowImage::Full => fields.push("full".to_string()),
+        RowImage::Partial { missing_before } => {
+            fields.push("partial".to_string());
+            fields.extend(missing_before.iter().map(|col| escape(col)));
+        }
+    }
+    fields
+}
+
+fn read_operation<'a>(fields: &mut impl Iterator<Item = &'a str>) -> Option<BinlogOperation> {
+    let operation_type = match fields.next()? {
+        "INSERT" => OperationType::Insert,
+        "UPDATE" => OperationType::Update,
+        "DELETE" => OperationType::Delete,
+        _ => return None,
+    };
+    let timestamp = optional(fields.next()?);
+    let position = parse_optional(fields.next()?)?;
+    let database = unescape(fields.next()?);
+    let table_name = unescape(fields.next()?);
+    let column_count: usize = fields.next()?.parse().ok()?;
+    let columns = read_values(fields, column_count)?;
+    let before_values = read_image(fields, column_count)?;
+    let after_values = read_image(fields, column_count)?;
+    let transaction = TransactionInfo {
+        xid: parse_optional(fields.next()?)?,
+        sequence_number: parse_optional(fields.next()?)?,
+        gtid: optional(fields.next()?),
+        server_id: parse_optional(fields.next()?)?,
+    };
+    let row_image = match fields.next()? {
+        "full" => RowImage::Full,
+        "partial" => RowImage::Partial { missing_before: fields.map(unescape).collect() },
+        _ => return None,
+    };
+
+    Some(BinlogOperation {
+        timestamp,
+        position,
+        operation_type,
+        table_name,
+        database,
+        columns,
+        before_values,
+        after_values,
+        row_image,
+        transaction,
+    })
+}
+
+fn read_image<'a>(fields: &mut impl Iterator<Item = &'a str>, column_count: usize) -> Option<Option<Vec<String>>> {
+    match fields.next()? {
+        "-" => Some(None),
+        "+" => read_values(fields, column_count).map(Some),
+        _ => None,
+    }
+}
+
+fn read_values<'a>(fields: &mut impl Iterator<Item = &'a str>, count: usize) -> Option<Vec<String>> {
+    let values: Vec<String> = fields.take(count).map(unescape).collect();
+    (values.len() == count).then_some(values)
+}
+
+fn optional(field: &str) -> Option<String> {
+    (field != NONE_FIELD).then(|| unescape(field))
+}
+
+fn optional_field<T: ToString>(value: Option<T>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_else(|| NONE_FIELD.to_string())
+}
+
+/// None if the field is missing a number it should hold, Some(None) for a None field
+fn parse_optional<T: std::str::FromStr>(field: &str) -> Option<Option<T>> {
+    match optional(field) {
+        Some(value) => Some(Some(value.parse().ok()?)),
+        None => Some(None),
+    }
+}
+
+/// Escapes the characters that separate fields and lines
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn unescape(field: &str) -> String {
+    let mut value = String::with_capacity(field.len());
+    let mut chars = field.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => value.push('\t'),
+            Some('n') => value.push('\n'),
+            Some('r') => value.push('\r'),
+            Some(other) => value.push(other),
+            None => value.push('\\'),
+        }
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_store_and_load_round_trip() {
+        let binlog_file = std::env::temp_dir().join(format!("operation_cache_{}.sql", std::process::id()));
+        let binlog_file = binlog_file.to_str().unwrap();
+        std::fs::write(binlog_file, "BEGIN\nCOMMIT\n").unwrap();
+
+        let operations = vec![
+            BinlogOperation {
+                timestamp: Some("251108 10:00:00".to_string()),
+                position: Some(120),
+                operation_type: OperationType::Update,
+                table_name: "books".to_string(),
+                database: "main".to_string(),
+                columns: vec!["id".to_string(), "title".to_string()],
+                before_values: Some(vec!["1".to_string(), "'a\tb\\N'".to_string()]),
+                after_values: Some(vec!["1".to_string(), "'line\nbreak'".to_string()]),
+                row_image: RowImage::Partial { missing_before: vec!["title".to_string()] },
+                transaction: TransactionInfo {
+                    xid: Some(96),
+                    sequence_number: Some(1),
+                    gtid: Some("3e11fa47-71ca-11e1-9e33-c80aa9429562:23".to_string()),
+                    server_id: Some(1),
+                },
+            },
+            BinlogOperation {
+                timestamp: None,
+                position: None,
+                operation_type: OperationType::Delete,
+                table_name: "books".to_string(),
+                database: "main".to_string(),
+                columns: vec!["id".to_string()],
+                before_values: Some(vec!["\\N".to_string()]),
+                after_values: None,
+                row_image: RowImage::Full,
+                transaction: TransactionInfo::default(),
+            },
+        ];
+        let cached = CachedParse {
+            operations,
+            schemas: vec![("books".to_string(), vec!["id".to_string(), "title".to_string()])],
+            file_markers: vec![
+                BinlogFileMarker { file: "mysql-bin.000001".to_string(), first_operation: 0, server_version: Some("8.0.36".to_string()), rotates_to: Some("mysql-bin.000002".to_string()) },
+                BinlogFileMarker { file: "mysql-bin.000002".to_string(), first_operation: 1, server_version: None, rotates_to: None },
+            ],
+        };
+
+        let key = CacheKey::for_file(binlog_file, 7).unwrap();
+        assert_eq!(key.file_size, 13);
+        store(binlog_file, &key, &cached).unwrap();
+
+        let loaded = load(binlog_file, &key).unwrap();
+        assert_eq!(format!("{:?}", loaded), format!("{:?}", cached));
+        assert!(load(binlog_file, &CacheKey { parser_fingerprint: 8, ..key.clone() }).is_none());
+
+        std::fs::write(binlog_file, "BEGIN\nROLLBACK\n").unwrap();
+        assert!(load(binlog_file, &CacheKey::for_file(binlog_file, 7).unwrap()).is_none());
+
+        std::fs::remove_file(binlog_file).ok();
+        std::fs::remove_file(cache_path(binlog_file)).ok();
+    }
+}
@@ -0,0 +1,256 @@
+use duckdb::Connection;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use sha2::{Digest, Sha256};
+use crate::binlog::{BinlogOperation, OperationType};
+use crate::operation_applier::StateStore;
+
+/// Problem found with a single parsed operation
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationIssueKind {
+    /// The operation has a different number of columns than the table in the snapshot
+    ColumnCountMismatch { expected: usize, found: usize },
+    /// A row image has a different number of values than the operation has columns
+    ValueCountMismatch { columns: usize, values: usize },
+    /// A row image the operation type requires (e.g. the after image of an INSERT) is missing
+    MissingRowImage,
+    /// Columns that don't exist in the snapshot's table
+    UnknownColumns(Vec<String>),
+}
+
+impl Display for ValidationIssueKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValidationIssueKind::ColumnCountMismatch { expected, found } => {
+                write!(f, "table has {} columns, operation has {}", expected, found)
+            }
+            ValidationIssueKind::ValueCountMismatch { columns, values } => {
+                write!(f, "{} columns but a row image has {} values", columns, values)
+            }
+            ValidationIssueKind::MissingRowImage => write!(f, "missing row image"),
+            ValidationIssueKind::UnknownColumns(columns) => write!(f, "unknown columns {:?}", columns),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationIssue {
+    /// Index of the operation in the deduplicated operation list
+    pub index: usize,
+    pub kind: ValidationIssueKind,
+}
+
+/// A run of operations removed because an identical run with the same binlog position and table
+/// was already parsed (typically from overlapping binlog files)
+#[derive(Debug, Clone, PartialEq)]
+pub struct DuplicateOperations {
+    pub position: Option<u32>,
+    pub table: String,
+    pub operation_count: usize,
+    /// Index the run's first operation had before duplicates were removed
+    pub index: usize,
+}
+
+/// Summary of the validation pass over parsed operations
+#[derive(Debug, Clone, Default)]
+pub struct ValidationReport {
+    pub issues: Vec<ValidationIssue>,
+    pub duplicates: Vec<DuplicateOperations>,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    pub fn duplicates_removed(&self) -> usize {
+        self.duplicates.iter().map(|d| d.operation_count).sum()
+    }
+
+    /// Indices the removed duplicates had before they were removed, in order
+    pub fn removed_indices(&self) -> Vec<usize> {
+        self.duplicates.iter().flat_map(|d| d.index..d.index + d.operation_count).collect()
+    }
+}
+
+/// Removes duplicated operations and checks the remaining ones against the snapshot's schemas,
+/// so that problems are reported up front rather than surfacing later as corrupted state.
+///
+/// Operations are grouped into runs with the same binlog position and table (one row event).
+/// A run is a duplicate if an identical run with the same position and table was seen before.
+/// Positions restart in every binlog file, so runs that only share position and table are kept.
+pub fn validate_operations(
+    conn: &Connection,
+    operations: &mut Vec<BinlogOperation>,
+) -> Result<ValidationReport, Box<dyn std::error::Error>> {
+    OperationValidator::default().validate(conn, operations)
+}
+
+/// Validates operations a batch at a time (e.g. one binlog file), like validate_operations,
+/// removing runs that duplicate runs of earlier batches too. Only a digest of each run is kept.
+#[derive(Default)]
+pub struct OperationValidator {
+    // Digests of the runs kept so far, per key
+    seen: HashMap<RunKey, Vec<RunDigest>>,
+    schemas: HashMap<String, Vec<String>>,
+}
+
+impl OperationValidator {
+    /// Validates the next batch. Indices in the report are within the batch.
+    pub fn validate(
+        &mut self,
+        conn: &Connection,
+        operations: &mut Vec<BinlogOperation>,
+    ) -> Result<ValidationReport, Box<dyn std::error::Error>> {
+        let mut report = ValidationReport::default();
+        self.remove_duplicates(operations, &mut report);
+
+        for (index, op) in operations.iter().enumerate() {
+            if !self.schemas.contains_key(&op.table_name) {
+                self.schemas.insert(op.table_name.clone(), conn.table_columns(&op.table_name)?);
+            }
+            let schema = &self.schemas[&op.table_name];
+            for kind in check_operation(op, schema) {
+                report.issues.push(ValidationIssue { index, kind });
+            }
+        }
+
+        Ok(report)
+    }
+
+    fn remove_duplicates(&mut self, operations: &mut Vec<BinlogOperation>, report: &mut ValidationReport) {
+        let mut keep = vec![true; operations.len()];
+
+        let mut start = 0;
+        while start < operations.len() {
+            let key = (operations[start].position, operations[start].table_name.clone());
+            let mut end = start + 1;
+            while end < operations.len() && (operations[end].position, &operations[end].table_name) == (key.0, &key.1) {
+                end += 1;
+            }
+
+            let digest = run_digest(&operations[start..end]);
+            let runs = self.seen.entry(key.clone()).or_default();
+            if key.0.is_some() && runs.contains(&digest) {
+                keep[start..end].iter_mut().for_each(|k| *k = false);
+                report.duplicates.push(DuplicateOperations { position: key.0, table: key.1, operation_count: end - start, index: start });
+            } else {
+                runs.push(digest);
+            }
+            start = end;
+        }
+
+        let mut keep = keep.into_iter();
+        operations.retain(|_| keep.next().unwrap());
+    }
+}
+
+fn check_operation(op: &BinlogOperation, schema: &[String]) -> Vec<ValidationIssueKind> {
+    let mut issues = Vec::new();
+
+    // Tables missing from the snapshot are filtered out while parsing.
+    // Partial row images only hold some of the columns.
+    if !schema.is_empty() {
+        if op.columns.len() != schema.len() && !op.row_image.is_partial() {
+            issues.push(ValidationIssueKind::ColumnCountMismatch { expected: schema.len(), found: op.columns.len() });
+        }
+        let unknown: Vec<String> = op.columns.iter()
+            .filter(|col| !schema.contains(col))
+            .cloned()
+            .collect();
+        if !unknown.is_empty() {
+            issues.push(ValidationIssueKind::UnknownColumns(unknown));
+        }
+    }
+
+    let (needs_before, needs_after) = match op.operation_type {
+        OperationType::Insert => (false, true),
+        OperationType::Update => (true, true),
+        OperationType::Delete => (true, false),
+    };
+    for (needed, image) in [(needs_before, &op.before_values), (needs_after, &op.after_values)] {
+        match image {
+            Some(values) if values.len() != op.columns.len() => {
+                issues.push(ValidationIssueKind::ValueCountMismatch { columns: op.columns.len(), values: values.len() });
+            }
+            None if needed => issues.push(ValidationIssueKind::MissingRowImage),
+            _ => {}
+        }
+    }
+
+    issues
+}
+
+/// Binlog position and table of a run of operations
+type RunKey = (Option<u32>, String);
+
+/// SHA-256 of the operations of a run, over everything but their position and transaction
+type RunDigest = [u8; 32];
+
+fn run_digest(operations: &[BinlogOperation]) -> RunDigest {
+    let mut hasher = Sha256::new();
+    for op in operations {
+        hasher.update(format!(
+            "{:?}\0{:?}\0{}\0{:?}\0{:?}\0{:?}\0{:?}\n",
+            op.operation_type, op.timestamp, op.database, op.columns, op.before_values, op.after_values, op.row_image
+        ));
+    }
+    hasher.finalize().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{books_db, books_op};
+
+    fn insert_op(position: u32, id: &str) -> BinlogOperation {
+        BinlogOperation { position: Some(position), ..books_op(OperationType::Insert, "251108 10:00:00", None, Some([id, "10"])) }
+    }
+
+    fn create_test_db() -> Connection {
+        books_db("id INTEGER, price INTEGER", "")
+    }
+
+    #[test]
+    fn test_removes_runs_repeated_by_overlapping_files() {
+        let mut operations = vec![
+            insert_op(100, "1"),
+            insert_op(100, "2"),
+            insert_op(200, "3"),
+            // Second file overlaps the first
+            insert_op(100, "1"),
+            insert_op(100, "2"),
+            // Same position and table, different rows: a new file, not a duplicate
+            insert_op(200, "4"),
+        ];
+
+        let report = validate_operations(&create_test_db(), &mut operations).unwrap();
+
+        assert_eq!(report.duplicates_removed(), 2);
+        assert_eq!(report.duplicates[0].position, Some(100));
+        let ids: Vec<&str> = operations.iter().map(|op| op.after_values.as_ref().unwrap()[0].as_str()).collect();
+        assert_eq!(ids, vec!["1", "2", "3", "4"]);
+        assert!(report.is_valid());
+    }
+
+    #[test]
+    fn test_flags_column_and_value_count_mismatches() {
+        let mut short_image = insert_op(100, "1");
+        short_image.after_values = Some(vec!["1".to_string()]);
+        let mut extra_column = insert_op(200, "2");
+        extra_column.columns.push("isbn".to_string());
+        extra_column.after_values.as_mut().unwrap().push("'x'".to_string());
+        let mut missing_image = insert_op(300, "3");
+        missing_image.after_values = None;
+        let mut operations = vec![short_image, extra_column, missing_image];
+
+        let report = validate_operations(&create_test_db(), &mut operations).unwrap();
+
+        assert_eq!(report.issues, vec![
+            ValidationIssue { index: 0, kind: ValidationIssueKind::ValueCountMismatch { columns: 2, values: 1 } },
+            ValidationIssue { index: 1, kind: ValidationIssueKind::ColumnCountMismatch { expected: 2, found: 3 } },
+            ValidationIssue { index: 1, kind: ValidationIssueKind::UnknownColumns(vec!["isbn".to_string()]) },
+            ValidationIssue { index: 2, kind: ValidationIssueKind::MissingRowImage },
+        ]);
+    }
+}
\ No newline at end of file
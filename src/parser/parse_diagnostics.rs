@@ -0,0 +1,212 @@
+use std::fmt::{Display, Formatter};
+
+/// What was wrong with a line the parser couldn't make sense of
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseAnomalyKind {
+    /// Reading the file failed; the rest of the file was not parsed
+    ReadError(String),
+    /// A `###` line inside a row event that is neither a column value nor an image heading
+    UnrecognisedRowImageLine,
+    /// A column number that isn't one of the table's columns; the value was ignored
+    ColumnOutOfRange { column: String, column_count: usize },
+    /// A `###` line outside of any row event
+    OrphanRowImageLine,
+    /// A transaction that was neither committed nor rolled back; its operations were dropped.
+    /// `first_timestamp` is that of its first operation.
+    UnterminatedTransaction { operations: usize, first_timestamp: Option<String> },
+}
+
+impl Display for ParseAnomalyKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseAnomalyKind::ReadError(error) => write!(f, "read error, rest of file skipped: {}", error),
+            ParseAnomalyKind::UnrecognisedRowImageLine => write!(f, "unrecognised row image line"),
+            ParseAnomalyKind::ColumnOutOfRange { column, column_count } => {
+                write!(f, "column @{} but the table has {} columns", column, column_count)
+            }
+            ParseAnomalyKind::OrphanRowImageLine => write!(f, "row image line outside a row event"),
+            ParseAnomalyKind::UnterminatedTransaction { operations, first_timestamp } => {
+                write!(f, "transaction without COMMIT, dropped {} operation(s)", operations)?;
+                match first_timestamp {
+                    Some(timestamp) => write!(f, " from {}", timestamp),
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+}
+
+/// A line the parser skipped or couldn't fully parse
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseAnomaly {
+    /// Binlog file the line is in (empty when parsing from a reader)
+    pub file: String,
+    /// 1-based line number in the file
+    pub line_number: usize,
+    /// Start of the line
+    pub snippet: String,
+    pub kind: ParseAnomalyKind,
+}
+
+impl Display for ParseAnomaly {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: {} ({:?})", self.file, self.line_number, self.kind, self.snippet)
+    }
+}
+
+/// An event header whose timestamp is earlier than the one before it, e.g. after a clock
+/// adjustment or in a merged relay log. Nothing is lost, so these don't count as anomalies.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimestampRegression {
+    /// Binlog file the header is in (empty when parsing from a reader)
+    pub file: String,
+    /// 1-based line number of the header
+    pub line_number: usize,
+    pub previous: String,
+    pub timestamp: String,
+}
+
+impl Display for TimestampRegression {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: timestamp goes back from {} to {}", self.file, self.line_number, self.previous, self.timestamp)
+    }
+}
+
+/// Row events of a table in one database going to the same DuckDB table as those of the table
+/// in another, because the snapshot doesn't namespace it (see namespaced_table_name). The rows
+/// of both databases end up mixed in one table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SharedTable {
+    /// Binlog file of the first event from `database` (empty when parsing from a reader)
+    pub file: String,
+    /// 1-based line number of the first event from `database`
+    pub line_number: usize,
+    /// DuckDB table
+    pub table: String,
+    /// Database of the first events that went to the table
+    pub first_database: String,
+    pub database: String,
+}
+
+impl Display for SharedTable {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{}: rows of {}.{} and {}.{} both go to table {}",
+            self.file, self.line_number, self.first_database, self.table, self.database, self.table, self.table
+        )
+    }
+}
+
+/// Anomalies found while parsing, in file and line order
+#[derive(Debug, Clone, Default)]
+pub struct ParseDiagnostics {
+    pub anomalies: Vec<ParseAnomaly>,
+    /// Non-monotonic timestamps, in file and line order
+    pub timestamp_regressions: Vec<TimestampRegression>,
+    /// Tables receiving row events from more than one database, once per extra database, in
+    /// file and line order
+    pub shared_tables: Vec<SharedTable>,
+}
+
+impl ParseDiagnostics {
+    /// Length of ParseAnomaly::snippet, in characters
+    pub const SNIPPET_LENGTH: usize = 120;
+
+    pub fn len(&self) -> usize {
+        self.anomalies.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.anomalies.is_empty()
+    }
+
+    pub(crate) fn record(&mut self, line_number: usize, line: &str, kind: ParseAnomalyKind) {
+        self.anomalies.push(ParseAnomaly {
+            file: String::new(),
+            line_number,
+            snippet: line.chars().take(Self::SNIPPET_LENGTH).collect(),
+            kind,
+        });
+    }
+
+    /// Number of transactions that were neither committed nor rolled back, and the timestamp of
+    /// the first one's first operation. The binlog range is incomplete if there are any.
+    pub fn unterminated_transactions(&self) -> (usize, Option<&str>) {
+        let mut unterminated = self.anomalies.iter().filter_map(|anomaly| match &anomaly.kind {
+            ParseAnomalyKind::UnterminatedTransaction { first_timestamp, .. } => Some(first_timestamp.as_deref()),
+            _ => None,
+        });
+        let first_timestamp = unterminated.next();
+        (first_timestamp.map_or(0, |_| 1 + unterminated.count()), first_timestamp.flatten())
+    }
+
+    pub fn append(&mut self, other: &mut ParseDiagnostics) {
+        self.anomalies.append(&mut other.anomalies);
+        self.timestamp_regressions.append(&mut other.timestamp_regressions);
+        self.shared_tables.append(&mut other.shared_tables);
+    }
+}
+
+/// Returned when parsing finds more anomalies than the parser's configured maximum
+#[derive(Debug)]
+pub struct TooManyParseAnomaliesError {
+    pub max_anomalies: usize,
+    /// The anomaly that went over the maximum
+    pub last: ParseAnomaly,
+}
+
+impl Display for TooManyParseAnomaliesError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "More than {} parse anomalies, aborting. Last: {}", self.max_anomalies, self.last)
+    }
+}
+
+impl std::error::Error for TooManyParseAnomaliesError {}
+
+/// Returned by a parser with strict transactions when a file has transactions that were neither
+/// committed nor rolled back, e.g. because the binlog was truncated in the middle of one
+#[derive(Debug)]
+pub struct UnterminatedTransactionsError {
+    pub file: String,
+    pub count: usize,
+    /// Timestamp of the first unterminated transaction's first operation
+    pub first_timestamp: Option<String>,
+}
+
+impl Display for UnterminatedTransactionsError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {} transaction(s) without COMMIT or ROLLBACK", self.file, self.count)?;
+        if let Some(timestamp) = &self.first_timestamp {
+            write!(f, ", the first from {}", timestamp)?;
+        }
+        write!(f, "; the binlog range is incomplete")
+    }
+}
+
+impl std::error::Error for UnterminatedTransactionsError {}
+
+/// Returned when mysqlbinlog didn't decode a row event's rows, which means the file was written
+/// without the flags the parser needs and its row changes can't be read
+#[derive(Debug)]
+pub struct UndecodedRowEventError {
+    pub file: String,
+    /// 1-based line number of the row event's header
+    pub line_number: usize,
+    /// Whether the rows were printed as a base64 `BINLOG '...'` block, instead of not at all
+    pub base64: bool,
+}
+
+impl Display for UndecodedRowEventError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let printed = if self.base64 { "only as a base64 BINLOG block" } else { "not printed" };
+        write!(
+            f,
+            "{}:{}: the rows of this row event were {}. Regenerate the file with \
+             `mysqlbinlog --base64-output=DECODE-ROWS --verbose`",
+            self.file, self.line_number, printed
+        )
+    }
+}
+
+impl std::error::Error for UndecodedRowEventError {}
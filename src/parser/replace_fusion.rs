@@ -0,0 +1,208 @@
+use duckdb::Connection;
+use std::collections::HashMap;
+use crate::binlog::{BinlogOperation, OperationType, RowImage};
+use crate::operation_applier::StateStore;
+
+/// A DELETE and INSERT of the same row that were fused into one UPDATE
+#[derive(Debug, Clone, PartialEq)]
+pub struct FusedReplace {
+    pub table: String,
+    /// Binlog positions of the DELETE and the INSERT
+    pub delete_position: Option<u32>,
+    pub insert_position: Option<u32>,
+    /// Index the INSERT had before it was fused away
+    pub insert_index: usize,
+}
+
+/// Summary of the REPLACE fusion pass over parsed operations
+#[derive(Debug, Clone, Default)]
+pub struct ReplaceFusionReport {
+    pub fused: Vec<FusedReplace>,
+}
+
+impl ReplaceFusionReport {
+    pub fn fused_count(&self) -> usize {
+        self.fused.len()
+    }
+
+    /// Indices the removed INSERTs had before fusion, in order
+    pub fn removed_indices(&self) -> Vec<usize> {
+        let mut indices: Vec<usize> = self.fused.iter().map(|fused| fused.insert_index).collect();
+        indices.sort_unstable();
+        indices
+    }
+}
+
+/// Fuses DELETE + INSERT pairs of the same row into a single UPDATE.
+///
+/// `REPLACE INTO` (and some upserts) are logged as a DELETE of the old row followed by an INSERT
+/// of the new one. Applied as-is, stepping between the two leaves the row missing, and diffs
+/// report a deletion and an insertion instead of a modification.
+///
+/// A pair is only fused when both operations belong to the same run of consecutive operations
+/// on one table in the same transaction and with the same timestamp, have the same primary key,
+/// and no operation in between touches that key. Tables without a declared primary key and
+/// operations with partial row images are left alone: without a key, a DELETE and an INSERT
+/// that happen to share values may be different rows.
+pub fn fuse_replaces(
+    conn: &Connection,
+    operations: &mut Vec<BinlogOperation>,
+) -> Result<ReplaceFusionReport, Box<dyn std::error::Error>> {
+    let mut report = ReplaceFusionReport::default();
+    let mut key_columns_by_table: HashMap<String, Vec<String>> = HashMap::new();
+    let mut removed = vec![false; operations.len()];
+
+    let mut run_start = 0;
+    while run_start < operations.len() {
+        let first = &operations[run_start];
+        let run_end = operations[run_start..].iter()
+            .position(|op| op.table_name != first.table_name
+                || op.timestamp != first.timestamp
+                || op.transaction != first.transaction)
+            .map_or(operations.len(), |len| run_start + len);
+
+        if !key_columns_by_table.contains_key(&first.table_name) {
+            let key_columns = conn.primary_key_columns(&first.table_name)?;
+            key_columns_by_table.insert(first.table_name.clone(), key_columns);
+        }
+        let key_columns = &key_columns_by_table[&first.table_name];
+        if key_columns.is_empty() {
+            run_start = run_end;
+            continue;
+        }
+
+        for delete_idx in run_start..run_end {
+            if removed[delete_idx] || operations[delete_idx].operation_type != OperationType::Delete {
+                continue;
+            }
+            let Some(insert_idx) = matching_insert(operations, &removed, delete_idx, run_end, key_columns) else {
+                continue;
+            };
+
+            let insert = &operations[insert_idx];
+            report.fused.push(FusedReplace {
+                table: insert.table_name.clone(),
+                delete_position: operations[delete_idx].position,
+                insert_position: insert.position,
+                insert_index: insert_idx,
+            });
+            let fused = BinlogOperation {
+                operation_type: OperationType::Update,
+                position: insert.position,
+                after_values: insert.after_values.clone(),
+                ..operations[delete_idx].clone()
+            };
+            operations[delete_idx] = fused;
+            removed[insert_idx] = true;
+        }
+
+        run_start = run_end;
+    }
+
+    let mut idx = 0;
+    operations.retain(|_| {
+        idx += 1;
+        !removed[idx - 1]
+    });
+    Ok(report)
+}
+
+/// The INSERT that re-creates the row deleted at `delete_idx`, if nothing else touches the row
+/// in between
+fn matching_insert(
+    operations: &[BinlogOperation],
+    removed: &[bool],
+    delete_idx: usize,
+    run_end: usize,
+    key_columns: &[String],
+) -> Option<usize> {
+    let delete = &operations[delete_idx];
+    if delete.row_image != RowImage::Full {
+        return None;
+    }
+    let deleted_key = row_key(delete, delete.before_values.as_ref()?, key_columns)?;
+
+    for idx in delete_idx + 1..run_end {
+        if removed[idx] {
+            continue;
+        }
+        let op = &operations[idx];
+        let touches_key = [&op.before_values, &op.after_values].into_iter()
+            .flatten()
+            .any(|values| row_key(op, values, key_columns).as_ref() == Some(&deleted_key));
+        if !touches_key {
+            continue;
+        }
+        let fusable = op.operation_type == OperationType::Insert
+            && op.row_image == RowImage::Full
+            && op.columns == delete.columns
+            && op.database == delete.database;
+        return fusable.then_some(idx);
+    }
+    None
+}
+
+fn row_key(op: &BinlogOperation, values: &[String], key_columns: &[String]) -> Option<Vec<String>> {
+    key_columns.iter()
+        .map(|key| op.columns.iter().position(|col| col == key).and_then(|i| values.get(i).cloned()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{books_db, books_op};
+
+    fn op(operation_type: OperationType, timestamp: &str, before: Option<[&str; 2]>, after: Option<[&str; 2]>) -> BinlogOperation {
+        BinlogOperation { position: Some(100), ..books_op(operation_type, timestamp, before, after) }
+    }
+
+    #[test]
+    fn test_fuses_delete_insert_of_same_key() {
+        let conn = books_db("id INTEGER PRIMARY KEY, price INTEGER", "");
+        let mut operations = vec![
+            // REPLACE INTO books VALUES (1, 15), (2, 25)
+            op(OperationType::Delete, "251108 10:00:00", Some(["1", "10"]), None),
+            op(OperationType::Delete, "251108 10:00:00", Some(["2", "20"]), None),
+            op(OperationType::Insert, "251108 10:00:00", None, Some(["1", "15"])),
+            op(OperationType::Insert, "251108 10:00:00", None, Some(["2", "25"])),
+            // Deleted and re-inserted later: not the same statement
+            op(OperationType::Delete, "251108 10:00:00", Some(["3", "30"]), None),
+            op(OperationType::Insert, "251108 10:05:00", None, Some(["3", "30"])),
+        ];
+
+        let report = fuse_replaces(&conn, &mut operations).unwrap();
+
+        assert_eq!(report.fused_count(), 2);
+        let types: Vec<OperationType> = operations.iter().map(|op| op.operation_type.clone()).collect();
+        assert_eq!(types, vec![OperationType::Update, OperationType::Update, OperationType::Delete, OperationType::Insert]);
+        assert_eq!(operations[0].before_values, Some(vec!["1".to_string(), "10".to_string()]));
+        assert_eq!(operations[0].after_values, Some(vec!["1".to_string(), "15".to_string()]));
+        assert_eq!(operations[1].after_values, Some(vec!["2".to_string(), "25".to_string()]));
+    }
+
+    #[test]
+    fn test_keeps_pairs_without_key_or_across_transactions() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE books (id INTEGER PRIMARY KEY, price INTEGER); CREATE TABLE notes (id INTEGER, price INTEGER);").unwrap();
+        let mut other_transaction = op(OperationType::Insert, "251108 10:00:00", None, Some(["1", "15"]));
+        other_transaction.transaction.xid = Some(7);
+        let mut operations = vec![
+            op(OperationType::Delete, "251108 10:00:00", Some(["1", "10"]), None),
+            other_transaction,
+        ];
+        assert_eq!(fuse_replaces(&conn, &mut operations).unwrap().fused_count(), 0);
+        assert_eq!(operations.len(), 2);
+
+        // Only the first column is shared, and notes has no primary key to tell the rows apart
+        let mut operations = vec![
+            op(OperationType::Delete, "251108 10:00:00", Some(["1", "10"]), None),
+            op(OperationType::Insert, "251108 10:00:00", None, Some(["1", "15"])),
+        ];
+        for op in &mut operations {
+            op.table_name = "notes".to_string();
+        }
+        assert_eq!(fuse_replaces(&conn, &mut operations).unwrap().fused_count(), 0);
+        assert_eq!(operations.len(), 2);
+    }
+}
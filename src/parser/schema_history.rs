@@ -0,0 +1,297 @@
+use std::collections::HashMap;
+
+/// Column list of a table from a binlog position onwards
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaVersion {
+    pub position: u32,
+    pub columns: Vec<String>,
+}
+
+/// Versioned column lists per table, built from ALTER TABLE statements found in the binlog.
+///
+/// Row events only carry column numbers (`@1`, `@2`, ...), so after a DDL statement the same number
+/// can refer to a different column. Looking up the version in effect at a row event's position keeps
+/// values in the right columns.
+#[derive(Debug, Clone, Default)]
+pub struct SchemaHistory {
+    versions: HashMap<String, Vec<SchemaVersion>>,
+}
+
+impl SchemaHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the columns of `table` from `position` onwards, replacing any version already
+    /// recorded at that position
+    pub fn record(&mut self, table: &str, position: u32, columns: Vec<String>) {
+        let versions = self.versions.entry(table.to_string()).or_default();
+        versions.retain(|version| version.position != position);
+        let idx = versions.partition_point(|version| version.position < position);
+        versions.insert(idx, SchemaVersion { position, columns });
+    }
+
+    /// Columns of `table` in effect at `position`, or None if no DDL for the table was seen before it
+    pub fn columns_at(&self, table: &str, position: u32) -> Option<&Vec<String>> {
+        self.versions.get(table)?
+            .iter()
+            .rev()
+            .find(|version| version.position <= position)
+            .map(|version| &version.columns)
+    }
+
+    /// Keeps only the latest version of each table, moved to position 0.
+    /// Binlog positions restart in every file, so this is called before parsing the next file.
+    pub fn rebase(&mut self) {
+        for versions in self.versions.values_mut() {
+            if let Some(mut latest) = versions.pop() {
+                latest.position = 0;
+                versions.clear();
+                versions.push(latest);
+            }
+        }
+    }
+
+    pub fn versions(&self, table: &str) -> &[SchemaVersion] {
+        self.versions.get(table).map(|v| v.as_slice()).unwrap_or(&[])
+    }
+}
+
+/// A column-level change made by an ALTER TABLE statement
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColumnChange {
+    Add { column: String, placement: Placement },
+    Drop { column: String },
+    /// CHANGE, RENAME COLUMN and MODIFY (where `from == to`)
+    Change { from: String, to: String, placement: Placement },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Placement {
+    /// Keep the current position (or append, for new columns)
+    Default,
+    First,
+    After(String),
+}
+
+/// Parses an `ALTER TABLE` statement into the database (if the table name is qualified with
+/// one), the table name and its column changes.
+/// Index, key and constraint clauses are ignored. Returns None for any other statement.
+pub fn parse_alter_table(statement: &str) -> Option<(Option<String>, String, Vec<ColumnChange>)> {
+    let statement = statement.trim().trim_end_matches(';').trim();
+    let mut words = statement.splitn(3, char::is_whitespace);
+    if !words.next()?.eq_ignore_ascii_case("ALTER") || !words.next()?.eq_ignore_ascii_case("TABLE") {
+        return None;
+    }
+    let rest = words.next()?.trim_start();
+    let (table_path, clauses) = rest.split_once(char::is_whitespace)?;
+    let (database, table) = match table_path.rsplit_once('.') {
+        Some((database, table)) => (Some(unquote(database)), unquote(table)),
+        None => (None, unquote(table_path)),
+    };
+
+    let changes = split_top_level(clauses, ',')
+        .iter()
+        .filter_map(|clause| parse_clause(clause))
+        .collect();
+    Some((database, table, changes))
+}
+
+/// Applies column changes to a column list
+pub fn apply_changes(columns: &[String], changes: &[ColumnChange]) -> Vec<String> {
+    let mut columns = columns.to_vec();
+    for change in changes {
+        match change {
+            ColumnChange::Add { column, placement } => {
+                // Already there: the history wasn't anchored to a snapshot taken after this
+                // statement, see TextBinlogParser::anchor_schema_history
+                if !columns.contains(column) {
+                    place(&mut columns, column.clone(), placement, None);
+                }
+            }
+            ColumnChange::Drop { column } => columns.retain(|c| c != column),
+            ColumnChange::Change { from, to, placement } => {
+                if let Some(idx) = columns.iter().position(|c| c == from) {
+                    columns.remove(idx);
+                    place(&mut columns, to.clone(), placement, Some(idx));
+                }
+            }
+        }
+    }
+    columns
+}
+
+/// Undoes column changes, giving the column list before them.
+/// Binlogs don't record where a dropped column was, so it is put back at the end; changed
+/// columns get their old name back in their current position.
+pub fn revert_changes(columns: &[String], changes: &[ColumnChange]) -> Vec<String> {
+    let mut columns = columns.to_vec();
+    for change in changes.iter().rev() {
+        match change {
+            ColumnChange::Add { column, .. } => columns.retain(|c| c != column),
+            ColumnChange::Drop { column } => {
+                if !columns.contains(column) {
+                    columns.push(column.clone());
+                }
+            }
+            ColumnChange::Change { from, to, .. } => {
+                if let Some(c) = columns.iter_mut().find(|c| *c == to) {
+                    *c = from.clone();
+                }
+            }
+        }
+    }
+    columns
+}
+
+fn place(columns: &mut Vec<String>, column: String, placement: &Placement, current_idx: Option<usize>) {
+    let idx = match placement {
+        Placement::First => 0,
+        Placement::After(after) => columns.iter()
+            .position(|c| c == after)
+            .map(|i| i + 1)
+            .unwrap_or(columns.len()),
+        Placement::Default => current_idx.unwrap_or(columns.len()),
+    };
+    columns.insert(idx.min(columns.len()), column);
+}
+
+fn parse_clause(clause: &str) -> Option<ColumnChange> {
+    let tokens: Vec<&str> = clause.split_whitespace().collect();
+    let keyword = |i: usize, word: &str| tokens.get(i).is_some_and(|t| t.eq_ignore_ascii_case(word));
+    // Skip the optional COLUMN keyword after ADD/DROP/CHANGE/MODIFY
+    let name_idx = if keyword(1, "COLUMN") { 2 } else { 1 };
+    let name = tokens.get(name_idx).map(|t| unquote(t))?;
+
+    if keyword(0, "ADD") {
+        if is_index_keyword(&name) || name.starts_with('(') {
+            return None;
+        }
+        Some(ColumnChange::Add { column: name, placement: parse_placement(&tokens) })
+    } else if keyword(0, "DROP") {
+        if is_index_keyword(&name) {
+            return None;
+        }
+        Some(ColumnChange::Drop { column: name })
+    } else if keyword(0, "CHANGE") {
+        let to = tokens.get(name_idx + 1).map(|t| unquote(t))?;
+        Some(ColumnChange::Change { from: name, to, placement: parse_placement(&tokens) })
+    } else if keyword(0, "MODIFY") {
+        Some(ColumnChange::Change { from: name.clone(), to: name, placement: parse_placement(&tokens) })
+    } else if keyword(0, "RENAME") && keyword(1, "COLUMN") && keyword(3, "TO") {
+        let to = tokens.get(4).map(|t| unquote(t))?;
+        Some(ColumnChange::Change { from: name, to, placement: Placement::Default })
+    } else {
+        None
+    }
+}
+
+fn parse_placement(tokens: &[&str]) -> Placement {
+    let len = tokens.len();
+    if len >= 1 && tokens[len - 1].eq_ignore_ascii_case("FIRST") {
+        Placement::First
+    } else if len >= 2 && tokens[len - 2].eq_ignore_ascii_case("AFTER") {
+        Placement::After(unquote(tokens[len - 1]))
+    } else {
+        Placement::Default
+    }
+}
+
+fn is_index_keyword(word: &str) -> bool {
+    ["INDEX", "KEY", "PRIMARY", "UNIQUE", "CONSTRAINT", "FOREIGN", "FULLTEXT", "SPATIAL", "CHECK", "PARTITION"]
+        .iter()
+        .any(|keyword| word.eq_ignore_ascii_case(keyword))
+}
+
+fn unquote(identifier: &str) -> String {
+    identifier.trim_matches('`').to_string()
+}
+
+/// Splits on `separator`, ignoring separators inside parentheses or quotes (e.g. `DECIMAL(10,2)`)
+fn split_top_level(text: &str, separator: char) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0;
+    let mut quote: Option<char> = None;
+
+    for c in text.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"' | '`') => quote = Some(c),
+            (None, '(') => depth += 1,
+            (None, ')') => depth -= 1,
+            (None, c) if c == separator && depth == 0 => {
+                parts.push(current.trim().to_string());
+                current.clear();
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    if !current.trim().is_empty() {
+        parts.push(current.trim().to_string());
+    }
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cols(names: &[&str]) -> Vec<String> {
+        names.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_alter_table_clauses() {
+        let (database, table, changes) = parse_alter_table(
+            "ALTER TABLE `shop`.`books` ADD COLUMN `isbn` VARCHAR(13) AFTER `id`, \
+             ADD INDEX idx_price (price), DROP COLUMN stock, CHANGE price cost DECIMAL(10,2) NOT NULL"
+        ).unwrap();
+
+        assert_eq!((database.as_deref(), table.as_str()), (Some("shop"), "books"));
+        assert_eq!(changes, vec![
+            ColumnChange::Add { column: "isbn".to_string(), placement: Placement::After("id".to_string()) },
+            ColumnChange::Drop { column: "stock".to_string() },
+            ColumnChange::Change { from: "price".to_string(), to: "cost".to_string(), placement: Placement::Default },
+        ]);
+        assert!(parse_alter_table("CREATE TABLE books (id INT)").is_none());
+    }
+
+    #[test]
+    fn test_apply_changes() {
+        let (_, _, changes) = parse_alter_table(
+            "alter table books add isbn varchar(13) after id, drop stock, rename column price to cost, add note text first"
+        ).unwrap();
+
+        let columns = apply_changes(&cols(&["id", "title", "price", "stock"]), &changes);
+        assert_eq!(columns, cols(&["note", "id", "isbn", "title", "cost"]));
+    }
+
+    #[test]
+    fn test_revert_changes() {
+        let (_, _, changes) = parse_alter_table(
+            "alter table books add isbn varchar(13) after id, drop stock, rename column price to cost"
+        ).unwrap();
+
+        let after = apply_changes(&cols(&["id", "title", "price", "stock"]), &changes);
+        assert_eq!(revert_changes(&after, &changes), cols(&["id", "title", "price", "stock"]));
+    }
+
+    #[test]
+    fn test_columns_at_position() {
+        let mut history = SchemaHistory::new();
+        history.record("books", 100, cols(&["id", "title"]));
+        history.record("books", 500, cols(&["id", "title", "isbn"]));
+
+        assert_eq!(history.columns_at("books", 50), None);
+        assert_eq!(history.columns_at("books", 300), Some(&cols(&["id", "title"])));
+        assert_eq!(history.columns_at("books", 500), Some(&cols(&["id", "title", "isbn"])));
+
+        history.rebase();
+        assert_eq!(history.columns_at("books", 0), Some(&cols(&["id", "title", "isbn"])));
+        assert_eq!(history.versions("books").len(), 1);
+    }
+}
\ No newline at end of file
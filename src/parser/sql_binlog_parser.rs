@@ -3,6 +3,9 @@ use mysql_binlog_connector_rust::event::event_data::EventData;
 use std::collections::HashMap;
 use std::fs::File;
 use duckdb::Connection;
+use tracing::{debug, info, warn};
+use crate::binlog::quote_identifier;
+use crate::operation_applier::StateStore;
 
 /*
 This is an attempt at to parse binlogs directly from binary format, but I don't think it works.
@@ -20,21 +23,11 @@ pub fn parse_binlog_file() {
 
     assert!(parser.check_magic(&mut file).is_ok());
     while let Ok((header, data)) = parser.next(&mut file) {
-        println!("header: {:?}", header);
-        println!("data: {:?}", data);
-        println!("");
+        debug!("header: {:?}", header);
+        debug!("data: {:?}", data);
     }
 }
 
-/// Get column names for a table from DuckDB
-fn get_column_names(conn: &Connection, table_name: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
-    let mut stmt = conn.prepare(&format!("DESCRIBE {}", table_name))?;
-    let columns: Vec<String> = stmt
-        .query_map([], |row| row.get(0))?
-        .collect::<Result<Vec<_>, _>>()?;
-    Ok(columns)
-}
-
 /// Build a mapping of all tables to their column names
 fn build_table_column_mapping(conn: &Connection) -> Result<HashMap<String, Vec<String>>, Box<dyn std::error::Error>> {
     let mut table_columns = HashMap::new();
@@ -45,7 +38,7 @@ fn build_table_column_mapping(conn: &Connection) -> Result<HashMap<String, Vec<S
         .collect::<Result<Vec<_>, _>>()?;
     
     for table_name in table_names {
-        let columns = get_column_names(conn, &table_name)?;
+        let columns = conn.table_columns(&table_name)?;
         table_columns.insert(table_name, columns);
     }
     
@@ -92,7 +85,7 @@ fn build_update_sql<T: std::fmt::Debug + PartialEq>(
         .enumerate()
         .filter(|(i, _)| before_values[*i] != after_values[*i])
         .map(|(_, (col_name, value))| {
-            format!("{} = {}", col_name, value_to_sql(value))
+            format!("{} = {}", quote_identifier(col_name), value_to_sql(value))
         })
         .collect();
     
@@ -103,16 +96,16 @@ fn build_update_sql<T: std::fmt::Debug + PartialEq>(
         .map(|(col_name, value)| {
             let debug_str = format!("{:?}", value);
             if debug_str == "None" || debug_str == "Null" {
-                format!("{} IS NULL", col_name)
+                format!("{} IS NULL", quote_identifier(col_name))
             } else {
-                format!("{} = {}", col_name, value_to_sql(value))
+                format!("{} = {}", quote_identifier(col_name), value_to_sql(value))
             }
         })
         .collect();
     
     format!(
         "UPDATE {} SET {} WHERE {}",
-        table_name,
+        quote_identifier(table_name),
         set_parts.join(", "),
         where_parts.join(" AND ")
     )
@@ -132,16 +125,15 @@ pub fn process_binlog_updates(
     parser.check_magic(&mut file)?;
     
     // Build mapping of table names to column names from DuckDB
-    println!("Building table column mapping from DuckDB...");
     let table_columns = build_table_column_mapping(conn)?;
-    println!("Found {} tables in DuckDB", table_columns.len());
+    debug!("Found {} tables in DuckDB", table_columns.len());
     
     let mut update_count = 0;
     
     while let Ok((_header, data)) = parser.next(&mut file) {
         match data {
             EventData::TableMap(table_map_event) => {
-                println!("TableMap event received for table: {}", table_map_event.table_name);
+                debug!("TableMap event received for table: {}", table_map_event.table_name);
             }
 
             EventData::UpdateRows(update_event) => {
@@ -162,12 +154,12 @@ pub fn process_binlog_updates(
                                 &after_row.column_values,
                             );
 
-                            println!("Executing: {}", update_sql);
+                            debug!("Executing: {}", update_sql);
                             conn.execute(&update_sql, [])?;
                             update_count += 1;
                         }
                     } else {
-                        eprintln!("Warning: Table '{}' not found in DuckDB snapshot", table_name);
+                        warn!("Table '{}' not found in DuckDB snapshot", table_name);
                     }
                 }
             }
@@ -178,6 +170,6 @@ pub fn process_binlog_updates(
         }
     }
     
-    println!("Applied {} UPDATE statements", update_count);
+    info!("Applied {} UPDATE statements", update_count);
     Ok(())
 }
\ No newline at end of file
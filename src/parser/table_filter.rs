@@ -0,0 +1,117 @@
+/// Include/exclude filter for row events, matched against `database.table`.
+///
+/// Patterns support `*` (any run of characters) and `?` (any single character).
+/// A pattern without a `.` matches the table name in any database, so `users` is the same as `*.users`.
+///
+/// An event is kept when it matches at least one include pattern (or there are no include patterns)
+/// and matches no exclude pattern.
+#[derive(Debug, Clone, Default)]
+pub struct TableFilter {
+    include: Vec<String>,
+    exclude: Vec<String>,
+}
+
+impl TableFilter {
+    /// Creates a filter that keeps every table
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn include(mut self, pattern: &str) -> Self {
+        self.include.push(Self::normalise_pattern(pattern));
+        self
+    }
+
+    pub fn exclude(mut self, pattern: &str) -> Self {
+        self.exclude.push(Self::normalise_pattern(pattern));
+        self
+    }
+
+    /// Returns true if row events for this table should be parsed
+    pub fn matches(&self, database: &str, table: &str) -> bool {
+        let qualified = format!("{}.{}", database, table);
+
+        let included = self.include.is_empty()
+            || self.include.iter().any(|pattern| wildcard_match(pattern, &qualified));
+        let excluded = self.exclude.iter().any(|pattern| wildcard_match(pattern, &qualified));
+
+        included && !excluded
+    }
+
+    fn normalise_pattern(pattern: &str) -> String {
+        if pattern.contains('.') {
+            pattern.to_string()
+        } else {
+            format!("*.{}", pattern)
+        }
+    }
+}
+
+/// Glob-style match supporting `*` and `?`
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` seen in the pattern, and the text position it was matched against
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = backtrack {
+            // Let the last `*` swallow one more character and retry
+            p = star_p + 1;
+            t = star_t + 1;
+            backtrack = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_filter_keeps_everything() {
+        let filter = TableFilter::new();
+        assert!(filter.matches("main", "users"));
+    }
+
+    #[test]
+    fn test_include_patterns() {
+        let filter = TableFilter::new().include("shop.order*").include("users");
+
+        assert!(filter.matches("shop", "orders"));
+        assert!(filter.matches("shop", "order_items"));
+        assert!(filter.matches("other", "users"));
+        assert!(!filter.matches("other", "orders"));
+        assert!(!filter.matches("shop", "products"));
+    }
+
+    #[test]
+    fn test_exclude_takes_precedence() {
+        let filter = TableFilter::new().include("shop.*").exclude("*.audit_?");
+
+        assert!(filter.matches("shop", "orders"));
+        assert!(!filter.matches("shop", "audit_1"));
+        assert!(filter.matches("shop", "audit_10"));
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("*", ""));
+        assert!(wildcard_match("a*c", "abbbc"));
+        assert!(wildcard_match("a*b*c", "axbyc"));
+        assert!(!wildcard_match("a*c", "abcd"));
+        assert!(!wildcard_match("a?c", "ac"));
+    }
+}
@@ -4,8 +4,25 @@ use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
 use std::fs::File;
 use std::io;
-use std::io::{BufRead, BufReader, BufWriter, Write};
-use crate::binlog::{BinlogOperation, OperationType};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use rayon::prelude::*;
+use tracing::{debug, info, warn};
+use crate::binlog::{namespaced_table_name, BinlogFileMarker, IdentifierCase, BinlogOperation, BinlogTimestamp, OperationType, RowImage, TransactionInfo};
+use crate::operation_applier::StateStore;
+use crate::loader::column_projection::ColumnProjection;
+use crate::loader::redaction::Redactions;
+use crate::parser::binlog_dialect::BinlogDialect;
+use crate::parser::binlog_reader::{self, BinlogCompression};
+use crate::parser::column_mapping::ColumnMapping;
+use crate::parser::line_tokenizer;
+use crate::parser::operation_cache::{self, CacheKey, CachedParse};
+use crate::parser::parse_diagnostics::{ParseAnomalyKind, ParseDiagnostics, SharedTable, TimestampRegression, TooManyParseAnomaliesError, UndecodedRowEventError, UnterminatedTransactionsError};
+use crate::parser::schema_history::{self, SchemaHistory};
+use crate::parser::table_filter::TableFilter;
+use crate::parser::value_decoder::{Charset, ValueDecoder};
+use crate::pensieve::CancelToken;
 
 #[derive(Debug)]
 pub struct NoSchemaTypesFoundError;
@@ -23,14 +40,40 @@ impl std::error::Error for NoSchemaTypesFoundError {}
 pub struct TextBinlogParser {
     conn: Connection,
     schema_cache: HashMap<String, Vec<String>>,
-    timestamp_regex: Regex,
-    position_regex: Regex,
+    // Snapshot spelling of each binlog table name looked up by snapshot_spelling
+    table_spellings: HashMap<String, String>,
+    // DuckDB table of each (database, table) of the binlog, see resolve_table
+    resolved_tables: HashMap<(String, String), String>,
+    // Databases whose row events went to each DuckDB table, with the line of the first event
+    table_databases: HashMap<String, Vec<(String, usize)>>,
+    identifier_case: IdentifierCase,
+    schema_history: SchemaHistory,
+    column_mapping: ColumnMapping,
+    column_projection: ColumnProjection,
+    redactions: Redactions,
+    table_filter: TableFilter,
+    value_decoder: ValueDecoder,
+    worker_threads: usize,
+    max_anomalies: Option<usize>,
+    strict_transactions: bool,
+    use_operation_cache: bool,
+    cancel: Option<CancelToken>,
+    // Operations outside (min, max) are skipped, see with_time_range
+    time_range: (Option<BinlogTimestamp>, Option<BinlogTimestamp>),
+    // Dialect given with with_dialect; otherwise it's detected for each file
+    forced_dialect: Option<BinlogDialect>,
+    dialect: BinlogDialect,
+    diagnostics: ParseDiagnostics,
+    // Rotate and Format_description events read by parse_reader
+    file_events: Vec<(usize, FileEvent)>,
+    // Binlog files of the last parsed file
+    file_markers: Vec<BinlogFileMarker>,
+    // Number of the line last read by parse_reader
+    line_number: Arc<AtomicUsize>,
     update_regex: Regex,
     insert_regex: Regex,
     delete_regex: Regex,
     table_name_regex: Regex,
-    column_value_regex: Regex,
-    begin_regex: Regex,
     commit_regex: Regex,
     rollback_regex: Regex,
 }
@@ -40,66 +83,585 @@ impl TextBinlogParser {
         Self {
             conn,
             schema_cache: HashMap::new(),
-            timestamp_regex: Regex::new(r"^#(\d{6})\s+(\d{1,2}:\d{2}:\d{2})").unwrap(),
-            position_regex: Regex::new(r"end_log_pos\s+(\d+)").unwrap(),
+            table_spellings: HashMap::new(),
+            resolved_tables: HashMap::new(),
+            table_databases: HashMap::new(),
+            identifier_case: IdentifierCase::default(),
+            schema_history: SchemaHistory::new(),
+            column_mapping: ColumnMapping::new(),
+            column_projection: ColumnProjection::new(),
+            redactions: Redactions::new(),
+            table_filter: TableFilter::new(),
+            value_decoder: ValueDecoder::new(),
+            worker_threads: 1,
+            max_anomalies: None,
+            strict_transactions: false,
+            use_operation_cache: false,
+            cancel: None,
+            time_range: (None, None),
+            forced_dialect: None,
+            dialect: BinlogDialect::default(),
+            diagnostics: ParseDiagnostics::default(),
+            file_events: Vec::new(),
+            file_markers: Vec::new(),
+            line_number: Arc::new(AtomicUsize::new(0)),
             update_regex: Regex::new(r"^### UPDATE\s+(.+)").unwrap(),
             insert_regex: Regex::new(r"^### INSERT INTO\s+(.+)").unwrap(),
             delete_regex: Regex::new(r"^### DELETE FROM\s+(.+)").unwrap(),
             table_name_regex: Regex::new(r"`([^`]+)`\.`([^`]+)`").unwrap(),
-            column_value_regex: Regex::new(r"^###\s+@(\d+)=(.*)$").unwrap(),
-            begin_regex: Regex::new(r"^BEGIN").unwrap(),
             commit_regex: Regex::new(r"^COMMIT").unwrap(),
             rollback_regex: Regex::new(r"^ROLLBACK").unwrap(),
         }
     }
 
+    /// Only parse row events for tables accepted by the filter.
+    /// Other row events are skipped before their values are read.
+    pub fn with_table_filter(mut self, table_filter: TableFilter) -> Self {
+        self.table_filter = table_filter;
+        self
+    }
+
+    /// Match binlog columns of mapped tables to snapshot columns through the mapping instead of
+    /// the snapshot schema's column order
+    pub fn with_column_mapping(mut self, column_mapping: ColumnMapping) -> Self {
+        self.column_mapping = column_mapping;
+        self.schema_cache.clear();
+        self.resolved_tables.clear();
+        self
+    }
+
+    /// Drop the values of columns a projection leaves out. Projected tables must have a column
+    /// mapping listing all their binlog columns, since the snapshot only has the projected ones.
+    pub fn with_column_projection(mut self, column_projection: ColumnProjection) -> Self {
+        self.column_projection = column_projection;
+        self
+    }
+
+    /// Redact the values of redacted columns as they are parsed, so they never reach operations
+    /// or the operation cache as they are
+    pub fn with_redactions(mut self, redactions: Redactions) -> Self {
+        self.redactions = redactions;
+        self
+    }
+
+    /// Decode values with the given decoder (e.g. to set per-column charsets)
+    pub fn with_value_decoder(mut self, value_decoder: ValueDecoder) -> Self {
+        self.value_decoder = value_decoder;
+        self
+    }
+
+    /// Parse files with this many worker threads (default 1, i.e. sequentially).
+    /// With more than one thread, each file is split at transaction boundaries and the chunks are
+    /// parsed in parallel; the resulting operations are in the same order as a sequential parse.
+    pub fn with_worker_threads(mut self, worker_threads: usize) -> Self {
+        self.worker_threads = worker_threads.max(1);
+        self
+    }
+
+    /// Abort parsing with a TooManyParseAnomaliesError once a file has more than `max_anomalies`
+    /// anomalies (unlimited by default)
+    pub fn with_max_anomalies(mut self, max_anomalies: usize) -> Self {
+        self.max_anomalies = Some(max_anomalies);
+        self
+    }
+
+    /// How binlog table names are matched to the snapshot's tables
+    pub fn with_identifier_case(mut self, identifier_case: IdentifierCase) -> Self {
+        self.identifier_case = identifier_case;
+        self
+    }
+
+    /// Fail a file's parse with an UnterminatedTransactionsError if it has transactions that were
+    /// neither committed nor rolled back, instead of dropping their operations (off by default)
+    pub fn with_strict_transactions(mut self, strict_transactions: bool) -> Self {
+        self.strict_transactions = strict_transactions;
+        self
+    }
+
+    /// Save the operations parsed from each file to a cache file next to it, and reuse them instead
+    /// of parsing the file again while the file and the parser settings stay the same.
+    /// Files with parse anomalies are not cached, so their anomalies are reported on every parse.
+    pub fn with_operation_cache(mut self, use_operation_cache: bool) -> Self {
+        self.use_operation_cache = use_operation_cache;
+        self
+    }
+
+    /// Stop parsing with a LoadCancelled error once `cancel` is cancelled, checked every
+    /// CancelToken::CHECK_INTERVAL lines
+    pub fn with_cancel_token(mut self, cancel: CancelToken) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    /// Skip row events with a timestamp before `min` or after `max` (both inclusive bounds)
+    /// without reading their values. Row events before the first timestamp are kept.
+    pub fn with_time_range(mut self, min: Option<BinlogTimestamp>, max: Option<BinlogTimestamp>) -> Self {
+        self.time_range = (min, max);
+        self
+    }
+
+    /// Parse every file as this dialect instead of detecting it from the file's first lines
+    pub fn with_dialect(mut self, dialect: BinlogDialect) -> Self {
+        self.forced_dialect = Some(dialect);
+        self
+    }
+
+    /// Runs the per-line patterns used by parse_file over a sample of lines and returns the time taken.
+    /// Used to estimate parse throughput without touching the database.
+    pub fn time_line_matching(&self, lines: &[String]) -> std::time::Duration {
+        let start = std::time::Instant::now();
+        for line in lines {
+            let _ = self.dialect.is_begin(line)
+                || self.commit_regex.is_match(line)
+                || self.rollback_regex.is_match(line);
+            let _ = line_tokenizer::event_timestamp(line);
+            let _ = line_tokenizer::end_log_pos(line);
+            let _ = line_tokenizer::server_id(line);
+            let _ = line_tokenizer::xid(line);
+            let _ = self.update_regex.captures(line);
+            let _ = self.insert_regex.captures(line);
+            let _ = self.delete_regex.captures(line);
+            let _ = line_tokenizer::column_value(line);
+        }
+        start.elapsed()
+    }
+
+    /// Binlog files the last parsed file holds (several if mysqlbinlog was given several), and
+    /// the index in its operations where each starts. Files without a Format_description event
+    /// are named after the parsed file.
+    pub fn file_markers(&self) -> &[BinlogFileMarker] {
+        &self.file_markers
+    }
+
+    /// Column lists recorded from ALTER TABLE statements in the last parsed file
+    pub fn get_schema_history(&self) -> &SchemaHistory {
+        &self.schema_history
+    }
+
+    pub fn get_connection(&self) -> &Connection {
+        &self.conn
+    }
+
     /// Take ownership of the connection (for use after parsing)
     pub fn into_connection(self) -> Connection {
         self.conn
     }
 
     pub fn parse_file(&mut self, filepath: &str) -> Result<Vec<BinlogOperation>, Box<dyn std::error::Error>> {
-        let file = File::open(filepath)?;
-        let reader = BufReader::with_capacity(10 * 1024 * 1024, file);
-        
+        self.parse_file_with_diagnostics(filepath).map(|(operations, _)| operations)
+    }
+
+    /// Parses a file like parse_file, and also returns the lines that were skipped or couldn't be
+    /// fully parsed
+    pub fn parse_file_with_diagnostics(
+        &mut self,
+        filepath: &str,
+    ) -> Result<(Vec<BinlogOperation>, ParseDiagnostics), Box<dyn std::error::Error>> {
+        // Positions restart in every file; carry over each table's latest schema
+        self.schema_history.rebase();
+        self.diagnostics = ParseDiagnostics::default();
+        self.file_events.clear();
+
+        let cache_key = if self.use_operation_cache {
+            Some(CacheKey::for_file(filepath, self.cache_fingerprint()?)?)
+        } else {
+            None
+        };
+        if let Some(cache_key) = &cache_key
+            && let Some(cached) = operation_cache::load(filepath, cache_key) {
+            info!("Loaded {} cached operations for {}", cached.operations.len(), filepath);
+            for (table, columns) in cached.schemas {
+                self.schema_history.record(&table, 0, columns);
+            }
+            self.file_markers = cached.file_markers;
+            return Ok((cached.operations, ParseDiagnostics::default()));
+        }
+
+        self.dialect = match self.forced_dialect {
+            Some(dialect) => dialect,
+            None => BinlogDialect::detect_file(filepath)?,
+        };
+        debug!("Parsing {} as {:?}", filepath, self.dialect);
+
+        // Compressed files can't be split at byte offsets, so they are always parsed sequentially
+        let result = if self.worker_threads > 1 && BinlogCompression::from_path(filepath) == BinlogCompression::None {
+            self.parse_file_parallel(filepath)
+        } else {
+            let reader = binlog_reader::open_binlog(filepath, 10 * 1024 * 1024)?;
+            self.parse_reader(reader, true)
+        };
+
+        let mut diagnostics = std::mem::take(&mut self.diagnostics);
+        for anomaly in &mut diagnostics.anomalies {
+            anomaly.file = filepath.to_string();
+        }
+        for regression in &mut diagnostics.timestamp_regressions {
+            regression.file = filepath.to_string();
+        }
+        for shared in &mut diagnostics.shared_tables {
+            shared.file = filepath.to_string();
+        }
+        match result {
+            Err(e) => match e.downcast::<TooManyParseAnomaliesError>() {
+                Ok(mut e) => {
+                    e.last.file = filepath.to_string();
+                    Err(e)
+                }
+                Err(e) => match e.downcast::<UndecodedRowEventError>() {
+                    Ok(mut e) => {
+                        e.file = filepath.to_string();
+                        Err(e)
+                    }
+                    Err(e) => Err(e),
+                },
+            },
+            Ok(_) if self.strict_transactions && diagnostics.unterminated_transactions().0 > 0 => {
+                let (count, first_timestamp) = diagnostics.unterminated_transactions();
+                Err(Box::new(UnterminatedTransactionsError {
+                    file: filepath.to_string(),
+                    count,
+                    first_timestamp: first_timestamp.map(str::to_string),
+                }))
+            }
+            Ok(operations) => {
+                self.file_markers = file_markers(&std::mem::take(&mut self.file_events), binlog_reader::binlog_file_name(filepath));
+                // Cached files are loaded without diagnostics, so keep reporting these
+                if let Some(cache_key) = &cache_key
+                    && diagnostics.is_empty()
+                    && diagnostics.timestamp_regressions.is_empty()
+                    && diagnostics.shared_tables.is_empty() {
+                    let cached = CachedParse { operations, schemas: self.latest_schemas(), file_markers: self.file_markers.clone() };
+                    if let Err(e) = operation_cache::store(filepath, cache_key, &cached) {
+                        warn!("Could not write operation cache for {}: {}", filepath, e);
+                    }
+                    return Ok((cached.operations, diagnostics));
+                }
+                Ok((operations, diagnostics))
+            }
+        }
+    }
+
+    /// Hash of everything besides the file's contents that decides which operations come out of
+    /// it: the table filter, the column list of every table (after column mapping and the schema
+    /// changes carried over from earlier files), the column charsets, the column projection, the
+    /// redactions, the time range, the identifier case and the forced dialect
+    fn cache_fingerprint(&mut self) -> Result<u64, Box<dyn std::error::Error>> {
+        self.prefetch_schemas()?;
+        let mut tables: Vec<&String> = self.schema_cache.keys().collect();
+        tables.sort();
+
+        let mut fingerprint = format!("{:?}\n", self.table_filter);
+        for table in tables {
+            fingerprint.push_str(&format!(
+                "{}\t{:?}\t{:?}\n",
+                table,
+                self.schema_cache[table],
+                self.schema_history.versions(table)
+            ));
+        }
+        fingerprint.push_str(&format!("{:?}\n{:?}\n{:?}", self.value_decoder.column_charsets(), self.value_decoder.decoded_columns(), self.column_projection));
+        if !self.redactions.is_empty() {
+            fingerprint.push_str(&format!("\n{:?}", self.redactions));
+        }
+        if self.time_range != (None, None) {
+            fingerprint.push_str(&format!("\n{:?}", self.time_range));
+        }
+        if self.identifier_case != IdentifierCase::default() {
+            fingerprint.push_str(&format!("\n{:?}", self.identifier_case));
+        }
+        if let Some(dialect) = self.forced_dialect {
+            fingerprint.push_str(&format!("\n{:?}", dialect));
+        }
+        Ok(operation_cache::fnv1a(operation_cache::FNV_OFFSET_BASIS, fingerprint.as_bytes()))
+    }
+
+    /// Latest column list of every table with a recorded schema change
+    fn latest_schemas(&self) -> Vec<(String, Vec<String>)> {
+        let mut tables: Vec<&String> = self.schema_cache.keys().collect();
+        tables.sort();
+        tables.into_iter()
+            .filter_map(|table| {
+                let latest = self.schema_history.versions(table).last()?;
+                Some((table.clone(), latest.columns.clone()))
+            })
+            .collect()
+    }
+
+    /// Records a problem with the line last read, failing once there are too many
+    fn record_anomaly(&mut self, line: &str, kind: ParseAnomalyKind) -> Result<(), Box<dyn std::error::Error>> {
+        self.diagnostics.record(self.line_number.load(Ordering::Relaxed), line, kind);
+        self.check_anomaly_count()
+    }
+
+    fn check_anomaly_count(&self) -> Result<(), Box<dyn std::error::Error>> {
+        match self.max_anomalies {
+            Some(max_anomalies) if self.diagnostics.len() > max_anomalies => Err(Box::new(TooManyParseAnomaliesError {
+                max_anomalies,
+                last: self.diagnostics.anomalies[max_anomalies].clone(),
+            })),
+            _ => Ok(()),
+        }
+    }
+
+    /// Splits the file into chunks at transaction boundaries, and parses the chunks on a
+    /// rayon pool. Table schemas are fetched up front, so each worker gets its own parser
+    /// with a pre-filled schema cache instead of sharing the DuckDB connection.
+    fn parse_file_parallel(&mut self, filepath: &str) -> Result<Vec<BinlogOperation>, Box<dyn std::error::Error>> {
+        self.prefetch_schemas()?;
+        self.scan_schema_changes(filepath)?;
+        let chunks = find_transaction_chunks(filepath, self.worker_threads * CHUNKS_PER_WORKER)?;
+
+        let schema_cache = self.schema_cache.clone();
+        let schema_history = self.schema_history.clone();
+        let table_filter = self.table_filter.clone();
+        let column_projection = self.column_projection.clone();
+        let redactions = self.redactions.clone();
+        let value_decoder = self.value_decoder.clone();
+        let dialect = self.dialect;
+        let identifier_case = self.identifier_case;
+        let time_range = self.time_range.clone();
+        let cancel = self.cancel.clone();
+        let table_databases = self.table_databases.clone();
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(self.worker_threads).build()?;
+
+        let chunk_results: Vec<Result<ChunkResult, String>> = pool.install(|| {
+            chunks.par_iter()
+                .map(|&(start, end)| {
+                    // The worker's own connection is empty; tables missing from the schema cache
+                    // are skipped, just like tables missing from the snapshot in a sequential parse
+                    let conn = Connection::open_in_memory().map_err(|e| e.to_string())?;
+                    let mut worker = TextBinlogParser::new(conn)
+                        .with_table_filter(table_filter.clone())
+                        .with_column_projection(column_projection.clone())
+                        .with_redactions(redactions.clone())
+                        .with_value_decoder(value_decoder.clone())
+                        .with_dialect(dialect)
+                        .with_identifier_case(identifier_case)
+                        .with_time_range(time_range.0.clone(), time_range.1.clone());
+                    worker.schema_cache = schema_cache.clone();
+                    worker.schema_history = schema_history.clone();
+                    worker.cancel = cancel.clone();
+                    worker.table_databases = table_databases.clone();
+
+                    let mut file = File::open(filepath).map_err(|e| e.to_string())?;
+                    file.seek(SeekFrom::Start(start)).map_err(|e| e.to_string())?;
+                    let reader = BufReader::with_capacity(1024 * 1024, file.take(end - start));
+                    let operations = worker.parse_reader(reader, false).map_err(|e| e.to_string())?;
+                    let line_count = worker.line_number.load(Ordering::Relaxed);
+                    Ok((operations, worker.diagnostics, worker.file_events, worker.table_databases, line_count))
+                })
+                .collect()
+        });
+
+        // Workers' errors come back as strings
+        if let Some(cancel) = &self.cancel {
+            cancel.check()?;
+        }
+
+        // Line numbers in each chunk's diagnostics are relative to the start of the chunk
+        let mut operations = Vec::new();
+        let mut lines_before_chunk = 0;
+        for result in chunk_results {
+            let (mut chunk_operations, mut chunk_diagnostics, chunk_file_events, chunk_table_databases, line_count) = result?;
+            self.file_events.extend(chunk_file_events.into_iter().map(|(idx, event)| (idx + operations.len(), event)));
+            operations.append(&mut chunk_operations);
+            for anomaly in &mut chunk_diagnostics.anomalies {
+                anomaly.line_number += lines_before_chunk;
+            }
+            for regression in &mut chunk_diagnostics.timestamp_regressions {
+                regression.line_number += lines_before_chunk;
+            }
+            // Workers only know the databases of earlier files and their own chunk
+            chunk_diagnostics.shared_tables.clear();
+            self.diagnostics.append(&mut chunk_diagnostics);
+            for (table, databases) in chunk_table_databases {
+                for (database, line_number) in databases {
+                    self.note_table_database(&table, &database, line_number + lines_before_chunk);
+                }
+            }
+            lines_before_chunk += line_count;
+        }
+        self.diagnostics.shared_tables.sort_by_key(|shared| shared.line_number);
+        self.check_anomaly_count()?;
+        Ok(operations)
+    }
+
+    /// Loads the column list of every table in the database into the schema cache
+    fn prefetch_schemas(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let mut stmt = self.conn.prepare("SELECT table_name FROM duckdb_tables() WHERE schema_name = 'main'")?;
+        let tables = stmt
+            .query_map([], |row| row.get::<usize, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        drop(stmt);
+
+        for table in tables {
+            self.get_table_schema(&table);
+        }
+        Ok(())
+    }
+
+    /// Anchors the schema history to a snapshot taken at `snapshot`, whose tables already have
+    /// the columns of every ALTER TABLE before it. Each table altered before the snapshot gets
+    /// the version in effect at the start of the first file, built by reverting those ALTERs
+    /// from the snapshot's columns; parsing then applies them forward again at their positions.
+    /// Called once, before parsing the first of `filepaths`.
+    pub fn anchor_schema_history(&mut self, filepaths: &[String], snapshot: &BinlogTimestamp) -> Result<(), Box<dyn std::error::Error>> {
+        let mut applied: Vec<(String, Vec<schema_history::ColumnChange>)> = Vec::new();
+        for filepath in filepaths {
+            let reader = binlog_reader::open_binlog(filepath, 10 * 1024 * 1024)?;
+            let mut lines = reader.split(b'\n').map(|line_result| {
+                line_result.map(|bytes| String::from_utf8_lossy(&bytes).to_string())
+            });
+            let mut current_timestamp: Option<BinlogTimestamp> = None;
+            let mut current_database = String::new();
+
+            while let Some(Ok(line)) = lines.next() {
+                if let Some((date, time)) = line_tokenizer::event_timestamp(&line) {
+                    current_timestamp = BinlogTimestamp::parse(&format!("{} {}", date, time)).ok();
+                }
+                if let Some(database) = line_tokenizer::use_database(&line) {
+                    current_database = database.to_string();
+                }
+                if is_alter_table(&line) {
+                    let statement = read_statement(line, &mut lines);
+                    if current_timestamp.as_ref().is_some_and(|timestamp| timestamp <= snapshot)
+                        && let Some((database, table, changes)) = schema_history::parse_alter_table(&statement) {
+                        let table = self.resolve_table(database.as_deref().unwrap_or(&current_database), &table);
+                        applied.push((table, changes));
+                    }
+                }
+            }
+        }
+
+        let mut tables: Vec<&String> = applied.iter().map(|(table, _)| table).collect();
+        tables.sort();
+        tables.dedup();
+        for table in tables {
+            let mut columns = self.get_table_schema(table);
+            if columns.is_empty() {
+                continue;
+            }
+            for (_, changes) in applied.iter().rev().filter(|(altered, _)| altered == table) {
+                columns = schema_history::revert_changes(&columns, changes);
+            }
+            self.schema_history.record(table, 0, columns);
+        }
+        Ok(())
+    }
+
+    /// Records the ALTER TABLE statements of a whole file up front, so that parallel workers
+    /// know the schema in effect at any position of their chunk
+    fn scan_schema_changes(&mut self, filepath: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let reader = BufReader::with_capacity(10 * 1024 * 1024, File::open(filepath)?);
+        let mut lines = reader.split(b'\n').map(|line_result| {
+            line_result.map(|bytes| String::from_utf8_lossy(&bytes).to_string())
+        });
+        let mut current_position: Option<u32> = None;
+        let mut current_database = String::new();
+
+        while let Some(Ok(line)) = lines.next() {
+            if line.starts_with('#')
+                && let Some(pos) = line_tokenizer::end_log_pos(&line) {
+                current_position = Some(pos);
+            }
+            if let Some(database) = line_tokenizer::use_database(&line) {
+                current_database = database.to_string();
+            }
+            if is_alter_table(&line) {
+                let statement = read_statement(line, &mut lines);
+                self.record_schema_change(&statement, &current_database, current_position);
+            }
+        }
+        Ok(())
+    }
+
+    /// Parses binlog text from a reader. `log_lines` logs progress every 100000 lines.
+    /// Anomalies are added to self.diagnostics.
+    fn parse_reader<R: BufRead>(&mut self, reader: R, log_lines: bool) -> Result<Vec<BinlogOperation>, Box<dyn std::error::Error>> {
         let mut operations = Vec::new();
+        self.line_number.store(0, Ordering::Relaxed);
+        let line_number = self.line_number.clone();
         // Use a manual line reader that handles binary data
-        let lines = reader.split(b'\n').map(|line_result| {
+        let lines = reader.split(b'\n').map(move |line_result| {
+            line_number.fetch_add(1, Ordering::Relaxed);
             line_result.map(|bytes| String::from_utf8_lossy(&bytes).to_string())
         });
         let mut lines = lines.peekable();
         
         let mut current_timestamp: Option<String> = None;
         let mut current_position: Option<u32> = None;
+        // The current event is outside the time range
+        let mut out_of_range = false;
+        // Database of unqualified table names in statements, from the last `use` line
+        let mut current_database = String::new();
 
         // These two variables help us keep track of whether a transaction is committed or rolled back.
         // We only consider transactions that are successfully committed.
         let mut in_transaction = false;
         let mut pending_operations: Vec<BinlogOperation> = Vec::new();
+        // GTID and Xid of the current transaction, and the server id of the last event
+        let mut transaction = TransactionInfo::default();
 
-        // These two variables are just for logging.
-        let mut writer = BufWriter::new(io::stdout().lock());
-        let mut i = 0;
+        // Line number of the last row event header, until its decoded rows are read. Files written
+        // without DECODE-ROWS and --verbose only have a BINLOG block, or nothing, after it.
+        let mut undecoded_row_event: Option<usize> = None;
+        let mut base64_block = false;
 
-        while let Some(Ok(line)) = lines.next() {
-            writeln!(writer, "LINE #{}", i).unwrap();
-            i += 1;
-            if i % 100000 == 0 {
-                writer.flush()?;
+        let mut lines_read = 0;
+
+        while let Some(line_result) = lines.next() {
+            let line = match line_result {
+                Ok(line) => line,
+                Err(e) => {
+                    self.record_anomaly("", ParseAnomalyKind::ReadError(e.to_string()))?;
+                    break;
+                }
+            };
+            lines_read += 1;
+            if log_lines && lines_read % 100_000 == 0 {
+                debug!("Read {} lines", lines_read);
+            }
+            if lines_read % CancelToken::CHECK_INTERVAL == 0 && let Some(cancel) = &self.cancel {
+                cancel.check()?;
+            }
+
+            if line.starts_with("BINLOG '") {
+                base64_block = true;
+            }
+            if line_tokenizer::event_timestamp(&line).is_some() {
+                if let Some(line_number) = undecoded_row_event.take() {
+                    return Err(Box::new(UndecodedRowEventError { file: String::new(), line_number, base64: base64_block }));
+                }
+                if line_tokenizer::is_row_event(&line) {
+                    undecoded_row_event = Some(self.line_number.load(Ordering::Relaxed));
+                    base64_block = false;
+                }
             }
 
-            if self.begin_regex.is_match(&line) {
+            if self.dialect.is_annotation(&line) {
+                continue;
+            }
+
+            if self.dialect.is_begin(&line) {
+                if in_transaction && !pending_operations.is_empty() {
+                    self.record_anomaly(&line, unterminated_transaction(&pending_operations))?;
+                }
                 in_transaction = true;
                 pending_operations.clear();
+                transaction.xid = None;
                 continue;
             }
             
             if self.commit_regex.is_match(&line) {
                 if in_transaction {
+                    for op in &mut pending_operations {
+                        op.transaction = transaction.clone();
+                    }
                     operations.append(&mut pending_operations);
                 }
                 in_transaction = false;
                 pending_operations.clear();
+                transaction = TransactionInfo { server_id: transaction.server_id, ..Default::default() };
                 continue;
             }
             
@@ -108,24 +670,80 @@ impl TextBinlogParser {
                     pending_operations.clear();
                 }
                 in_transaction = false;
+                transaction = TransactionInfo { server_id: transaction.server_id, ..Default::default() };
                 continue;
             }
             
-            if let Some(captures) = self.timestamp_regex.captures(&line) {
-                let date = &captures[1];
-                let time = &captures[2];
-                current_timestamp = Some(format!("{} {}", date, time));
+            if line.starts_with('#') {
+                if let Some(server_id) = line_tokenizer::server_id(&line) {
+                    transaction.server_id = Some(server_id);
+                }
+                if let Some(xid) = line_tokenizer::xid(&line) {
+                    transaction.xid = Some(xid);
+                }
+                if let Some(file) = line_tokenizer::rotate_target(&line) {
+                    self.file_events.push((operations.len(), FileEvent::Rotate(file.to_string())));
+                }
+                if let Some(version) = line_tokenizer::format_description_version(&line) {
+                    self.file_events.push((operations.len(), FileEvent::FormatDescription(version.to_string())));
+                }
+                if let Some(sequence_number) = line_tokenizer::gtid_sequence_number(&line) {
+                    transaction.sequence_number = Some(sequence_number);
+                    transaction.gtid = None;
+                }
+                if self.dialect == BinlogDialect::MariaDb
+                    && let Some((gtid, sequence_number)) = line_tokenizer::mariadb_gtid(&line) {
+                    transaction.sequence_number = Some(sequence_number);
+                    transaction.gtid = Some(gtid.to_string());
+                }
+            } else if let Some(gtid) = line_tokenizer::gtid_next(&line) {
+                transaction.gtid = Some(gtid.to_string());
             }
             
-            if let Some(captures) = self.position_regex.captures(&line) {
-                if let Ok(pos) = captures[1].parse::<u32>() {
-                    current_position = Some(pos);
+            if let Some((date, time)) = line_tokenizer::event_timestamp(&line) {
+                let timestamp = format!("{} {}", date, time);
+                if let Some(previous) = &current_timestamp
+                    && let (Ok(before), Ok(now)) = (BinlogTimestamp::parse(previous), BinlogTimestamp::parse(&timestamp))
+                    && now < before {
+                    self.diagnostics.timestamp_regressions.push(TimestampRegression {
+                        file: String::new(),
+                        line_number: self.line_number.load(Ordering::Relaxed),
+                        previous: previous.clone(),
+                        timestamp: timestamp.clone(),
+                    });
                 }
+                out_of_range = self.is_out_of_range(&timestamp);
+                current_timestamp = Some(timestamp);
+            }
+            
+            if let Some(pos) = line_tokenizer::end_log_pos(&line) {
+                current_position = Some(pos);
             }
             
+            if let Some(database) = line_tokenizer::use_database(&line) {
+                current_database = database.to_string();
+            }
+
+            if is_alter_table(&line) {
+                let statement = read_statement(line, &mut lines);
+                self.record_schema_change(&statement, &current_database, current_position);
+                continue;
+            }
+
+            if out_of_range
+                && (self.update_regex.is_match(&line) || self.insert_regex.is_match(&line) || self.delete_regex.is_match(&line)) {
+                self.skip_to_next_sql_operation(&mut lines);
+                undecoded_row_event = None;
+                continue;
+            }
+
+            let mut row_event = false;
             if let Some(captures) = self.update_regex.captures(&line) {
+                row_event = true;
                 let table_path = captures[1].to_string();
-                if let Some(op) = self.parse_update(&mut lines, &table_path, &current_timestamp, current_position)? {
+                if let Some(mut op) = self.parse_update(&mut lines, &table_path, &current_timestamp, current_position)? {
+                    self.column_projection.project(&mut op);
+                    self.redactions.redact(&mut op);
                     if in_transaction {
                         pending_operations.push(op);
                     } else {
@@ -136,8 +754,11 @@ impl TextBinlogParser {
             }
             
             if let Some(captures) = self.insert_regex.captures(&line) {
+                row_event = true;
                 let table_path = captures[1].to_string();
-                if let Some(op) = self.parse_insert(&mut lines, &table_path, &current_timestamp, current_position)? {
+                if let Some(mut op) = self.parse_insert(&mut lines, &table_path, &current_timestamp, current_position)? {
+                    self.column_projection.project(&mut op);
+                    self.redactions.redact(&mut op);
                     if in_transaction {
                         pending_operations.push(op);
                     } else {
@@ -148,8 +769,11 @@ impl TextBinlogParser {
             }
             
             if let Some(captures) = self.delete_regex.captures(&line) {
+                row_event = true;
                 let table_path = captures[1].to_string();
-                if let Some(op) = self.parse_delete(&mut lines, &table_path, &current_timestamp, current_position)? {
+                if let Some(mut op) = self.parse_delete(&mut lines, &table_path, &current_timestamp, current_position)? {
+                    self.column_projection.project(&mut op);
+                    self.redactions.redact(&mut op);
                     if in_transaction {
                         pending_operations.push(op);
                     } else {
@@ -158,6 +782,20 @@ impl TextBinlogParser {
                     }
                 }
             }
+
+            if row_event {
+                undecoded_row_event = None;
+            } else if line.starts_with("###") {
+                self.record_anomaly(&line, ParseAnomalyKind::OrphanRowImageLine)?;
+            }
+        }
+
+        if let Some(line_number) = undecoded_row_event {
+            return Err(Box::new(UndecodedRowEventError { file: String::new(), line_number, base64: base64_block }));
+        }
+
+        if in_transaction && !pending_operations.is_empty() {
+            self.record_anomaly("", unterminated_transaction(&pending_operations))?;
         }
         
         Ok(operations)
@@ -174,7 +812,12 @@ impl TextBinlogParser {
         I: Iterator<Item = Result<String, io::Error>>
     {
         let (db, table) = self.extract_table_name(table_path);
-        let columns = self.get_table_schema(&table);
+        if !self.table_filter.matches(&db, &table) {
+            self.skip_to_next_sql_operation(lines);
+            return Ok(None);
+        }
+        let table = self.resolve_row_event_table(&db, &table);
+        let columns = self.columns_at(&table, position);
 
         // Columns will be empty if the table was not found in the parquet snapshot, and hence,
         // not loaded into DuckDB
@@ -197,18 +840,14 @@ impl TextBinlogParser {
                 break;
             }
             
-            if line.contains("### SET") {
+            if is_image_heading(line, "SET") {
                 found_set = true;
                 lines.next(); // Consume the SET line
                 break;
             }
             
             let line = lines.next().unwrap().unwrap();
-            if let Some(captures) = self.column_value_regex.captures(&line) {
-                let col_num: usize = captures[1].parse()?;
-                let value = captures[2].to_string();
-                where_values.insert(col_num, value);
-            }
+            self.read_image_line(&line, columns.len(), &mut where_values)?;
         }
         
         // Parse SET clause
@@ -225,26 +864,32 @@ impl TextBinlogParser {
                 }
                 
                 let line = lines.next().unwrap().unwrap();
-                if let Some(captures) = self.column_value_regex.captures(&line) {
-                    let col_num: usize = captures[1].parse()?;
-                    let value = captures[2].to_string();
-                    set_values.insert(col_num, value);
-                }
+                self.read_image_line(&line, columns.len(), &mut set_values)?;
             }
         }
         
-        // Convert HashMap to Vec (ordered by column index)
-        let mut before_vals = vec!["NULL".to_string(); columns.len()];
-        let mut after_vals = vec!["NULL".to_string(); columns.len()];
-        
-        for (i, _col) in columns.iter().enumerate() {
+        let is_full = (1..=columns.len()).all(|idx| where_values.contains_key(&idx) && set_values.contains_key(&idx));
+
+        // Convert HashMap to Vec (ordered by column index). With a partial row image, only the
+        // columns logged in either image are kept: the WHERE image holds the key, the SET image the
+        // changed columns, and a column missing from SET is unchanged.
+        let mut op_columns = Vec::new();
+        let mut before_vals = Vec::new();
+        let mut after_vals = Vec::new();
+        let mut missing_before = Vec::new();
+        for (i, col) in columns.iter().enumerate() {
             let col_idx = i + 1; // @1 = column 0, etc.
-            if let Some(val) = where_values.get(&col_idx) {
-                before_vals[i] = val.clone();
+            let before = where_values.get(&col_idx).map(|val| self.value_decoder.decode(&table, col, val));
+            let after = set_values.get(&col_idx).map(|val| self.value_decoder.decode(&table, col, val));
+            if !is_full && before.is_none() && after.is_none() {
+                continue;
             }
-            if let Some(val) = set_values.get(&col_idx) {
-                after_vals[i] = val.clone();
+            if !is_full && before.is_none() {
+                missing_before.push(col.clone());
             }
+            op_columns.push(col.clone());
+            after_vals.push(after.or_else(|| before.clone()).unwrap_or_else(|| "NULL".to_string()));
+            before_vals.push(before.unwrap_or_else(|| "NULL".to_string()));
         }
         
         Ok(Some(BinlogOperation {
@@ -253,9 +898,11 @@ impl TextBinlogParser {
             operation_type: OperationType::Update,
             table_name: table,
             database: db,
-            columns,
+            columns: op_columns,
             before_values: Some(before_vals),
             after_values: Some(after_vals),
+            row_image: if is_full { RowImage::Full } else { RowImage::Partial { missing_before } },
+            transaction: TransactionInfo::default(),
         }))
     }
 
@@ -271,7 +918,12 @@ impl TextBinlogParser {
         I: Iterator<Item = Result<String, std::io::Error>>
     {
         let (db, table) = self.extract_table_name(table_path);
-        let columns = self.get_table_schema(&table);
+        if !self.table_filter.matches(&db, &table) {
+            self.skip_to_next_sql_operation(lines);
+            return Ok(None);
+        }
+        let table = self.resolve_row_event_table(&db, &table);
+        let columns = self.columns_at(&table, position);
         
         if columns.is_empty() {
             self.skip_to_next_sql_operation(lines);
@@ -291,19 +943,19 @@ impl TextBinlogParser {
             }
             
             let line = lines.next().unwrap().unwrap();
-            if let Some(captures) = self.column_value_regex.captures(&line) {
-                let col_num: usize = captures[1].parse()?;
-                let value = captures[2].to_string();
-                values.insert(col_num, value);
-            }
+            self.read_image_line(&line, columns.len(), &mut values)?;
         }
         
-        // Convert HashMap to Vec (ordered by column index)
-        let mut vals = vec!["NULL".to_string(); columns.len()];
-        for i in 0..columns.len() {
+        // Convert HashMap to Vec (ordered by column index). Columns missing from a partial row
+        // image are left out, so they get their default value.
+        let is_full = (1..=columns.len()).all(|idx| values.contains_key(&idx));
+        let mut op_columns = Vec::new();
+        let mut vals = Vec::new();
+        for (i, col) in columns.iter().enumerate() {
             let col_idx = i + 1;
-            if let Some(val) = values.get(&col_idx) {
-                vals[i] = val.clone();
+            if let Some(v) = values.get(&col_idx) {
+                op_columns.push(col.clone());
+                vals.push(self.value_decoder.decode(&table, col, v));
             }
         }
         
@@ -313,9 +965,11 @@ impl TextBinlogParser {
             operation_type: OperationType::Insert,
             table_name: table,
             database: db,
-            columns,
+            columns: op_columns,
             before_values: None,
             after_values: Some(vals),
+            row_image: if is_full { RowImage::Full } else { RowImage::Partial { missing_before: Vec::new() } },
+            transaction: TransactionInfo::default(),
         }))
     }
 
@@ -330,7 +984,12 @@ impl TextBinlogParser {
         I: Iterator<Item = Result<String, std::io::Error>>
     {
         let (db, table) = self.extract_table_name(table_path);
-        let columns = self.get_table_schema(&table);
+        if !self.table_filter.matches(&db, &table) {
+            self.skip_to_next_sql_operation(lines);
+            return Ok(None);
+        }
+        let table = self.resolve_row_event_table(&db, &table);
+        let columns = self.columns_at(&table, position);
         
         if columns.is_empty() {
             self.skip_to_next_sql_operation(lines);
@@ -350,19 +1009,19 @@ impl TextBinlogParser {
             }
             
             let line = lines.next().unwrap().unwrap();
-            if let Some(captures) = self.column_value_regex.captures(&line) {
-                let col_num: usize = captures[1].parse()?;
-                let value = captures[2].to_string();
-                where_values.insert(col_num, value);
-            }
+            self.read_image_line(&line, columns.len(), &mut where_values)?;
         }
         
-        // Convert HashMap to Vec (ordered by column index)
+        // Convert HashMap to Vec (ordered by column index). Columns missing from a partial row
+        // image are placeholders, filled in from the database when the delete is applied, so the
+        // row can be restored when stepping back.
         let mut before_vals = vec!["NULL".to_string(); columns.len()];
-        for (i, _col) in columns.iter().enumerate() {
+        let mut missing_before = Vec::new();
+        for (i, col) in columns.iter().enumerate() {
             let col_idx = i + 1;
-            if let Some(val) = where_values.get(&col_idx) {
-                before_vals[i] = val.clone();
+            match where_values.get(&col_idx) {
+                Some(val) => before_vals[i] = self.value_decoder.decode(&table, col, val),
+                None => missing_before.push(col.clone()),
             }
         }
         
@@ -375,9 +1034,35 @@ impl TextBinlogParser {
             columns,
             before_values: Some(before_vals),
             after_values: None,
+            row_image: if missing_before.is_empty() { RowImage::Full } else { RowImage::Partial { missing_before } },
+            transaction: TransactionInfo::default(),
         }))
     }
 
+    /// Adds the value on a row image line (`###   @2='abc'`) to `values`, keyed by column number.
+    /// Anything but a column value of the table or an image heading is recorded as an anomaly.
+    fn read_image_line(
+        &mut self,
+        line: &str,
+        column_count: usize,
+        values: &mut HashMap<usize, String>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some((col_num, value)) = line_tokenizer::column_value(line) {
+            match col_num.parse::<usize>() {
+                Ok(col_num) if (1..=column_count).contains(&col_num) => {
+                    values.insert(col_num, value.to_string());
+                }
+                _ => {
+                    let kind = ParseAnomalyKind::ColumnOutOfRange { column: col_num.to_string(), column_count };
+                    self.record_anomaly(line, kind)?;
+                }
+            }
+        } else if !is_image_heading(line, "WHERE") && !is_image_heading(line, "SET") {
+            self.record_anomaly(line, ParseAnomalyKind::UnrecognisedRowImageLine)?;
+        }
+        Ok(())
+    }
+
     pub(crate) fn extract_table_name(&self, table_path: &str) -> (String, String) {
         if let Some(captures) = self.table_name_regex.captures(table_path) {
             let db = captures[1].to_string();
@@ -388,33 +1073,154 @@ impl TextBinlogParser {
         }
     }
 
+    /// DuckDB table of `database`.`table`: the namespaced `database__table` if the snapshot has
+    /// it, so that tables with the same name in several databases don't collide, or else `table`
+    fn resolve_table(&mut self, database: &str, table: &str) -> String {
+        let key = (database.to_string(), table.to_string());
+        if let Some(resolved) = self.resolved_tables.get(&key) {
+            return resolved.clone();
+        }
+        let mut resolved = None;
+        if !database.is_empty() {
+            let namespaced = self.snapshot_spelling(&namespaced_table_name(database, table));
+            if self.schema_history.versions(&namespaced).last().is_some() || !self.get_table_schema(&namespaced).is_empty() {
+                resolved = Some(namespaced);
+            }
+        }
+        let resolved = resolved.unwrap_or_else(|| self.snapshot_spelling(table));
+        self.resolved_tables.insert(key, resolved.clone());
+        resolved
+    }
+
+    /// resolve_table for a row event, recording a SharedTable when the table already had row
+    /// events from another database
+    fn resolve_row_event_table(&mut self, database: &str, table: &str) -> String {
+        let resolved = self.resolve_table(database, table);
+        if !database.is_empty() && !self.get_table_schema(&resolved).is_empty() {
+            self.note_table_database(&resolved, database, self.line_number.load(Ordering::Relaxed));
+        }
+        resolved
+    }
+
+    fn note_table_database(&mut self, table: &str, database: &str, line_number: usize) {
+        let databases = self.table_databases.entry(table.to_string()).or_default();
+        if databases.iter().any(|(seen, _)| seen == database) {
+            return;
+        }
+        if let Some((first_database, _)) = databases.first() {
+            self.diagnostics.shared_tables.push(SharedTable {
+                file: String::new(),
+                line_number,
+                table: table.to_string(),
+                first_database: first_database.clone(),
+                database: database.to_string(),
+            });
+        }
+        databases.push((database.to_string(), line_number));
+    }
+
+    /// The snapshot's spelling of `table` under the identifier case policy. DuckDB itself ignores
+    /// case, so with IdentifierCase::Sensitive a table the snapshot spells differently is given no
+    /// columns, which skips it like a missing table.
+    fn snapshot_spelling(&mut self, table: &str) -> String {
+        if let Some(spelling) = self.table_spellings.get(table) {
+            return spelling.clone();
+        }
+        // Parallel workers have an empty connection but the snapshot's tables in their schema cache
+        let spelling = self.schema_cache.keys()
+            .find(|cached| cached.as_str() == table)
+            .or_else(|| self.schema_cache.keys().find(|cached| cached.to_lowercase() == table.to_lowercase()))
+            .cloned()
+            .or_else(|| self.conn.query_row(
+                "SELECT table_name FROM duckdb_tables() WHERE schema_name = 'main' AND lower(table_name) = lower(?)",
+                [table],
+                |row| row.get::<usize, String>(0),
+            ).ok());
+
+        let resolved = match (spelling, self.identifier_case) {
+            (Some(spelling), IdentifierCase::Insensitive) => spelling,
+            (Some(spelling), IdentifierCase::Sensitive) if spelling != table => {
+                debug!("Skipping table {}: the snapshot spells it {}", table, spelling);
+                self.schema_cache.insert(table.to_string(), Vec::new());
+                table.to_string()
+            }
+            _ => table.to_string(),
+        };
+        self.table_spellings.insert(table.to_string(), resolved.clone());
+        resolved
+    }
+
+    /// Columns in effect at `position`: from the latest ALTER TABLE before it, or else the DuckDB schema
+    fn columns_at(&mut self, table_name: &str, position: Option<u32>) -> Vec<String> {
+        if let Some(columns) = self.schema_history.columns_at(table_name, position.unwrap_or(0)) {
+            return columns.clone();
+        }
+        self.get_table_schema(table_name)
+    }
+
+    /// Records a new schema version for the table altered by a DDL statement.
+    /// Unless anchor_schema_history was called, the DuckDB schema is taken as the schema before
+    /// the first ALTER TABLE in the binlog.
+    fn record_schema_change(&mut self, statement: &str, current_database: &str, position: Option<u32>) {
+        let Some((database, table, changes)) = schema_history::parse_alter_table(statement) else {
+            return;
+        };
+        let table = self.resolve_table(database.as_deref().unwrap_or(current_database), &table);
+        let position = position.unwrap_or(0);
+        // Tables missing from the snapshot are skipped when parsing anyway
+        let columns = self.columns_at(&table, Some(position));
+        if columns.is_empty() || changes.is_empty() {
+            return;
+        }
+
+        let new_columns = schema_history::apply_changes(&columns, &changes);
+        self.schema_history.record(&table, position, new_columns);
+    }
+
     /// Get table schema (columns only) - used during parsing to know expected columns
     fn get_table_schema(&mut self, table_name: &str) -> Vec<String> {
         if let Some(cols) = self.schema_cache.get(table_name) {
             return cols.clone();
         }
+        self.detect_binary_columns(table_name);
+        if let Some(columns) = self.column_mapping.columns(table_name) {
+            let columns = columns.clone();
+            self.schema_cache.insert(table_name.to_string(), columns.clone());
+            return columns;
+        }
 
-        let query = format!("PRAGMA table_info('{}')", table_name);
-        let Ok(mut stmt) = self.conn.prepare(&query) else {
-            return Vec::new()
-        };
+        let columns = self.conn.table_columns(table_name).unwrap_or_default();
 
-        let Ok(rows) = stmt.query_map([], |row| {
-            let name: String = row.get(1)?;
-            Ok(name)
-        }) else {
-            return Vec::new();
-        };
+        self.schema_cache.insert(table_name.to_string(), columns.clone());
+        columns
+    }
 
-        let mut columns = Vec::new();
-        for row in rows {
-            if let Ok(name) = row {
-                columns.push(name);
+    /// Decodes the values of the table's BLOB columns as binary, unless a charset was set for them
+    fn detect_binary_columns(&mut self, table_name: &str) {
+        let Ok(mut stmt) = self.conn.prepare(
+            "SELECT column_name FROM duckdb_columns() WHERE schema_name = 'main' AND table_name = ? AND data_type = 'BLOB'"
+        ) else {
+            return;
+        };
+        let Ok(rows) = stmt.query_map([table_name], |row| row.get::<usize, String>(0)) else {
+            return;
+        };
+        for column in rows.flatten() {
+            if !self.value_decoder.has_column_charset(table_name, &column) {
+                self.value_decoder.set_column_charset(table_name, &column, Charset::Binary);
             }
         }
+    }
 
-        self.schema_cache.insert(table_name.to_string(), columns.clone());
-        columns
+    fn is_out_of_range(&self, timestamp: &str) -> bool {
+        let (min, max) = &self.time_range;
+        if min.is_none() && max.is_none() {
+            return false;
+        }
+        let Ok(timestamp) = BinlogTimestamp::parse(timestamp) else {
+            return false;
+        };
+        min.as_ref().is_some_and(|min| timestamp < *min) || max.as_ref().is_some_and(|max| timestamp > *max)
     }
 
     fn skip_to_next_sql_operation<I>(&self, lines: &mut std::iter::Peekable<I>)
@@ -433,37 +1239,160 @@ impl TextBinlogParser {
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::operation_applier::OperationApplier;
-
-    // ===========================================
-    // Helper Functions
-    // ===========================================
-
-    fn create_test_db() -> Connection {
-        let conn = Connection::open_in_memory().unwrap();
-        conn.execute_batch(
-            "CREATE TABLE users (
-                id INTEGER PRIMARY KEY,
-                name VARCHAR,
-                email VARCHAR,
-                age INTEGER,
-                balance DECIMAL(10,2),
-                is_active BOOLEAN,
-                created_at TIMESTAMP
-            )"
-        ).unwrap();
-        
-        conn.execute_batch(
-            "INSERT INTO users VALUES 
-                (1, 'Alice', 'alice@example.com', 30, 1000.50, true, '2024-01-01 10:00:00'),
-                (2, 'Bob', 'bob@example.com', 25, 500.00, true, '2024-01-02 11:00:00'),
-                (3, 'Charlie', 'charlie@example.com', 35, 1500.75, false, '2024-01-03 12:00:00')"
-        ).unwrap();
-        
-        conn
+fn unterminated_transaction(pending_operations: &[BinlogOperation]) -> ParseAnomalyKind {
+    ParseAnomalyKind::UnterminatedTransaction {
+        operations: pending_operations.len(),
+        first_timestamp: pending_operations.iter().find_map(|op| op.timestamp.clone()),
+    }
+}
+
+/// A binlog event marking a file boundary
+#[derive(Debug, Clone)]
+enum FileEvent {
+    /// Rotate to the named file, at the end of a file
+    Rotate(String),
+    /// Format_description with the server version, at the start of a file
+    FormatDescription(String),
+}
+
+/// Markers of the files told apart by the events, each at the number of operations before it.
+/// A file's name comes from the Rotate event before it, which also ends the previous file; files
+/// without one are named after the previous file, or get `default_file` if they are the first.
+fn file_markers(events: &[(usize, FileEvent)], default_file: &str) -> Vec<BinlogFileMarker> {
+    let mut markers = vec![BinlogFileMarker { file: default_file.to_string(), first_operation: 0, server_version: None, rotates_to: None }];
+    let mut next_file = None;
+    for (first_operation, event) in events {
+        match event {
+            FileEvent::Rotate(file) => {
+                markers.last_mut().unwrap().rotates_to = Some(file.clone());
+                next_file = Some(file.clone());
+            }
+            FileEvent::FormatDescription(version) => {
+                let previous = markers.last().unwrap();
+                // The file started before its first event, or is named by the Rotate before it
+                let replaces_previous = previous.server_version.is_none() && previous.first_operation == *first_operation;
+                let file = match next_file.take() {
+                    Some(file) => file,
+                    None if replaces_previous => previous.file.clone(),
+                    None => BinlogFileMarker::next_file_name(&previous.file),
+                };
+                if replaces_previous {
+                    markers.pop();
+                }
+                markers.push(BinlogFileMarker { file, first_operation: *first_operation, server_version: Some(version.clone()), rotates_to: None });
+            }
+        }
+    }
+    markers
+}
+
+/// True for a row image heading such as `### SET`, tolerating extra spaces and lower case
+fn is_image_heading(line: &str, heading: &str) -> bool {
+    line.strip_prefix("###").is_some_and(|rest| rest.trim().eq_ignore_ascii_case(heading))
+}
+
+fn is_alter_table(line: &str) -> bool {
+    line.get(..11).is_some_and(|prefix| prefix.eq_ignore_ascii_case("ALTER TABLE"))
+}
+
+/// Reads a statement from its first line up to mysqlbinlog's `/*!*/;` delimiter
+fn read_statement<I>(first_line: String, lines: &mut I) -> String
+where
+    I: Iterator<Item = Result<String, io::Error>>
+{
+    let mut statement = first_line;
+    while !statement.trim_end().ends_with("/*!*/;") {
+        match lines.next() {
+            Some(Ok(line)) => {
+                statement.push('\n');
+                statement.push_str(&line);
+            }
+            _ => break,
+        }
+    }
+    statement.trim_end().trim_end_matches("/*!*/;").to_string()
+}
+
+/// Operations, diagnostics, Rotate and Format_description events and line count of one chunk of a
+/// parallel parse
+type ChunkResult = (Vec<BinlogOperation>, ParseDiagnostics, Vec<(usize, FileEvent)>, HashMap<String, Vec<(String, usize)>>, usize);
+
+/// Number of chunks per worker thread, so uneven chunks still spread across the pool
+const CHUNKS_PER_WORKER: usize = 4;
+
+/// Splits a binlog file into roughly `target_chunks` byte ranges. Every range but the first starts
+/// right after a COMMIT or ROLLBACK line, so no transaction is split across two ranges, and the
+/// GTID event preceding a transaction's BEGIN stays in the same range as the transaction.
+fn find_transaction_chunks(filepath: &str, target_chunks: usize) -> Result<Vec<(u64, u64)>, Box<dyn std::error::Error>> {
+    let file_size = std::fs::metadata(filepath)?.len();
+    let mut boundaries = vec![0];
+
+    let mut reader = BufReader::new(File::open(filepath)?);
+    let mut line = Vec::new();
+    for k in 1..target_chunks as u64 {
+        let target = file_size * k / target_chunks as u64;
+        let last = *boundaries.last().unwrap();
+        if target <= last {
+            continue;
+        }
+
+        // Align to the start of the next line, then look for the end of the next transaction
+        reader.seek(SeekFrom::Start(target - 1))?;
+        let mut offset = target - 1;
+        line.clear();
+        offset += reader.read_until(b'\n', &mut line)? as u64;
+        loop {
+            line.clear();
+            let read = reader.read_until(b'\n', &mut line)? as u64;
+            if read == 0 {
+                break;
+            }
+            offset += read;
+            if line.starts_with(b"COMMIT") || line.starts_with(b"ROLLBACK") {
+                if offset > last && offset < file_size {
+                    boundaries.push(offset);
+                }
+                break;
+            }
+        }
+    }
+
+    boundaries.push(file_size);
+    boundaries.dedup();
+    Ok(boundaries.windows(2).map(|w| (w[0], w[1])).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::operation_applier::OperationApplier;
+
+    // ===========================================
+    // Helper Functions
+    // ===========================================
+
+    fn create_test_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE users (
+                id INTEGER PRIMARY KEY,
+                name VARCHAR,
+                email VARCHAR,
+                age INTEGER,
+                balance DECIMAL(10,2),
+                is_active BOOLEAN,
+                created_at TIMESTAMP
+            )"
+        ).unwrap();
+        
+        conn.execute_batch(
+            "INSERT INTO users VALUES 
+                (1, 'Alice', 'alice@example.com', 30, 1000.50, true, '2024-01-01 10:00:00'),
+                (2, 'Bob', 'bob@example.com', 25, 500.00, true, '2024-01-02 11:00:00'),
+                (3, 'Charlie', 'charlie@example.com', 35, 1500.75, false, '2024-01-03 12:00:00')"
+        ).unwrap();
+        
+        conn
     }
 
     fn create_temp_binlog(content: &str) -> std::path::PathBuf {
@@ -609,6 +1538,657 @@ mod tests {
         std::fs::remove_file(temp_file).ok();
     }
 
+    #[test]
+    fn test_table_filter_skips_excluded_tables() {
+        let conn = create_test_db();
+        let mut parser = TextBinlogParser::new(conn)
+            .with_table_filter(TableFilter::new().exclude("main.users"));
+        
+        let binlog_content = r#"
+#251020 19:43:32 server id 123  end_log_pos 2000
+### INSERT INTO `main`.`users`
+### SET
+###   @1=4
+###   @2='David'
+#251020 19:43:33 server id 123  end_log_pos 2100
+### INSERT INTO `other`.`users`
+### SET
+###   @1=5
+###   @2='Eve'
+"#;
+        
+        let temp_file = create_temp_binlog(binlog_content);
+        let operations = parser.parse_file(temp_file.to_str().unwrap()).unwrap();
+        
+        assert_eq!(operations.len(), 1);
+        assert_eq!(operations[0].database, "other");
+        
+        std::fs::remove_file(temp_file).ok();
+    }
+
+    #[test]
+    fn test_time_range_skips_operations_outside_it() {
+        let binlog_content = r#"BEGIN
+#251020 19:43:32 server id 123  end_log_pos 1000
+### INSERT INTO `main`.`users`
+### SET
+###   @1=10
+#251020 20:00:00 server id 123  end_log_pos 1100
+### INSERT INTO `main`.`users`
+### SET
+###   @1=11
+COMMIT
+BEGIN
+#251020 21:00:01 server id 123  end_log_pos 1200
+### DELETE FROM `main`.`users`
+### WHERE
+###   @1=10
+COMMIT
+"#;
+        let temp_file = create_temp_binlog(binlog_content);
+        let range = (BinlogTimestamp::parse("251020 19:50:00").ok(), BinlogTimestamp::parse("251020 21:00:00").ok());
+
+        for worker_threads in [1, 2] {
+            let mut parser = TextBinlogParser::new(create_test_db())
+                .with_worker_threads(worker_threads)
+                .with_time_range(range.0.clone(), range.1.clone());
+            let (operations, diagnostics) = parser.parse_file_with_diagnostics(temp_file.to_str().unwrap()).unwrap();
+
+            assert!(diagnostics.is_empty());
+            assert_eq!(operations.len(), 1);
+            assert_eq!(operations[0].after_values, Some(vec!["11".to_string()]));
+        }
+
+        std::fs::remove_file(temp_file).ok();
+    }
+
+    #[test]
+    fn test_same_table_name_in_two_databases_resolves_namespaced_table() {
+        let conn = create_test_db();
+        conn.execute_batch("CREATE TABLE shop__users (id INTEGER PRIMARY KEY, name VARCHAR)").unwrap();
+        let binlog_content = r#"
+#251020 19:43:32 server id 123  end_log_pos 1000
+### INSERT INTO `shop`.`users`
+### SET
+###   @1=1
+###   @2='shopper'
+#251020 19:43:33 server id 123  end_log_pos 1100
+### INSERT INTO `main`.`users`
+### SET
+###   @1=2
+"#;
+        let temp_file = create_temp_binlog(binlog_content);
+        let mut parser = TextBinlogParser::new(conn);
+        let operations = parser.parse_file(temp_file.to_str().unwrap()).unwrap();
+
+        assert_eq!(operations.len(), 2);
+        assert_eq!(operations[0].table_name, "shop__users");
+        assert_eq!(operations[0].columns, vec!["id", "name"]);
+        assert_eq!(operations[0].mysql_table_name(), "users");
+        assert_eq!(operations[1].table_name, "users");
+        assert_eq!(operations[1].mysql_table_name(), "users");
+        assert!(parser.parse_file_with_diagnostics(temp_file.to_str().unwrap()).unwrap().1.shared_tables.is_empty());
+
+        std::fs::remove_file(temp_file).ok();
+    }
+
+    #[test]
+    fn test_same_table_name_in_two_databases_without_namespaced_table_is_reported() {
+        let binlog_content = r#"
+#251020 19:43:32 server id 123  end_log_pos 1000
+### INSERT INTO `main`.`users`
+### SET
+###   @1=1
+#251020 19:43:33 server id 123  end_log_pos 1100
+### INSERT INTO `shop`.`users`
+### SET
+###   @1=2
+#251020 19:43:34 server id 123  end_log_pos 1200
+### INSERT INTO `shop`.`users`
+### SET
+###   @1=3
+"#;
+        let temp_file = create_temp_binlog(binlog_content);
+        let path = temp_file.to_str().unwrap();
+        let (operations, diagnostics) = TextBinlogParser::new(create_test_db()).parse_file_with_diagnostics(path).unwrap();
+
+        assert!(operations.iter().all(|op| op.table_name == "users"));
+        assert_eq!(diagnostics.shared_tables, vec![SharedTable {
+            file: path.to_string(),
+            line_number: 7,
+            table: "users".to_string(),
+            first_database: "main".to_string(),
+            database: "shop".to_string(),
+        }]);
+        let (_, parallel_diagnostics) = TextBinlogParser::new(create_test_db()).with_worker_threads(2).parse_file_with_diagnostics(path).unwrap();
+        assert_eq!(parallel_diagnostics.shared_tables, diagnostics.shared_tables);
+
+        std::fs::remove_file(temp_file).ok();
+    }
+
+    #[test]
+    fn test_parse_decodes_escaped_string_values() {
+        let conn = create_test_db();
+        let mut parser = TextBinlogParser::new(conn);
+        
+        let binlog_content = r#"
+#251020 19:43:32 server id 123  end_log_pos 2000
+### INSERT INTO `main`.`users`
+### SET
+###   @1=5 /* INT meta=0 nullable=0 is_null=0 */
+###   @2='Ren\xc3\xa9e O\x27Brien' /* VARSTRING(255) meta=255 nullable=1 is_null=0 */
+"#;
+        
+        let temp_file = create_temp_binlog(binlog_content);
+        let operations = parser.parse_file(temp_file.to_str().unwrap()).unwrap();
+        
+        let after = operations[0].after_values.as_ref().unwrap();
+        assert_eq!(after[0], "5");
+        assert_eq!(after[1], "'Renée O''Brien'");
+        
+        std::fs::remove_file(temp_file).ok();
+    }
+
+    #[test]
+    fn test_parses_mariadb_dialect() {
+        let binlog_content = r#"
+#251020 19:00:00 server id 1  end_log_pos 256 CRC32 0x1 	Start: binlog v 4, server v 10.6.12-MariaDB-log created 251020 19:00:00
+#251020 19:43:32 server id 7  end_log_pos 342 CRC32 0x1 	GTID 0-7-23 trans
+/*!100001 SET @@session.gtid_seq_no=23*//*!*/;
+START TRANSACTION
+/*!*/;
+#251020 19:43:32 server id 7  end_log_pos 420 CRC32 0x1 	Annotate_rows:
+#Q> INSERT INTO users (id, name) VALUES (4, 'Xid = 99')
+#251020 19:43:32 server id 7  end_log_pos 480 CRC32 0x1 	Write_rows: table id 32 flags: STMT_END_F
+### INSERT INTO `main`.`users`
+### SET
+###   @1=4
+###   @2='David'
+#251020 19:43:32 server id 7  end_log_pos 511 CRC32 0x1 	Xid = 96
+COMMIT/*!*/;
+#251020 19:44:00 server id 7  end_log_pos 600 CRC32 0x1 	GTID 0-7-24 trans
+START TRANSACTION
+/*!*/;
+#251020 19:44:00 server id 7  end_log_pos 680 CRC32 0x1 	Write_rows: table id 32 flags: STMT_END_F
+### INSERT INTO `main`.`users`
+### SET
+###   @1=5
+###   @2='Eve'
+ROLLBACK/*!*/;
+"#;
+        let temp_file = create_temp_binlog(binlog_content);
+        let path = temp_file.to_str().unwrap();
+        assert_eq!(BinlogDialect::detect_file(path).unwrap(), BinlogDialect::MariaDb);
+
+        let mut parser = TextBinlogParser::new(create_test_db());
+        let (operations, diagnostics) = parser.parse_file_with_diagnostics(path).unwrap();
+        assert_eq!(operations.len(), 1);
+        assert_eq!(operations[0].transaction, TransactionInfo {
+            xid: Some(96),
+            sequence_number: Some(23),
+            gtid: Some("0-7-23".to_string()),
+            server_id: Some(7),
+        });
+        assert!(diagnostics.is_empty());
+
+        std::fs::remove_file(temp_file).ok();
+    }
+
+    #[test]
+    fn test_parallel_parse_matches_sequential_parse() {
+        let mut binlog_content = String::new();
+        for i in 0..50 {
+            binlog_content.push_str(&format!(
+                "#251020 19:{:02}:00 server id 123  end_log_pos {}\tGTID\tlast_committed={}\tsequence_number={}\n\
+                 #251020 19:{:02}:00 server id 123  end_log_pos {}\nBEGIN\n\
+                 #251020 19:{:02}:00 server id 123  end_log_pos {}\n\
+                 ### UPDATE `main`.`users`\n### WHERE\n###   @1=1\n###   @4={}\n### SET\n###   @1=1\n###   @4={}\n\
+                 #251020 19:{:02}:00 server id 123  end_log_pos {}\tXid = {}\n\
+                 {}\n",
+                i, 998 + i * 10, i, i + 1, i, 1000 + i * 10, i, 1005 + i * 10, 30 + i, 31 + i, i, 1008 + i * 10, 100 + i,
+                if i % 7 == 0 { "ROLLBACK" } else { "COMMIT" }
+            ));
+        }
+        let temp_file = create_temp_binlog(&binlog_content);
+        let path = temp_file.to_str().unwrap();
+
+        let sequential = TextBinlogParser::new(create_test_db()).parse_file(path).unwrap();
+        let parallel = TextBinlogParser::new(create_test_db())
+            .with_worker_threads(4)
+            .parse_file(path)
+            .unwrap();
+
+        assert_eq!(sequential.len(), 42);
+        assert_eq!(sequential[0].transaction, TransactionInfo {
+            xid: Some(101),
+            sequence_number: Some(2),
+            gtid: None,
+            server_id: Some(123),
+        });
+        assert_eq!(format!("{:?}", parallel), format!("{:?}", sequential));
+        assert!(find_transaction_chunks(path, 8).unwrap().len() > 1);
+
+        std::fs::remove_file(temp_file).ok();
+    }
+
+    #[test]
+    fn test_operation_cache_reused_until_schema_changes() {
+        let binlog_content = r#"
+#251020 19:43:32 server id 123  end_log_pos 2000
+BEGIN
+### UPDATE `main`.`users`
+### WHERE
+###   @1=1
+###   @4=30
+### SET
+###   @1=1
+###   @4=31
+COMMIT
+"#;
+        let temp_file = create_temp_binlog(binlog_content);
+        let path = temp_file.to_str().unwrap();
+
+        let parsed = TextBinlogParser::new(create_test_db()).with_operation_cache(true).parse_file(path).unwrap();
+        assert!(operation_cache::cache_path(path).exists());
+
+        // The cache is read instead of the file
+        let key = CacheKey::for_file(path, TextBinlogParser::new(create_test_db()).cache_fingerprint().unwrap()).unwrap();
+        let mut cached = operation_cache::load(path, &key).unwrap();
+        cached.operations[0].after_values.as_mut().unwrap()[1] = "99".to_string();
+        operation_cache::store(path, &key, &cached).unwrap();
+        let reparsed = TextBinlogParser::new(create_test_db()).with_operation_cache(true).parse_file(path).unwrap();
+        assert_eq!(reparsed[0].after_values.as_ref().unwrap()[1], "99");
+
+        // A different table schema can give different operations, so the file is parsed again
+        let conn = create_test_db();
+        conn.execute_batch("ALTER TABLE users ADD COLUMN note VARCHAR;").unwrap();
+        let reparsed = TextBinlogParser::new(conn).with_operation_cache(true).parse_file(path).unwrap();
+        assert_eq!(reparsed[0].after_values, parsed[0].after_values);
+
+        // Forcing a dialect can also give different operations
+        let forced = TextBinlogParser::new(create_test_db()).with_dialect(BinlogDialect::MariaDb).cache_fingerprint().unwrap();
+        assert_ne!(forced, TextBinlogParser::new(create_test_db()).cache_fingerprint().unwrap());
+
+        std::fs::remove_file(operation_cache::cache_path(path)).ok();
+        std::fs::remove_file(temp_file).ok();
+    }
+
+    #[test]
+    fn test_column_mapping_matches_renamed_parquet_columns() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE users (userId INTEGER, id INTEGER, name VARCHAR);").unwrap();
+        let mapping = ColumnMapping::new().with_table("users", &["id", "userId", "name"]);
+        let mut parser = TextBinlogParser::new(conn).with_column_mapping(mapping);
+
+        let binlog_content = r#"
+#251020 19:00:00 server id 123  end_log_pos 1000
+BEGIN
+### INSERT INTO `main`.`users`
+### SET
+###   @1=1
+###   @2=42
+###   @3='Ada'
+COMMIT
+"#;
+
+        let temp_file = create_temp_binlog(binlog_content);
+        let operations = parser.parse_file(temp_file.to_str().unwrap()).unwrap();
+
+        assert_eq!(operations[0].columns, vec!["id", "userId", "name"]);
+        assert_eq!(operations[0].after_values, Some(vec!["1".to_string(), "42".to_string(), "'Ada'".to_string()]));
+        std::fs::remove_file(temp_file).ok();
+    }
+
+    #[test]
+    fn test_alter_table_changes_column_mapping() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE books (id INTEGER, title VARCHAR);").unwrap();
+        let mut parser = TextBinlogParser::new(conn);
+
+        let binlog_content = r#"
+#251020 19:00:00 server id 123  end_log_pos 1000
+BEGIN
+### INSERT INTO `main`.`books`
+### SET
+###   @1=1
+###   @2='Dune'
+COMMIT
+#251020 19:10:00 server id 123  end_log_pos 2000 	Query	thread_id=8	exec_time=0	error_code=0
+use `main`/*!*/;
+SET TIMESTAMP=1760987400/*!*/;
+ALTER TABLE `books`
+  ADD COLUMN `isbn` VARCHAR(13) AFTER `id`
+/*!*/;
+#251020 19:20:00 server id 123  end_log_pos 3000
+BEGIN
+### INSERT INTO `main`.`books`
+### SET
+###   @1=2
+###   @2='9780441013593'
+###   @3='Dune Messiah'
+COMMIT
+"#;
+
+        let temp_file = create_temp_binlog(binlog_content);
+        let operations = parser.parse_file(temp_file.to_str().unwrap()).unwrap();
+
+        assert_eq!(operations.len(), 2);
+        assert_eq!(operations[0].columns, vec!["id", "title"]);
+        assert_eq!(operations[1].columns, vec!["id", "isbn", "title"]);
+        assert_eq!(operations[1].after_values.as_ref().unwrap()[2], "'Dune Messiah'");
+        assert_eq!(parser.get_schema_history().versions("books")[0].position, 2000);
+
+        std::fs::remove_file(temp_file).ok();
+    }
+
+    #[test]
+    fn test_schema_history_anchored_to_snapshot_after_alter() {
+        // The snapshot was taken at 19:15, after the first ALTER and before the second
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE books (id INTEGER, isbn VARCHAR, title VARCHAR);").unwrap();
+        let mut parser = TextBinlogParser::new(conn);
+
+        let binlog_content = r#"
+#251020 19:00:00 server id 123  end_log_pos 1000
+BEGIN
+### INSERT INTO `main`.`books`
+### SET
+###   @1=1
+###   @2='Dune'
+###   @3=5
+COMMIT
+#251020 19:10:00 server id 123  end_log_pos 2000 	Query	thread_id=8	exec_time=0	error_code=0
+use `main`/*!*/;
+ALTER TABLE `books` ADD COLUMN `isbn` VARCHAR(13) AFTER `id`, DROP COLUMN `stock`
+/*!*/;
+#251020 19:20:00 server id 123  end_log_pos 3000
+BEGIN
+### INSERT INTO `main`.`books`
+### SET
+###   @1=2
+###   @2='9780441013593'
+###   @3='Dune Messiah'
+COMMIT
+#251020 19:30:00 server id 123  end_log_pos 4000 	Query	thread_id=8	exec_time=0	error_code=0
+ALTER TABLE `books` ADD COLUMN `note` TEXT
+/*!*/;
+#251020 19:40:00 server id 123  end_log_pos 5000
+BEGIN
+### INSERT INTO `main`.`books`
+### SET
+###   @1=3
+###   @2='9780441172696'
+###   @3='Children of Dune'
+###   @4='signed'
+COMMIT
+"#;
+
+        let temp_file = create_temp_binlog(binlog_content);
+        let path = temp_file.to_str().unwrap().to_string();
+        parser.anchor_schema_history(std::slice::from_ref(&path), &BinlogTimestamp::parse("251020 19:15:00").unwrap()).unwrap();
+        let operations = parser.parse_file(&path).unwrap();
+
+        assert_eq!(operations.len(), 3);
+        assert_eq!(operations[0].columns, vec!["id", "title", "stock"]);
+        assert_eq!(operations[0].after_values.as_ref().unwrap()[1], "'Dune'");
+        assert_eq!(operations[1].columns, vec!["id", "isbn", "title"]);
+        assert_eq!(operations[1].after_values.as_ref().unwrap()[2], "'Dune Messiah'");
+        assert_eq!(operations[2].columns, vec!["id", "isbn", "title", "note"]);
+
+        std::fs::remove_file(temp_file).ok();
+    }
+
+    #[test]
+    fn test_parse_minimal_row_images() {
+        let conn = create_test_db();
+        let mut parser = TextBinlogParser::new(conn);
+
+        let binlog_content = r#"
+#251020 19:43:32 server id 123  end_log_pos 1000
+### UPDATE `main`.`users`
+###   WHERE
+###   @1=1
+###   SET
+###   @4=31
+### DELETE FROM `main`.`users`
+### WHERE
+###   @1=2
+### INSERT INTO `main`.`users`
+### SET
+###   @1=4
+###   @2='David'
+"#;
+
+        let temp_file = create_temp_binlog(binlog_content);
+        let operations = parser.parse_file(temp_file.to_str().unwrap()).unwrap();
+        assert_eq!(operations.len(), 3);
+
+        let update = &operations[0];
+        assert_eq!(update.columns, vec!["id".to_string(), "age".to_string()]);
+        assert_eq!(update.before_values, Some(vec!["1".to_string(), "NULL".to_string()]));
+        assert_eq!(update.after_values, Some(vec!["1".to_string(), "31".to_string()]));
+        assert_eq!(update.row_image, RowImage::Partial { missing_before: vec!["age".to_string()] });
+
+        let delete = &operations[1];
+        assert_eq!(delete.columns.len(), 7);
+        assert_eq!(delete.row_image.missing_before().len(), 6);
+
+        let insert = &operations[2];
+        assert_eq!(insert.columns, vec!["id".to_string(), "name".to_string()]);
+        assert!(insert.row_image.is_partial());
+
+        // Missing before values come from the database when applying
+        let mut applier = OperationApplier::new(parser.into_connection());
+        assert!(applier.apply_operation_conditionally(update).unwrap());
+        let completed = applier.complete_row_image(delete).unwrap().unwrap();
+        assert_eq!(completed.before_values.as_ref().unwrap()[1], "'Bob'");
+        assert!(applier.apply_operation_conditionally(delete).unwrap());
+        assert!(applier.apply_operation_conditionally(&completed.invert()).unwrap());
+
+        let (age, name): (i32, String) = applier.get_connection()
+            .query_row("SELECT (SELECT age FROM users WHERE id = 1), (SELECT name FROM users WHERE id = 2)", [], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap();
+        assert_eq!((age, name.as_str()), (31, "Bob"));
+
+        std::fs::remove_file(temp_file).ok();
+    }
+
+    #[test]
+    fn test_parse_diagnostics_record_anomalies() {
+        let binlog_content = r#"BEGIN
+#251020 19:43:32 server id 123  end_log_pos 1000
+### INSERT INTO `main`.`users`
+### SET
+###   @1=10
+###   @9=99
+###   garbage
+COMMIT
+### stray line
+BEGIN
+#251020 19:44:00 server id 123  end_log_pos 1100
+### DELETE FROM `main`.`users`
+### WHERE
+###   @1=1
+"#;
+        let temp_file = create_temp_binlog(binlog_content);
+        let path = temp_file.to_str().unwrap();
+
+        for worker_threads in [1, 2] {
+            let mut parser = TextBinlogParser::new(create_test_db()).with_worker_threads(worker_threads);
+            let (operations, diagnostics) = parser.parse_file_with_diagnostics(path).unwrap();
+            assert_eq!(operations.len(), 1);
+
+            let found: Vec<(usize, ParseAnomalyKind)> = diagnostics.anomalies.iter()
+                .map(|anomaly| (anomaly.line_number, anomaly.kind.clone()))
+                .collect();
+            assert_eq!(found, vec![
+                (6, ParseAnomalyKind::ColumnOutOfRange { column: "9".to_string(), column_count: 7 }),
+                (7, ParseAnomalyKind::UnrecognisedRowImageLine),
+                (9, ParseAnomalyKind::OrphanRowImageLine),
+                (14, ParseAnomalyKind::UnterminatedTransaction { operations: 1, first_timestamp: Some("251020 19:44:00".to_string()) }),
+            ]);
+            assert_eq!(diagnostics.anomalies[1].snippet, "###   garbage");
+            assert_eq!(diagnostics.anomalies[0].file, path);
+        }
+
+        let mut parser = TextBinlogParser::new(create_test_db()).with_max_anomalies(2);
+        let error = parser.parse_file(path).unwrap_err();
+        let error = error.downcast_ref::<TooManyParseAnomaliesError>().unwrap();
+        assert_eq!(error.last.kind, ParseAnomalyKind::OrphanRowImageLine);
+
+        std::fs::remove_file(temp_file).ok();
+    }
+
+    #[test]
+    fn test_strict_transactions_reject_truncated_binlogs() {
+        let binlog_content = r#"BEGIN
+#251020 19:43:32 server id 123  end_log_pos 1000
+### INSERT INTO `main`.`users`
+### SET
+###   @1=10
+COMMIT
+BEGIN
+#251020 19:45:00 server id 123  end_log_pos 1100
+### INSERT INTO `main`.`users`
+### SET
+###   @1=11
+#251020 19:45:01 server id 123  end_log_pos 1200
+### INSERT INTO `main`.`users`
+### SET
+###   @1=12
+"#;
+        let temp_file = create_temp_binlog(binlog_content);
+        let path = temp_file.to_str().unwrap();
+
+        let (operations, diagnostics) = TextBinlogParser::new(create_test_db()).parse_file_with_diagnostics(path).unwrap();
+        assert_eq!(operations.len(), 1);
+        assert_eq!(diagnostics.unterminated_transactions(), (1, Some("251020 19:45:00")));
+
+        let error = TextBinlogParser::new(create_test_db()).with_strict_transactions(true).parse_file(path).unwrap_err();
+        let error = error.downcast_ref::<UnterminatedTransactionsError>().unwrap();
+        assert_eq!((error.count, error.first_timestamp.as_deref()), (1, Some("251020 19:45:00")));
+
+        std::fs::remove_file(temp_file).ok();
+    }
+
+    #[test]
+    fn test_cancelled_parse_stops_with_load_cancelled() {
+        let binlog_content = "# padding\n".repeat(CancelToken::CHECK_INTERVAL + 1);
+        let temp_file = create_temp_binlog(&binlog_content);
+        let path = temp_file.to_str().unwrap();
+
+        let cancel = CancelToken::new();
+        assert!(TextBinlogParser::new(create_test_db()).with_cancel_token(cancel.clone()).parse_file(path).is_ok());
+        cancel.cancel();
+        let error = TextBinlogParser::new(create_test_db()).with_cancel_token(cancel).parse_file(path).unwrap_err();
+        assert!(error.downcast_ref::<crate::pensieve::LoadCancelled>().is_some());
+
+        std::fs::remove_file(temp_file).ok();
+    }
+
+    #[test]
+    fn test_mixed_case_and_reserved_identifiers() {
+        let binlog_content = r#"BEGIN
+#251020 19:43:32 server id 123  end_log_pos 1000
+### INSERT INTO `main`.`order`
+### SET
+###   @1=1
+###   @2='books'
+COMMIT
+BEGIN
+#251020 19:44:00 server id 123  end_log_pos 1100
+### UPDATE `main`.`order`
+### WHERE
+###   @1=1
+###   @2='books'
+### SET
+###   @1=1
+###   @2='games'
+COMMIT
+"#;
+        let temp_file = create_temp_binlog(binlog_content);
+        let path = temp_file.to_str().unwrap();
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(r#"CREATE TABLE "Order" (id INTEGER PRIMARY KEY, "group" VARCHAR);"#).unwrap();
+
+        for worker_threads in [1, 2] {
+            let operations = TextBinlogParser::new(conn.try_clone().unwrap()).with_worker_threads(worker_threads).parse_file(path).unwrap();
+            assert_eq!(operations.iter().map(|op| op.table_name.as_str()).collect::<Vec<_>>(), vec!["Order", "Order"]);
+        }
+        let operations = TextBinlogParser::new(conn.try_clone().unwrap())
+            .with_identifier_case(IdentifierCase::Sensitive)
+            .parse_file(path)
+            .unwrap();
+        assert!(operations.is_empty());
+
+        let operations = TextBinlogParser::new(conn.try_clone().unwrap()).parse_file(path).unwrap();
+        let mut applier = OperationApplier::new(conn);
+        for op in &operations {
+            assert!(applier.apply_operation_conditionally(op).unwrap());
+        }
+        let group: String = applier.get_connection().query_row(r#"SELECT "group" FROM "Order" WHERE id = 1"#, [], |row| row.get(0)).unwrap();
+        assert_eq!(group, "games");
+
+        std::fs::remove_file(temp_file).ok();
+    }
+
+    #[test]
+    fn test_timestamp_regressions_are_reported() {
+        let binlog_content = r#"BEGIN
+#251020 19:43:32 server id 123  end_log_pos 1000
+### INSERT INTO `main`.`users`
+### SET
+###   @1=10
+COMMIT
+BEGIN
+#251020  9:50:00 server id 123  end_log_pos 1100
+### INSERT INTO `main`.`users`
+### SET
+###   @1=11
+COMMIT
+"#;
+        let temp_file = create_temp_binlog(binlog_content);
+        let path = temp_file.to_str().unwrap();
+
+        let mut parser = TextBinlogParser::new(create_test_db()).with_max_anomalies(0);
+        let (operations, diagnostics) = parser.parse_file_with_diagnostics(path).unwrap();
+
+        assert_eq!(operations.len(), 2);
+        assert!(diagnostics.is_empty());
+        assert_eq!(diagnostics.timestamp_regressions, vec![TimestampRegression {
+            file: path.to_string(),
+            line_number: 8,
+            previous: "251020 19:43:32".to_string(),
+            timestamp: "251020 9:50:00".to_string(),
+        }]);
+
+        std::fs::remove_file(temp_file).ok();
+    }
+
+    #[test]
+    fn test_undecoded_row_events_fail_fast() {
+        let header = "#251020 19:43:32 server id 123  end_log_pos 1000 CRC32 0x1b2c3d4e \tWrite_rows: table id 108 flags: STMT_END_F";
+        let commit = "#251020 19:43:32 server id 123  end_log_pos 1100 CRC32 0x4e3d2c1b \tXid = 7\nCOMMIT/*!*/;";
+        let base64 = "BINLOG '\nWq9VaR57AAAALQAAAOgDAAAAAGwAAAAAAAEAAgAC/wAEAAAA\n'/*!*/;";
+        let decoded = "### INSERT INTO `main`.`users`\n### SET\n###   @1=4";
+
+        for (rows, error) in [(format!("{}\n{}", base64, decoded), None), (base64.to_string(), Some(true)), (String::new(), Some(false))] {
+            let temp_file = create_temp_binlog(&format!("BEGIN\n{}\n{}\n{}\n", header, rows, commit));
+            let path = temp_file.to_str().unwrap();
+            let result = TextBinlogParser::new(create_test_db()).parse_file(path);
+            match error {
+                None => assert_eq!(result.unwrap().len(), 1),
+                Some(base64) => {
+                    let error = result.unwrap_err();
+                    let error = error.downcast_ref::<UndecodedRowEventError>().unwrap();
+                    assert_eq!((error.file.as_str(), error.line_number, error.base64), (path, 2, base64));
+                    assert!(error.to_string().contains("--base64-output=DECODE-ROWS --verbose"));
+                }
+            }
+            std::fs::remove_file(temp_file).ok();
+        }
+    }
+
     #[test]
     fn test_invert_insert_to_delete() {
         let insert_op = BinlogOperation {
@@ -620,6 +2200,8 @@ mod tests {
             columns: vec!["id".to_string(), "name".to_string()],
             before_values: None,
             after_values: Some(vec!["10".to_string(), "'NewUser'".to_string()]),
+            row_image: RowImage::Full,
+            transaction: TransactionInfo::default(),
         };
         
         let inverted = insert_op.invert();
@@ -641,6 +2223,8 @@ mod tests {
             columns: vec!["id".to_string(), "name".to_string()],
             before_values: Some(vec!["1".to_string(), "'Alice'".to_string()]),
             after_values: Some(vec!["1".to_string(), "'Alice Smith'".to_string()]),
+            row_image: RowImage::Full,
+            transaction: TransactionInfo::default(),
         };
         
         let inverted = update_op.invert();
@@ -661,6 +2245,8 @@ mod tests {
             columns: vec!["id".to_string(), "name".to_string()],
             before_values: Some(vec!["3".to_string(), "'Charlie'".to_string()]),
             after_values: None,
+            row_image: RowImage::Full,
+            transaction: TransactionInfo::default(),
         };
         
         let inverted = delete_op.invert();
@@ -673,7 +2259,7 @@ mod tests {
     #[test]
     fn test_generate_insert_sql() {
         let conn = create_test_db();
-        let applier = OperationApplier::new(conn);
+        let mut applier = OperationApplier::new(conn);
         
         let insert_op = BinlogOperation {
             timestamp: None,
@@ -684,17 +2270,19 @@ mod tests {
             columns: vec!["id".to_string(), "name".to_string(), "email".to_string()],
             before_values: None,
             after_values: Some(vec!["4".to_string(), "'David'".to_string(), "'david@test.com'".to_string()]),
+            row_image: RowImage::Full,
+            transaction: TransactionInfo::default(),
         };
         
         let sql = applier.generate_sql(&insert_op);
 
-        assert_eq!(sql, "INSERT INTO users (id, name, email) VALUES (4, 'David', 'david@test.com');");
+        assert_eq!(sql, r#"INSERT INTO "users" ("id", "name", "email") VALUES (4, 'David', 'david@test.com');"#);
     }
 
     #[test]
     fn test_generate_update_sql() {
         let conn = create_test_db();
-        let applier = OperationApplier::new(conn);
+        let mut applier = OperationApplier::new(conn);
         
         let update_op = BinlogOperation {
             timestamp: None,
@@ -705,17 +2293,19 @@ mod tests {
             columns: vec!["id".to_string(), "name".to_string()],
             before_values: Some(vec!["1".to_string(), "'Alice'".to_string()]),
             after_values: Some(vec!["1".to_string(), "'Alice Smith'".to_string()]),
+            row_image: RowImage::Full,
+            transaction: TransactionInfo::default(),
         };
         
         let sql = applier.generate_sql(&update_op);
 
-        assert_eq!(sql, "UPDATE users SET id = 1, name = 'Alice Smith' WHERE id = 1 AND name = 'Alice';");
+        assert_eq!(sql, r#"UPDATE "users" SET "name" = 'Alice Smith' WHERE "id" = 1 AND "name" = 'Alice';"#);
     }
 
     #[test]
     fn test_generate_delete_sql() {
         let conn = create_test_db();
-        let applier = OperationApplier::new(conn);
+        let mut applier = OperationApplier::new(conn);
         
         let delete_op = BinlogOperation {
             timestamp: None,
@@ -726,11 +2316,133 @@ mod tests {
             columns: vec!["id".to_string(), "name".to_string()],
             before_values: Some(vec!["3".to_string(), "'Charlie'".to_string()]),
             after_values: None,
+            row_image: RowImage::Full,
+            transaction: TransactionInfo::default(),
         };
         
         let sql = applier.generate_sql(&delete_op);
 
-        assert_eq!(sql, "DELETE FROM users WHERE id = 3 AND name = 'Charlie';");
+        assert_eq!(sql, r#"DELETE FROM "users" WHERE "id" = 3 AND "name" = 'Charlie';"#);
+    }
+
+    #[test]
+    fn test_null_before_values_match_with_is_null() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE tags (name VARCHAR, parent VARCHAR);
+             INSERT INTO tags VALUES ('a', 'x'), ('a', NULL);"
+        ).unwrap();
+        let mut applier = OperationApplier::new(conn);
+
+        let delete_op = BinlogOperation {
+            timestamp: None,
+            position: None,
+            operation_type: OperationType::Delete,
+            table_name: "tags".to_string(),
+            database: "main".to_string(),
+            columns: vec!["name".to_string(), "parent".to_string()],
+            before_values: Some(vec!["'a'".to_string(), "NULL".to_string()]),
+            after_values: None,
+            row_image: RowImage::Full,
+            transaction: TransactionInfo::default(),
+        };
+
+        assert_eq!(applier.generate_sql(&delete_op), r#"DELETE FROM "tags" WHERE "name" = 'a' AND "parent" IS NULL;"#);
+        assert!(applier.should_apply(&delete_op).unwrap());
+        assert!(applier.apply_operation_conditionally(&delete_op).unwrap());
+
+        let remaining: Vec<Option<String>> = applier.get_connection()
+            .prepare("SELECT parent FROM tags").unwrap()
+            .query_map([], |row| row.get(0)).unwrap()
+            .collect::<Result<_, _>>().unwrap();
+        assert_eq!(remaining, vec![Some("x".to_string())]);
+        assert!(!applier.should_apply(&delete_op).unwrap());
+    }
+
+    #[test]
+    fn test_should_apply_compares_values_by_column_type() {
+        let conn = create_test_db();
+        let mut applier = OperationApplier::new(conn);
+
+        // DuckDB reads these back as 500.00, true and '2024-01-02 11:00:00'
+        let update = BinlogOperation {
+            timestamp: None,
+            position: None,
+            operation_type: OperationType::Update,
+            table_name: "users".to_string(),
+            database: "main".to_string(),
+            columns: vec!["id".to_string(), "balance".to_string(), "is_active".to_string(), "created_at".to_string()],
+            before_values: Some(vec!["2".to_string(), "500.0".to_string(), "1".to_string(),
+                                    "'2024-01-02 11:00:00.000000'".to_string()]),
+            after_values: Some(vec!["2".to_string(), "600".to_string(), "1".to_string(),
+                                   "'2024-01-02 11:00:00.000000'".to_string()]),
+            row_image: RowImage::Full,
+            transaction: TransactionInfo::default(),
+        };
+
+        assert!(applier.should_apply(&update).unwrap());
+        assert!(applier.apply_operation_conditionally(&update).unwrap());
+        assert!(!applier.should_apply(&update).unwrap());
+        assert!(applier.get_mismatch_counts().is_empty());
+    }
+
+    #[test]
+    fn test_blob_values_round_trip_bytewise() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE files (id INTEGER PRIMARY KEY, data BLOB); INSERT INTO files VALUES (1, unhex('00FF41'));").unwrap();
+        let mut parser = TextBinlogParser::new(conn);
+
+        let binlog_content = r#"
+#251020 19:43:32 server id 123  end_log_pos 2000
+### UPDATE `main`.`files`
+### WHERE
+###   @1=1 /* INT meta=0 nullable=0 is_null=0 */
+###   @2='\x00\xffA' /* BLOB/TEXT meta=2 nullable=1 is_null=0 */
+### SET
+###   @1=1 /* INT meta=0 nullable=0 is_null=0 */
+###   @2='\x27\\' /* BLOB/TEXT meta=2 nullable=1 is_null=0 */
+"#;
+        let temp_file = create_temp_binlog(binlog_content);
+        let operations = parser.parse_file(temp_file.to_str().unwrap()).unwrap();
+        std::fs::remove_file(temp_file).ok();
+        let update = &operations[0];
+        assert_eq!(update.before_values.as_ref().unwrap()[1], "unhex('00FF41')");
+        assert_eq!(update.after_values.as_ref().unwrap()[1], "unhex('275C')");
+
+        let mut applier = OperationApplier::new(parser.into_connection());
+        assert!(applier.apply_operation_conditionally(update).unwrap());
+        assert!(!applier.should_apply(update).unwrap());
+        assert!(applier.apply_operation_conditionally(&update.invert()).unwrap());
+        let data: String = applier.get_connection().query_row("SELECT hex(data) FROM files", [], |row| row.get(0)).unwrap();
+        assert_eq!(data, "00FF41");
+        assert!(applier.get_mismatch_counts().is_empty());
+    }
+
+    #[test]
+    fn test_json_values_compare_whatever_their_key_order() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(r#"CREATE TABLE docs (id INTEGER PRIMARY KEY, body JSON); INSERT INTO docs VALUES (1, '{"b": 1, "a": [1, 2]}');"#).unwrap();
+        let mut applier = OperationApplier::new(conn);
+        let update = BinlogOperation {
+            timestamp: None,
+            position: None,
+            operation_type: OperationType::Update,
+            table_name: "docs".to_string(),
+            database: "main".to_string(),
+            columns: vec!["id".to_string(), "body".to_string()],
+            before_values: Some(vec!["1".to_string(), r#"'{"a":[1,2],"b":1}'"#.to_string()]),
+            after_values: Some(vec!["1".to_string(), r#"'{"a":[1,2],"b":2}'"#.to_string()]),
+            row_image: RowImage::Full,
+            transaction: TransactionInfo::default(),
+        };
+
+        assert_eq!(applier.generate_sql(&update), r##"UPDATE "docs" SET "body" = '{"a":[1,2],"b":2}' WHERE "id" = 1;"##);
+        assert!(applier.apply_operation_conditionally(&update).unwrap());
+        assert!(!applier.should_apply(&update).unwrap());
+        assert!(applier.apply_operation_conditionally(&update.invert()).unwrap());
+        let body: String = applier.get_connection().query_row("SELECT body FROM docs", [], |row| row.get(0)).unwrap();
+        assert_eq!(body, r#"{"a":[1,2],"b":1}"#);
+        assert!(applier.get_mismatch_counts().is_empty());
     }
 
     #[test]
@@ -752,6 +2464,8 @@ mod tests {
                                    "'new@test.com'".to_string(), "25".to_string(), 
                                    "100.0".to_string(), "1".to_string(), 
                                    "'2024-01-01 10:00:00'".to_string()]),
+            row_image: RowImage::Full,
+            transaction: TransactionInfo::default(),
         };
         
         let should_apply = applier.should_apply(&new_insert).unwrap();
@@ -782,6 +2496,8 @@ mod tests {
                                    "'alice@example.com'".to_string(), "31".to_string(), 
                                    "1000.5".to_string(), "1".to_string(), 
                                    "'2024-01-01 10:00:00'".to_string()]),
+            row_image: RowImage::Full,
+            transaction: TransactionInfo::default(),
         };
         
         let should_apply = applier.should_apply(&invalid_update).unwrap();
@@ -808,6 +2524,8 @@ mod tests {
                                     "0.0".to_string(), "0".to_string(), 
                                     "'2024-01-01 10:00:00'".to_string()]),
             after_values: None,
+            row_image: RowImage::Full,
+            transaction: TransactionInfo::default(),
         };
         
         let should_apply = applier.should_apply(&delete_nonexistent).unwrap();
@@ -837,6 +2555,8 @@ mod tests {
                                    "'alice@example.com'".to_string(), "31".to_string(), 
                                    "1000.50".to_string(), "1".to_string(), 
                                    "'2024-01-01 10:00:00'".to_string()]),
+            row_image: RowImage::Full,
+            transaction: TransactionInfo::default(),
         };
         
         // Step 2: Apply forward (should work - before-image matches)
@@ -893,6 +2613,8 @@ mod tests {
                                    "'bob@example.com'".to_string(), "25".to_string(), 
                                    "500.00".to_string(), "1".to_string(), 
                                    "'2024-01-02 11:00:00'".to_string()]),
+            row_image: RowImage::Full,
+            transaction: TransactionInfo::default(),
         };
         
         // This should be skipped (row already exists with same values)
@@ -0,0 +1,125 @@
+use crate::binlog::BinlogOperation;
+
+/// Summary of a timestamp backfill pass over parsed operations
+#[derive(Debug, Clone, Default)]
+pub struct TimestampBackfillReport {
+    /// Indices of operations that inherited the timestamp of an earlier operation
+    pub inherited_from_previous: Vec<usize>,
+    /// Indices of operations that took the timestamp of a later operation
+    /// (this happens when parsing starts mid-file, before the first header line)
+    pub inherited_from_next: Vec<usize>,
+    /// Indices of operations that still have no timestamp (no operation had one)
+    pub still_missing: Vec<usize>,
+}
+
+impl TimestampBackfillReport {
+    pub fn backfilled_count(&self) -> usize {
+        self.inherited_from_previous.len() + self.inherited_from_next.len()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.still_missing.is_empty()
+    }
+}
+
+/// Fills in missing operation timestamps so that no operation is silently excluded from
+/// normalisation windows or timestamp navigation.
+///
+/// Operations without a timestamp inherit the timestamp of the closest earlier operation.
+/// Operations before the first timestamped operation (e.g. when parsing started mid-file)
+/// take the timestamp of the first timestamped operation instead.
+pub fn backfill_timestamps(operations: &mut [BinlogOperation]) -> TimestampBackfillReport {
+    backfill_timestamps_after(operations, None)
+}
+
+/// Like backfill_timestamps, for operations that follow others, e.g. the next binlog file:
+/// operations before the first timestamped one inherit `previous_timestamp` if there is one.
+pub fn backfill_timestamps_after(operations: &mut [BinlogOperation], previous_timestamp: Option<&str>) -> TimestampBackfillReport {
+    let mut report = TimestampBackfillReport::default();
+
+    let mut last_timestamp: Option<String> = previous_timestamp.map(str::to_string);
+    for (idx, op) in operations.iter_mut().enumerate() {
+        match &op.timestamp {
+            Some(ts) => last_timestamp = Some(ts.clone()),
+            None => {
+                if let Some(ts) = &last_timestamp {
+                    op.timestamp = Some(ts.clone());
+                    report.inherited_from_previous.push(idx);
+                }
+            }
+        }
+    }
+
+    // Anything still missing a timestamp comes before the first timestamped operation
+    let first_timestamp = operations.iter().find_map(|op| op.timestamp.clone());
+    for (idx, op) in operations.iter_mut().enumerate() {
+        if op.timestamp.is_some() {
+            break;
+        }
+        match &first_timestamp {
+            Some(ts) => {
+                op.timestamp = Some(ts.clone());
+                report.inherited_from_next.push(idx);
+            }
+            None => report.still_missing.push(idx),
+        }
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binlog::OperationType;
+    use crate::test_support::operation;
+
+    fn op_with_timestamp(timestamp: Option<&str>) -> BinlogOperation {
+        BinlogOperation {
+            timestamp: timestamp.map(|ts| ts.to_string()),
+            ..operation(OperationType::Insert, "users", &["id"], None, Some(&["1"]))
+        }
+    }
+
+    #[test]
+    fn test_backfill_inherits_from_previous_operation() {
+        let mut operations = vec![
+            op_with_timestamp(Some("251108 10:00:00")),
+            op_with_timestamp(None),
+            op_with_timestamp(Some("251108 11:00:00")),
+            op_with_timestamp(None),
+        ];
+
+        let report = backfill_timestamps(&mut operations);
+
+        assert_eq!(operations[1].timestamp, Some("251108 10:00:00".to_string()));
+        assert_eq!(operations[3].timestamp, Some("251108 11:00:00".to_string()));
+        assert_eq!(report.inherited_from_previous, vec![1, 3]);
+        assert!(report.is_complete());
+    }
+
+    #[test]
+    fn test_backfill_leading_operations_from_next() {
+        let mut operations = vec![
+            op_with_timestamp(None),
+            op_with_timestamp(None),
+            op_with_timestamp(Some("251108 10:00:00")),
+        ];
+
+        let report = backfill_timestamps(&mut operations);
+
+        assert_eq!(operations[0].timestamp, Some("251108 10:00:00".to_string()));
+        assert_eq!(report.inherited_from_next, vec![0, 1]);
+        assert_eq!(report.backfilled_count(), 2);
+    }
+
+    #[test]
+    fn test_backfill_reports_operations_still_missing() {
+        let mut operations = vec![op_with_timestamp(None), op_with_timestamp(None)];
+
+        let report = backfill_timestamps(&mut operations);
+
+        assert_eq!(report.still_missing, vec![0, 1]);
+        assert!(!report.is_complete());
+    }
+}
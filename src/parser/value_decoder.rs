@@ -0,0 +1,239 @@
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+
+/// Character set used to turn a string column's raw bytes into text
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Charset {
+    /// UTF-8, falling back to Latin-1 for byte sequences that aren't valid UTF-8
+    #[default]
+    Utf8,
+    Latin1,
+    /// Not text: the bytes are kept as they are, in a BLOB literal (see blob_literal)
+    Binary,
+}
+
+/// Decodes an application-encoded column value (protobuf hex, base64 JSON, ...) from its text,
+/// so analyses see the decoded value
+#[derive(Clone)]
+pub struct ColumnDecoder(Arc<dyn Fn(&str) -> String + Send + Sync>);
+
+impl ColumnDecoder {
+    pub fn new(decode: impl Fn(&str) -> String + Send + Sync + 'static) -> Self {
+        Self(Arc::new(decode))
+    }
+
+    pub fn decode(&self, text: &str) -> String {
+        (self.0)(text)
+    }
+}
+
+impl Debug for ColumnDecoder {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ColumnDecoder")
+    }
+}
+
+/// Decodes values as printed by mysqlbinlog into SQL literals DuckDB understands.
+///
+/// mysqlbinlog prints string values quoted, with non-printable bytes (and quotes/backslashes)
+/// escaped as `\xNN`, so `café` comes out as `'caf\xc3\xa9'`. Left as-is, these never compare
+/// equal to the values in DuckDB. The decoder unescapes the bytes, decodes them using the
+/// column's charset, and re-quotes the result with standard SQL escaping (`'` becomes `''`).
+///
+/// Type annotations added by `mysqlbinlog -vv` (`/* VARSTRING(255) meta=... */`) are stripped.
+///
+/// Binary columns (BLOB, VARBINARY) are printed the same way, but their bytes aren't text: with
+/// Charset::Binary they become a BLOB literal instead.
+///
+/// Columns with a ColumnDecoder hold its decoding of their text instead.
+#[derive(Debug, Clone, Default)]
+pub struct ValueDecoder {
+    column_charsets: HashMap<(String, String), Charset>,
+    column_decoders: HashMap<(String, String), ColumnDecoder>,
+}
+
+impl ValueDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the charset for one column (columns default to UTF-8)
+    pub fn with_column_charset(mut self, table: &str, column: &str, charset: Charset) -> Self {
+        self.set_column_charset(table, column, charset);
+        self
+    }
+
+    pub fn set_column_charset(&mut self, table: &str, column: &str, charset: Charset) {
+        self.column_charsets.insert((table.to_string(), column.to_string()), charset);
+    }
+
+    pub fn has_column_charset(&self, table: &str, column: &str) -> bool {
+        self.column_charsets.contains_key(&(table.to_string(), column.to_string()))
+    }
+
+    /// Column charsets set with with_column_charset, in (table, column) order
+    pub fn column_charsets(&self) -> Vec<(&str, &str, Charset)> {
+        let mut charsets: Vec<(&str, &str, Charset)> = self.column_charsets.iter()
+            .map(|((table, column), charset)| (table.as_str(), column.as_str(), *charset))
+            .collect();
+        charsets.sort_by(|a, b| (a.0, a.1).cmp(&(b.0, b.1)));
+        charsets
+    }
+
+    /// Decodes one column's text values with `decoder` after unescaping them
+    pub fn with_column_decoder(mut self, table: &str, column: &str, decoder: ColumnDecoder) -> Self {
+        self.set_column_decoder(table, column, decoder);
+        self
+    }
+
+    pub fn set_column_decoder(&mut self, table: &str, column: &str, decoder: ColumnDecoder) {
+        self.column_decoders.insert((table.to_string(), column.to_string()), decoder);
+    }
+
+    /// (table, column) of every column with a ColumnDecoder, in order
+    pub fn decoded_columns(&self) -> Vec<(&str, &str)> {
+        let mut columns: Vec<(&str, &str)> = self.column_decoders.keys()
+            .map(|(table, column)| (table.as_str(), column.as_str()))
+            .collect();
+        columns.sort();
+        columns
+    }
+
+    pub fn decode(&self, table: &str, column: &str, raw: &str) -> String {
+        let value = strip_type_comment(raw.trim());
+
+        if value.len() < 2 || !value.starts_with('\'') || !value.ends_with('\'') {
+            return value.to_string();
+        }
+
+        self.decode_bytes(table, column, unescape(&value[1..value.len() - 1]))
+    }
+
+    /// The SQL literal of a string column's raw bytes, e.g. as received from a replication stream
+    pub fn decode_bytes(&self, table: &str, column: &str, bytes: Vec<u8>) -> String {
+        let key = (table.to_string(), column.to_string());
+        let charset = self.column_charsets.get(&key).copied().unwrap_or_default();
+        let decoder = self.column_decoders.get(&key);
+
+        let text = match charset {
+            Charset::Latin1 => decode_latin1(&bytes),
+            Charset::Binary if decoder.is_none() => return blob_literal(&bytes),
+            _ => String::from_utf8(bytes)
+                .unwrap_or_else(|e| decode_latin1(e.as_bytes())),
+        };
+        let text = match decoder {
+            Some(decoder) => decoder.decode(&text),
+            None => text,
+        };
+
+        format!("'{}'", text.replace('\'', "''"))
+    }
+}
+
+/// SQL literal for binary data: `unhex('ABCD')`, with upper-case hex digits. DuckDB reads `x'ABCD'`
+/// as a string, but both DuckDB and SQLite have unhex.
+pub fn blob_literal(bytes: &[u8]) -> String {
+    let hex: String = bytes.iter().map(|b| format!("{:02X}", b)).collect();
+    format!("unhex('{}')", hex)
+}
+
+fn strip_type_comment(value: &str) -> &str {
+    if value.ends_with("*/")
+        && let Some(idx) = value.rfind(" /* ") {
+        return value[..idx].trim_end();
+    }
+    value
+}
+
+/// Turns MySQL escapes (`\xNN`, `\\`, `\'`, `\n`, ...) back into raw bytes
+fn unescape(escaped: &str) -> Vec<u8> {
+    let bytes = escaped.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] != b'\\' || i + 1 >= bytes.len() {
+            out.push(bytes[i]);
+            i += 1;
+            continue;
+        }
+
+        match bytes[i + 1] {
+            b'x' if is_hex_pair(&bytes[i + 2..]) => {
+                let hex = std::str::from_utf8(&bytes[i + 2..i + 4]).unwrap();
+                out.push(u8::from_str_radix(hex, 16).unwrap());
+                i += 4;
+                continue;
+            }
+            b'n' => out.push(b'\n'),
+            b't' => out.push(b'\t'),
+            b'r' => out.push(b'\r'),
+            b'0' => out.push(0),
+            b'\\' => out.push(b'\\'),
+            b'\'' => out.push(b'\''),
+            b'"' => out.push(b'"'),
+            other => {
+                out.push(b'\\');
+                out.push(other);
+            }
+        }
+        i += 2;
+    }
+
+    out
+}
+
+fn is_hex_pair(bytes: &[u8]) -> bool {
+    bytes.len() >= 2 && bytes[0].is_ascii_hexdigit() && bytes[1].is_ascii_hexdigit()
+}
+
+fn decode_latin1(bytes: &[u8]) -> String {
+    bytes.iter().map(|&b| b as char).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decodes_utf8_hex_escapes() {
+        let decoder = ValueDecoder::new();
+        assert_eq!(decoder.decode("t", "c", r"'caf\xc3\xa9'"), "'café'");
+    }
+
+    #[test]
+    fn test_requotes_escaped_quotes_and_backslashes() {
+        let decoder = ValueDecoder::new();
+        assert_eq!(decoder.decode("t", "c", r"'it\x27s'"), "'it''s'");
+        assert_eq!(decoder.decode("t", "c", r"'it\'s'"), "'it''s'");
+        assert_eq!(decoder.decode("t", "c", r"'a\\b'"), r"'a\b'");
+    }
+
+    #[test]
+    fn test_latin1_column_charset() {
+        let decoder = ValueDecoder::new().with_column_charset("t", "c", Charset::Latin1);
+        assert_eq!(decoder.decode("t", "c", r"'caf\xe9'"), "'café'");
+        // Invalid UTF-8 in a UTF-8 column falls back to Latin-1
+        assert_eq!(ValueDecoder::new().decode("t", "c", r"'caf\xe9'"), "'café'");
+    }
+
+    #[test]
+    fn test_column_decoder_decodes_text() {
+        let upper = ColumnDecoder::new(|text| text.to_uppercase());
+        let decoder = ValueDecoder::new().with_column_decoder("t", "c", upper);
+        assert_eq!(decoder.decode("t", "c", r"'caf\xc3\xa9'"), "'CAFÉ'");
+        assert_eq!(decoder.decode("t", "c", "NULL"), "NULL");
+        assert_eq!(decoder.decode("t", "other", "'abc'"), "'abc'");
+        assert_eq!(decoder.decoded_columns(), vec![("t", "c")]);
+    }
+
+    #[test]
+    fn test_non_string_values_and_type_comments() {
+        let decoder = ValueDecoder::new();
+        assert_eq!(decoder.decode("t", "c", "42"), "42");
+        assert_eq!(decoder.decode("t", "c", "NULL"), "NULL");
+        assert_eq!(decoder.decode("t", "c", "'abc' /* VARSTRING(255) meta=255 nullable=1 is_null=0 */"), "'abc'");
+        assert_eq!(decoder.decode("t", "c", "7 /* INT meta=0 nullable=0 is_null=0 */"), "7");
+    }
+}
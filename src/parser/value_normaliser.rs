@@ -0,0 +1,179 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+
+/// Brings SQL literals into a canonical form for their column's DuckDB type, so that values from
+/// the binlog can be compared with values read back from DuckDB.
+///
+/// The same value is often written differently on each side: `1000.50` in the binlog is `1000.5`
+/// in a DOUBLE column, `'2024-01-01 10:00:00.500000'` is `'2024-01-01 10:00:00.5'` in a TIMESTAMP
+/// column, and booleans are `1`/`0` in the binlog but `true`/`false` in DuckDB. Blobs (`unhex('ab')`,
+/// see value_decoder::blob_literal) compare byte for byte, and JSON documents whatever their key order
+/// and whitespace.
+/// Values of other types (strings, ...) are left unchanged.
+#[derive(Debug, Clone, Default)]
+pub struct ValueNormaliser;
+
+impl ValueNormaliser {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Canonical form of a SQL literal for a column of the given DuckDB type (e.g. `DECIMAL(10,2)`).
+    /// Values that don't parse as the type are returned unchanged.
+    pub fn normalise(&self, value: &str, column_type: &str) -> String {
+        if value == "NULL" {
+            return value.to_string();
+        }
+        let column_type = column_type.to_ascii_uppercase();
+        let unquoted = unquote(value);
+
+        let normalised = if column_type.starts_with("DECIMAL") || column_type.starts_with("NUMERIC")
+            || is_integer_type(&column_type) {
+            normalise_decimal(unquoted)
+        } else if column_type == "DOUBLE" || column_type == "FLOAT8" {
+            unquoted.trim().parse::<f64>().ok().map(|v| v.to_string())
+        } else if column_type == "FLOAT" || column_type == "REAL" || column_type == "FLOAT4" {
+            unquoted.trim().parse::<f32>().ok().map(|v| v.to_string())
+        } else if column_type.starts_with("BOOL") {
+            normalise_bool(unquoted)
+        } else if column_type.starts_with("TIMESTAMP") || column_type == "DATETIME" {
+            normalise_timestamp(unquoted, timestamp_precision(&column_type))
+        } else if column_type == "DATE" {
+            NaiveDate::parse_from_str(unquoted, "%Y-%m-%d").ok().map(|d| d.to_string())
+        } else if column_type == "JSON" {
+            normalise_json(unquoted)
+        } else if column_type == "BLOB" {
+            normalise_blob(value)
+        } else if column_type.starts_with("TIME") {
+            NaiveTime::parse_from_str(unquoted, "%H:%M:%S%.f").ok().map(|t| t.format("%H:%M:%S%.f").to_string())
+        } else {
+            None
+        };
+
+        normalised.unwrap_or_else(|| value.to_string())
+    }
+
+    /// Whether two SQL literals hold the same value for a column of the given DuckDB type
+    pub fn values_equal(&self, a: &str, b: &str, column_type: &str) -> bool {
+        a == b || self.normalise(a, column_type) == self.normalise(b, column_type)
+    }
+}
+
+fn is_integer_type(column_type: &str) -> bool {
+    matches!(
+        column_type,
+        "TINYINT" | "SMALLINT" | "INTEGER" | "INT" | "BIGINT" | "HUGEINT"
+            | "UTINYINT" | "USMALLINT" | "UINTEGER" | "UBIGINT" | "UHUGEINT"
+    )
+}
+
+fn unquote(value: &str) -> &str {
+    value.strip_prefix('\'')
+        .and_then(|v| v.strip_suffix('\''))
+        .unwrap_or(value)
+}
+
+/// Exact decimal text without sign noise, leading zeros or trailing fractional zeros
+fn normalise_decimal(value: &str) -> Option<String> {
+    let value = value.trim();
+    let (negative, digits) = match value.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let (integer, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+    if integer.is_empty() && fraction.is_empty()
+        || !integer.bytes().chain(fraction.bytes()).all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let integer = integer.trim_start_matches('0');
+    let fraction = fraction.trim_end_matches('0');
+    let mut normalised = if integer.is_empty() { "0".to_string() } else { integer.to_string() };
+    if !fraction.is_empty() {
+        normalised.push('.');
+        normalised.push_str(fraction);
+    }
+    if negative && normalised != "0" {
+        normalised.insert(0, '-');
+    }
+    Some(normalised)
+}
+
+fn normalise_bool(value: &str) -> Option<String> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "t" => Some("1".to_string()),
+        "0" | "false" | "f" => Some("0".to_string()),
+        _ => None,
+    }
+}
+
+/// Compact JSON with object keys sorted, as a quoted SQL literal
+fn normalise_json(value: &str) -> Option<String> {
+    let document: serde_json::Value = serde_json::from_str(&value.replace("''", "'")).ok()?;
+    Some(format!("'{}'", document.to_string().replace('\'', "''")))
+}
+
+/// Blob literal with upper-case hex digits
+fn normalise_blob(value: &str) -> Option<String> {
+    let hex = value.strip_prefix("unhex('")?.strip_suffix("')")?;
+    hex.bytes().all(|b| b.is_ascii_hexdigit()).then(|| format!("unhex('{}')", hex.to_ascii_uppercase()))
+}
+
+/// Digits of fractional seconds a timestamp type keeps: DuckDB truncates what a MySQL
+/// DATETIME(6) value holds beyond them
+fn timestamp_precision(column_type: &str) -> u32 {
+    match column_type {
+        "TIMESTAMP_S" => 0,
+        "TIMESTAMP_MS" => 3,
+        "TIMESTAMP_NS" => 9,
+        _ => 6,
+    }
+}
+
+/// Timestamps as `YYYY-MM-DD HH:MM:SS[.fraction]`, converted to UTC if they carry an offset, with
+/// the fraction truncated to `precision` digits
+fn normalise_timestamp(value: &str, precision: u32) -> Option<String> {
+    let value = value.trim();
+    let datetime = ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f"].iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .or_else(|| ["%Y-%m-%d %H:%M:%S%.f%#z", "%Y-%m-%dT%H:%M:%S%.f%#z"].iter()
+            .find_map(|format| DateTime::parse_from_str(value, format).ok())
+            .map(|datetime| datetime.naive_utc()))
+        .or_else(|| NaiveDate::parse_from_str(value, "%Y-%m-%d").ok().and_then(|d| d.and_hms_opt(0, 0, 0)))?;
+    let unit = 10u32.pow(9 - precision);
+    let datetime = datetime.with_nanosecond(datetime.nanosecond() / unit * unit)?;
+    Some(datetime.format("%Y-%m-%d %H:%M:%S%.f").to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalises_values_by_type() {
+        let normaliser = ValueNormaliser::new();
+
+        assert!(normaliser.values_equal("1000.50", "1000.5", "DOUBLE"));
+        assert!(normaliser.values_equal("1000.50", "1000.5", "DECIMAL(10,2)"));
+        assert!(normaliser.values_equal("-0.0", "0", "DECIMAL(10,2)"));
+        assert!(normaliser.values_equal("007", "7", "INTEGER"));
+        assert!(normaliser.values_equal("1", "true", "BOOLEAN"));
+        assert!(normaliser.values_equal(
+            "'2024-01-01 10:00:00.500000'", "'2024-01-01 10:00:00.5'", "TIMESTAMP"));
+        assert!(normaliser.values_equal(
+            "'2024-01-01 10:00:00.123456'", "'2024-01-01 10:00:00.123'", "TIMESTAMP_MS"));
+        assert!(!normaliser.values_equal(
+            "'2024-01-01 10:00:00.123456'", "'2024-01-01 10:00:00.123'", "TIMESTAMP"));
+        assert!(normaliser.values_equal(
+            "'2024-01-01 12:00:00+02'", "'2024-01-01 10:00:00'", "TIMESTAMP WITH TIME ZONE"));
+        assert!(normaliser.values_equal("'10:00:00.000'", "'10:00:00'", "TIME"));
+        assert!(normaliser.values_equal("unhex('ab00')", "unhex('AB00')", "BLOB"));
+        assert!(normaliser.values_equal(r#"'{"b": [1, 2], "a": "it''s"}'"#, r#"'{"a":"it''s","b":[1,2]}'"#, "JSON"));
+
+        assert!(!normaliser.values_equal("1000.51", "1000.5", "DECIMAL(10,2)"));
+        assert!(!normaliser.values_equal("NULL", "0", "INTEGER"));
+        assert!(!normaliser.values_equal("'1.50'", "'1.5'", "VARCHAR"));
+        assert!(!normaliser.values_equal("unhex('AB00')", "unhex('AB')", "BLOB"));
+        assert!(!normaliser.values_equal(r#"'{"a": [1, 2]}'"#, r#"'{"a": [2, 1]}'"#, "JSON"));
+        assert_eq!(normaliser.normalise("'not a date'", "DATE"), "'not a date'");
+    }
+}
@@ -1,10 +1,379 @@
 use duckdb::Connection;
+use duckdb::types::Value;
+use crate::parser::binlog_reader;
+use crate::parser::column_mapping::ColumnMapping;
+use crate::parser::operation_validation::{DuplicateOperations, OperationValidator, ValidationIssue, ValidationReport};
+use crate::parser::parse_diagnostics::ParseDiagnostics;
+use crate::parser::replace_fusion::{self, FusedReplace, ReplaceFusionReport};
+use crate::parser::table_filter::TableFilter;
 use crate::parser::text_binlog_parser::TextBinlogParser;
-use crate::snapshot_normaliser::timestamp_normaliser::TimestampNormaliser;
-use crate::snapshot_manager::SnapshotManager;
-use crate::loader::parquet_loader;
-use std::path::PathBuf;
+use crate::parser::timestamp_backfill::{self, TimestampBackfillReport};
+use crate::parser::value_decoder::{ColumnDecoder, ValueDecoder};
+use crate::snapshot_normaliser::timestamp_normaliser::{AnchorStrategy, EmptyWindowPolicy, NormalisationReport, TimestampNormaliser};
+use crate::snapshot_manager::{RowValues, SnapshotExport, SnapshotManager, TableOperationStats, VerificationReport};
+use crate::snapshot_manager::operation_store::{OperationSource, OperationStore};
+use crate::loader::{binlog_only_tables, column_decoding, parquet_loader, remote_files};
+use crate::loader::column_projection::ColumnProjection;
+use crate::loader::redaction::{Redaction, Redactions};
+use crate::loader::resource_limits::ResourceLimits;
+use crate::binlog::{quote_identifier, BinlogFileMarker, BinlogOperation, BinlogTimestamp, IdentifierCase, TimestampOptions, NAMESPACE_SEPARATOR};
+use crate::operation_applier::OperationApplier;
+use chrono::{DateTime, Duration, Utc};
+use std::collections::BTreeMap;
+use std::collections::btree_map::Entry;
+use std::path::{Path, PathBuf};
 use std::fs;
+use std::fmt::{Display, Formatter};
+use std::ops::ControlFlow;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::{debug, debug_span, info, info_span, warn};
+
+/// Operations of all the parsed binlog files, with their diagnostics, binlog file markers and
+/// the reports of the passes over them
+struct LoadedOperations {
+    operations: OperationStore,
+    parse_diagnostics: ParseDiagnostics,
+    file_markers: Vec<BinlogFileMarker>,
+    timestamp_backfill_report: TimestampBackfillReport,
+    replace_fusion_report: ReplaceFusionReport,
+    validation_report: ValidationReport,
+}
+
+/// Where Pensieve loads its data from and how the snapshot database is stored
+#[derive(Debug, Clone)]
+pub struct PensieveConfig {
+    /// Local db_data directory, or object store URI such as `s3://bucket/db_data`
+    pub data_dir: String,
+    /// Approximate timestamp of snapshot (format: "YYMMDD HH:MM:SS")
+    pub snapshot_timestamp: String,
+    /// Size of window to search around snapshot (e.g., 6 hours)
+    pub window_hours: i64,
+    /// DuckDB database file to load the tables into, for snapshots that don't fit in memory.
+    /// The database is in memory if None.
+    pub database_path: Option<PathBuf>,
+    /// Restore tables from the database file instead of reading parquet again, when the file
+    /// was loaded from the same parquet files before. Ignored without `database_path`.
+    pub reuse_database: bool,
+    /// Century and server timezone of binlog timestamps, for converting them to UTC
+    pub timestamp_options: TimestampOptions,
+    /// Fuse the DELETE + INSERT pairs logged for REPLACE INTO into single UPDATEs. On by default;
+    /// only pairs in one transaction on a table with a declared primary key are fused.
+    pub fuse_replaces: bool,
+    /// Abort loading once a binlog file has more unparseable lines than this (unlimited if None)
+    pub max_parse_anomalies: Option<usize>,
+    /// Fail loading if a binlog file has transactions without COMMIT, e.g. because it was
+    /// truncated, instead of warning and dropping their operations
+    pub strict_transactions: bool,
+    /// How binlog table names are matched to the snapshot's tables
+    pub identifier_case: IdentifierCase,
+    /// Make diffs and other analyses fail when they span a gap between the binlog files, e.g. a
+    /// missing file (see SnapshotManager::binlog_gaps)
+    pub refuse_binlog_gaps: bool,
+    /// What to do when no operations fall in the window around the snapshot timestamp
+    pub empty_window_policy: EmptyWindowPolicy,
+    /// Which operation in the window to normalise the snapshot to
+    pub anchor_strategy: AnchorStrategy,
+    /// Binlog-to-snapshot column mappings for tables whose parquet export renamed columns. Mapping
+    /// files (`<table>/column_mapping.json`) in a local db_data directory are added to these.
+    pub column_mapping: ColumnMapping,
+    /// Columns to load of wide tables; other tables are loaded whole
+    pub column_projection: ColumnProjection,
+    /// Cache the operations parsed from each binlog file next to it (`<file>.pensieve-cache`) and
+    /// reuse them while the file is unchanged
+    pub cache_operations: bool,
+    /// The binlog ends before the snapshot was taken: skip normalisation, start at the final
+    /// operation and only expect backward navigation (see SnapshotManager::set_backwards_only)
+    pub backwards_only: bool,
+    /// Keep at most about this many operations in memory, spilling the rest to a temporary file
+    /// while the binlog files are parsed (see SnapshotManager::set_max_operations_in_memory)
+    pub max_operations_in_memory: Option<usize>,
+    /// Tables with no snapshot, reconstructed from scratch by replaying the binlog
+    pub binlog_only_tables: Vec<String>,
+    /// CREATE TABLE statements for binlog-only tables. Tables it doesn't create get their columns
+    /// from their first INSERT.
+    pub binlog_only_ddl: Option<PathBuf>,
+    /// Memory and thread limits of the DuckDB connection, also capping the binlog parser's threads
+    pub resource_limits: ResourceLimits,
+    /// Apply at most this many operations per second during bulk navigation (see
+    /// SnapshotManager::set_max_operations_per_second)
+    pub max_operations_per_second: Option<f64>,
+    /// Discard operations before this timestamp (YYMMDD HH:MM:SS) while parsing
+    pub min_timestamp: Option<String>,
+    /// Discard operations after this timestamp (YYMMDD HH:MM:SS) while parsing
+    pub max_timestamp: Option<String>,
+    /// Insert batches of INSERTs during normalisation and binlog-only table reconstruction with
+    /// DuckDB's Appender instead of SQL (see OperationApplier::set_use_appender)
+    pub appender_inserts: bool,
+    /// (table, column, decoder) of application-encoded columns, decoded in the snapshot and in
+    /// every operation (see ColumnDecoder)
+    pub column_decoders: Vec<(String, String, ColumnDecoder)>,
+    /// Columns redacted in the snapshot and in every operation (see Redactions)
+    pub redactions: Redactions,
+}
+
+impl Default for PensieveConfig {
+    fn default() -> Self {
+        Self {
+            data_dir: "db_data".to_string(),
+            snapshot_timestamp: String::new(),
+            window_hours: 6,
+            database_path: None,
+            reuse_database: true,
+            timestamp_options: TimestampOptions::default(),
+            fuse_replaces: true,
+            max_parse_anomalies: None,
+            strict_transactions: false,
+            identifier_case: IdentifierCase::default(),
+            refuse_binlog_gaps: false,
+            empty_window_policy: EmptyWindowPolicy::default(),
+            anchor_strategy: AnchorStrategy::default(),
+            column_mapping: ColumnMapping::new(),
+            column_projection: ColumnProjection::new(),
+            cache_operations: true,
+            backwards_only: false,
+            max_operations_in_memory: None,
+            binlog_only_tables: Vec::new(),
+            binlog_only_ddl: None,
+            resource_limits: ResourceLimits::default(),
+            max_operations_per_second: None,
+            appender_inserts: false,
+            column_decoders: Vec::new(),
+            redactions: Redactions::new(),
+            min_timestamp: None,
+            max_timestamp: None,
+        }
+    }
+}
+
+impl PensieveConfig {
+    pub fn new(data_dir: &str, snapshot_timestamp: &str, window_hours: i64) -> Self {
+        Self {
+            data_dir: data_dir.to_string(),
+            snapshot_timestamp: snapshot_timestamp.to_string(),
+            window_hours,
+            ..Self::default()
+        }
+    }
+
+    /// Loads the tables into a DuckDB database file instead of memory
+    pub fn with_database_path(mut self, database_path: impl Into<PathBuf>) -> Self {
+        self.database_path = Some(database_path.into());
+        self
+    }
+
+    pub fn with_reuse_database(mut self, reuse_database: bool) -> Self {
+        self.reuse_database = reuse_database;
+        self
+    }
+
+    pub fn with_timestamp_options(mut self, timestamp_options: TimestampOptions) -> Self {
+        self.timestamp_options = timestamp_options;
+        self
+    }
+
+    pub fn with_fuse_replaces(mut self, fuse_replaces: bool) -> Self {
+        self.fuse_replaces = fuse_replaces;
+        self
+    }
+
+    pub fn with_max_parse_anomalies(mut self, max_parse_anomalies: usize) -> Self {
+        self.max_parse_anomalies = Some(max_parse_anomalies);
+        self
+    }
+
+    pub fn with_strict_transactions(mut self, strict_transactions: bool) -> Self {
+        self.strict_transactions = strict_transactions;
+        self
+    }
+
+    pub fn with_identifier_case(mut self, identifier_case: IdentifierCase) -> Self {
+        self.identifier_case = identifier_case;
+        self
+    }
+
+    pub fn with_refuse_binlog_gaps(mut self, refuse_binlog_gaps: bool) -> Self {
+        self.refuse_binlog_gaps = refuse_binlog_gaps;
+        self
+    }
+
+    pub fn with_empty_window_policy(mut self, empty_window_policy: EmptyWindowPolicy) -> Self {
+        self.empty_window_policy = empty_window_policy;
+        self
+    }
+
+    pub fn with_anchor_strategy(mut self, anchor_strategy: AnchorStrategy) -> Self {
+        self.anchor_strategy = anchor_strategy;
+        self
+    }
+
+    pub fn with_column_mapping(mut self, column_mapping: ColumnMapping) -> Self {
+        self.column_mapping = column_mapping;
+        self
+    }
+
+    pub fn with_column_projection(mut self, column_projection: ColumnProjection) -> Self {
+        self.column_projection = column_projection;
+        self
+    }
+
+    pub fn with_cache_operations(mut self, cache_operations: bool) -> Self {
+        self.cache_operations = cache_operations;
+        self
+    }
+
+    pub fn with_backwards_only(mut self, backwards_only: bool) -> Self {
+        self.backwards_only = backwards_only;
+        self
+    }
+
+    pub fn with_max_operations_in_memory(mut self, max_operations_in_memory: usize) -> Self {
+        self.max_operations_in_memory = Some(max_operations_in_memory);
+        self
+    }
+
+    pub fn with_binlog_only_tables(mut self, tables: &[&str]) -> Self {
+        self.binlog_only_tables = tables.iter().map(|table| table.to_string()).collect();
+        self
+    }
+
+    pub fn with_binlog_only_ddl(mut self, ddl_file: impl Into<PathBuf>) -> Self {
+        self.binlog_only_ddl = Some(ddl_file.into());
+        self
+    }
+
+    /// DuckDB memory limit, e.g. `4GB`
+    pub fn with_memory_limit(mut self, memory_limit: &str) -> Self {
+        self.resource_limits = self.resource_limits.with_memory_limit(memory_limit);
+        self
+    }
+
+    pub fn with_max_threads(mut self, max_threads: usize) -> Self {
+        self.resource_limits = self.resource_limits.with_max_threads(max_threads);
+        self
+    }
+
+    /// Only keeps operations from `min_timestamp` to `max_timestamp` (YYMMDD HH:MM:SS, inclusive),
+    /// discarding the others while parsing
+    pub fn with_time_range(mut self, min_timestamp: Option<&str>, max_timestamp: Option<&str>) -> Self {
+        self.min_timestamp = min_timestamp.map(str::to_string);
+        self.max_timestamp = max_timestamp.map(str::to_string);
+        self
+    }
+
+    pub fn with_max_operations_per_second(mut self, max_operations_per_second: f64) -> Self {
+        self.max_operations_per_second = Some(max_operations_per_second);
+        self
+    }
+
+    pub fn with_appender_inserts(mut self, appender_inserts: bool) -> Self {
+        self.appender_inserts = appender_inserts;
+        self
+    }
+
+    /// Decodes a column's text with `decode`, e.g. base64 JSON or protobuf hex, in the snapshot
+    /// and in every operation, so queries, diffs and exports see decoded values
+    pub fn with_column_decoder(mut self, table: &str, column: &str, decode: impl Fn(&str) -> String + Send + Sync + 'static) -> Self {
+        self.column_decoders.push((table.to_string(), column.to_string(), ColumnDecoder::new(decode)));
+        self
+    }
+
+    /// Redacts a column (e.g. PII) as the snapshot is loaded and as the binlog is parsed, so the
+    /// database, caches, exports and script results never hold its values
+    pub fn with_redaction(mut self, table: &str, column: &str, redaction: Redaction) -> Self {
+        self.redactions = self.redactions.with_column(table, column, redaction);
+        self
+    }
+
+    /// Salt of Redaction::Hash
+    pub fn with_redaction_salt(mut self, salt: &str) -> Self {
+        self.redactions = self.redactions.with_salt(salt);
+        self
+    }
+}
+
+/// Phase of building a Pensieve, reported to the progress callback of PensieveBuilder::load_with
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoadProgress {
+    DiscoveringFiles,
+    LoadingTables { table_count: usize },
+    /// About to parse the `file_index`th of `file_count` binlog files (counting from 0)
+    ParsingBinlog { file: String, file_index: usize, file_count: usize },
+    ValidatingOperations { operation_count: usize },
+    Normalising,
+    Done,
+}
+
+/// Cancels a PensieveBuilder::load_with running on another thread. Clones share the same flag.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancelToken {
+    /// Lines or operations between two checks of the flag inside long loops
+    pub(crate) const CHECK_INTERVAL: usize = 10_000;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn check(&self) -> Result<(), LoadCancelled> {
+        if self.is_cancelled() { Err(LoadCancelled) } else { Ok(()) }
+    }
+}
+
+/// Error returned by PensieveBuilder::load_with when its CancelToken was cancelled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoadCancelled;
+
+impl Display for LoadCancelled {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Loading was cancelled")
+    }
+}
+
+impl std::error::Error for LoadCancelled {}
+
+/// Builds a Pensieve from a configuration, with progress reporting and cancellation.
+///
+/// Cancellation is checked between phases, between binlog files, and every few thousand lines
+/// or operations while parsing and normalising, so a cancelled load stops soon after, or once
+/// the current step finishes for steps that can't be interrupted (e.g. loading the parquet files).
+pub struct PensieveBuilder {
+    config: PensieveConfig,
+}
+
+impl PensieveBuilder {
+    pub fn new(config: PensieveConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn load(&self) -> Result<Pensieve, Box<dyn std::error::Error>> {
+        Pensieve::from_config(&self.config)
+    }
+
+    /// Loads the snapshot, calling `progress` at the start of each phase.
+    /// Returns a LoadCancelled error once `cancel` is cancelled.
+    pub fn load_with(
+        &self,
+        cancel: &CancelToken,
+        mut progress: impl FnMut(&LoadProgress),
+    ) -> Result<Pensieve, Box<dyn std::error::Error>> {
+        Pensieve::build(&self.config, cancel, &mut progress)
+    }
+}
+
+/// (table, parquet files) per table, and the binlog files to parse in order
+type DiscoveredFiles = (Vec<(String, Vec<String>)>, Vec<String>);
 
 /// Pensieve takes a MySQL binlog and DB snapshot in parquet format.
 /// It parses this data and generates an in-memory DuckDB table.
@@ -18,14 +387,23 @@ use std::fs;
 /// You must place them following this hierarchy:
 /// db_data
 ///  L my_table
-///    L binlog.sql
+///    L mysql-bin.000101.sql
+///    L mysql-bin.000102.sql
 ///    L snapshot-part-01.parquet
 ///    L snapshot-part-02.parquet
 ///
-/// Pensieve uses this hierarchy to infer the name of your table. (Pensieve currently only supports one table).
+/// Pensieve uses this hierarchy to infer the names of your tables. Every table directory is loaded
+/// into the same database, so tables can be joined at any point in time.
+/// Binlog files are parsed in file name order into a single stream of operations. A binlog file with
+/// the same name in several table directories is treated as one file and parsed once.
 pub struct Pensieve {
     manager: SnapshotManager,
-    table_name: String,
+    table_names: Vec<String>,
+    parse_diagnostics: ParseDiagnostics,
+    timestamp_backfill_report: TimestampBackfillReport,
+    replace_fusion_report: ReplaceFusionReport,
+    validation_report: ValidationReport,
+    normalisation_report: NormalisationReport,
 }
 
 impl Pensieve {
@@ -43,63 +421,384 @@ impl Pensieve {
     ) -> Result<Self, Box<dyn std::error::Error>> {
         let current_dir = std::env::current_dir()?;
         let db_data_path = current_dir.join("db_data");
-        
-        println!("Looking for db_data at: {:?}", db_data_path);
-        
-        let tables = Self::discover_tables(&db_data_path)?;
-        println!("Found tables: {:?}", tables);
-        
-        if tables.is_empty() {
-            return Err("No tables found in db_data".into());
+        Self::from_data_dir(&db_data_path.to_string_lossy(), snapshot_timestamp, window_hours)
+    }
+
+    /// Creates a new Pensieve from a db_data directory at any location
+    ///
+    /// # Arguments
+    /// * `data_dir` - Local path, or object store URI such as `s3://bucket/db_data`. Remote parquet
+    ///   files are read directly by DuckDB; remote binlogs are downloaded to a local cache first.
+    /// * `snapshot_timestamp` - Approximate timestamp of snapshot (format: "YYMMDD HH:MM:SS")
+    /// * `window_hours` - Size of window to search around snapshot (e.g., 6 hours)
+    ///
+    /// # Returns
+    /// A Pensieve instance with normalized snapshot ready for querying
+    pub fn from_data_dir(
+        data_dir: &str,
+        snapshot_timestamp: &str,
+        window_hours: i64,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::from_config(&PensieveConfig::new(data_dir, snapshot_timestamp, window_hours))
+    }
+
+    /// Creates a new Pensieve from a configuration, e.g. to keep the snapshot in a database file
+    ///
+    /// # Arguments
+    /// * `config` - Data location, snapshot timestamp and database options
+    ///
+    /// # Returns
+    /// A Pensieve instance with normalized snapshot ready for querying
+    pub fn from_config(config: &PensieveConfig) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::build(config, &CancelToken::new(), &mut |_| {})
+    }
+
+    fn build(
+        config: &PensieveConfig,
+        cancel: &CancelToken,
+        progress: &mut dyn FnMut(&LoadProgress),
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        cancel.check()?;
+        progress(&LoadProgress::DiscoveringFiles);
+        let data_dir = config.data_dir.as_str();
+        let discover_span = info_span!("discover", data_dir).entered();
+
+        let (table_parquet_files, binlog_files) = if remote_files::is_remote(data_dir) {
+            Self::discover_remote(data_dir)?
+        } else {
+            Self::discover_local(&PathBuf::from(data_dir))?
+        };
+        drop(discover_span);
+
+        let mut tables: Vec<String> = table_parquet_files.iter().map(|(table, _)| table.clone()).collect();
+        let mut column_mapping = config.column_mapping.clone();
+        if !remote_files::is_remote(data_dir) {
+            for table in &tables {
+                column_mapping = column_mapping.with_table_dir(table, &PathBuf::from(data_dir).join(table))?;
+            }
         }
+        for table in &config.binlog_only_tables {
+            // Its snapshot would be dropped to reconstruct it from the binlog alone
+            if tables.contains(table) {
+                return Err(format!("{} has snapshot files, so it can't be binlog-only", table).into());
+            }
+            tables.push(table.clone());
+        }
+        // A `db__table` table holds the rows of `db`.`table`
+        let table_filter = tables.iter().fold(TableFilter::new(), |filter, table| {
+            match table.split_once(NAMESPACE_SEPARATOR) {
+                Some((database, name)) => filter.include(table).include(&format!("{}.{}", database, name)),
+                None => filter.include(table),
+            }
+        });
         
-        // Use the first table (TODO: Add support for multiple tables)
-        let table_name = tables.first().unwrap().clone();
-        println!("\n=== Loading table: {} ===", table_name);
-        
-        let table_path = db_data_path.join(&table_name);
-        
-        let parquet_files = Self::discover_parquet_files(&table_path)?;
-        println!("Found {} parquet file(s)", parquet_files.len());
-        
-        let binlog_file = Self::discover_binlog_file(&table_path)?;
-        println!("Found binlog file: {}", binlog_file);
+        // The snapshot is redacted before it is decoded, the binlog after
+        if let Some((table, column, _)) = config.column_decoders.iter()
+            .find(|(table, column, _)| config.redactions.redaction(table, column).is_some()) {
+            return Err(format!("{}.{} can't be both decoded and redacted", table, column).into());
+        }
+
+        cancel.check()?;
+        progress(&LoadProgress::LoadingTables { table_count: tables.len() });
+        let load_span = info_span!("load_tables", table_count = tables.len()).entered();
+        let conn = match &config.database_path {
+            Some(database_path) => parquet_loader::load_tables_into_database_file(
+                database_path,
+                &table_parquet_files,
+                config.reuse_database,
+                &config.column_projection,
+                &config.redactions,
+                &config.resource_limits,
+            )?,
+            None => parquet_loader::load_tables_from_parquet_files(
+                &table_parquet_files,
+                &config.column_projection,
+                &config.redactions,
+                &config.resource_limits,
+            )?,
+        };
         
-        println!("\n=== Loading Parquet Files ===");
-        let parquet_refs: Vec<&str> = parquet_files.iter().map(|s| s.as_str()).collect();
-        let conn = parquet_loader::load_table_from_parquet_files(&table_name, &parquet_refs)?;
+        binlog_only_tables::create_binlog_only_tables(
+            &conn,
+            &config.binlog_only_tables,
+            &binlog_files,
+            config.binlog_only_ddl.as_deref(),
+        )?;
+        // Their redacted values are text whatever the DDL says
+        for (table, column, _) in config.redactions.columns() {
+            if config.binlog_only_tables.iter().any(|binlog_only| binlog_only == table) {
+                conn.execute_batch(&format!(
+                    "ALTER TABLE main.{} ALTER COLUMN {} TYPE VARCHAR;",
+                    quote_identifier(table), quote_identifier(column)
+                ))?;
+            }
+        }
+        column_mapping.validate(&conn, &config.column_projection)?;
+        column_decoding::decode_snapshot_columns(&conn, &config.column_decoders)?;
+        // The parser matches binlog values against every column of a projected table, not just
+        // the loaded ones
+        for (table, files) in &table_parquet_files {
+            if config.column_projection.columns(table).is_some() && column_mapping.columns(table).is_none() {
+                let columns = parquet_loader::snapshot_columns(&conn, files)?;
+                let columns: Vec<&str> = columns.iter().map(|c| c.as_str()).collect();
+                column_mapping = column_mapping.with_table(table, &columns);
+            }
+        }
+        drop(load_span);
         
-        println!("\n=== Parsing Binlog ===");
-        let mut parser = TextBinlogParser::new(conn);
-        let operations = parser.parse_file(&binlog_file)?;
+        let parse_span = info_span!("parse", file_count = binlog_files.len()).entered();
+        let worker_threads = config.resource_limits
+            .threads(std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1));
+        let mut parser = TextBinlogParser::new(conn)
+            .with_worker_threads(worker_threads)
+            .with_table_filter(table_filter)
+            .with_column_mapping(column_mapping)
+            .with_column_projection(config.column_projection.clone())
+            .with_redactions(config.redactions.clone())
+            .with_value_decoder(config.column_decoders.iter().fold(ValueDecoder::new(), |decoder, (table, column, column_decoder)| {
+                decoder.with_column_decoder(table, column, column_decoder.clone())
+            }))
+            .with_operation_cache(config.cache_operations)
+            .with_strict_transactions(config.strict_transactions)
+            .with_identifier_case(config.identifier_case)
+            .with_cancel_token(cancel.clone())
+            .with_time_range(
+                config.min_timestamp.as_deref().map(BinlogTimestamp::parse).transpose()?,
+                config.max_timestamp.as_deref().map(BinlogTimestamp::parse).transpose()?,
+            );
+        if let Some(max_parse_anomalies) = config.max_parse_anomalies {
+            parser = parser.with_max_anomalies(max_parse_anomalies);
+        }
+        let LoadedOperations {
+            operations,
+            parse_diagnostics,
+            file_markers: binlog_file_markers,
+            timestamp_backfill_report,
+            replace_fusion_report,
+            validation_report,
+        } = Self::load_operations(&mut parser, &binlog_files, config, cancel, progress)?;
         
-        println!("Parsed {} operations from binlog", operations.len());
-        println!("First 5 operations:");
-        for (i, op) in operations.iter().take(5).enumerate() {
-            println!("  {}: {}", i, op);
+        info!("Parsed {} operations from binlog", operations.len());
+        for anomaly in parse_diagnostics.anomalies.iter().take(10) {
+            warn!("{}", anomaly);
+        }
+        if parse_diagnostics.len() > 10 {
+            warn!("{} more parse anomalies", parse_diagnostics.len() - 10);
+        }
+        if let Some(first) = parse_diagnostics.timestamp_regressions.first() {
+            warn!(
+                "Timestamps go backwards {} time(s), first at {}; goto_timestamp stops at the first operation later than its target",
+                parse_diagnostics.timestamp_regressions.len(), first
+            );
+        }
+        for shared in &parse_diagnostics.shared_tables {
+            warn!("{}; name the snapshot's tables database{}table to keep them apart", shared, crate::binlog::NAMESPACE_SEPARATOR);
+        }
+        if let (count @ 1.., first_timestamp) = parse_diagnostics.unterminated_transactions() {
+            warn!(
+                "Dropped {} transaction(s) without COMMIT, first from {}; the binlog range may be incomplete",
+                count, first_timestamp.unwrap_or("an unknown time")
+            );
+        }
+        drop(parse_span);
+
+        cancel.check()?;
+        progress(&LoadProgress::ValidatingOperations { operation_count: operations.len() });
+        let validate_span = info_span!("validate", operation_count = operations.len()).entered();
+        if timestamp_backfill_report.backfilled_count() > 0 {
+            info!("Backfilled timestamps for {} operation(s)", timestamp_backfill_report.backfilled_count());
+        }
+        if !timestamp_backfill_report.is_complete() {
+            warn!("{} operation(s) still have no timestamp", timestamp_backfill_report.still_missing.len());
         }
+        if replace_fusion_report.fused_count() > 0 {
+            info!("Fused {} DELETE + INSERT pair(s) into UPDATEs", replace_fusion_report.fused_count());
+        }
+        if validation_report.duplicates_removed() > 0 {
+            info!("Removed {} duplicated operation(s)", validation_report.duplicates_removed());
+        }
+        for issue in validation_report.issues.iter().take(10) {
+            warn!("operation {} ({}): {}", issue.index, operations.operation(issue.index)?, issue.kind);
+        }
+        if validation_report.issues.len() > 10 {
+            warn!("{} more invalid operation(s)", validation_report.issues.len() - 10);
+        }
+        operations.for_each(0..5, |i, op| {
+            debug!("Operation {}: {}", i, op);
+            ControlFlow::Continue(())
+        })?;
+        drop(validate_span);
         
-        println!("\n=== Normalizing Snapshot ===");
+        cancel.check()?;
+        progress(&LoadProgress::Normalising);
+        let normalise_span = info_span!("normalise").entered();
         let conn = parser.into_connection();
         
-        let (conn, operations, tx_zero_idx) = TimestampNormaliser::normalize(
-            conn,
-            operations,
-            snapshot_timestamp,
-            window_hours,
-        )?;
-        
-        let manager = SnapshotManager::new(conn, operations, tx_zero_idx);
+        let (conn, tx_zero_idx, normalisation_report) = if config.backwards_only {
+            Self::anchor_to_final_operation(conn, &operations, &config.snapshot_timestamp)?
+        } else {
+            TimestampNormaliser::normalize_source(
+                OperationApplier::new(conn).with_appender(config.appender_inserts),
+                &operations,
+                &config.snapshot_timestamp,
+                config.window_hours,
+                config.empty_window_policy,
+                config.anchor_strategy,
+                cancel,
+            )?
+        };
+        let conn = if config.binlog_only_tables.is_empty() || operations.is_empty() {
+            conn
+        } else {
+            let mut replayed = Vec::new();
+            operations.for_each(0..tx_zero_idx + 1, |_, op| {
+                if config.binlog_only_tables.contains(&op.table_name) {
+                    replayed.push(op.clone());
+                }
+                ControlFlow::Continue(())
+            })?;
+            let position = replayed.len().saturating_sub(1);
+            binlog_only_tables::reconstruct_binlog_only_tables(conn, &config.binlog_only_tables, &replayed, position, config.appender_inserts)?
+        };
         
-        println!("\n=== Snapshot Normalized ===");
-        println!("Snapshot position: {}", manager.get_position());
-        println!("Snapshot timestamp: {:?}", manager.get_timestamp());
+        let mut manager = SnapshotManager::with_operation_store(conn, operations, tx_zero_idx);
+        manager.set_backwards_only(config.backwards_only);
+        manager.set_redactions(config.redactions.clone());
+        manager.set_timestamp_options(config.timestamp_options);
+        manager.set_decoded_columns(config.column_decoders.iter().map(|(table, column, _)| (table.clone(), column.clone())).collect());
+        manager.set_binlog_files(binlog_file_markers);
+        manager.set_refuse_binlog_gaps(config.refuse_binlog_gaps);
+        for gap in manager.binlog_gaps() {
+            warn!("Binlog gap: {}", gap);
+        }
+        manager.set_max_operations_per_second(config.max_operations_per_second);
+        // Rebuilding only reloads tables with snapshot files
+        if config.binlog_only_tables.is_empty() {
+            manager.set_snapshot_source(&table_parquet_files, &config.column_projection, &config.column_decoders, &normalisation_report);
+        }
+        info!("Snapshot normalised to position {} ({:?})", manager.get_position(), manager.get_timestamp());
+        drop(normalise_span);
+        progress(&LoadProgress::Done);
         
-        Ok(Self { manager, table_name })
+        Ok(Self {
+            manager,
+            table_names: tables,
+            parse_diagnostics,
+            timestamp_backfill_report,
+            replace_fusion_report,
+            validation_report,
+            normalisation_report,
+        })
     }
     
+    /// Backwards-only mode: the snapshot is the state after the final operation, so nothing is
+    /// normalised. Warns about operations later than the snapshot timestamp, which contradict the
+    /// mode and would be treated as already in the snapshot.
+    fn anchor_to_final_operation(
+        conn: Connection,
+        operations: &OperationStore,
+        snapshot_timestamp: &str,
+    ) -> Result<(Connection, usize, NormalisationReport), Box<dyn std::error::Error>> {
+        let snapshot = BinlogTimestamp::parse(snapshot_timestamp)?;
+        let mut after_snapshot = 0;
+        operations.for_each(0..operations.len(), |_, op| {
+            if op.timestamp.as_ref().and_then(|ts| BinlogTimestamp::parse(ts).ok()).is_some_and(|ts| ts > snapshot) {
+                after_snapshot += 1;
+            }
+            ControlFlow::Continue(())
+        })?;
+        if after_snapshot > 0 {
+            warn!(
+                "Backwards-only mode expects the binlog to end before the snapshot, but {} operation(s) are after {}",
+                after_snapshot, snapshot_timestamp
+            );
+        }
+
+        let final_idx = operations.len().saturating_sub(1);
+        let report = NormalisationReport {
+            tx_zero_idx: (!operations.is_empty()).then_some(final_idx),
+            ..NormalisationReport::default()
+        };
+        Ok((conn, final_idx, report))
+    }
+
+    /// Binlog files of a local db_data directory, found without loading anything, e.g. to profile
+    /// them first
+    pub fn binlog_files(db_data_path: &Path) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        Ok(Self::discover_local(&db_data_path.to_path_buf())?.1)
+    }
+
+    /// Discovers tables, their snapshot files, and binlog files in a local db_data directory.
+    /// A binlog file with the same name in several table directories is only returned once.
+    fn discover_local(db_data_path: &PathBuf) -> Result<DiscoveredFiles, Box<dyn std::error::Error>> {
+        let tables = Self::discover_tables(db_data_path)?;
+        info!("Found tables: {:?}", tables);
+        
+        if tables.is_empty() {
+            return Err("No tables found in db_data".into());
+        }
+        
+        let mut table_parquet_files = Vec::new();
+        let mut binlog_files_by_name: BTreeMap<String, String> = BTreeMap::new();
+        for table_name in &tables {
+            let _table_span = debug_span!("discover_table", table = table_name.as_str()).entered();
+            let table_path = db_data_path.join(table_name);
+
+            let parquet_files = Self::discover_snapshot_files(&table_path)?;
+            table_parquet_files.push((table_name.clone(), parquet_files));
+
+            match Self::discover_binlog_files(&table_path) {
+                Ok(binlog_files) => {
+                    debug!("Found {} binlog file(s): {:?}", binlog_files.len(), binlog_files);
+                    for binlog_file in binlog_files {
+                        let file_name = Path::new(&binlog_file).file_name()
+                            .map(|name| name.to_string_lossy().to_string())
+                            .unwrap_or_else(|| binlog_file.clone());
+                        binlog_files_by_name.entry(file_name).or_insert(binlog_file);
+                    }
+                }
+                Err(e) => warn!("{}", e),
+            }
+        }
+
+        let binlog_files: Vec<String> = binlog_files_by_name.into_values().collect();
+        if binlog_files.is_empty() {
+            return Err("No SQL binlog files found in any table directory".into());
+        }
+        Ok((table_parquet_files, binlog_files))
+    }
+
+    /// Discovers tables in an object store db_data root and downloads their binlog files to
+    /// `<temp dir>/pensieve_binlogs` (see remote_files::download), since binlogs are parsed line by
+    /// line from local disk
+    fn discover_remote(data_dir: &str) -> Result<DiscoveredFiles, Box<dyn std::error::Error>> {
+        let conn = Connection::open_in_memory()?;
+        remote_files::prepare_connection(&conn)?;
+
+        let discovery = remote_files::discover(&conn, data_dir)?;
+        info!("Found tables: {:?}", discovery.table_parquet_files.iter().map(|(table, _)| table).collect::<Vec<_>>());
+
+        let cache_dir = std::env::temp_dir().join("pensieve_binlogs");
+        let mut binlog_files_by_name: BTreeMap<String, String> = BTreeMap::new();
+        for (_, uris) in &discovery.table_binlog_files {
+            for uri in uris {
+                let file_name = uri.rsplit('/').next().unwrap_or(uri).to_string();
+                if let Entry::Vacant(entry) = binlog_files_by_name.entry(file_name) {
+                    let local_path = remote_files::download(&conn, uri, &cache_dir)?;
+                    entry.insert(local_path.to_string_lossy().to_string());
+                }
+            }
+        }
+
+        let binlog_files: Vec<String> = binlog_files_by_name.into_values().collect();
+        if binlog_files.is_empty() {
+            return Err(format!("No SQL binlog files found under {}", data_dir).into());
+        }
+        Ok((discovery.table_parquet_files, binlog_files))
+    }
+
     /// Discovers table directories in db_data folder
-    fn discover_tables(db_data_path: &PathBuf) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    pub(crate) fn discover_tables(db_data_path: &PathBuf) -> Result<Vec<String>, Box<dyn std::error::Error>> {
         let mut tables = Vec::new();
         
         if !db_data_path.exists() {
@@ -110,10 +809,9 @@ impl Pensieve {
             let entry = entry?;
             let path = entry.path();
             
-            if path.is_dir() {
-                if let Some(table_name) = path.file_name().and_then(|n| n.to_str()) {
-                    tables.push(table_name.to_string());
-                }
+            if path.is_dir()
+                && let Some(table_name) = path.file_name().and_then(|n| n.to_str()) {
+                tables.push(table_name.to_string());
             }
         }
         
@@ -121,55 +819,173 @@ impl Pensieve {
             return Err("No table directories found in db_data".into());
         }
         
+        tables.sort();
         Ok(tables)
     }
     
-    /// Discovers parquet files in a table directory
-    fn discover_parquet_files(table_path: &PathBuf) -> Result<Vec<String>, Box<dyn std::error::Error>> {
-        let mut parquet_files = Vec::new();
+    /// Discovers the snapshot files of a table directory: its parquet files, or else its CSV
+    /// files, or else its JSONL files (see SnapshotFormat). Subdirectories are searched too, so
+    /// partitioned exports (`<table>/dt=2025-11-08/part-0.parquet`) are found.
+    pub(crate) fn discover_snapshot_files(table_path: &PathBuf) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let mut files = Vec::new();
+        Self::collect_files(table_path, &mut files)?;
         
-        for entry in fs::read_dir(table_path)? {
-            let entry = entry?;
-            let path = entry.path();
-            
-            if path.is_file() {
-                if let Some(ext) = path.extension() {
-                    if ext == "parquet" {
-                        if let Some(path_str) = path.to_str() {
-                            parquet_files.push(path_str.to_string());
-                        }
-                    }
-                }
+        match parquet_loader::select_snapshot_files(&files) {
+            Some((format, snapshot_files)) => {
+                debug!("Found {} {:?} snapshot file(s)", snapshot_files.len(), format);
+                Ok(snapshot_files)
             }
+            None => Err(format!("No parquet, CSV or JSONL files found in {:?}", table_path).into()),
         }
-        
-        if parquet_files.is_empty() {
-            return Err(format!("No parquet files found in {:?}", table_path).into());
+    }
+    
+    fn collect_files(directory: &Path, files: &mut Vec<String>) -> Result<(), Box<dyn std::error::Error>> {
+        for entry in fs::read_dir(directory)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                Self::collect_files(&path, files)?;
+            } else if let Some(path_str) = path.to_str() {
+                files.push(path_str.to_string());
+            }
         }
-        
-        // Sort for consistent ordering
-        parquet_files.sort();
-        Ok(parquet_files)
+        Ok(())
     }
     
-    /// Discovers SQL binlog file in a table directory
-    fn discover_binlog_file(table_path: &PathBuf) -> Result<String, Box<dyn std::error::Error>> {
+    /// Compares the state at the given timestamp with a reference dump laid out like db_data
+    /// (`<dump_dir>/<table>/*.parquet`, or CSV or JSONL files). See
+    /// SnapshotManager::verify_against_reference.
+    pub fn verify_against_dump(&mut self, timestamp: &str, dump_dir: &Path) -> Result<VerificationReport, Box<dyn std::error::Error>> {
+        let dump_dir = dump_dir.to_path_buf();
+        let mut reference = Vec::new();
+        for table in Self::discover_tables(&dump_dir)? {
+            let files = Self::discover_snapshot_files(&dump_dir.join(&table))?;
+            reference.push((table, files));
+        }
+        self.manager.verify_against_reference(timestamp, &reference)
+    }
+
+    /// Discovers SQL binlog files in a table directory, plain or compressed (`.sql.gz`, `.sql.zst`),
+    /// ordered by file name (e.g. mysql-bin.000101.sql before mysql-bin.000102.sql.gz)
+    pub(crate) fn discover_binlog_files(table_path: &PathBuf) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let mut binlog_files = Vec::new();
+
         for entry in fs::read_dir(table_path)? {
             let entry = entry?;
             let path = entry.path();
             
-            if path.is_file() {
-                if let Some(ext) = path.extension() {
-                    if ext == "sql" {
-                        if let Some(path_str) = path.to_str() {
-                            return Ok(path_str.to_string());
-                        }
-                    }
-                }
+            if path.is_file()
+                && binlog_reader::is_binlog_file(&path)
+                && let Some(path_str) = path.to_str() {
+                binlog_files.push(path_str.to_string());
             }
         }
         
-        Err(format!("No SQL binlog file found in {:?}", table_path).into())
+        if binlog_files.is_empty() {
+            return Err(format!("No SQL binlog file found in {:?}", table_path).into());
+        }
+
+        binlog_files.sort();
+        Ok(binlog_files)
+    }
+
+    /// Parses binlog files in order into a single operation stream, with the markers of the
+    /// files the operations came from. Timestamps are backfilled, REPLACEs fused and operations
+    /// validated a file at a time, before the file's operations are added to the stream, so that
+    /// at most one file's operations are held in memory beyond the max_operations_in_memory
+    /// limit. Report indices are into the whole stream, as if each pass had run over it at once.
+    fn load_operations(
+        parser: &mut TextBinlogParser,
+        binlog_files: &[String],
+        config: &PensieveConfig,
+        cancel: &CancelToken,
+        progress: &mut dyn FnMut(&LoadProgress),
+    ) -> Result<LoadedOperations, Box<dyn std::error::Error>> {
+        let operations = match config.max_operations_in_memory {
+            Some(max_operations) => OperationStore::in_memory(Vec::new()).with_limit(max_operations)?,
+            None => OperationStore::in_memory(Vec::new()),
+        };
+        let mut loaded = LoadedOperations {
+            operations,
+            parse_diagnostics: ParseDiagnostics::default(),
+            file_markers: Vec::new(),
+            timestamp_backfill_report: TimestampBackfillReport::default(),
+            replace_fusion_report: ReplaceFusionReport::default(),
+            validation_report: ValidationReport::default(),
+        };
+        // The snapshot's tables already have the columns of the ALTERs before it
+        parser.anchor_schema_history(binlog_files, &BinlogTimestamp::parse(&config.snapshot_timestamp)?)?;
+        let mut validator = OperationValidator::default();
+        let mut last_timestamp: Option<String> = None;
+        // Operations before fusion and before validation, in the files parsed so far
+        let (mut parsed_count, mut fused_count) = (0, 0);
+
+        for (file_index, binlog_file) in binlog_files.iter().enumerate() {
+            cancel.check()?;
+            progress(&LoadProgress::ParsingBinlog {
+                file: binlog_file.clone(),
+                file_index,
+                file_count: binlog_files.len(),
+            });
+            let (mut file_operations, mut file_diagnostics) = parser.parse_file_with_diagnostics(binlog_file)?;
+            loaded.parse_diagnostics.append(&mut file_diagnostics);
+            info!("Parsed {} operations from {}", file_operations.len(), binlog_file);
+            let mut file_markers = parser.file_markers().to_vec();
+            let parsed_len = file_operations.len();
+
+            let backfill = timestamp_backfill::backfill_timestamps_after(&mut file_operations, last_timestamp.as_deref());
+            let report = &mut loaded.timestamp_backfill_report;
+            report.inherited_from_previous.extend(backfill.inherited_from_previous.iter().map(|idx| idx + parsed_count));
+            report.inherited_from_next.extend(backfill.inherited_from_next.iter().map(|idx| idx + parsed_count));
+            report.still_missing.extend(backfill.still_missing.iter().map(|idx| idx + parsed_count));
+            last_timestamp = file_operations.iter().rev().find_map(|op| op.timestamp.clone()).or(last_timestamp);
+
+            if config.fuse_replaces {
+                let fusion = replace_fusion::fuse_replaces(parser.get_connection(), &mut file_operations)?;
+                BinlogFileMarker::remove_operations(&mut file_markers, &fusion.removed_indices());
+                loaded.replace_fusion_report.fused.extend(fusion.fused.into_iter()
+                    .map(|fused| FusedReplace { insert_index: fused.insert_index + parsed_count, ..fused }));
+            }
+            parsed_count += parsed_len;
+            let fused_len = file_operations.len();
+
+            let validation = validator.validate(parser.get_connection(), &mut file_operations)?;
+            BinlogFileMarker::remove_operations(&mut file_markers, &validation.removed_indices());
+            let report = &mut loaded.validation_report;
+            report.duplicates.extend(validation.duplicates.into_iter()
+                .map(|duplicate| DuplicateOperations { index: duplicate.index + fused_count, ..duplicate }));
+            report.issues.extend(validation.issues.into_iter()
+                .map(|issue| ValidationIssue { index: issue.index + loaded.operations.len(), ..issue }));
+            fused_count += fused_len;
+
+            loaded.file_markers.extend(file_markers.into_iter().map(|marker| BinlogFileMarker {
+                first_operation: marker.first_operation + loaded.operations.len(),
+                ..marker
+            }));
+            loaded.operations.extend(file_operations)?;
+        }
+
+        // Files without any timestamp before the first one that has some take its first timestamp
+        let report = &mut loaded.timestamp_backfill_report;
+        if !report.still_missing.is_empty() && last_timestamp.is_some() {
+            let mut leading = Vec::new();
+            let mut first_timestamp = None;
+            loaded.operations.for_each(0..loaded.operations.len(), |idx, op| match &op.timestamp {
+                Some(timestamp) => {
+                    first_timestamp = Some(timestamp.clone());
+                    ControlFlow::Break(())
+                }
+                None => {
+                    leading.push((idx, op.clone()));
+                    ControlFlow::Continue(())
+                }
+            })?;
+            for (idx, op) in leading {
+                loaded.operations.set(idx, BinlogOperation { timestamp: first_timestamp.clone(), ..op });
+            }
+            report.inherited_from_next.append(&mut report.still_missing);
+        }
+
+        Ok(loaded)
     }
     
     pub fn get_snapshot_position(&self) -> usize {
@@ -180,14 +996,141 @@ impl Pensieve {
         self.manager.get_timestamp()
     }
     
+    /// Snapshot timestamp converted to UTC with the configured timestamp options
+    pub fn get_snapshot_time_utc(&self) -> Option<DateTime<Utc>> {
+        self.get_timestamp_options().to_utc(self.manager.get_timestamp()?).ok()
+    }
+
+    pub fn get_timestamp_options(&self) -> &TimestampOptions {
+        self.manager.get_timestamp_options()
+    }
+
     pub fn get_connection(&self) -> &Connection {
         self.manager.get_connection()
     }
     
+    /// Name of the first loaded table (in name order)
     pub fn get_table_name(&self) -> &str {
-        &self.table_name
+        &self.table_names[0]
+    }
+
+    /// Names of all loaded tables, in name order
+    pub fn get_table_names(&self) -> &[String] {
+        &self.table_names
+    }
+
+    /// Get the binlog lines that were skipped or couldn't be fully parsed
+    pub fn get_parse_diagnostics(&self) -> &ParseDiagnostics {
+        &self.parse_diagnostics
+    }
+
+    /// Get the report of operations whose timestamps were backfilled (or are still missing) after parsing
+    pub fn get_timestamp_backfill_report(&self) -> &TimestampBackfillReport {
+        &self.timestamp_backfill_report
     }
     
+    /// Get the report of DELETE + INSERT pairs fused into UPDATEs after parsing
+    pub fn get_replace_fusion_report(&self) -> &ReplaceFusionReport {
+        &self.replace_fusion_report
+    }
+
+    /// Get the report of duplicated and invalid operations found after parsing
+    pub fn get_validation_report(&self) -> &ValidationReport {
+        &self.validation_report
+    }
+
+    /// Get the report of how the snapshot was anchored: what normalisation did with each
+    /// operation in the window, and why
+    pub fn get_normalisation_report(&self) -> &NormalisationReport {
+        &self.normalisation_report
+    }
+    
+    /// Per-table operation counts, time range, distinct rows touched and operations per time bucket,
+    /// in table name order. See SnapshotManager::operation_stats.
+    pub fn operation_stats(&self, bucket_size: Duration) -> Result<Vec<TableOperationStats>, Box<dyn std::error::Error>> {
+        self.manager.operation_stats(bucket_size)
+    }
+
+    /// Navigates the snapshot to the given timestamp and returns its connection for querying.
+    /// The snapshot stays at this position afterwards.
+    pub fn snapshot_at(&mut self, timestamp: &str) -> Result<&Connection, Box<dyn std::error::Error>> {
+        self.manager.goto_timestamp(timestamp)?;
+        Ok(self.manager.get_connection())
+    }
+
+    /// Like `snapshot_at`, with a UTC time (e.g. from application logs) converted to the binlog
+    /// server's local time
+    pub fn snapshot_at_utc(&mut self, datetime: &DateTime<Utc>) -> Result<&Connection, Box<dyn std::error::Error>> {
+        let timestamp = self.get_timestamp_options().from_utc(datetime);
+        self.snapshot_at(&timestamp.to_binlog_format())
+    }
+
+    /// One row of a table as it was at the given timestamp, found by replaying only the operations
+    /// on that row. `key` holds its primary key values as SQL literals (e.g. `["1"]`). The snapshot
+    /// doesn't move. See SnapshotManager::row_at.
+    pub fn row_at(&mut self, table: &str, key: &[&str], timestamp: &str) -> Result<Option<RowValues>, Box<dyn std::error::Error>> {
+        self.manager.row_at(table, key, timestamp)
+    }
+
+    /// Runs a SQL query against the database as it was at the given timestamp.
+    ///
+    /// The snapshot is moved back to its previous position afterwards, so this can be called
+    /// between other navigation calls without disturbing them.
+    ///
+    /// # Arguments
+    /// * `timestamp` - Point in time to query (format: "YYMMDD HH:MM:SS")
+    /// * `sql` - Any query DuckDB can run against the loaded tables
+    ///
+    /// # Returns
+    /// One Vec of values per result row
+    pub fn query_at(&mut self, timestamp: &str, sql: &str) -> Result<Vec<Vec<Value>>, Box<dyn std::error::Error>> {
+        let previous_position = self.manager.get_position();
+        self.manager.goto_timestamp(timestamp)?;
+        let result = self.manager.query(sql);
+        self.manager.goto_position(previous_position)?;
+        result
+    }
+
+    /// Like `query_at`, deserializing each row into a `T` by column name. See
+    /// SnapshotManager::query_as.
+    pub fn query_as_at<T: serde::de::DeserializeOwned>(&mut self, timestamp: &str, sql: &str) -> Result<Vec<T>, Box<dyn std::error::Error>> {
+        let previous_position = self.manager.get_position();
+        self.manager.goto_timestamp(timestamp)?;
+        let result = self.manager.query_as(sql);
+        self.manager.goto_position(previous_position)?;
+        result
+    }
+
+    /// Runs a SQL query against the database as it was at the given timestamp and writes the
+    /// result to a parquet file. Like `query_at`, the snapshot is moved back to its previous
+    /// position afterwards.
+    ///
+    /// # Returns
+    /// The number of rows written
+    pub fn materialise_at(&mut self, timestamp: &str, sql: &str, output_path: &str) -> Result<usize, Box<dyn std::error::Error>> {
+        let previous_position = self.manager.get_position();
+        self.manager.goto_timestamp(timestamp)?;
+        let result = self.manager.materialise(sql, output_path);
+        self.manager.goto_position(previous_position)?;
+        result
+    }
+
+    /// Writes every table as it was at the given timestamp to `<dir>/<table>/snapshot.parquet`,
+    /// with a manifest.json. Like `materialise_at`, the snapshot is moved back to its previous
+    /// position afterwards. See SnapshotManager::export_snapshot.
+    pub fn export_snapshot_at(&mut self, timestamp: &str, dir: &Path) -> Result<SnapshotExport, Box<dyn std::error::Error>> {
+        let previous_position = self.manager.get_position();
+        self.manager.goto_timestamp(timestamp)?;
+        let result = self.manager.export_snapshot(dir);
+        self.manager.goto_position(previous_position)?;
+        result
+    }
+
+    /// Get the underlying SnapshotManager
+    pub fn get_manager(&self) -> &SnapshotManager {
+        &self.manager
+    }
+
     /// Get mutable access to the underlying SnapshotManager
     pub fn get_manager_mut(&mut self) -> &mut SnapshotManager {
         &mut self.manager
@@ -198,3 +1141,234 @@ impl Pensieve {
         self.manager
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// db_data directory with one table, one row and an empty binlog file
+    fn create_data_dir(name: &str) -> PathBuf {
+        let data_dir = std::env::temp_dir().join(format!("{}_{}", name, std::process::id()));
+        let table_dir = data_dir.join("books");
+        fs::create_dir_all(&table_dir).unwrap();
+        Connection::open_in_memory().unwrap().execute_batch(&format!(
+            "COPY (SELECT 1 AS id, 10 AS price) TO '{}' (FORMAT PARQUET);",
+            table_dir.join("snapshot.parquet").display()
+        )).unwrap();
+        fs::write(table_dir.join("mysql-bin.000001.sql"), "").unwrap();
+        data_dir
+    }
+
+    #[test]
+    fn test_discover_binlog_files_orders_by_name_and_skips_other_files() {
+        let table_dir = std::env::temp_dir().join(format!("pensieve_discovery_{}", std::process::id()));
+        fs::create_dir_all(table_dir.join("mysql-bin.000008.sql")).unwrap();
+        for file in ["mysql-bin.000010.sql", "mysql-bin.000009.sql.gz", "mysql-bin.000011.sql.zst", "mysql-bin.index", "notes.txt", "mysql-bin.000012.sql.bak"] {
+            fs::write(table_dir.join(file), "").unwrap();
+        }
+
+        let files: Vec<String> = Pensieve::discover_binlog_files(&table_dir).unwrap()
+            .iter()
+            .map(|file| Path::new(file).file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(files, vec!["mysql-bin.000009.sql.gz", "mysql-bin.000010.sql", "mysql-bin.000011.sql.zst"]);
+
+        fs::remove_dir_all(&table_dir).unwrap();
+        fs::create_dir_all(&table_dir).unwrap();
+        fs::write(table_dir.join("notes.txt"), "").unwrap();
+        assert!(Pensieve::discover_binlog_files(&table_dir).is_err());
+        fs::remove_dir_all(&table_dir).unwrap();
+    }
+
+    #[test]
+    fn test_load_with_reports_progress_and_cancels() {
+        let data_dir = create_data_dir("pensieve_builder");
+        let builder = PensieveBuilder::new(PensieveConfig::new(&data_dir.to_string_lossy(), "251108 10:00:00", 1));
+
+        let mut phases = Vec::new();
+        let pensieve = builder.load_with(&CancelToken::new(), |phase| phases.push(phase.clone())).unwrap();
+        assert_eq!(pensieve.get_table_names(), ["books".to_string()]);
+        assert_eq!(phases.first(), Some(&LoadProgress::DiscoveringFiles));
+        assert_eq!(phases[1], LoadProgress::LoadingTables { table_count: 1 });
+        assert!(matches!(&phases[2], LoadProgress::ParsingBinlog { file_index: 0, file_count: 1, .. }));
+        assert_eq!(phases.last(), Some(&LoadProgress::Done));
+
+        let cancel = CancelToken::new();
+        let result = builder.load_with(&cancel, |phase| {
+            if matches!(phase, LoadProgress::ParsingBinlog { .. }) {
+                cancel.cancel();
+            }
+        });
+        let error = result.err().unwrap();
+        assert!(error.downcast_ref::<LoadCancelled>().is_some());
+
+        fs::remove_dir_all(data_dir).ok();
+    }
+
+    #[test]
+    fn test_binlog_only_table_with_snapshot_files_is_refused() {
+        let data_dir = create_data_dir("pensieve_binlog_only");
+        let config = PensieveConfig::new(&data_dir.to_string_lossy(), "251108 10:00:00", 1)
+            .with_binlog_only_tables(&["books"]);
+
+        let error = Pensieve::from_config(&config).err().unwrap();
+        assert_eq!(error.to_string(), "books has snapshot files, so it can't be binlog-only");
+
+        fs::remove_dir_all(data_dir).ok();
+    }
+
+    #[test]
+    fn test_column_projection_loads_and_parses_only_projected_columns() {
+        let data_dir = create_data_dir("pensieve_projection");
+        fs::write(data_dir.join("books").join("mysql-bin.000001.sql"), r#"
+BEGIN
+#251108  9:30:00 server id 1  end_log_pos 100
+### INSERT INTO `main`.`books`
+### SET
+###   @1=2
+###   @2=20
+COMMIT
+"#).unwrap();
+        let config = PensieveConfig::new(&data_dir.to_string_lossy(), "251108 10:00:00", 1)
+            .with_column_projection(ColumnProjection::new().with_table("books", &["price"]))
+            .with_cache_operations(false);
+
+        let pensieve = Pensieve::from_config(&config).unwrap();
+        let columns: i64 = pensieve.get_connection()
+            .query_row("SELECT COUNT(*) FROM duckdb_columns() WHERE table_name = 'books'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(columns, 1);
+        let op = pensieve.get_manager().get_operation(0).unwrap();
+        assert_eq!(op.columns, vec!["price"]);
+        assert_eq!(op.after_values, Some(vec!["20".to_string()]));
+
+        fs::remove_dir_all(data_dir).ok();
+    }
+
+    #[test]
+    fn test_redacted_column_never_holds_raw_values() {
+        let data_dir = create_data_dir("pensieve_redaction");
+        fs::write(data_dir.join("books").join("mysql-bin.000001.sql"), r#"
+BEGIN
+#251108 10:30:00 server id 1  end_log_pos 100
+### UPDATE `main`.`books`
+### WHERE
+###   @1=1
+###   @2=10
+### SET
+###   @1=1
+###   @2=12
+COMMIT
+"#).unwrap();
+        let config = PensieveConfig::new(&data_dir.to_string_lossy(), "251108 10:00:00", 1)
+            .with_database_path(data_dir.join("snapshot.duckdb"))
+            .with_redaction("books", "price", Redaction::Hash)
+            .with_redaction_salt("salt")
+            .with_cache_operations(false);
+
+        let pensieve = Pensieve::from_config(&config).unwrap();
+        let manager = pensieve.get_manager();
+        let hash = |value: &str| -> String {
+            manager.get_connection().query_row("SELECT sha256(?)", [format!("salt{}", value)], |row| row.get(0)).unwrap()
+        };
+        let (snapshot_hash, updated_hash) = (hash("10"), hash("12"));
+        let price = |manager: &SnapshotManager, table: &str| -> String {
+            manager.get_connection().query_row(&format!("SELECT price FROM {} WHERE id = 1", table), [], |row| row.get(0)).unwrap()
+        };
+        assert_eq!(price(manager, "pensieve_snapshot.books"), snapshot_hash);
+        assert_eq!(price(manager, "books"), updated_hash);
+        assert_eq!(manager.get_operation(0).unwrap().after_values, Some(vec!["1".to_string(), format!("'{}'", updated_hash)]));
+
+        fs::remove_dir_all(data_dir).ok();
+    }
+
+    #[test]
+    fn test_operations_spill_while_parsing_and_duplicates_are_found_across_files() {
+        let data_dir = create_data_dir("pensieve_spill_while_parsing");
+        let insert = |id: i64, time: &str, end_log_pos: u32| format!(
+            "BEGIN\n#251108 {} server id 1  end_log_pos {}\n### INSERT INTO `main`.`books`\n### SET\n###   @1={}\n###   @2={}\nCOMMIT\n",
+            time, end_log_pos, id, id * 10
+        );
+        let table_dir = data_dir.join("books");
+        fs::write(table_dir.join("mysql-bin.000001.sql"), [insert(2, "9:00:00", 100), insert(3, "9:10:00", 200), insert(4, "9:20:00", 300)].concat()).unwrap();
+        // The second file starts with the last event of the first again
+        fs::write(table_dir.join("mysql-bin.000002.sql"), [insert(4, "9:20:00", 300), insert(5, "10:30:00", 400)].concat()).unwrap();
+        let config = PensieveConfig::new(&data_dir.to_string_lossy(), "251108 10:00:00", 1)
+            .with_max_operations_in_memory(2)
+            .with_cache_operations(false);
+
+        let pensieve = Pensieve::from_config(&config).unwrap();
+        let manager = pensieve.get_manager();
+        assert!(manager.operations_spilled());
+        assert_eq!(pensieve.get_validation_report().duplicates_removed(), 1);
+        let mut ids = Vec::new();
+        manager.for_each_operation(0, manager.operation_count(), |_, op| {
+            ids.push(op.after_values.clone().unwrap().remove(0));
+            Ok(())
+        }).unwrap();
+        assert_eq!(ids, vec!["2", "3", "4", "5"]);
+        let mut stmt = manager.get_connection().prepare("SELECT id FROM books ORDER BY id").unwrap();
+        let ids: Vec<i64> = stmt.query_map([], |row| row.get(0)).unwrap().collect::<Result<_, _>>().unwrap();
+        assert_eq!((manager.get_position(), ids), (2, vec![1, 2, 3, 4]));
+
+        fs::remove_dir_all(data_dir).ok();
+    }
+
+    #[test]
+    fn test_backwards_only_starts_at_final_operation() {
+        let data_dir = create_data_dir("pensieve_backwards_only");
+        fs::write(data_dir.join("books").join("mysql-bin.000001.sql"), r#"
+#251108  9:00:00 server id 1  end_log_pos 100
+### INSERT INTO `main`.`books`
+### SET
+###   @1=1
+###   @2=5
+#251108  9:30:00 server id 1  end_log_pos 200
+### UPDATE `main`.`books`
+### WHERE
+###   @1=1
+###   @2=5
+### SET
+###   @1=1
+###   @2=10
+"#).unwrap();
+        let config = PensieveConfig::new(&data_dir.to_string_lossy(), "251108 10:00:00", 1)
+            .with_backwards_only(true)
+            .with_cache_operations(false);
+
+        let mut pensieve = Pensieve::from_config(&config).unwrap();
+        let manager = pensieve.get_manager_mut();
+        assert!(manager.is_backwards_only());
+        assert_eq!(manager.get_position(), 1);
+        assert!(!manager.step_forward().unwrap());
+        assert!(manager.step_backward().unwrap());
+        let price: i64 = manager.get_connection().query_row("SELECT price FROM books WHERE id = 1", [], |row| row.get(0)).unwrap();
+        assert_eq!(price, 5);
+
+        fs::remove_dir_all(data_dir).ok();
+    }
+
+    #[test]
+    fn test_partitioned_snapshot_loads_with_partition_columns() {
+        let data_dir = std::env::temp_dir().join(format!("pensieve_partitioned_{}", std::process::id()));
+        let table_dir = data_dir.join("books");
+        fs::create_dir_all(&table_dir).unwrap();
+        Connection::open_in_memory().unwrap().execute_batch(&format!(
+            "COPY (SELECT 1 AS id, DATE '2025-11-08' AS dt UNION ALL SELECT 2, DATE '2025-11-09')
+             TO '{}' (FORMAT PARQUET, PARTITION_BY (dt));",
+            table_dir.display()
+        )).unwrap();
+        fs::write(table_dir.join("mysql-bin.000001.sql"), "").unwrap();
+
+        let files = Pensieve::discover_snapshot_files(&table_dir).unwrap();
+        assert_eq!(files.len(), 2);
+        let config = PensieveConfig::new(&data_dir.to_string_lossy(), "251108 10:00:00", 1).with_cache_operations(false);
+        let pensieve = Pensieve::from_config(&config).unwrap();
+        let dates: String = pensieve.get_connection()
+            .query_row("SELECT string_agg(dt::VARCHAR, ',' ORDER BY id) FROM books", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(dates, "2025-11-08,2025-11-09");
+
+        fs::remove_dir_all(data_dir).ok();
+    }
+}
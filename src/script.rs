@@ -1,32 +1,144 @@
+pub mod audit_log;
+pub mod column_blame;
 pub mod last_non_null;
+pub mod result_writer;
+pub mod row_lifecycle;
+pub mod script_args;
+pub mod script_context;
+pub mod time_bucket;
 
-use crate::snapshot_manager::SnapshotManager;
+pub use result_writer::ScriptResultWriter;
+pub use script_args::ScriptArgs;
+pub use script_context::ScriptContext;
+
+use tracing::info;
 
 #[derive(Debug, Clone)]
 pub struct ScriptResult {
+    /// Output this row belongs to, for scripts that produce one output per table.
+    /// None for scripts with a single output.
+    pub table: Option<String>,
     pub columns: Vec<String>,
     pub values: Vec<String>,
 }
 
+/// Name and usage of every script that run_script can run
+pub const SCRIPTS: [(&str, &str); 5] = [
+    ("last-non-null", last_non_null::USAGE),
+    ("audit-log", audit_log::USAGE),
+    ("time-bucket", time_bucket::USAGE),
+    ("row-lifecycle", row_lifecycle::USAGE),
+    ("column-blame", column_blame::USAGE),
+];
+
+/// Runs a script by name with its command-line arguments. Every script also accepts
+/// `--data-dir <dir>`, `--timestamp <YYMMDD HH:MM:SS>`, `--window <hours>` and `--output-dir <dir>`
+/// (see ScriptArgs).
+pub fn run_script(name: &str, args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    match name {
+        "last-non-null" => last_non_null::run_last_non_null(args),
+        "audit-log" => audit_log::run_audit_log(args),
+        "time-bucket" => time_bucket::run_time_bucket(args),
+        "row-lifecycle" => row_lifecycle::run_row_lifecycle(args),
+        "column-blame" => column_blame::run_column_blame(args),
+        _ => Err(format!("Unknown script: {}", name).into()),
+    }
+}
+
 pub trait PensieveScript {
-    fn execute(&mut self, manager: &mut SnapshotManager) -> Result<Vec<ScriptResult>, Box<dyn std::error::Error>>;
+    /// Runs the script, emitting its results to `sink` as they are found
+    fn execute(&mut self, context: &mut ScriptContext, sink: &mut dyn ResultSink) -> Result<(), Box<dyn std::error::Error>>;
     fn headers(&self) -> Vec<String>;
 }
 
-pub fn write_csv(results: &[ScriptResult], output_path: &str) -> Result<(), Box<dyn std::error::Error>> {
-    use std::fs::File;
-    use std::io::Write;
-    
-    let mut file = File::create(output_path)?;
-    
-    if let Some(first) = results.first() {
-        writeln!(file, "{}", first.columns.join(","))?;
+/// Receives a script's results one at a time: a ScriptResultWriter writes them out straight
+/// away, a Vec collects them
+pub trait ResultSink {
+    fn emit(&mut self, result: ScriptResult) -> Result<(), Box<dyn std::error::Error>>;
+}
+
+impl ResultSink for Vec<ScriptResult> {
+    fn emit(&mut self, result: ScriptResult) -> Result<(), Box<dyn std::error::Error>> {
+        self.push(result);
+        Ok(())
     }
-    
+}
+
+/// Loads the snapshot and binlog `args` point at and runs `run` with a script context for them
+pub fn with_script_context<T>(
+    args: &ScriptArgs,
+    run: impl FnOnce(&mut ScriptContext) -> Result<T, Box<dyn std::error::Error>>,
+) -> Result<T, Box<dyn std::error::Error>> {
+    info!("Loading snapshot and binlog...");
+    let mut manager = args.load_pensieve()?.into_manager();
+    let mut context = args.script_context(&mut manager)?;
+    run(&mut context)
+}
+
+/// Runs `script` on the snapshot `args` point at, writing its results as they are found to the CSV
+/// file `output` in the output directory
+pub fn run_to_csv(args: &ScriptArgs, script: &mut dyn PensieveScript, output: &str) -> Result<(), Box<dyn std::error::Error>> {
+    with_script_context(args, |context| {
+        let output = context.output_path(output)?;
+        info!("Writing results to {}...", output);
+        let mut writer = ScriptResultWriter::create(&output)?;
+        script.execute(context, &mut writer)?;
+        let rows_written = writer.rows_written();
+        writer.finish()?;
+
+        info!("Done! {} results written to {}", rows_written, output);
+        Ok(())
+    })
+}
+
+pub fn write_csv(results: &[ScriptResult], output_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let mut writer = ScriptResultWriter::create(output_path)?;
     for result in results {
-        writeln!(file, "{}", result.values.join(","))?;
+        writer.write(result)?;
     }
-    
+    writer.finish()?;
     Ok(())
 }
 
+/// Writes one CSV file per output table (`<output_dir>/<table>.csv`, or `results.csv` for results
+/// without a table). Each file's header comes from its first result.
+/// Returns the paths written, in table name order.
+pub fn write_csv_per_table(results: &[ScriptResult], output_dir: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let mut writer = ScriptResultWriter::per_table(output_dir)?;
+    for result in results {
+        writer.write(result)?;
+    }
+    writer.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(table: Option<&str>, columns: &[&str], values: &[&str]) -> ScriptResult {
+        ScriptResult {
+            table: table.map(|t| t.to_string()),
+            columns: columns.iter().map(|s| s.to_string()).collect(),
+            values: values.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_write_csv_per_table() {
+        let results = vec![
+            result(Some("books"), &["id", "price"], &["1", "10"]),
+            result(Some("authors"), &["id", "name"], &["7", "Herbert"]),
+            result(Some("books"), &["id", "price"], &["2", "20"]),
+        ];
+        let output_dir = std::env::temp_dir().join(format!("script_results_{}", std::process::id()));
+        let output_dir = output_dir.to_str().unwrap();
+
+        let paths = write_csv_per_table(&results, output_dir).unwrap();
+
+        assert_eq!(paths.len(), 2);
+        assert!(paths[0].ends_with("authors.csv"));
+        assert_eq!(std::fs::read_to_string(&paths[1]).unwrap(), "id,price\n1,10\n2,20\n");
+
+        std::fs::remove_dir_all(output_dir).ok();
+    }
+}
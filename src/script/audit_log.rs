@@ -0,0 +1,20 @@
+use crate::export::audit_log::{export_audit_log, AuditFormat};
+use crate::script::{with_script_context, ScriptArgs};
+use tracing::info;
+
+pub const USAGE: &str = "--output <file.parquet|file.jsonl>";
+
+/// Exports the whole parsed operation stream as an audit log, one record per changed column.
+/// The format follows the output extension: `.jsonl` for JSON lines, parquet otherwise.
+pub fn run_audit_log(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let args = ScriptArgs::parse("audit-log", USAGE, args)?;
+    let output = args.get_or("--output", "audit_log.parquet");
+
+    info!("=== Audit Log Export ===");
+    info!("Output: {}", output);
+
+    with_script_context(&args, |context| export_audit_log(context.manager, &output, AuditFormat::from_path(&output)))?;
+
+    info!("Done! Audit log written to {}", output);
+    Ok(())
+}
\ No newline at end of file
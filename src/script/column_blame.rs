@@ -0,0 +1,142 @@
+use crate::binlog::BinlogOperation;
+use crate::export::audit_log::literal_text;
+use crate::script::{run_to_csv, PensieveScript, ResultSink, ScriptArgs, ScriptContext, ScriptResult};
+use tracing::info;
+
+pub const USAGE: &str = "--table <name> --key <value[,value...]> --output <file.csv>";
+
+/// Reports, for each column of one row, the operation that last changed it: `git blame` for a
+/// row, from the operation stream without replaying it. The row is given by its primary key
+/// values (or first column), as text in key column order. Columns no operation changed, i.e. as in
+/// the snapshot, have no position.
+pub struct ColumnBlameScript {
+    pub table_name: String,
+    pub key_values: Vec<String>,
+}
+
+impl PensieveScript for ColumnBlameScript {
+    fn execute(&mut self, context: &mut ScriptContext, sink: &mut dyn ResultSink) -> Result<(), Box<dyn std::error::Error>> {
+        context.require_table(&self.table_name)?;
+        let manager = &mut *context.manager;
+        let columns = manager.table_columns(&self.table_name)?;
+        let key_columns = manager.primary_key_columns(&self.table_name, &columns)?;
+        if key_columns.len() != self.key_values.len() {
+            return Err(format!("{} is keyed by {}; got {} key values", self.table_name, key_columns.join(", "), self.key_values.len()).into());
+        }
+
+        let mut blame: Vec<Option<(usize, BinlogOperation, usize)>> = vec![None; columns.len()];
+        manager.for_each_operation(0, manager.operation_count(), |idx, op| {
+            if op.table_name != self.table_name || !self.touches_row(op, &key_columns) {
+                return Ok(());
+            }
+            for changed in op.changed_columns() {
+                let Some(column) = columns.iter().position(|column| column == changed) else {
+                    continue;
+                };
+                let op_column = op.columns.iter().position(|column| column == changed).unwrap_or_default();
+                blame[column] = Some((idx, op.clone(), op_column));
+            }
+            Ok(())
+        })?;
+
+        info!("Blamed {} of {} columns", blame.iter().flatten().count(), columns.len());
+        columns.iter()
+            .zip(blame)
+            .try_for_each(|(column, blame)| {
+                let mut values = vec![column.clone()];
+                values.extend(match blame {
+                    Some((idx, op, op_column)) => {
+                        let value_at = |image: &Option<Vec<String>>| image.as_ref()
+                            .and_then(|values| values.get(op_column))
+                            .and_then(|value| literal_text(value))
+                            .unwrap_or_default();
+                        let timestamp = op.timestamp.as_ref()
+                            .and_then(|ts| manager.get_timestamp_options().format(ts))
+                            .unwrap_or_default();
+                        [
+                            idx.to_string(),
+                            timestamp,
+                            op.operation_type.to_string(),
+                            value_at(&op.before_values),
+                            value_at(&op.after_values),
+                        ]
+                    }
+                    None => Default::default(),
+                });
+                sink.emit(ScriptResult {
+                    table: None,
+                    columns: self.headers(),
+                    values,
+                })
+            })
+    }
+
+    fn headers(&self) -> Vec<String> {
+        ["column", "position", "changed_at", "operation", "old_value", "new_value"]
+            .iter().map(|column| column.to_string()).collect()
+    }
+}
+
+impl ColumnBlameScript {
+    /// Whether the before or after image of `op` has the row's key
+    fn touches_row(&self, op: &BinlogOperation, key_columns: &[String]) -> bool {
+        let has_key = |image: &Option<Vec<String>>| {
+            let Some(values) = image else {
+                return false;
+            };
+            key_columns.iter().zip(&self.key_values).all(|(key, expected)| {
+                op.columns.iter()
+                    .position(|column| column == key)
+                    .and_then(|idx| values.get(idx))
+                    .and_then(|value| literal_text(value))
+                    .is_some_and(|value| value == *expected)
+            })
+        };
+        has_key(&op.before_values) || has_key(&op.after_values)
+    }
+}
+
+pub fn run_column_blame(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let args = ScriptArgs::parse("column-blame", USAGE, args)?;
+    let table_name = args.get_required("--table")?;
+    let key_values = args.get_required("--key")?.split(',').map(|value| value.to_string()).collect();
+    let output = args.get_or("--output", "blame.csv");
+
+    info!("=== Column Blame ===");
+    info!("Table: {}", table_name);
+
+    let mut script = ColumnBlameScript { table_name, key_values };
+    run_to_csv(&args, &mut script, &output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snapshot_manager::SnapshotManager;
+    use crate::binlog::OperationType;
+    use crate::test_support::{books_db, operation};
+
+    fn update(minute: u32, before: [&str; 3], after: [&str; 3]) -> BinlogOperation {
+        BinlogOperation {
+            timestamp: Some(format!("251108 10:{:02}:00", minute)),
+            ..operation(OperationType::Update, "books", &["id", "title", "price"], Some(&before), Some(&after))
+        }
+    }
+
+    #[test]
+    fn test_blames_last_change_of_each_column() {
+        let conn = books_db("id INTEGER PRIMARY KEY, title VARCHAR, price INTEGER", "");
+        let operations = vec![
+            update(0, ["1", "'Dune'", "10"], ["1", "'Dune'", "12"]),
+            update(1, ["2", "'Emma'", "5"], ["2", "'Emma'", "6"]),
+            update(2, ["1", "'Dune'", "12"], ["1", "'Dune'", "15"]),
+        ];
+        let mut manager = SnapshotManager::new(conn, operations, 0);
+
+        let mut script = ColumnBlameScript { table_name: "books".to_string(), key_values: vec!["1".to_string()] };
+        let mut results = Vec::new();
+        script.execute(&mut ScriptContext::new(&mut manager).unwrap(), &mut results).unwrap();
+        let rows: Vec<String> = results.iter().map(|result| result.values.join(",")).collect();
+        assert_eq!(rows, vec!["id,,,,,", "title,,,,,", "price,2,2025-11-08 10:02:00,UPDATE,12,15"]);
+    }
+}
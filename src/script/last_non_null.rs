@@ -1,7 +1,7 @@
 use std::collections::HashMap;
-use crate::pensieve::Pensieve;
-use crate::script::{write_csv, PensieveScript, ScriptResult};
-use crate::snapshot_manager::SnapshotManager;
+use crate::binlog::quote_identifier;
+use crate::script::{run_to_csv, PensieveScript, ResultSink, ScriptArgs, ScriptContext, ScriptResult};
+use tracing::{debug, info};
 
 pub struct LastNonNullScript {
     pub table_name: String,
@@ -9,17 +9,19 @@ pub struct LastNonNullScript {
 }
 
 impl PensieveScript for LastNonNullScript {
-    fn execute(&mut self, manager: &mut SnapshotManager) -> Result<Vec<ScriptResult>, Box<dyn std::error::Error>> {
+    fn execute(&mut self, context: &mut ScriptContext, sink: &mut dyn ResultSink) -> Result<(), Box<dyn std::error::Error>> {
+        context.require_table(&self.table_name)?;
+        let manager = &mut *context.manager;
         let mut last_values: HashMap<i64, String> = HashMap::new();
 
         manager.goto_position(0)?;
         
         let total_ops = manager.operation_count();
-        println!("Analyzing {} operations", total_ops);
+        info!("Analyzing {} operations", total_ops);
         
         for pos in 0..total_ops {
             if pos % 10 == 0 {
-                println!("Progress: {}/{}", pos, total_ops);
+                debug!("Progress: {}/{}", pos, total_ops);
             }
             
             manager.step_forward()?;
@@ -27,44 +29,43 @@ impl PensieveScript for LastNonNullScript {
             
             let query = format!(
                 "SELECT id, CAST({} AS VARCHAR) FROM {} WHERE {} IS NOT NULL",
-                self.column_name, self.table_name, self.column_name
+                quote_identifier(&self.column_name), quote_identifier(&self.table_name), quote_identifier(&self.column_name)
             );
             
-            if let Ok(mut stmt) = conn.prepare(&query) {
-                if let Ok(mut rows) = stmt.query([]) {
-                    while let Ok(Some(row)) = rows.next() {
-                        if let (Ok(file_id), Ok(value)) = (row.get::<usize, i64>(0), row.get::<usize, String>(1)) {
-                            if let Some(existing_value) = last_values.get(&file_id) {
-                                if *existing_value != value {
-                                    last_values.insert(file_id, value);
-                                }
-                            } else {
+            if let Ok(mut stmt) = conn.prepare(&query)
+                && let Ok(mut rows) = stmt.query([]) {
+                while let Ok(Some(row)) = rows.next() {
+                    if let (Ok(file_id), Ok(value)) = (row.get::<usize, i64>(0), row.get::<usize, String>(1)) {
+                        if let Some(existing_value) = last_values.get(&file_id) {
+                            if *existing_value != value {
                                 last_values.insert(file_id, value);
                             }
+                        } else {
+                            last_values.insert(file_id, value);
                         }
                     }
                 }
             }
         }
         
-        let mut results = Vec::new();
         let mut file_ids: Vec<_> = last_values.keys().collect();
         file_ids.sort();
         
         for file_id in file_ids {
             let value = last_values.get(file_id).unwrap();
             
-            results.push(ScriptResult {
+            sink.emit(ScriptResult {
+                table: None,
                 columns: self.headers(),
                 values: vec![
                     file_id.to_string(),
                     value.clone(),
                 ],
-            });
+            })?;
         }
         
-        println!("Analysis complete! Found {} results", results.len());
-        Ok(results)
+        info!("Analysis complete! Found {} results", last_values.len());
+        Ok(())
     }
 
     fn headers(&self) -> Vec<String> {
@@ -75,61 +76,21 @@ impl PensieveScript for LastNonNullScript {
     }
 }
 
-pub fn run_last_non_null(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
-    let mut table_name = "books".to_string();
-    let mut column_name = "price".to_string();
-    let mut output = "results.csv".to_string();
-    let mut snapshot_timestamp = "251111 01:45:00".to_string();
-    let mut window_hours = 1;
-
-    let mut i = 0;
-    while i < args.len() {
-        match args[i].as_str() {
-            "--table" => {
-                i += 1;
-                table_name = args[i].clone();
-            }
-            "--column" => {
-                i += 1;
-                column_name = args[i].clone();
-            }
-            "--output" => {
-                i += 1;
-                output = args[i].clone();
-            }
-            "--timestamp" => {
-                i += 1;
-                snapshot_timestamp = args[i].clone();
-            }
-            "--window" => {
-                i += 1;
-                window_hours = args[i].parse().unwrap_or(6);
-            }
-            _ => {}
-        }
-        i += 1;
-    }
+pub const USAGE: &str = "--table <name> --column <name> --output <file.csv>";
 
-    println!("=== Last Non-Null Value Finder ===");
-    println!("Table: {}", table_name);
-    println!("Column: {}", column_name);
-    println!();
-
-    println!("Loading snapshot and binlog...");
-    let pensieve = Pensieve::new(&snapshot_timestamp, window_hours)?;
+pub fn run_last_non_null(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let args = ScriptArgs::parse("last-non-null", USAGE, args)?;
+    let table_name = args.get_or("--table", "books");
+    let column_name = args.get_or("--column", "price");
+    let output = args.get_or("--output", "results.csv");
 
-    let mut manager = pensieve.into_manager();
+    info!("=== Last Non-Null Value Finder ===");
+    info!("Table: {}", table_name);
+    info!("Column: {}", column_name);
 
     let mut script = LastNonNullScript {
         table_name,
         column_name,
     };
-
-    let results = script.execute(&mut manager)?;
-
-    println!("Writing results to {}...", output);
-    write_csv(&results, &output)?;
-
-    println!("Done! Results written to {}", output);
-    Ok(())
+    run_to_csv(&args, &mut script, &output)
 }
\ No newline at end of file
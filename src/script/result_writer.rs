@@ -0,0 +1,154 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use crate::script::{ResultSink, ScriptResult};
+
+/// How often buffered rows are flushed to disk by default
+const DEFAULT_FLUSH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Writes script results to CSV as they are emitted, flushing periodically, so a long run that
+/// fails part way keeps what it had found. Writes to one file, or one file per output table
+/// (`<dir>/<table>.csv`, `results.csv` for results without a table). Each file's header comes
+/// from its first result.
+pub struct ScriptResultWriter {
+    // None for a single file
+    output_dir: Option<PathBuf>,
+    files: BTreeMap<String, OutputFile>,
+    flush_interval: Duration,
+    last_flush: Instant,
+    rows_written: usize,
+}
+
+impl ScriptResultWriter {
+    /// Writes every result to `path`, which is created (empty) straight away
+    pub fn create(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut writer = Self::new(None);
+        writer.files.insert(String::new(), OutputFile::create(path)?);
+        Ok(writer)
+    }
+
+    /// Writes the results of each table to `<output_dir>/<table>.csv`
+    pub fn per_table(output_dir: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        std::fs::create_dir_all(output_dir)?;
+        Ok(Self::new(Some(PathBuf::from(output_dir))))
+    }
+
+    fn new(output_dir: Option<PathBuf>) -> Self {
+        Self {
+            output_dir,
+            files: BTreeMap::new(),
+            flush_interval: DEFAULT_FLUSH_INTERVAL,
+            last_flush: Instant::now(),
+            rows_written: 0,
+        }
+    }
+
+    /// Flushes at most this long after a row is written. Zero flushes every row.
+    pub fn with_flush_interval(mut self, flush_interval: Duration) -> Self {
+        self.flush_interval = flush_interval;
+        self
+    }
+
+    pub fn rows_written(&self) -> usize {
+        self.rows_written
+    }
+
+    pub fn write(&mut self, result: &ScriptResult) -> Result<(), Box<dyn std::error::Error>> {
+        let key = match &self.output_dir {
+            Some(_) => result.table.as_deref().unwrap_or("results"),
+            None => "",
+        };
+        if !self.files.contains_key(key)
+            && let Some(output_dir) = &self.output_dir {
+            let path = output_dir.join(format!("{}.csv", key));
+            self.files.insert(key.to_string(), OutputFile::create(&path.to_string_lossy())?);
+        }
+        let output = self.files.get_mut(key).unwrap();
+        if !output.has_header {
+            write_row(&mut output.file, &result.columns)?;
+            output.has_header = true;
+        }
+        write_row(&mut output.file, &result.values)?;
+        self.rows_written += 1;
+
+        if self.last_flush.elapsed() >= self.flush_interval {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        for output in self.files.values_mut() {
+            output.file.flush()?;
+        }
+        self.last_flush = Instant::now();
+        Ok(())
+    }
+
+    /// Flushes everything and returns the paths written, in table name order
+    pub fn finish(mut self) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        self.flush()?;
+        Ok(std::mem::take(&mut self.files).into_values().map(|output| output.path).collect())
+    }
+}
+
+struct OutputFile {
+    path: String,
+    file: BufWriter<File>,
+    has_header: bool,
+}
+
+impl OutputFile {
+    fn create(path: &str) -> std::io::Result<Self> {
+        Ok(Self { path: path.to_string(), file: BufWriter::new(File::create(path)?), has_header: false })
+    }
+}
+
+impl ResultSink for ScriptResultWriter {
+    fn emit(&mut self, result: ScriptResult) -> Result<(), Box<dyn std::error::Error>> {
+        self.write(&result)
+    }
+}
+
+fn write_row(file: &mut BufWriter<File>, values: &[String]) -> std::io::Result<()> {
+    let fields: Vec<Cow<str>> = values.iter().map(|value| csv_field(value)).collect();
+    writeln!(file, "{}", fields.join(","))
+}
+
+/// `value` as a CSV field: quoted, with quotes doubled, if it has a comma, quote or line break
+pub fn csv_field(value: &str) -> Cow<'_, str> {
+    if value.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", value.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rows_are_quoted_and_on_disk_before_finish() {
+        let path = std::env::temp_dir().join(format!("result_writer_{}.csv", std::process::id()));
+        let path = path.to_str().unwrap();
+        let mut writer = ScriptResultWriter::create(path).unwrap().with_flush_interval(Duration::ZERO);
+        let result = |values: &[&str]| ScriptResult {
+            table: None,
+            columns: vec!["id".to_string(), "title".to_string()],
+            values: values.iter().map(|v| v.to_string()).collect(),
+        };
+
+        writer.emit(result(&["1", "Dune, Messiah"])).unwrap();
+        assert_eq!(std::fs::read_to_string(path).unwrap(), "id,title\n1,\"Dune, Messiah\"\n");
+
+        writer.emit(result(&["2", "The \"Stand\"\nUncut"])).unwrap();
+        assert_eq!(writer.finish().unwrap(), vec![path.to_string()]);
+        assert!(std::fs::read_to_string(path).unwrap().ends_with("2,\"The \"\"Stand\"\"\nUncut\"\n"));
+
+        std::fs::remove_file(path).ok();
+    }
+}
@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+use crate::binlog::{BinlogOperation, OperationType};
+use crate::script::{run_to_csv, PensieveScript, ResultSink, ScriptArgs, ScriptContext, ScriptResult};
+use tracing::info;
+
+pub const USAGE: &str = "--table <name> --output <file.csv>";
+
+/// Reports, for every row of a table the binlog touches, when it was inserted, last modified and
+/// deleted, without navigating the snapshot. Rows are identified by primary key (or first column);
+/// an UPDATE that changes the key deletes the old key and inserts the new one.
+pub struct RowLifecycleScript {
+    pub table_name: String,
+}
+
+#[derive(Default)]
+struct Lifecycle {
+    inserted: Option<usize>,
+    last_modified: usize,
+    /// Only if no later operation brought the row back
+    deleted: Option<usize>,
+}
+
+impl PensieveScript for RowLifecycleScript {
+    fn execute(&mut self, context: &mut ScriptContext, sink: &mut dyn ResultSink) -> Result<(), Box<dyn std::error::Error>> {
+        context.require_table(&self.table_name)?;
+        let manager = &mut *context.manager;
+        let columns = manager.table_columns(&self.table_name)?;
+        let key_columns = manager.primary_key_columns(&self.table_name, &columns)?;
+
+        let mut rows: Vec<(String, Lifecycle)> = Vec::new();
+        let mut row_index: HashMap<String, usize> = HashMap::new();
+        let mut touch = |key: String, idx: usize, operation_type: OperationType| {
+            let i = *row_index.entry(key.clone()).or_insert_with(|| {
+                rows.push((key, Lifecycle::default()));
+                rows.len() - 1
+            });
+            let lifecycle = &mut rows[i].1;
+            lifecycle.last_modified = idx;
+            match operation_type {
+                OperationType::Insert => {
+                    lifecycle.inserted.get_or_insert(idx);
+                    lifecycle.deleted = None;
+                }
+                OperationType::Update => lifecycle.deleted = None,
+                OperationType::Delete => lifecycle.deleted = Some(idx),
+            }
+        };
+
+        manager.for_each_operation(0, manager.operation_count(), |idx, op| {
+            if op.table_name != self.table_name {
+                return Ok(());
+            }
+            let before = row_key(op, op.before_values.as_ref(), &key_columns);
+            let after = row_key(op, op.after_values.as_ref(), &key_columns);
+            match (&op.operation_type, before, after) {
+                (OperationType::Update, Some(before), Some(after)) if before != after => {
+                    touch(before, idx, OperationType::Delete);
+                    touch(after, idx, OperationType::Insert);
+                }
+                (OperationType::Insert, _, Some(key)) | (_, Some(key), _) | (_, None, Some(key)) => {
+                    touch(key, idx, op.operation_type.clone());
+                }
+                _ => {}
+            }
+            Ok(())
+        })?;
+
+        let timestamp_of = |idx: usize| {
+            manager.get_operation(idx).and_then(|op| op.timestamp.as_ref())
+                .and_then(|ts| manager.get_timestamp_options().format(ts))
+                .unwrap_or_default()
+        };
+        let position_columns = |idx: Option<usize>| match idx {
+            Some(idx) => [idx.to_string(), timestamp_of(idx)],
+            None => [String::new(), String::new()],
+        };
+
+        info!("Found {} rows of {} in the binlog", rows.len(), self.table_name);
+        rows.into_iter()
+            .try_for_each(|(key, lifecycle)| {
+                let mut values = vec![key];
+                values.extend(position_columns(lifecycle.inserted));
+                values.extend(position_columns(Some(lifecycle.last_modified)));
+                values.extend(position_columns(lifecycle.deleted));
+                sink.emit(ScriptResult {
+                    table: None,
+                    columns: self.headers(),
+                    values,
+                })
+            })
+    }
+
+    fn headers(&self) -> Vec<String> {
+        [
+            "primary_key",
+            "inserted_position",
+            "inserted_at",
+            "last_modified_position",
+            "last_modified_at",
+            "deleted_position",
+            "deleted_at",
+        ].iter().map(|column| column.to_string()).collect()
+    }
+}
+
+/// Key of the row an image belongs to, e.g. `id=1`, None if the image lacks a key column
+fn row_key(op: &BinlogOperation, values: Option<&Vec<String>>, key_columns: &[String]) -> Option<String> {
+    let values = values?;
+    let parts = key_columns.iter()
+        .map(|key| {
+            let idx = op.columns.iter().position(|column| column == key)?;
+            values.get(idx).map(|value| format!("{}={}", key, value.trim_matches('\'')))
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some(parts.join(" "))
+}
+
+pub fn run_row_lifecycle(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let args = ScriptArgs::parse("row-lifecycle", USAGE, args)?;
+    let table_name = args.get_required("--table")?;
+    let output = args.get_or("--output", "row_lifecycle.csv");
+
+    info!("=== Row Lifecycle ===");
+    info!("Table: {}", table_name);
+
+    let mut script = RowLifecycleScript { table_name };
+    run_to_csv(&args, &mut script, &output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snapshot_manager::SnapshotManager;
+    use crate::test_support::{books_db, operation};
+
+    fn op(operation_type: OperationType, minute: u32, before: Option<&str>, after: Option<&str>) -> BinlogOperation {
+        let (before, after) = (before.map(|id| [id, "'x'"]), after.map(|id| [id, "'x'"]));
+        BinlogOperation {
+            timestamp: Some(format!("251108 10:{:02}:00", minute)),
+            ..operation(operation_type, "books", &["id", "title"], before.as_ref().map(|v| &v[..]), after.as_ref().map(|v| &v[..]))
+        }
+    }
+
+    #[test]
+    fn test_reports_insert_modify_and_delete_per_row() {
+        let conn = books_db("id INTEGER PRIMARY KEY, title VARCHAR", "(1, 'x')");
+        let operations = vec![
+            op(OperationType::Insert, 0, None, Some("2")),
+            op(OperationType::Update, 1, Some("1"), Some("1")),
+            op(OperationType::Update, 2, Some("2"), Some("2")),
+            op(OperationType::Delete, 3, Some("1"), None),
+            op(OperationType::Update, 4, Some("2"), Some("3")),
+        ];
+        let mut manager = SnapshotManager::new(conn, operations, 0);
+
+        let mut results = Vec::new();
+
+        RowLifecycleScript { table_name: "books".to_string() }.execute(&mut ScriptContext::new(&mut manager).unwrap(), &mut results).unwrap();
+
+        let rows: Vec<String> = results.iter().map(|result| result.values.join(",")).collect();
+        assert_eq!(rows, vec![
+            "id=2,0,2025-11-08 10:00:00,4,2025-11-08 10:04:00,4,2025-11-08 10:04:00",
+            "id=1,,,3,2025-11-08 10:03:00,3,2025-11-08 10:03:00",
+            "id=3,4,2025-11-08 10:04:00,4,2025-11-08 10:04:00,,",
+        ]);
+    }
+}
@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use crate::pensieve::Pensieve;
+use crate::script::ScriptContext;
+use crate::snapshot_manager::SnapshotManager;
+
+/// Options every script accepts, for loading the snapshot
+pub const COMMON_USAGE: &str = "[--data-dir <dir>] [--timestamp <YYMMDD HH:MM:SS>] [--window <hours>] [--output-dir <dir>]";
+
+/// Command-line flags of a script, `--flag value` pairs. A flag followed by another flag or by
+/// nothing is a switch. Unknown flags are kept, so scripts only ask for the ones they use.
+#[derive(Debug, Clone)]
+pub struct ScriptArgs {
+    name: String,
+    usage: String,
+    values: HashMap<String, Option<String>>,
+}
+
+impl ScriptArgs {
+    /// `usage` lists the script's own options; usage() adds the common ones
+    pub fn parse(name: &str, usage: &str, args: &[String]) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut values = HashMap::new();
+        let mut args = args.iter().peekable();
+        while let Some(arg) = args.next() {
+            if !arg.starts_with("--") {
+                return Err(format!("Unexpected argument {}\n{}", arg, Self::usage_of(name, usage)).into());
+            }
+            let value = args.next_if(|value| !value.starts_with("--")).cloned();
+            values.insert(arg.clone(), value);
+        }
+        Ok(Self {
+            name: name.to_string(),
+            usage: usage.to_string(),
+            values,
+        })
+    }
+
+    pub fn usage(&self) -> String {
+        Self::usage_of(&self.name, &self.usage)
+    }
+
+    fn usage_of(name: &str, usage: &str) -> String {
+        format!("Usage: script {} {} {}", name, usage, COMMON_USAGE)
+    }
+
+    /// Value of `flag`, or an error with the usage if it's missing
+    pub fn get_required(&self, flag: &str) -> Result<String, Box<dyn std::error::Error>> {
+        self.get_optional(flag)
+            .ok_or_else(|| format!("Missing {} <value>\n{}", flag, self.usage()).into())
+    }
+
+    pub fn get_optional(&self, flag: &str) -> Option<String> {
+        self.values.get(flag).cloned().flatten()
+    }
+
+    pub fn get_or(&self, flag: &str, default: &str) -> String {
+        self.get_optional(flag).unwrap_or_else(|| default.to_string())
+    }
+
+    /// Value of `flag` parsed as `T`, None if it's missing
+    pub fn get_parsed<T: FromStr>(&self, flag: &str) -> Result<Option<T>, Box<dyn std::error::Error>>
+    where
+        T::Err: std::fmt::Display,
+    {
+        self.get_optional(flag)
+            .map(|value| {
+                value.parse()
+                    .map_err(|e| format!("Invalid {} {}: {}\n{}", flag, value, e, self.usage()).into())
+            })
+            .transpose()
+    }
+
+    pub fn get_parsed_or<T: FromStr>(&self, flag: &str, default: T) -> Result<T, Box<dyn std::error::Error>>
+    where
+        T::Err: std::fmt::Display,
+    {
+        Ok(self.get_parsed(flag)?.unwrap_or(default))
+    }
+
+    /// Whether `flag` was given, with or without a value
+    pub fn has_flag(&self, flag: &str) -> bool {
+        self.values.contains_key(flag)
+    }
+
+    /// Loads the snapshot and binlog from `--data-dir` (default `db_data`), at `--timestamp`
+    /// with a normalisation window of `--window` hours (default 1)
+    pub fn load_pensieve(&self) -> Result<Pensieve, Box<dyn std::error::Error>> {
+        let data_dir = self.get_or("--data-dir", "db_data");
+        let snapshot_timestamp = self.get_or("--timestamp", "251111 01:45:00");
+        let window_hours = self.get_parsed_or("--window", 1)?;
+        Pensieve::from_data_dir(&data_dir, &snapshot_timestamp, window_hours)
+    }
+
+    /// Context for running a script on a freshly loaded manager, writing to `--output-dir`
+    /// (default the current directory)
+    pub fn script_context<'a>(&self, manager: &'a mut SnapshotManager) -> Result<ScriptContext<'a>, Box<dyn std::error::Error>> {
+        Ok(ScriptContext::new(manager)?.with_output_dir(self.get_or("--output-dir", ".")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_values_switches_and_types() {
+        let args: Vec<String> = ["--table", "books", "--dry-run", "--window", "3"]
+            .iter().map(|arg| arg.to_string()).collect();
+        let args = ScriptArgs::parse("example", "--table <name> [--dry-run]", &args).unwrap();
+
+        assert_eq!(args.get_required("--table").unwrap(), "books");
+        assert_eq!(args.get_optional("--output"), None);
+        assert_eq!(args.get_or("--output", "results.csv"), "results.csv");
+        assert!(args.has_flag("--dry-run") && args.get_optional("--dry-run").is_none());
+        assert_eq!(args.get_parsed_or::<i64>("--window", 1).unwrap(), 3);
+
+        let missing = args.get_required("--column").unwrap_err().to_string();
+        assert!(missing.contains("Missing --column") && missing.contains("Usage: script example --table <name>"));
+        assert!(args.get_parsed::<i64>("--table").is_err());
+        assert!(ScriptArgs::parse("example", "", &["books".to_string()]).is_err());
+    }
+}
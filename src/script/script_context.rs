@@ -0,0 +1,90 @@
+use std::path::PathBuf;
+use chrono::Duration;
+use crate::snapshot_manager::{SnapshotManager, TableOperationStats};
+
+/// What a script runs against: the snapshot manager, and what is known about the snapshot it was
+/// loaded at, so scripts don't have to hardcode table names or output locations
+pub struct ScriptContext<'a> {
+    pub manager: &'a mut SnapshotManager,
+    /// Tables in the snapshot database, in name order
+    pub table_names: Vec<String>,
+    /// Timestamp and position normalisation anchored the snapshot at
+    pub snapshot_timestamp: Option<String>,
+    pub snapshot_position: usize,
+    /// Directory relative output paths are resolved against
+    pub output_dir: PathBuf,
+}
+
+impl<'a> ScriptContext<'a> {
+    /// Context of a manager that is still at its snapshot position, as after loading
+    pub fn new(manager: &'a mut SnapshotManager) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self {
+            table_names: manager.list_tables()?,
+            snapshot_timestamp: manager.get_timestamp().cloned(),
+            snapshot_position: manager.get_position(),
+            output_dir: PathBuf::from("."),
+            manager,
+        })
+    }
+
+    pub fn with_output_dir(mut self, output_dir: impl Into<PathBuf>) -> Self {
+        self.output_dir = output_dir.into();
+        self
+    }
+
+    pub fn operation_count(&self) -> usize {
+        self.manager.operation_count()
+    }
+
+    /// Per-table operation stats of the whole binlog. See SnapshotManager::operation_stats.
+    pub fn operation_stats(&self, bucket_size: Duration) -> Result<Vec<TableOperationStats>, Box<dyn std::error::Error>> {
+        self.manager.operation_stats(bucket_size)
+    }
+
+    /// `file_name` in the output directory, creating the directory. Absolute paths are kept.
+    pub fn output_path(&self, file_name: &str) -> Result<String, Box<dyn std::error::Error>> {
+        let path = self.output_dir.join(file_name);
+        if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        Ok(path.to_string_lossy().to_string())
+    }
+
+    /// Whether `table` is in the snapshot database
+    pub fn has_table(&self, table: &str) -> bool {
+        self.table_names.iter().any(|name| name == table)
+    }
+
+    /// Errors naming the snapshot's tables if `table` isn't one of them
+    pub fn require_table(&self, table: &str) -> Result<(), Box<dyn std::error::Error>> {
+        if self.has_table(table) {
+            return Ok(());
+        }
+        Err(format!("Unknown table {}; the snapshot has {}", table, self.table_names.join(", ")).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use duckdb::Connection;
+
+    #[test]
+    fn test_context_describes_the_snapshot() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE books (id INTEGER PRIMARY KEY); CREATE TABLE authors (id INTEGER PRIMARY KEY)").unwrap();
+        let mut manager = SnapshotManager::new(conn, Vec::new(), 0);
+        let output_dir = std::env::temp_dir().join(format!("script_context_{}", std::process::id()));
+
+        let context = ScriptContext::new(&mut manager).unwrap().with_output_dir(&output_dir);
+
+        assert_eq!(context.table_names, vec!["authors", "books"]);
+        assert_eq!((context.snapshot_position, context.operation_count()), (0, 0));
+        assert!(context.require_table("books").is_ok());
+        assert!(context.require_table("users").unwrap_err().to_string().contains("authors, books"));
+        let path = context.output_path("results.csv").unwrap();
+        assert!(output_dir.exists() && path.ends_with("results.csv"));
+
+        std::fs::remove_dir_all(output_dir).ok();
+    }
+}
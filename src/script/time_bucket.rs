@@ -0,0 +1,174 @@
+use chrono::{DateTime, Duration};
+use crate::binlog::{quote_identifier, BinlogTimestamp};
+use crate::script::{run_to_csv, PensieveScript, ResultSink, ScriptArgs, ScriptContext, ScriptResult};
+use tracing::info;
+
+/// Evaluates an aggregate over a table at the end of every time bucket, e.g. `SUM(price)` every
+/// 15 minutes, to reconstruct how a metric evolved over the span of the binlog.
+///
+/// Buckets are aligned to multiples of the bucket size since the Unix epoch. Buckets without any
+/// operation get the value of the previous bucket, since the table didn't change.
+pub struct TimeBucketScript {
+    pub table_name: String,
+    /// Aggregate SQL expression, e.g. `COUNT(*)` or `AVG(price)`
+    pub expression: String,
+    pub bucket_size: Duration,
+}
+
+impl PensieveScript for TimeBucketScript {
+    fn execute(&mut self, context: &mut ScriptContext, sink: &mut dyn ResultSink) -> Result<(), Box<dyn std::error::Error>> {
+        context.require_table(&self.table_name)?;
+        let manager = &mut *context.manager;
+        let bucket_seconds = self.bucket_size.num_seconds();
+        if bucket_seconds <= 0 {
+            return Err("Bucket size must be at least one second".into());
+        }
+
+        // Last operation index of every bucket that has operations, in order
+        let mut bucket_ends: Vec<(i64, usize)> = Vec::new();
+        for idx in 0..manager.operation_count() {
+            let Some(ts) = manager.get_operation(idx)
+                .and_then(|op| op.timestamp.as_ref())
+                .and_then(|ts| BinlogTimestamp::parse(ts).ok()) else {
+                continue;
+            };
+            let bucket = ts.as_datetime().and_utc().timestamp().div_euclid(bucket_seconds);
+            match bucket_ends.last_mut() {
+                Some((last_bucket, last_idx)) if *last_bucket == bucket => *last_idx = idx,
+                _ => bucket_ends.push((bucket, idx)),
+            }
+        }
+
+        let query = format!("SELECT CAST({} AS VARCHAR) FROM {}", self.expression, quote_identifier(&self.table_name));
+        info!("Evaluating {} over {} bucket(s) with operations", self.expression, bucket_ends.len());
+
+        let mut result_count = 0;
+        let mut previous: Option<(i64, String)> = None;
+        for (bucket, last_idx) in bucket_ends {
+            // Fill buckets without operations with the last value
+            if let Some((previous_bucket, value)) = &previous {
+                for empty_bucket in previous_bucket + 1..bucket {
+                    sink.emit(self.result(empty_bucket * bucket_seconds, value))?;
+                    result_count += 1;
+                }
+            }
+
+            manager.goto_position(last_idx)?;
+            let value: Option<String> = manager.get_connection().query_row(&query, [], |row| row.get(0))?;
+            let value = value.unwrap_or_else(|| "NULL".to_string());
+            sink.emit(self.result(bucket * bucket_seconds, &value))?;
+            result_count += 1;
+            previous = Some((bucket, value));
+        }
+
+        info!("Analysis complete! Found {} results", result_count);
+        Ok(())
+    }
+
+    fn headers(&self) -> Vec<String> {
+        vec![
+            "bucket_start".to_string(),
+            "value".to_string(),
+        ]
+    }
+}
+
+impl TimeBucketScript {
+    fn result(&self, bucket_start_seconds: i64, value: &str) -> ScriptResult {
+        let bucket_start = DateTime::from_timestamp(bucket_start_seconds, 0)
+            .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_default();
+        ScriptResult {
+            table: None,
+            columns: self.headers(),
+            values: vec![bucket_start, value.to_string()],
+        }
+    }
+}
+
+/// Parses a bucket size like `30s`, `15m`, `2h` or `1d`. A bare number is a number of minutes.
+pub fn parse_bucket_size(size: &str) -> Result<Duration, Box<dyn std::error::Error>> {
+    let size = size.trim();
+    let (number, unit) = match size.find(|c: char| !c.is_ascii_digit()) {
+        Some(idx) => size.split_at(idx),
+        None => (size, "m"),
+    };
+    let number: i64 = number.parse().map_err(|_| format!("Invalid bucket size: {}", size))?;
+    match unit {
+        "s" => Ok(Duration::seconds(number)),
+        "m" => Ok(Duration::minutes(number)),
+        "h" => Ok(Duration::hours(number)),
+        "d" => Ok(Duration::days(number)),
+        _ => Err(format!("Invalid bucket size unit: {}", unit).into()),
+    }
+}
+
+pub const USAGE: &str = "--table <name> --expression <sql> --bucket <15m|1h|...> --output <file.csv>";
+
+pub fn run_time_bucket(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let args = ScriptArgs::parse("time-bucket", USAGE, args)?;
+    let table_name = args.get_or("--table", "books");
+    let expression = args.get_or("--expression", "COUNT(*)");
+    let bucket = args.get_or("--bucket", "15m");
+    let output = args.get_or("--output", "results.csv");
+
+    info!("=== Time Bucket Aggregation ===");
+    info!("Table: {}", table_name);
+    info!("Expression: {}", expression);
+    info!("Bucket: {}", bucket);
+
+    let bucket_size = parse_bucket_size(&bucket)?;
+
+    let mut script = TimeBucketScript {
+        table_name,
+        expression,
+        bucket_size,
+    };
+    run_to_csv(&args, &mut script, &output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snapshot_manager::SnapshotManager;
+    use crate::test_support::{books_db, insert_id};
+
+    #[test]
+    fn test_emits_one_row_per_bucket() {
+        let conn = books_db("id INTEGER", "(1)");
+        let operations = vec![
+            insert_id("251108 10:05:00", 1),
+            insert_id("251108 10:10:00", 2),
+            insert_id("251108 10:20:00", 3),
+            // Nothing between 10:30 and 10:45
+            insert_id("251108 10:50:00", 4),
+        ];
+        let mut manager = SnapshotManager::new(conn, operations, 0);
+        let mut script = TimeBucketScript {
+            table_name: "books".to_string(),
+            expression: "COUNT(*)".to_string(),
+            bucket_size: parse_bucket_size("15m").unwrap(),
+        };
+
+        let mut results = Vec::new();
+
+        script.execute(&mut ScriptContext::new(&mut manager).unwrap(), &mut results).unwrap();
+
+        let rows: Vec<Vec<String>> = results.into_iter().map(|r| r.values).collect();
+
+        assert_eq!(rows, vec![
+            vec!["2025-11-08 10:00:00".to_string(), "2".to_string()],
+            vec!["2025-11-08 10:15:00".to_string(), "3".to_string()],
+            vec!["2025-11-08 10:30:00".to_string(), "3".to_string()],
+            vec!["2025-11-08 10:45:00".to_string(), "4".to_string()],
+        ]);
+    }
+
+    #[test]
+    fn test_parse_bucket_size() {
+        assert_eq!(parse_bucket_size("90s").unwrap(), Duration::seconds(90));
+        assert_eq!(parse_bucket_size("15").unwrap(), Duration::minutes(15));
+        assert_eq!(parse_bucket_size("2h").unwrap(), Duration::hours(2));
+        assert!(parse_bucket_size("5w").is_err());
+    }
+}
\ No newline at end of file
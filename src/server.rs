@@ -0,0 +1,263 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+use duckdb::types::Value;
+use serde_json::{json, Value as JsonValue};
+use tracing::{info, warn};
+use crate::snapshot_manager::SnapshotManager;
+use crate::snapshot_manager::snapshot_diff::json_value;
+
+/// Largest request body accepted, to bound what a client can make the server buffer
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+
+/// How long a read or write on a connection may block, so that a client that stops sending or
+/// reading can't hold up the requests queued behind it
+const IO_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Serves the snapshot over a small HTTP JSON API, so notebooks and BI tools can navigate and
+/// query it remotely while Pensieve holds the state. Requests are handled one at a time, in the
+/// order they arrive, since they share one position:
+///
+/// - `GET /position`: `{"position", "timestamp", "operation_count"}`
+/// - `POST /goto` with `{"position": n}` or `{"timestamp": "YYMMDD HH:MM:SS"}`: the new position
+/// - `POST /step` with `{"count": n}`, negative to step backwards: the new position
+/// - `POST /query` with `{"sql": "..."}`: `{"position", "columns", "rows"}`, values as in ChangeSet
+///
+/// Errors are `{"error": "..."}` with a 4xx status. Queries run on the snapshot's connection, so
+/// statements that write change the snapshot; there is no authentication, so binding to an
+/// address other than a loopback one is refused unless `allow_remote` is set.
+pub fn serve(manager: &mut SnapshotManager, address: &str, allow_remote: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let listener = TcpListener::bind(address)?;
+    let local_address = listener.local_addr()?;
+    if !allow_remote && !local_address.ip().is_loopback() {
+        return Err(format!(
+            "Refusing to serve unauthenticated queries on {}, which isn't a loopback address; allow remote clients explicitly to do so",
+            local_address
+        ).into());
+    }
+    info!("Serving the snapshot on http://{}", local_address);
+    for stream in listener.incoming() {
+        if let Err(e) = stream.map_err(|e| e.into()).and_then(|stream| serve_connection(manager, stream)) {
+            warn!("Request failed: {}", e);
+        }
+    }
+    Ok(())
+}
+
+/// Reads one request from the stream and writes its response
+pub fn serve_connection(manager: &mut SnapshotManager, stream: TcpStream) -> Result<(), Box<dyn std::error::Error>> {
+    serve_connection_with_timeout(manager, stream, IO_TIMEOUT)
+}
+
+fn serve_connection_with_timeout(manager: &mut SnapshotManager, stream: TcpStream, timeout: Duration) -> Result<(), Box<dyn std::error::Error>> {
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let (method, path) = (parts.next().unwrap_or_default().to_string(), parts.next().unwrap_or_default().to_string());
+
+    let mut content_length = Ok(0);
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':')
+            && name.eq_ignore_ascii_case("content-length") {
+            content_length = value.trim().parse::<usize>().map_err(|_| value.trim().to_string());
+        }
+    }
+    let (status, body) = match content_length {
+        Err(value) => (400, json!({ "error": format!("Invalid Content-Length {:?}", value) })),
+        Ok(content_length) if content_length > MAX_BODY_BYTES => {
+            (413, json!({ "error": format!("Request bodies are limited to {} bytes", MAX_BODY_BYTES) }))
+        }
+        Ok(content_length) => {
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body)?;
+            handle_request(manager, &method, &path, &String::from_utf8_lossy(&body))
+        }
+    };
+
+    let body = body.to_string();
+    let reason = match status {
+        200 => "OK",
+        404 => "Not Found",
+        413 => "Payload Too Large",
+        _ => "Bad Request",
+    };
+    let mut stream = stream;
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status, reason, body.len(), body
+    )?;
+    stream.flush()?;
+    Ok(())
+}
+
+/// Status and JSON response of one request
+pub fn handle_request(manager: &mut SnapshotManager, method: &str, path: &str, body: &str) -> (u16, JsonValue) {
+    let request: JsonValue = if body.trim().is_empty() {
+        JsonValue::Null
+    } else {
+        match serde_json::from_str(body) {
+            Ok(request) => request,
+            Err(e) => return (400, json!({ "error": format!("Invalid JSON: {}", e) })),
+        }
+    };
+    let result = match (method, path) {
+        ("GET", "/position") => Ok(position(manager)),
+        ("POST", "/goto") => goto(manager, &request),
+        ("POST", "/step") => step(manager, &request),
+        ("POST", "/query") => query(manager, &request),
+        _ => return (404, json!({ "error": format!("No endpoint {} {}", method, path) })),
+    };
+    match result {
+        Ok(response) => (200, response),
+        Err(e) => (400, json!({ "error": e.to_string() })),
+    }
+}
+
+fn position(manager: &SnapshotManager) -> JsonValue {
+    json!({
+        "position": manager.get_position(),
+        "timestamp": manager.get_timestamp(),
+        "operation_count": manager.operation_count(),
+    })
+}
+
+fn goto(manager: &mut SnapshotManager, request: &JsonValue) -> Result<JsonValue, Box<dyn std::error::Error>> {
+    match (request["position"].as_u64(), request["timestamp"].as_str()) {
+        (Some(target), None) => manager.goto_position(target as usize)?,
+        (None, Some(timestamp)) => manager.goto_timestamp(timestamp)?,
+        _ => return Err("Expected {\"position\": n} or {\"timestamp\": \"YYMMDD HH:MM:SS\"}".into()),
+    }
+    Ok(position(manager))
+}
+
+fn step(manager: &mut SnapshotManager, request: &JsonValue) -> Result<JsonValue, Box<dyn std::error::Error>> {
+    let count = match &request["count"] {
+        JsonValue::Null => 1,
+        count => count.as_i64().ok_or("Expected {\"count\": n}")?,
+    };
+    if count < 0 {
+        manager.step_backward_by(count.unsigned_abs() as usize)?;
+    } else {
+        manager.step_forward_by(count as usize)?;
+    }
+    Ok(position(manager))
+}
+
+fn query(manager: &mut SnapshotManager, request: &JsonValue) -> Result<JsonValue, Box<dyn std::error::Error>> {
+    let sql = request["sql"].as_str().ok_or("Expected {\"sql\": \"...\"}")?;
+    let mut stmt = manager.get_connection().prepare(sql)?;
+    let mut rows = stmt.query([])?;
+    let columns = rows.as_ref().map(|stmt| stmt.column_names()).unwrap_or_default();
+    let mut values = Vec::new();
+    while let Some(row) = rows.next()? {
+        let row: Vec<JsonValue> = (0..columns.len())
+            .map(|idx| row.get::<usize, Value>(idx).map(|value| json_value(&value)))
+            .collect::<Result<_, _>>()?;
+        values.push(row);
+    }
+    Ok(json!({ "position": manager.get_position(), "columns": columns, "rows": values }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{books_db, insert_id};
+
+    #[test]
+    fn test_navigate_and_query_over_http() {
+        let conn = books_db("id INTEGER PRIMARY KEY", "(1)");
+        let operations = vec![insert_id("251108 10:00:00", 1), insert_id("251108 11:00:00", 2), insert_id("251108 12:00:00", 3)];
+        let mut manager = SnapshotManager::new(conn, operations, 0);
+
+        assert_eq!(handle_request(&mut manager, "POST", "/step", r#"{"count": 2}"#).1["position"], 2);
+        assert_eq!(handle_request(&mut manager, "POST", "/goto", r#"{"timestamp": "251108 11:30:00"}"#).1["position"], 1);
+        assert_eq!(handle_request(&mut manager, "POST", "/goto", "{}").0, 400);
+        assert_eq!(handle_request(&mut manager, "DELETE", "/position", "").0, 404);
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let client = std::thread::spawn(move || {
+            let mut stream = TcpStream::connect(address).unwrap();
+            let body = r#"{"sql": "SELECT id, 'x' AS label FROM books ORDER BY id"}"#;
+            write!(stream, "POST /query HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        });
+        serve_connection(&mut manager, listener.accept().unwrap().0).unwrap();
+
+        let response = client.join().unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        let body: JsonValue = serde_json::from_str(response.split("\r\n\r\n").nth(1).unwrap()).unwrap();
+        assert_eq!(body, json!({ "position": 1, "columns": ["id", "label"], "rows": [[1, "x"], [2, "x"]] }));
+    }
+
+    /// Sends `request` to a connection served by `manager` and returns the response
+    fn exchange(manager: &mut SnapshotManager, request: String) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let client = std::thread::spawn(move || {
+            let mut stream = TcpStream::connect(address).unwrap();
+            stream.write_all(request.as_bytes()).unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        });
+        serve_connection(manager, listener.accept().unwrap().0).unwrap();
+        client.join().unwrap()
+    }
+
+    #[test]
+    fn test_replies_to_bad_content_lengths() {
+        let conn = books_db("id INTEGER PRIMARY KEY", "");
+        let mut manager = SnapshotManager::new(conn, vec![insert_id("251108 10:00:00", 1)], 0);
+
+        let response = exchange(&mut manager, "POST /step HTTP/1.1\r\nContent-Length: twelve\r\n\r\n".to_string());
+        assert!(response.starts_with("HTTP/1.1 400 Bad Request\r\n"), "{}", response);
+        assert!(response.contains("Invalid Content-Length"));
+
+        let response = exchange(&mut manager, "POST /step HTTP/1.1\r\nContent-Length: 99999999999999999999999\r\n\r\n".to_string());
+        assert!(response.starts_with("HTTP/1.1 400 Bad Request\r\n"), "{}", response);
+
+        let response = exchange(&mut manager, format!("POST /step HTTP/1.1\r\nContent-Length: {}\r\n\r\n", MAX_BODY_BYTES + 1));
+        assert!(response.starts_with("HTTP/1.1 413 Payload Too Large\r\n"), "{}", response);
+        assert_eq!(manager.get_position(), 0);
+    }
+
+    #[test]
+    fn test_silent_client_times_out() {
+        let conn = books_db("id INTEGER PRIMARY KEY", "");
+        let mut manager = SnapshotManager::new(conn, vec![insert_id("251108 10:00:00", 1)], 0);
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        // Connects and sends nothing, holding the connection open for longer than the timeout
+        let client = std::thread::spawn(move || {
+            let stream = TcpStream::connect(address).unwrap();
+            std::thread::sleep(Duration::from_secs(5));
+            drop(stream);
+        });
+        let started = std::time::Instant::now();
+        let result = serve_connection_with_timeout(&mut manager, listener.accept().unwrap().0, Duration::from_millis(200));
+        assert!(result.is_err());
+        assert!(started.elapsed() < Duration::from_secs(4));
+        client.join().unwrap();
+    }
+
+    #[test]
+    fn test_refuses_non_loopback_address_unless_allowed() {
+        let conn = books_db("id INTEGER PRIMARY KEY", "");
+        let mut manager = SnapshotManager::new(conn, vec![insert_id("251108 10:00:00", 1)], 0);
+
+        let error = serve(&mut manager, "0.0.0.0:0", false).unwrap_err();
+        assert!(error.to_string().contains("isn't a loopback address"));
+    }
+}
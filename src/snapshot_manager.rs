@@ -1,2 +1,42 @@
+#[allow(clippy::module_inception)]
 pub mod snapshot_manager;
-pub use snapshot_manager::SnapshotManager;
+pub mod binlog_gaps;
+pub mod binlog_locations;
+pub mod bookmarks;
+pub mod chunked_navigation;
+pub mod invariants;
+pub mod operation_search;
+pub mod operation_stats;
+pub mod range_replay;
+pub mod row_lookup;
+pub mod row_provenance;
+pub mod snapshot_export;
+pub mod snapshot_reader;
+pub mod snapshot_rebuild;
+pub mod snapshot_diff;
+pub mod snapshot_stats;
+pub mod subscriptions;
+pub mod time_steps;
+pub mod typed_query;
+pub mod verification;
+pub mod watch;
+mod operation_index;
+pub(crate) mod operation_store;
+mod throttle;
+mod undo_history;
+pub use bookmarks::Bookmark;
+pub use chunked_navigation::NavigationProgress;
+pub use invariants::{Invariant, InvariantViolation};
+pub use operation_search::OperationFilter;
+pub use operation_stats::TableOperationStats;
+pub use row_lookup::RowValues;
+pub use row_provenance::RowProvenance;
+pub use snapshot_manager::{CursorMode, SnapshotManager};
+pub use snapshot_diff::{ChangeSet, TableDiff};
+pub use snapshot_export::{ExportedTable, SnapshotExport};
+pub use snapshot_reader::{RowsAtPosition, SnapshotReader};
+pub use snapshot_stats::SnapshotStats;
+pub use subscriptions::{RowChange, RowChangeCallback};
+pub use time_steps::BoundaryStep;
+pub use verification::{TableVerification, VerificationReport};
+pub use watch::{Watch, WatchSample};
@@ -0,0 +1,120 @@
+use crate::binlog::{BinlogGap, BinlogGapKind, BinlogTimestamp, SpansBinlogGapError};
+use crate::snapshot_manager::SnapshotManager;
+
+impl SnapshotManager {
+    /// Breaks in the continuity of the binlog files (see set_binlog_files), in order: files
+    /// missing between two consecutive files, files that are numbered or timestamped before the
+    /// file they follow, and files that end without a Rotate event to the next
+    pub fn binlog_gaps(&self) -> Vec<BinlogGap> {
+        self.binlog_files.windows(2)
+            .filter_map(|pair| {
+                let position = pair[1].first_operation;
+                let from = (0..position).rev().find_map(|idx| self.get_operation(idx)?.timestamp.clone());
+                let to = (position..self.operation_count()).find_map(|idx| self.get_operation(idx)?.timestamp.clone());
+                let goes_back = match (&from, &to) {
+                    (Some(from), Some(to)) => BinlogTimestamp::parse(to).ok() < BinlogTimestamp::parse(from).ok(),
+                    _ => false,
+                };
+                let kind = BinlogGap::between_files(&pair[0], &pair[1])
+                    .or(goes_back.then_some(BinlogGapKind::OutOfOrder))
+                    .or(pair[0].is_truncated().then_some(BinlogGapKind::Truncated))?;
+                Some(BinlogGap {
+                    position,
+                    previous_file: pair[0].file.clone(),
+                    next_file: pair[1].file.clone(),
+                    kind,
+                    from,
+                    to,
+                })
+            })
+            .collect()
+    }
+
+    /// Makes diffs and other analyses between two positions fail with a SpansBinlogGapError when
+    /// a binlog gap lies between them, instead of silently missing the gap's operations
+    pub fn set_refuse_binlog_gaps(&mut self, refuse_binlog_gaps: bool) {
+        self.refuse_binlog_gaps = refuse_binlog_gaps;
+    }
+
+    /// Fails with a SpansBinlogGapError if binlog gaps are refused and one lies between the two positions
+    pub fn check_continuity(&self, position_a: usize, position_b: usize) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.refuse_binlog_gaps {
+            return Ok(());
+        }
+        match self.binlog_gaps().into_iter().find(|gap| gap.spans(position_a, position_b)) {
+            Some(gap) => Err(SpansBinlogGapError { gap }.into()),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::binlog::BinlogFileMarker;
+    use super::*;
+    use crate::test_support::{books_db, insert_id};
+
+    fn marker(file: &str, first_operation: usize) -> BinlogFileMarker {
+        BinlogFileMarker { file: file.to_string(), first_operation, server_version: None, rotates_to: None }
+    }
+
+    #[test]
+    fn test_missing_and_out_of_order_files_are_gaps() {
+        let conn = books_db("id INTEGER PRIMARY KEY", "(1)");
+        let operations = vec![
+            insert_id("251108 10:00:00", 1),
+            insert_id("251108 11:00:00", 2),
+            insert_id("251108 14:00:00", 3),
+            insert_id("251108 15:00:00", 4),
+            insert_id("251108 12:00:00", 5),
+        ];
+        let mut manager = SnapshotManager::new(conn, operations, 0);
+        manager.set_binlog_files(vec![
+            marker("mysql-bin.000001", 0),
+            marker("mysql-bin.000002", 1),
+            marker("mysql-bin.000005", 2),
+            marker("mysql-bin.000006", 4),
+        ]);
+
+        let gaps = manager.binlog_gaps();
+        assert_eq!(gaps.len(), 2);
+        assert_eq!(gaps[0].kind, BinlogGapKind::MissingFiles(vec!["mysql-bin.000003".to_string(), "mysql-bin.000004".to_string()]));
+        assert_eq!(gaps[0].to_string(), "mysql-bin.000003 to mysql-bin.000004 are missing before position 2 (251108 11:00:00 to 251108 14:00:00)");
+        assert_eq!((gaps[1].position, &gaps[1].kind), (4, &BinlogGapKind::OutOfOrder));
+
+        assert!(manager.check_continuity(0, 3).is_ok());
+        manager.set_refuse_binlog_gaps(true);
+        assert!(manager.check_continuity(0, 1).is_ok());
+        assert!(manager.check_continuity(2, 3).is_ok());
+        assert_eq!(manager.check_continuity(3, 1).unwrap_err().downcast_ref::<SpansBinlogGapError>().unwrap().gap.position, 2);
+        assert!(manager.diff(0, 3, "books").is_err());
+        assert!(manager.diff(0, 1, "books").is_ok());
+    }
+
+    #[test]
+    fn test_file_ending_without_rotate_is_truncated() {
+        let conn = books_db("id INTEGER PRIMARY KEY", "");
+        let operations = vec![
+            insert_id("251108 10:00:00", 1),
+            insert_id("251108 11:00:00", 2),
+            insert_id("251108 12:00:00", 3),
+        ];
+        let dumped = |file: &str, first_operation: usize, rotates_to: Option<&str>| BinlogFileMarker {
+            server_version: Some("8.0.36".to_string()),
+            rotates_to: rotates_to.map(str::to_string),
+            ..marker(file, first_operation)
+        };
+        let mut manager = SnapshotManager::new(conn, operations, 0);
+        manager.set_binlog_files(vec![
+            dumped("mysql-bin.000001", 0, Some("mysql-bin.000002")),
+            dumped("mysql-bin.000002", 1, None),
+            // The last file is still being written, so it doesn't end with a Rotate yet
+            dumped("mysql-bin.000003", 2, None),
+        ]);
+
+        let gaps = manager.binlog_gaps();
+        assert_eq!(gaps.len(), 1);
+        assert_eq!((gaps[0].position, &gaps[0].kind), (2, &BinlogGapKind::Truncated));
+        assert_eq!(gaps[0].to_string(), "mysql-bin.000002 ends without rotating to mysql-bin.000003 before position 2 (251108 11:00:00 to 251108 12:00:00)");
+    }
+}
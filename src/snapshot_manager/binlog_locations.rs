@@ -0,0 +1,94 @@
+use crate::binlog::{BinlogFileMarker, BinlogLocation};
+use crate::snapshot_manager::SnapshotManager;
+
+impl SnapshotManager {
+    /// Records which binlog file each operation came from (see TextBinlogParser::file_markers),
+    /// so positions can be translated to binlog files and offsets. Markers are in order.
+    pub fn set_binlog_files(&mut self, binlog_files: Vec<BinlogFileMarker>) {
+        self.binlog_files = binlog_files;
+    }
+
+    /// Binlog files the operations came from, in order
+    pub fn binlog_files(&self) -> &[BinlogFileMarker] {
+        &self.binlog_files
+    }
+
+    /// Binlog file and end_log_pos of the operation at `position`, to use with mysqlbinlog and
+    /// other tools. None without binlog files, or if the operation has no binlog position.
+    pub fn binlog_location(&self, position: usize) -> Option<BinlogLocation> {
+        let offset = self.get_operation(position)?.position?;
+        let marker = self.binlog_file_of(position)?;
+        Some(BinlogLocation { file: marker.file.clone(), offset })
+    }
+
+    /// Position of the last operation of `file` ending at or before `offset`, the inverse of
+    /// binlog_location
+    pub fn position_at_binlog_location(&self, file: &str, offset: u32) -> Option<usize> {
+        let idx = self.binlog_files.iter().position(|marker| marker.file == file)?;
+        let start = self.binlog_files[idx].first_operation;
+        let end = self.binlog_files.get(idx + 1).map_or(self.operation_count(), |next| next.first_operation);
+        (start..end).rev().find(|&position| {
+            self.get_operation(position).and_then(|op| op.position).is_some_and(|op_offset| op_offset <= offset)
+        })
+    }
+
+    fn binlog_file_of(&self, position: usize) -> Option<&BinlogFileMarker> {
+        let idx = self.binlog_files.partition_point(|marker| marker.first_operation <= position);
+        self.binlog_files.get(idx.checked_sub(1)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use duckdb::Connection;
+    use crate::binlog::{BinlogFileMarker, BinlogLocation};
+    use crate::parser::text_binlog_parser::TextBinlogParser;
+    use crate::snapshot_manager::SnapshotManager;
+
+    #[test]
+    fn test_positions_translate_to_binlog_files_and_offsets() {
+        let path = std::env::temp_dir().join(format!("binlog_locations_{}.sql", std::process::id()));
+        std::fs::write(&path, r#"
+#251108 10:00:00 server id 1  end_log_pos 126 CRC32 0x1 	Start: binlog v 4, server v 8.0.36 created 251108 10:00:00
+BEGIN
+#251108 10:00:01 server id 1  end_log_pos 300 CRC32 0x1 	Write_rows: table id 1 flags: STMT_END_F
+### INSERT INTO `main`.`books`
+### SET
+###   @1=1
+COMMIT
+#251108 10:00:02 server id 1  end_log_pos 400 CRC32 0x1 	Rotate to mysql-bin.000008  pos: 4
+#251108 10:00:02 server id 1  end_log_pos 126 CRC32 0x1 	Start: binlog v 4, server v 8.0.36 created 251108 10:00:02
+BEGIN
+#251108 10:00:03 server id 1  end_log_pos 250 CRC32 0x1 	Write_rows: table id 1 flags: STMT_END_F
+### INSERT INTO `main`.`books`
+### SET
+###   @1=2
+COMMIT
+BEGIN
+#251108 10:00:04 server id 1  end_log_pos 350 CRC32 0x1 	Write_rows: table id 1 flags: STMT_END_F
+### INSERT INTO `main`.`books`
+### SET
+###   @1=3
+COMMIT
+"#).unwrap();
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE books (id INTEGER PRIMARY KEY);").unwrap();
+        let mut parser = TextBinlogParser::new(conn);
+        let operations = parser.parse_file(&path.to_string_lossy()).unwrap();
+        let markers = parser.file_markers().to_vec();
+        let file = path.file_stem().unwrap().to_string_lossy().to_string();
+        assert_eq!(markers, vec![
+            BinlogFileMarker { file: file.clone(), first_operation: 0, server_version: Some("8.0.36".to_string()), rotates_to: Some("mysql-bin.000008".to_string()) },
+            BinlogFileMarker { file: "mysql-bin.000008".to_string(), first_operation: 1, server_version: Some("8.0.36".to_string()), rotates_to: None },
+        ]);
+
+        let mut manager = SnapshotManager::new(parser.into_connection(), operations, 0);
+        manager.set_binlog_files(markers);
+        assert_eq!(manager.binlog_location(0), Some(BinlogLocation { file, offset: 300 }));
+        assert_eq!(manager.binlog_location(2).unwrap().to_string(), "mysql-bin.000008:350");
+        assert_eq!(manager.position_at_binlog_location("mysql-bin.000008", 300), Some(1));
+        assert_eq!(manager.position_at_binlog_location("mysql-bin.000008", 100), None);
+
+        std::fs::remove_file(path).ok();
+    }
+}
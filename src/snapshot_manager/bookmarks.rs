@@ -0,0 +1,136 @@
+use std::fs;
+use tracing::warn;
+use crate::snapshot_manager::SnapshotManager;
+
+/// A named position in the operation stream
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bookmark {
+    pub position: usize,
+    pub timestamp: Option<String>,
+}
+
+impl SnapshotManager {
+    /// Bookmarks the current position under `name`, replacing any bookmark with that name
+    pub fn bookmark(&mut self, name: &str) {
+        let bookmark = Bookmark {
+            position: self.get_position(),
+            timestamp: self.get_timestamp().cloned(),
+        };
+        self.bookmarks.insert(name.to_string(), bookmark);
+    }
+
+    /// Moves the snapshot to a bookmarked position
+    pub fn goto_bookmark(&mut self, name: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let position = self.bookmarks.get(name)
+            .map(|bookmark| bookmark.position)
+            .ok_or_else(|| format!("No bookmark named {}", name))?;
+        self.goto_position(position)
+    }
+
+    /// Removes a bookmark. Returns false if there was no such bookmark.
+    pub fn remove_bookmark(&mut self, name: &str) -> bool {
+        self.bookmarks.remove(name).is_some()
+    }
+
+    /// Bookmarks in name order
+    pub fn get_bookmarks(&self) -> Vec<(&str, &Bookmark)> {
+        self.bookmarks.iter().map(|(name, bookmark)| (name.as_str(), bookmark)).collect()
+    }
+
+    /// Writes the bookmarks to a file, one `name<TAB>position<TAB>timestamp` line each, so an
+    /// investigation can be resumed in a later session
+    pub fn save_bookmarks(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let lines: Vec<String> = self.bookmarks.iter()
+            .map(|(name, bookmark)| format!(
+                "{}\t{}\t{}",
+                name,
+                bookmark.position,
+                bookmark.timestamp.as_deref().unwrap_or("")
+            ))
+            .collect();
+        fs::write(path, lines.join("\n"))?;
+        Ok(())
+    }
+
+    /// Loads bookmarks written by save_bookmarks, replacing bookmarks with the same names.
+    /// A bookmark whose position no longer has the saved timestamp (the binlog changed since it
+    /// was saved) is skipped with a warning. Returns the number of bookmarks loaded.
+    pub fn load_bookmarks(&mut self, path: &str) -> Result<usize, Box<dyn std::error::Error>> {
+        let content = fs::read_to_string(path)?;
+        let mut loaded = 0;
+
+        for line in content.lines().filter(|line| !line.is_empty()) {
+            let mut fields = line.splitn(3, '\t');
+            let (Some(name), Some(position), timestamp) = (fields.next(), fields.next(), fields.next()) else {
+                return Err(format!("Invalid bookmark line: {}", line).into());
+            };
+            let position: usize = position.parse().map_err(|_| format!("Invalid bookmark position: {}", line))?;
+            let timestamp = timestamp.filter(|ts| !ts.is_empty()).map(|ts| ts.to_string());
+
+            let current_timestamp = self.get_operation(position).map(|op| op.timestamp.clone());
+            if current_timestamp != Some(timestamp.clone()) {
+                warn!("Skipping bookmark {}, position {} no longer matches {:?}", name, position, timestamp);
+                continue;
+            }
+
+            self.bookmarks.insert(name.to_string(), Bookmark { position, timestamp });
+            loaded += 1;
+        }
+
+        Ok(loaded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{books_db, insert_id};
+
+    fn create_test_manager() -> SnapshotManager {
+        let conn = books_db("id INTEGER", "(1)");
+        let operations = vec![
+            insert_id("251108 10:00:00", 1),
+            insert_id("251108 11:00:00", 2),
+            insert_id("251108 12:00:00", 3),
+        ];
+        SnapshotManager::new(conn, operations, 0)
+    }
+
+    #[test]
+    fn test_goto_bookmark() {
+        let mut manager = create_test_manager();
+        manager.goto_position(2).unwrap();
+        manager.bookmark("incident");
+        manager.goto_position(0).unwrap();
+
+        manager.goto_bookmark("incident").unwrap();
+        assert_eq!(manager.get_position(), 2);
+        assert_eq!(manager.get_bookmarks()[0].1.timestamp.as_deref(), Some("251108 12:00:00"));
+        assert!(manager.goto_bookmark("missing").is_err());
+    }
+
+    #[test]
+    fn test_bookmarks_survive_save_and_load() {
+        let path = std::env::temp_dir().join(format!("bookmarks_{}.tsv", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        let mut manager = create_test_manager();
+        manager.step_forward().unwrap();
+        manager.bookmark("before");
+        manager.step_forward().unwrap();
+        manager.bookmark("after");
+        manager.save_bookmarks(path).unwrap();
+
+        let mut manager = create_test_manager();
+        assert_eq!(manager.load_bookmarks(path).unwrap(), 2);
+        manager.goto_bookmark("before").unwrap();
+        assert_eq!(manager.get_position(), 1);
+
+        // Same positions, different binlog
+        let conn = books_db("id INTEGER", "");
+        let mut other = SnapshotManager::new(conn, vec![insert_id("251109 10:00:00", 1)], 0);
+        assert_eq!(other.load_bookmarks(path).unwrap(), 0);
+
+        fs::remove_file(path).ok();
+    }
+}
@@ -0,0 +1,83 @@
+use crate::snapshot_manager::SnapshotManager;
+
+/// Where a chunked navigation stands after a call to navigate_chunked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NavigationProgress {
+    pub position: usize,
+    pub target: usize,
+    /// Operations still to apply before reaching the target
+    pub remaining: usize,
+}
+
+impl NavigationProgress {
+    pub fn is_done(&self) -> bool {
+        self.remaining == 0
+    }
+}
+
+impl SnapshotManager {
+    /// Moves towards `target_position` by at most `chunk_size` operations, then returns so the
+    /// caller can report progress or yield. Call again with the same target to resume, until the
+    /// returned progress is done. Operations are replayed one by one, unlike goto_position which
+    /// consolidates long jumps, so every chunk takes a bounded amount of time.
+    pub fn navigate_chunked(
+        &mut self,
+        target_position: usize,
+        chunk_size: usize,
+    ) -> Result<NavigationProgress, Box<dyn std::error::Error>> {
+        if target_position >= self.operation_count() {
+            return Err("Target position out of bounds".into());
+        }
+        if chunk_size == 0 {
+            return Err("Chunk size must be at least 1".into());
+        }
+
+        let position = self.get_position();
+        let steps = target_position.abs_diff(position).min(chunk_size);
+        if target_position > position {
+            self.step_forward_by(steps)?;
+        } else {
+            self.step_backward_by(steps)?;
+        }
+
+        let position = self.get_position();
+        Ok(NavigationProgress {
+            position,
+            target: target_position,
+            remaining: target_position.abs_diff(position),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::binlog::BinlogOperation;
+    use crate::snapshot_manager::SnapshotManager;
+    use crate::test_support::{books_db, insert_id};
+
+    fn insert(id: usize) -> BinlogOperation {
+        insert_id(&format!("251108 10:00:{:02}", id), id)
+    }
+
+    #[test]
+    fn test_navigate_chunked_resumes_until_done() {
+        let conn = books_db("id INTEGER PRIMARY KEY", "(0)");
+        let mut manager = SnapshotManager::new(conn, (0..10).map(insert).collect(), 0);
+
+        let mut chunks = Vec::new();
+        loop {
+            let progress = manager.navigate_chunked(9, 4).unwrap();
+            chunks.push(progress.position);
+            if progress.is_done() {
+                break;
+            }
+        }
+        assert_eq!(chunks, vec![4, 8, 9]);
+        let count: i64 = manager.get_connection().query_row("SELECT COUNT(*) FROM books", [], |row| row.get(0)).unwrap();
+        assert_eq!(count, 10);
+
+        let progress = manager.navigate_chunked(2, 5).unwrap();
+        assert_eq!((progress.position, progress.remaining), (4, 2));
+        assert!(manager.navigate_chunked(2, 0).is_err());
+    }
+}
@@ -0,0 +1,136 @@
+use crate::snapshot_manager::SnapshotManager;
+
+/// A SQL boolean expression that must hold at every position the snapshot moves to
+#[derive(Debug, Clone)]
+pub struct Invariant {
+    pub name: String,
+    pub expression: String,
+    /// Checked once this many operations have been applied or undone since the last check
+    pub every: usize,
+    operations_since_check: usize,
+}
+
+/// Returned by navigation when an invariant doesn't hold. The snapshot is left at `position`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InvariantViolation {
+    pub name: String,
+    pub expression: String,
+    pub position: usize,
+    pub timestamp: Option<String>,
+}
+
+impl std::fmt::Display for InvariantViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Invariant {} ({}) doesn't hold at position {} ({:?})",
+            self.name, self.expression, self.position, self.timestamp
+        )
+    }
+}
+
+impl std::error::Error for InvariantViolation {}
+
+/// Registered invariants and the position they were last brought up to date at
+#[derive(Default)]
+pub(super) struct Invariants {
+    invariants: Vec<Invariant>,
+    last_position: usize,
+}
+
+impl SnapshotManager {
+    /// Registers an invariant checked after every operation applied or undone by navigation.
+    /// `expression` is a SQL boolean expression, e.g.
+    /// `(SELECT COUNT(*) FROM orders WHERE total < 0) = 0`; NULL counts as not holding.
+    /// Registering a name again replaces the previous invariant.
+    ///
+    /// Returns an InvariantViolation if it doesn't hold at the current position, and doesn't
+    /// register it.
+    pub fn add_invariant(&mut self, name: &str, expression: &str) -> Result<(), Box<dyn std::error::Error>> {
+        self.add_invariant_every(name, expression, 1)
+    }
+
+    /// Like add_invariant, checking the invariant only every `every` operations, for expressions
+    /// too slow to run after each one. Jumps (goto_position) count every operation they skip over.
+    pub fn add_invariant_every(&mut self, name: &str, expression: &str, every: usize) -> Result<(), Box<dyn std::error::Error>> {
+        self.remove_invariant(name);
+        let invariant = Invariant {
+            name: name.to_string(),
+            expression: expression.to_string(),
+            every: every.max(1),
+            operations_since_check: 0,
+        };
+        self.check_invariant(&invariant)?;
+        self.invariants.last_position = self.get_position();
+        self.invariants.invariants.push(invariant);
+        Ok(())
+    }
+
+    /// Returns false if there was no such invariant
+    pub fn remove_invariant(&mut self, name: &str) -> bool {
+        let count = self.invariants.invariants.len();
+        self.invariants.invariants.retain(|invariant| invariant.name != name);
+        self.invariants.invariants.len() != count
+    }
+
+    pub fn get_invariants(&self) -> &[Invariant] {
+        &self.invariants.invariants
+    }
+
+    /// Checks the invariants that are due after the move to the current position
+    pub(super) fn check_invariants(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let moved = self.get_position().abs_diff(self.invariants.last_position);
+        self.invariants.last_position = self.get_position();
+        for i in 0..self.invariants.invariants.len() {
+            let invariant = &mut self.invariants.invariants[i];
+            invariant.operations_since_check += moved;
+            if moved == 0 || invariant.operations_since_check < invariant.every {
+                continue;
+            }
+            invariant.operations_since_check = 0;
+            self.check_invariant(&self.invariants.invariants[i])?;
+        }
+        Ok(())
+    }
+
+    fn check_invariant(&self, invariant: &Invariant) -> Result<(), Box<dyn std::error::Error>> {
+        let holds: Option<bool> = self.get_connection()
+            .query_row(&format!("SELECT CAST(({}) AS BOOLEAN)", invariant.expression), [], |row| row.get(0))
+            .map_err(|e| format!("Failed to check invariant {}: {}", invariant.name, e))?;
+        if holds == Some(true) {
+            return Ok(());
+        }
+        Err(Box::new(InvariantViolation {
+            name: invariant.name.clone(),
+            expression: invariant.expression.clone(),
+            position: self.get_position(),
+            timestamp: self.get_timestamp().cloned(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{books_db, insert_id};
+
+    #[test]
+    fn test_violated_invariant_stops_navigation_at_offending_position() {
+        let conn = books_db("id INTEGER", "(1)");
+        let operations = (1..=5).map(|id| insert_id(&format!("251108 10:0{}:00", id), id)).collect();
+        let mut manager = SnapshotManager::new(conn, operations, 0);
+
+        manager.add_invariant("few_books", "(SELECT COUNT(*) FROM books) <= 3").unwrap();
+        manager.add_invariant_every("no_negative_ids", "(SELECT MIN(id) FROM books) > 0", 2).unwrap();
+        assert!(manager.add_invariant("empty", "NOT EXISTS (SELECT * FROM books)").is_err());
+        assert_eq!(manager.get_invariants().len(), 2);
+
+        let error = manager.step_forward_by(4).err().unwrap();
+        let violation = error.downcast_ref::<InvariantViolation>().unwrap();
+        assert_eq!((violation.name.as_str(), violation.position), ("few_books", 3));
+        assert_eq!(manager.get_position(), 3);
+
+        assert!(manager.remove_invariant("few_books"));
+        manager.goto_position(4).unwrap();
+    }
+}
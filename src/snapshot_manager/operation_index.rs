@@ -0,0 +1,265 @@
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::ops::ControlFlow;
+use crate::binlog::{BinlogOperation, OperationType, RowImage, TransactionInfo};
+use crate::snapshot_manager::operation_store::{OperationSource, OperationStore};
+
+/// Table name and primary key values of a row
+type RowKey = (String, String);
+
+/// One operation's effect on one row: which of the operation's images belong to the row.
+/// An UPDATE that changes the primary key leaves one row (before image only) and enters another
+/// (after image only).
+#[derive(Debug, Clone, Copy)]
+struct RowTouch {
+    position: usize,
+    before: bool,
+    after: bool,
+}
+
+/// Per-row index of operations, keyed by table and primary key, used to jump over long spans
+/// of operations with one consolidated operation per affected row
+#[derive(Debug, Default)]
+pub(crate) struct OperationIndex {
+    rows: HashMap<RowKey, Vec<RowTouch>>,
+    /// Rows touched by each operation; None if the operation's row can't be identified
+    /// (unknown table, missing key column or NULL key) or its row image is partial
+    op_rows: Vec<Option<Vec<RowKey>>>,
+}
+
+impl OperationIndex {
+    /// Indexes operations using the given key columns per table. Operations on tables without
+    /// key columns can't be keyed.
+    pub(crate) fn build(operations: &OperationStore, key_columns: &HashMap<String, Vec<String>>) -> io::Result<Self> {
+        let mut index = Self::default();
+
+        operations.for_each(0..operations.len(), |position, op| {
+            let key = |values: Option<&Vec<String>>| -> Option<RowKey> {
+                let keys = key_columns.get(&op.table_name).filter(|keys| !keys.is_empty())?;
+                let values = values?;
+                let mut parts = Vec::with_capacity(keys.len());
+                for key in keys {
+                    let idx = op.columns.iter().position(|c| c == key)?;
+                    let value = values.get(idx)?;
+                    if value == "NULL" {
+                        return None;
+                    }
+                    parts.push(value.as_str());
+                }
+                Some((op.table_name.clone(), parts.join("\u{1f}")))
+            };
+
+            // Partial row images can't be merged into whole-row images
+            if !op.row_image.is_complete_for(&op.columns) {
+                index.op_rows.push(None);
+                return ControlFlow::Continue(());
+            }
+
+            let touches = match op.operation_type {
+                OperationType::Insert => key(op.after_values.as_ref()).map(|k| vec![(k, false, true)]),
+                OperationType::Delete => key(op.before_values.as_ref()).map(|k| vec![(k, true, false)]),
+                OperationType::Update => match (key(op.before_values.as_ref()), key(op.after_values.as_ref())) {
+                    (Some(before), Some(after)) if before == after => Some(vec![(before, true, true)]),
+                    (Some(before), Some(after)) => Some(vec![(before, true, false), (after, false, true)]),
+                    _ => None,
+                },
+            };
+
+            let op_rows = touches.map(|touches| {
+                touches.into_iter()
+                    .map(|(row, before, after)| {
+                        index.rows.entry(row.clone()).or_default().push(RowTouch { position, before, after });
+                        row
+                    })
+                    .collect()
+            });
+            index.op_rows.push(op_rows);
+            ControlFlow::Continue(())
+        })?;
+
+        Ok(index)
+    }
+
+    /// Operations that take the database from position `from` to position `to` in one step per
+    /// affected row: deletes first, then updates, then inserts.
+    /// Returns None if an operation in the span can't be keyed and the span has to be replayed.
+    pub(crate) fn consolidate(&self, operations: &OperationStore, from: usize, to: usize) -> io::Result<Option<Vec<BinlogOperation>>> {
+        let forward = to > from;
+        // Operations from+1..=to are applied going forward; to+1..=from are undone going backward
+        let (lo, hi) = if forward { (from + 1, to) } else { (to + 1, from) };
+
+        let mut seen = HashSet::new();
+        let mut touched_rows = Vec::new();
+        for idx in lo..=hi {
+            let Some(Some(rows)) = self.op_rows.get(idx) else {
+                return Ok(None);
+            };
+            for row in rows {
+                if seen.insert(row) {
+                    touched_rows.push(row);
+                }
+            }
+        }
+
+        let mut deletes = Vec::new();
+        let mut updates = Vec::new();
+        let mut inserts = Vec::new();
+
+        for row in touched_rows {
+            let touches = &self.rows[row];
+            let start = touches.partition_point(|t| t.position < lo);
+            let end = touches.partition_point(|t| t.position <= hi);
+            let (first, last) = (&touches[start], &touches[end - 1]);
+
+            let (start_image, end_image) = if forward {
+                (image(operations, first, false)?, image(operations, last, true)?)
+            } else {
+                (image(operations, last, true)?, image(operations, first, false)?)
+            };
+            let template = operations.operation(if forward { last.position } else { first.position })?;
+            let make_op = |operation_type, before: Option<&Image>, after: Option<&Image>| BinlogOperation {
+                timestamp: template.timestamp.clone(),
+                position: template.position,
+                operation_type,
+                table_name: template.table_name.clone(),
+                database: template.database.clone(),
+                columns: after.or(before).map(|image| image.0.clone()).unwrap_or_default(),
+                before_values: before.map(|image| image.1.clone()),
+                after_values: after.map(|image| image.1.clone()),
+                row_image: RowImage::Full,
+                transaction: TransactionInfo::default(),
+            };
+
+            match (start_image, end_image) {
+                (None, None) => {}
+                (None, Some(after)) => inserts.push(make_op(OperationType::Insert, None, Some(&after))),
+                (Some(before), None) => deletes.push(make_op(OperationType::Delete, Some(&before), None)),
+                (Some(before), Some(after)) if before.0 == after.0 => {
+                    if before.1 != after.1 {
+                        updates.push(make_op(OperationType::Update, Some(&before), Some(&after)));
+                    }
+                }
+                // The schema changed within the span; replace the row instead
+                (Some(before), Some(after)) => {
+                    deletes.push(make_op(OperationType::Delete, Some(&before), None));
+                    inserts.push(make_op(OperationType::Insert, None, Some(&after)));
+                }
+            }
+        }
+
+        deletes.append(&mut updates);
+        deletes.append(&mut inserts);
+        Ok(Some(deletes))
+    }
+}
+
+/// Columns and values of a row image
+type Image = (Vec<String>, Vec<String>);
+
+/// The before or after image an operation holds for a row, if the row exists on that side
+fn image(operations: &OperationStore, touch: &RowTouch, after: bool) -> io::Result<Option<Image>> {
+    let op = operations.operation(touch.position)?;
+    let (present, values) = if after {
+        (touch.after, op.after_values.as_ref())
+    } else {
+        (touch.before, op.before_values.as_ref())
+    };
+    if !present {
+        return Ok(None);
+    }
+    Ok(values.map(|values| (op.columns.clone(), values.clone())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::operation_applier::OperationApplier;
+    use crate::test_support::{books_db, books_op};
+
+    #[test]
+    fn test_consolidate_collapses_each_row_to_its_net_effect() {
+        let operations = vec![
+            books_op(OperationType::Insert, "251108 10:00:00", None, Some(["1", "10"])),
+            books_op(OperationType::Update, "251108 10:00:00", Some(["1", "10"]), Some(["1", "11"])),
+            books_op(OperationType::Update, "251108 10:00:00", Some(["1", "11"]), Some(["1", "12"])),
+            books_op(OperationType::Insert, "251108 10:00:00", None, Some(["2", "20"])),
+            books_op(OperationType::Delete, "251108 10:00:00", Some(["2", "20"]), None),
+            books_op(OperationType::Insert, "251108 10:00:00", None, Some(["3", "30"])),
+            // Primary key change: row 3 goes away and row 4 appears
+            books_op(OperationType::Update, "251108 10:00:00", Some(["3", "30"]), Some(["4", "30"])),
+        ];
+        let operations = OperationStore::in_memory(operations);
+        let key_columns = HashMap::from([("books".to_string(), vec!["id".to_string()])]);
+        let index = OperationIndex::build(&operations, &key_columns).unwrap();
+
+        let forward = index.consolidate(&operations, 0, 6).unwrap().unwrap();
+        let values = |v: [&str; 2]| Some(v.iter().map(|s| s.to_string()).collect::<Vec<_>>());
+        assert_eq!(forward.len(), 2);
+        assert_eq!(forward[0].operation_type, OperationType::Update);
+        assert_eq!(forward[0].before_values, values(["1", "10"]));
+        assert_eq!(forward[0].after_values, values(["1", "12"]));
+        assert_eq!(forward[1].operation_type, OperationType::Insert);
+        assert_eq!(forward[1].after_values, values(["4", "30"]));
+
+        let backward = index.consolidate(&operations, 6, 0).unwrap().unwrap();
+        assert_eq!(backward.len(), 2);
+        assert_eq!(backward[0].operation_type, OperationType::Delete);
+        assert_eq!(backward[0].before_values, values(["4", "30"]));
+        assert_eq!(backward[1].after_values, values(["1", "10"]));
+    }
+
+    #[test]
+    fn test_consolidate_gives_up_on_unkeyed_operations() {
+        let operations = vec![
+            books_op(OperationType::Insert, "251108 10:00:00", None, Some(["1", "10"])),
+            books_op(OperationType::Insert, "251108 10:00:00", None, Some(["NULL", "20"])),
+        ];
+        let operations = OperationStore::in_memory(operations);
+        let key_columns = HashMap::from([("books".to_string(), vec!["id".to_string()])]);
+        let index = OperationIndex::build(&operations, &key_columns).unwrap();
+
+        assert!(index.consolidate(&operations, 0, 1).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_stepping_back_across_a_consolidated_range_matches_undoing_each_operation() {
+        let operations = vec![
+            books_op(OperationType::Insert, "251108 10:00:00", None, Some(["1", "10"])),
+            books_op(OperationType::Insert, "251108 10:00:00", None, Some(["2", "20"])),
+            books_op(OperationType::Update, "251108 10:00:00", Some(["1", "10"]), Some(["1", "11"])),
+            books_op(OperationType::Delete, "251108 10:00:00", Some(["2", "20"]), None),
+            books_op(OperationType::Insert, "251108 10:00:00", None, Some(["3", "30"])),
+            books_op(OperationType::Update, "251108 10:00:00", Some(["3", "30"]), Some(["4", "31"])),
+            books_op(OperationType::Update, "251108 10:00:00", Some(["1", "11"]), Some(["1", "12"])),
+        ];
+        let store = OperationStore::in_memory(operations.clone());
+        let key_columns = HashMap::from([("books".to_string(), vec!["id".to_string()])]);
+        let index = OperationIndex::build(&store, &key_columns).unwrap();
+
+        // Both tables start at the last position, then go back to the first
+        let table_at_end = || {
+            let conn = books_db("id INTEGER PRIMARY KEY, price INTEGER", "");
+            let mut applier = OperationApplier::new(conn);
+            for op in &operations {
+                applier.execute_operation(op).unwrap();
+            }
+            applier
+        };
+        let rows = |applier: &OperationApplier| -> Vec<(i64, i64)> {
+            let mut stmt = applier.get_connection().prepare("SELECT id, price FROM books ORDER BY id").unwrap();
+            stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?))).unwrap().map(|row| row.unwrap()).collect()
+        };
+
+        let mut stepped = table_at_end();
+        for op in operations[1..].iter().rev() {
+            stepped.execute_operation(&op.invert()).unwrap();
+        }
+        let mut consolidated = table_at_end();
+        for op in index.consolidate(&store, operations.len() - 1, 0).unwrap().unwrap() {
+            consolidated.execute_operation(&op).unwrap();
+        }
+
+        assert_eq!(rows(&stepped), vec![(1, 10)]);
+        assert_eq!(rows(&consolidated), rows(&stepped));
+    }
+}
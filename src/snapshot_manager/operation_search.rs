@@ -0,0 +1,221 @@
+use crate::binlog::{BinlogOperation, BinlogTimestamp, OperationType};
+use crate::export::audit_log::literal_text;
+use crate::snapshot_manager::SnapshotManager;
+
+/// Which operations find_operations returns. Every condition given must hold. Values are SQL
+/// literals, like binlog values (`42`, `'abc'`, `NULL`), compared by their text.
+#[derive(Debug, Clone, Default)]
+pub struct OperationFilter {
+    table: Option<String>,
+    operation_type: Option<OperationType>,
+    time_range: (Option<BinlogTimestamp>, Option<BinlogTimestamp>),
+    key_values: Option<Vec<String>>,
+    changed: Vec<String>,
+    changed_to: Vec<(String, String)>,
+    changed_from: Vec<(String, String)>,
+}
+
+impl OperationFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_table(mut self, table: &str) -> Self {
+        self.table = Some(table.to_string());
+        self
+    }
+
+    pub fn with_operation_type(mut self, operation_type: OperationType) -> Self {
+        self.operation_type = Some(operation_type);
+        self
+    }
+
+    /// Operations with a timestamp between `min` and `max` (both inclusive bounds). Operations
+    /// without a timestamp don't match a time range.
+    pub fn with_time_range(mut self, min: Option<BinlogTimestamp>, max: Option<BinlogTimestamp>) -> Self {
+        self.time_range = (min, max);
+        self
+    }
+
+    /// Operations touching the row with these primary key values (or first column), in key
+    /// column order. Needs a table.
+    pub fn with_key(mut self, key_values: &[&str]) -> Self {
+        self.key_values = Some(key_values.iter().map(|value| value.to_string()).collect());
+        self
+    }
+
+    /// Operations that change `column`: UPDATEs that change its value, INSERTs and DELETEs
+    pub fn with_changed(mut self, column: &str) -> Self {
+        self.changed.push(column.to_string());
+        self
+    }
+
+    /// Operations that change `column` to `value`: UPDATEs that change it and INSERTs of rows
+    /// with that value
+    pub fn with_changed_to(mut self, column: &str, value: &str) -> Self {
+        self.changed_to.push((column.to_string(), value.to_string()));
+        self
+    }
+
+    /// Operations that change `column` away from `value`: UPDATEs that change it and DELETEs of
+    /// rows with that value
+    pub fn with_changed_from(mut self, column: &str, value: &str) -> Self {
+        self.changed_from.push((column.to_string(), value.to_string()));
+        self
+    }
+
+    fn matches(&self, op: &BinlogOperation, key_columns: &[String]) -> bool {
+        if self.table.as_ref().is_some_and(|table| *table != op.table_name)
+            || self.operation_type.as_ref().is_some_and(|operation_type| *operation_type != op.operation_type) {
+            return false;
+        }
+        if self.time_range.0.is_some() || self.time_range.1.is_some() {
+            let Some(timestamp) = op.timestamp.as_ref().and_then(|ts| BinlogTimestamp::parse(ts).ok()) else {
+                return false;
+            };
+            let (min, max) = &self.time_range;
+            if min.as_ref().is_some_and(|min| timestamp < *min) || max.as_ref().is_some_and(|max| timestamp > *max) {
+                return false;
+            }
+        }
+        if let Some(key_values) = &self.key_values {
+            let has_key = |image: &Option<Vec<String>>| key_columns.iter()
+                .zip(key_values)
+                .all(|(key, expected)| value_is(op, image, key, expected));
+            if !has_key(&op.before_values) && !has_key(&op.after_values) {
+                return false;
+            }
+        }
+        let changed = op.changed_columns();
+        self.changed.iter().all(|column| changed.contains(&column.as_str()))
+            && self.changed_to.iter().all(|(column, value)| changed.contains(&column.as_str()) && value_is(op, &op.after_values, column, value))
+            && self.changed_from.iter().all(|(column, value)| changed.contains(&column.as_str()) && value_is(op, &op.before_values, column, value))
+    }
+}
+
+/// Whether the image has `expected` (a SQL literal) for `column`
+fn value_is(op: &BinlogOperation, image: &Option<Vec<String>>, column: &str, expected: &str) -> bool {
+    op.columns.iter()
+        .position(|c| c == column)
+        .and_then(|idx| image.as_ref()?.get(idx))
+        .is_some_and(|value| literal_text(value) == literal_text(expected))
+}
+
+impl SnapshotManager {
+    /// Positions of the operations matching the filter, in order, e.g. to find when a value
+    /// became X without replaying or writing a script. Reads the operation stream and doesn't
+    /// move the position.
+    pub fn find_operations(&self, filter: &OperationFilter) -> Result<Vec<usize>, Box<dyn std::error::Error>> {
+        self.positions_matching(filter, 0, self.operation_count())
+    }
+
+    /// Goes to the first operation after the current position that changes `column` of the row
+    /// of `table` with these primary key values (SQL literals), so the row shows the new value.
+    /// Returns the new position, None if no later operation changes it.
+    pub fn goto_next_change(&mut self, table: &str, key_values: &[&str], column: &str) -> Result<Option<usize>, Box<dyn std::error::Error>> {
+        let filter = OperationFilter::new().with_table(table).with_key(key_values).with_changed(column);
+        let target = self.positions_matching(&filter, self.get_position() + 1, self.operation_count())?.first().copied();
+        if let Some(target) = target {
+            self.goto_position(target)?;
+        }
+        Ok(target)
+    }
+
+    /// Like goto_next_change, going back to the last operation before the current position that
+    /// changes the column
+    pub fn goto_prev_change(&mut self, table: &str, key_values: &[&str], column: &str) -> Result<Option<usize>, Box<dyn std::error::Error>> {
+        let filter = OperationFilter::new().with_table(table).with_key(key_values).with_changed(column);
+        let target = self.positions_matching(&filter, 0, self.get_position())?.last().copied();
+        if let Some(target) = target {
+            self.goto_position(target)?;
+        }
+        Ok(target)
+    }
+
+    /// Positions in `start..end` of the operations matching the filter
+    fn positions_matching(&self, filter: &OperationFilter, start: usize, end: usize) -> Result<Vec<usize>, Box<dyn std::error::Error>> {
+        let key_columns = match (&filter.key_values, &filter.table) {
+            (None, _) => Vec::new(),
+            (Some(_), None) => return Err("Searching by key needs a table".into()),
+            (Some(key_values), Some(table)) => {
+                let key_columns = self.primary_key_columns(table, &self.table_columns(table)?)?;
+                if key_columns.len() != key_values.len() {
+                    return Err(format!("{} is keyed by {}; got {} key values", table, key_columns.join(", "), key_values.len()).into());
+                }
+                key_columns
+            }
+        };
+
+        let mut positions = Vec::new();
+        self.for_each_operation(start, end, |position, op| {
+            if filter.matches(op, &key_columns) {
+                positions.push(position);
+            }
+            Ok(())
+        })?;
+        Ok(positions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{books_db, operation};
+
+    fn op(operation_type: OperationType, timestamp: &str, before: Option<[&str; 2]>, after: Option<[&str; 2]>) -> BinlogOperation {
+        BinlogOperation {
+            timestamp: Some(timestamp.to_string()),
+            ..operation(operation_type, "books", &["id", "status"], before.as_ref().map(|v| &v[..]), after.as_ref().map(|v| &v[..]))
+        }
+    }
+
+    #[test]
+    fn test_find_when_a_value_changed() {
+        let conn = books_db("id INTEGER PRIMARY KEY, status VARCHAR", "");
+        let operations = vec![
+            op(OperationType::Insert, "251108 10:00:00", None, Some(["1", "'draft'"])),
+            op(OperationType::Update, "251108 11:00:00", Some(["1", "'draft'"]), Some(["1", "'sold'"])),
+            op(OperationType::Insert, "251108 12:00:00", None, Some(["2", "'sold'"])),
+            op(OperationType::Update, "251108 13:00:00", Some(["2", "'sold'"]), Some(["2", "'sold'"])),
+            op(OperationType::Delete, "251108 14:00:00", Some(["1", "'sold'"]), None),
+        ];
+        let manager = SnapshotManager::new(conn, operations, 0);
+        let find = |filter: OperationFilter| manager.find_operations(&filter).unwrap();
+
+        assert_eq!(find(OperationFilter::new().with_changed_to("status", "'sold'")), vec![1, 2]);
+        assert_eq!(find(OperationFilter::new().with_table("books").with_key(&["1"])), vec![0, 1, 4]);
+        assert_eq!(find(OperationFilter::new().with_table("books").with_key(&["'1'"]).with_changed_from("status", "'sold'")), vec![4]);
+        assert_eq!(find(OperationFilter::new().with_operation_type(OperationType::Insert).with_time_range(
+            Some(BinlogTimestamp::parse("251108 11:00:00").unwrap()),
+            None,
+        )), vec![2]);
+        assert!(manager.find_operations(&OperationFilter::new().with_key(&["1"])).is_err());
+    }
+
+    #[test]
+    fn test_goto_change_lands_after_each_change() {
+        let conn = books_db("id INTEGER PRIMARY KEY, status VARCHAR", "(1, 'draft')");
+        let operations = vec![
+            op(OperationType::Insert, "251108 10:00:00", None, Some(["1", "'draft'"])),
+            op(OperationType::Insert, "251108 10:30:00", None, Some(["2", "'draft'"])),
+            op(OperationType::Update, "251108 11:00:00", Some(["1", "'draft'"]), Some(["1", "'sold'"])),
+            op(OperationType::Update, "251108 12:00:00", Some(["2", "'draft'"]), Some(["2", "'sold'"])),
+            op(OperationType::Update, "251108 13:00:00", Some(["1", "'sold'"]), Some(["1", "'sold'"])),
+        ];
+        let mut manager = SnapshotManager::new(conn, operations, 0);
+        let status = |manager: &SnapshotManager| -> String {
+            manager.get_connection().query_row("SELECT status FROM books WHERE id = 1", [], |row| row.get(0)).unwrap()
+        };
+
+        assert_eq!(manager.goto_next_change("books", &["1"], "status").unwrap(), Some(2));
+        assert_eq!(status(&manager), "sold");
+        assert_eq!(manager.goto_next_change("books", &["1"], "status").unwrap(), None);
+        assert_eq!(manager.get_position(), 2);
+
+        manager.goto_position(4).unwrap();
+        assert_eq!(manager.goto_prev_change("books", &["1"], "status").unwrap(), Some(2));
+        assert_eq!(manager.goto_prev_change("books", &["1"], "status").unwrap(), Some(0));
+        assert_eq!(status(&manager), "draft");
+        assert_eq!(manager.goto_prev_change("books", &["1"], "status").unwrap(), None);
+    }
+}
@@ -0,0 +1,158 @@
+use std::collections::{BTreeMap, HashSet};
+use chrono::{DateTime, Duration, NaiveDateTime};
+use crate::binlog::{BinlogOperation, BinlogTimestamp, OperationType};
+use crate::snapshot_manager::SnapshotManager;
+
+/// What the operation stream does to one table
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TableOperationStats {
+    pub table: String,
+    pub inserts: usize,
+    pub updates: usize,
+    pub deletes: usize,
+    /// Earliest and latest operation timestamps
+    pub first_timestamp: Option<String>,
+    pub last_timestamp: Option<String>,
+    /// Distinct rows touched, by primary key. An UPDATE that changes the key touches two rows.
+    pub distinct_rows: usize,
+    /// (bucket start, operation count) for every time bucket with operations, in time order.
+    /// Buckets are aligned to multiples of the bucket size since the Unix epoch.
+    pub buckets: Vec<(NaiveDateTime, usize)>,
+}
+
+impl TableOperationStats {
+    pub fn operation_count(&self) -> usize {
+        self.inserts + self.updates + self.deletes
+    }
+}
+
+/// Accumulates one table's stats
+#[derive(Default)]
+struct TableAccumulator {
+    stats: TableOperationStats,
+    key_columns: Vec<String>,
+    first: Option<BinlogTimestamp>,
+    last: Option<BinlogTimestamp>,
+    rows: HashSet<String>,
+    buckets: BTreeMap<i64, usize>,
+}
+
+impl TableAccumulator {
+    fn add(&mut self, op: &BinlogOperation, bucket_seconds: i64) {
+        match op.operation_type {
+            OperationType::Insert => self.stats.inserts += 1,
+            OperationType::Update => self.stats.updates += 1,
+            OperationType::Delete => self.stats.deletes += 1,
+        }
+
+        for image in [&op.before_values, &op.after_values].into_iter().flatten() {
+            let key: Vec<&str> = op.columns.iter()
+                .zip(image)
+                .filter(|(col, _)| self.key_columns.contains(col))
+                .map(|(_, value)| value.as_str())
+                .collect();
+            if !key.is_empty() {
+                self.rows.insert(key.join("\u{1f}"));
+            }
+        }
+
+        let Some((raw, timestamp)) = op.timestamp.as_ref()
+            .and_then(|ts| BinlogTimestamp::parse(ts).ok().map(|parsed| (ts, parsed))) else {
+            return;
+        };
+        let bucket = timestamp.as_datetime().and_utc().timestamp().div_euclid(bucket_seconds);
+        *self.buckets.entry(bucket).or_default() += 1;
+        if self.first.as_ref().is_none_or(|first| timestamp < *first) {
+            self.stats.first_timestamp = Some(raw.clone());
+            self.first = Some(timestamp.clone());
+        }
+        if self.last.as_ref().is_none_or(|last| timestamp > *last) {
+            self.stats.last_timestamp = Some(raw.clone());
+            self.last = Some(timestamp);
+        }
+    }
+
+    fn finish(mut self, bucket_seconds: i64) -> TableOperationStats {
+        self.stats.distinct_rows = self.rows.len();
+        self.stats.buckets = self.buckets.into_iter()
+            .filter_map(|(bucket, count)| {
+                let start = DateTime::from_timestamp(bucket * bucket_seconds, 0)?.naive_utc();
+                Some((start, count))
+            })
+            .collect();
+        self.stats
+    }
+}
+
+impl SnapshotManager {
+    /// Operation counts, time range, distinct rows touched and operations per time bucket for every
+    /// table in the operation stream, in table name order. Helps choose a window size and spot
+    /// unexpected churn before an analysis. The snapshot's position is not changed.
+    pub fn operation_stats(&self, bucket_size: Duration) -> Result<Vec<TableOperationStats>, Box<dyn std::error::Error>> {
+        let bucket_seconds = bucket_size.num_seconds();
+        if bucket_seconds <= 0 {
+            return Err("Bucket size must be at least one second".into());
+        }
+
+        let mut tables: BTreeMap<String, TableAccumulator> = BTreeMap::new();
+        self.for_each_operation(0, self.operation_count(), |_, op| {
+            if !tables.contains_key(&op.table_name) {
+                let accumulator = TableAccumulator {
+                    stats: TableOperationStats { table: op.table_name.clone(), ..Default::default() },
+                    key_columns: self.primary_key_columns(&op.table_name, &op.columns)?,
+                    ..Default::default()
+                };
+                tables.insert(op.table_name.clone(), accumulator);
+            }
+            tables.get_mut(&op.table_name).unwrap().add(op, bucket_seconds);
+            Ok(())
+        })?;
+
+        Ok(tables.into_values().map(|accumulator| accumulator.finish(bucket_seconds)).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use duckdb::Connection;
+    use crate::test_support::books_op;
+
+    fn op(operation_type: OperationType, timestamp: &str, table: &str, before: Option<[&str; 2]>, after: Option<[&str; 2]>) -> BinlogOperation {
+        BinlogOperation { table_name: table.to_string(), ..books_op(operation_type, timestamp, before, after) }
+    }
+
+    #[test]
+    fn test_operation_stats_per_table() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE books (id INTEGER PRIMARY KEY, price INTEGER);
+             CREATE TABLE authors (id INTEGER PRIMARY KEY, price INTEGER);"
+        ).unwrap();
+        let operations = vec![
+            op(OperationType::Insert, "251108 10:05:00", "books", None, Some(["1", "10"])),
+            op(OperationType::Insert, "251108 10:20:00", "books", None, Some(["2", "20"])),
+            op(OperationType::Insert, "251108 09:59:00", "authors", None, Some(["7", "0"])),
+            op(OperationType::Update, "251108 11:40:00", "books", Some(["1", "10"]), Some(["1", "12"])),
+            op(OperationType::Update, "251108 11:45:00", "books", Some(["2", "20"]), Some(["3", "20"])),
+            op(OperationType::Delete, "251108 11:50:00", "books", Some(["3", "20"]), None),
+        ];
+        let manager = SnapshotManager::new(conn, operations, 0);
+
+        let stats = manager.operation_stats(Duration::hours(1)).unwrap();
+
+        assert_eq!(stats.iter().map(|s| s.table.as_str()).collect::<Vec<_>>(), vec!["authors", "books"]);
+        let books = &stats[1];
+        assert_eq!((books.inserts, books.updates, books.deletes), (2, 2, 1));
+        assert_eq!(books.operation_count(), 5);
+        assert_eq!(books.first_timestamp.as_deref(), Some("251108 10:05:00"));
+        assert_eq!(books.last_timestamp.as_deref(), Some("251108 11:50:00"));
+        assert_eq!(books.distinct_rows, 3);
+        let buckets: Vec<(String, usize)> = books.buckets.iter()
+            .map(|(start, count)| (start.format("%H:%M").to_string(), *count))
+            .collect();
+        assert_eq!(buckets, vec![("10:00".to_string(), 2), ("11:00".to_string(), 3)]);
+        assert_eq!(stats[0].distinct_rows, 1);
+        assert_eq!(manager.get_position(), 0);
+    }
+}
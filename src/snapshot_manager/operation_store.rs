@@ -0,0 +1,354 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufWriter, Read, Seek, SeekFrom, Write};
+use std::ops::{ControlFlow, Range};
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tracing::{info, warn};
+use crate::binlog::BinlogOperation;
+use crate::parser::operation_cache;
+
+/// Pages kept in memory around the cursor once operations are spilled: its own and its neighbours
+const PAGES_KEPT: usize = 3;
+
+static SPILL_FILE_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// The operations of a SnapshotManager. They are all in memory, or once there are more than a
+/// limit, spilled to a temporary file and read back a page at a time, keeping only the pages
+/// around the cursor.
+pub(crate) struct OperationStore {
+    len: usize,
+    // Every operation is in page 0 unless spilled
+    page_size: usize,
+    pages: Vec<OnceLock<Vec<BinlogOperation>>>,
+    spill: Option<SpillFile>,
+    // Operations changed after they were spilled (completed row images)
+    changed: HashMap<usize, BinlogOperation>,
+    // Set by with_limit
+    max_in_memory: Option<usize>,
+}
+
+/// Temporary file of encoded operations, one per line, removed when dropped
+struct SpillFile {
+    path: PathBuf,
+    // Byte offset of the start of each page, and of the end of the file
+    page_offsets: Vec<u64>,
+}
+
+impl Drop for SpillFile {
+    fn drop(&mut self) {
+        std::fs::remove_file(&self.path).ok();
+    }
+}
+
+impl OperationStore {
+    pub(crate) fn in_memory(operations: Vec<BinlogOperation>) -> Self {
+        Self {
+            len: operations.len(),
+            page_size: operations.len().max(1),
+            pages: vec![OnceLock::from(operations)],
+            spill: None,
+            changed: HashMap::new(),
+            max_in_memory: None,
+        }
+    }
+
+    /// Keeps at most about `max_in_memory` operations in memory, spilling them to a temporary
+    /// file if there are more, now or once extend adds more
+    pub(crate) fn with_limit(self, max_in_memory: usize) -> io::Result<Self> {
+        let operations = self.into_operations()?;
+        let mut store = Self { max_in_memory: Some(max_in_memory), ..Self::in_memory(Vec::new()) };
+        store.extend(operations)?;
+        Ok(store)
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub(crate) fn is_spilled(&self) -> bool {
+        self.spill.is_some()
+    }
+
+    /// The operation at `idx`, reading its page in if it was spilled. None if out of bounds;
+    /// fails if the page can't be read back from the spill file.
+    pub(crate) fn try_get(&self, idx: usize) -> io::Result<Option<&BinlogOperation>> {
+        if idx >= self.len {
+            return Ok(None);
+        }
+        if let Some(op) = self.changed.get(&idx) {
+            return Ok(Some(op));
+        }
+        let cell = &self.pages[idx / self.page_size];
+        let page = match cell.get() {
+            Some(page) => page,
+            None => {
+                let page = self.read_page(idx / self.page_size)?;
+                cell.get_or_init(|| page)
+            }
+        };
+        Ok(page.get(idx % self.page_size))
+    }
+
+    /// Like try_get, also None (with a warning) if the operation can't be read back
+    pub(crate) fn get(&self, idx: usize) -> Option<&BinlogOperation> {
+        self.try_get(idx).unwrap_or_else(|e| {
+            warn!("Failed to read spilled operation {}: {}", idx, e);
+            None
+        })
+    }
+
+    pub(crate) fn set(&mut self, idx: usize, op: BinlogOperation) {
+        match self.pages[0].get_mut() {
+            Some(operations) if self.spill.is_none() => operations[idx] = op,
+            _ => {
+                self.changed.insert(idx, op);
+            }
+        }
+    }
+
+    /// Adds operations at the end. Once spilled, or once there are more than the limit, they are
+    /// written to the end of the spill file.
+    pub(crate) fn extend(&mut self, operations: Vec<BinlogOperation>) -> io::Result<()> {
+        if self.spill.is_none() {
+            match self.max_in_memory {
+                Some(max_in_memory) if self.len + operations.len() > max_in_memory => self.start_spilling(max_in_memory)?,
+                _ => {
+                    self.pages[0].get_or_init(Vec::new);
+                    let page = self.pages[0].get_mut().unwrap();
+                    page.extend(operations);
+                    self.len = page.len();
+                    self.page_size = self.len.max(1);
+                    return Ok(());
+                }
+            }
+        }
+        let spill = self.spill.as_mut().expect("operations are spilled");
+
+        let mut writer = BufWriter::new(File::options().append(true).open(&spill.path)?);
+        for op in &operations {
+            if self.len.is_multiple_of(self.page_size) {
+                let end = *spill.page_offsets.last().unwrap_or(&0);
+                spill.page_offsets.push(end);
+                self.pages.push(OnceLock::new());
+            }
+            let line = operation_cache::encode_operation(op);
+            writeln!(writer, "{}", line)?;
+            *spill.page_offsets.last_mut().unwrap() += line.len() as u64 + 1;
+            // The last page is read again with its new operations
+            self.pages.last_mut().unwrap().take();
+            self.len += 1;
+        }
+        writer.flush()
+    }
+
+    /// Moves the operations in memory to a new spill file, with pages of a third of the limit
+    fn start_spilling(&mut self, max_in_memory: usize) -> io::Result<()> {
+        let path = std::env::temp_dir().join(format!(
+            "pensieve_operations_{}_{}.spill",
+            std::process::id(),
+            SPILL_FILE_COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+        File::create(&path)?;
+        let page_size = (max_in_memory / PAGES_KEPT).max(1);
+        info!("Spilling operations to {:?} past {} in memory, {} per page", path, max_in_memory, page_size);
+        let operations = self.pages[0].take().unwrap_or_default();
+        self.len = 0;
+        self.page_size = page_size;
+        self.pages = Vec::new();
+        self.spill = Some(SpillFile { path, page_offsets: vec![0] });
+        self.extend(operations)
+    }
+
+    /// Operations `start..end`, borrowed if they are all in memory
+    pub(crate) fn range(&self, range: Range<usize>) -> io::Result<Cow<'_, [BinlogOperation]>> {
+        let range = range.start.min(self.len)..range.end.min(self.len);
+        if self.spill.is_none() {
+            return Ok(match self.pages[0].get() {
+                Some(operations) => Cow::Borrowed(&operations[range]),
+                None => Cow::Owned(Vec::new()),
+            });
+        }
+        let mut operations = Vec::with_capacity(range.len());
+        self.for_each(range, |_, op| {
+            operations.push(op.clone());
+            ControlFlow::Continue(())
+        })?;
+        Ok(Cow::Owned(operations))
+    }
+
+    /// Calls `f` with each operation of the range in order, until it breaks. Spilled pages that
+    /// aren't in memory are read for the call and dropped afterwards, so scans don't page in
+    /// everything. Fails if a spilled page can't be read back.
+    pub(crate) fn for_each(&self, range: Range<usize>, mut f: impl FnMut(usize, &BinlogOperation) -> ControlFlow<()>) -> io::Result<()> {
+        let range = range.start.min(self.len)..range.end.min(self.len);
+        let mut idx = range.start;
+        while idx < range.end {
+            let page_number = idx / self.page_size;
+            let read;
+            let page = match self.pages[page_number].get() {
+                Some(page) => page,
+                None => {
+                    read = self.read_page(page_number)?;
+                    &read
+                }
+            };
+            let page_end = ((page_number + 1) * self.page_size).min(range.end);
+            for (offset, op) in page[idx % self.page_size..page_end - page_number * self.page_size].iter().enumerate() {
+                let op = self.changed.get(&(idx + offset)).unwrap_or(op);
+                if f(idx + offset, op).is_break() {
+                    return Ok(());
+                }
+            }
+            idx = page_end;
+        }
+        Ok(())
+    }
+
+    /// Drops spilled pages that aren't around `position`
+    pub(crate) fn evict_around(&mut self, position: usize) {
+        if self.spill.is_none() {
+            return;
+        }
+        let current = position / self.page_size;
+        let kept = current.saturating_sub(PAGES_KEPT / 2)..=current + PAGES_KEPT / 2;
+        for (page_number, page) in self.pages.iter_mut().enumerate() {
+            if !kept.contains(&page_number) {
+                page.take();
+            }
+        }
+    }
+
+    fn into_operations(mut self) -> io::Result<Vec<BinlogOperation>> {
+        if self.spill.is_none() {
+            return Ok(self.pages[0].take().unwrap_or_default());
+        }
+        let mut operations = Vec::with_capacity(self.len);
+        for page_number in 0..self.pages.len() {
+            match self.pages[page_number].take() {
+                Some(page) => operations.extend(page),
+                None => operations.extend(self.read_page(page_number)?),
+            }
+        }
+        for (idx, op) in self.changed.drain() {
+            operations[idx] = op;
+        }
+        Ok(operations)
+    }
+
+    fn read_page(&self, page_number: usize) -> io::Result<Vec<BinlogOperation>> {
+        let Some(spill) = &self.spill else {
+            return Ok(Vec::new());
+        };
+        let start = spill.page_offsets[page_number];
+        let mut file = File::open(&spill.path)?;
+        file.seek(SeekFrom::Start(start))?;
+        let reader = io::BufReader::new(file.take(spill.page_offsets[page_number + 1] - start));
+        reader.lines()
+            .map(|line| {
+                operation_cache::decode_operation(&line?)
+                    .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("Corrupt operation in {:?}", spill.path)))
+            })
+            .collect()
+    }
+}
+
+/// Operations read by index, from a slice or an OperationStore
+pub(crate) trait OperationSource {
+    fn operation_count(&self) -> usize;
+
+    /// The operation at `idx`. Fails if it is out of bounds or was spilled and can't be read back.
+    fn operation(&self, idx: usize) -> io::Result<&BinlogOperation>;
+
+    /// Calls `f` with every operation in order, without keeping spilled pages in memory
+    fn scan(&self, f: &mut dyn FnMut(usize, &BinlogOperation)) -> io::Result<()>;
+}
+
+fn out_of_bounds(idx: usize, len: usize) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, format!("Operation {} out of bounds ({} operations)", idx, len))
+}
+
+impl OperationSource for [BinlogOperation] {
+    fn operation_count(&self) -> usize {
+        self.len()
+    }
+
+    fn operation(&self, idx: usize) -> io::Result<&BinlogOperation> {
+        self.get(idx).ok_or_else(|| out_of_bounds(idx, self.len()))
+    }
+
+    fn scan(&self, f: &mut dyn FnMut(usize, &BinlogOperation)) -> io::Result<()> {
+        self.iter().enumerate().for_each(|(idx, op)| f(idx, op));
+        Ok(())
+    }
+}
+
+impl OperationSource for OperationStore {
+    fn operation_count(&self) -> usize {
+        self.len
+    }
+
+    fn operation(&self, idx: usize) -> io::Result<&BinlogOperation> {
+        self.try_get(idx)?.ok_or_else(|| out_of_bounds(idx, self.len))
+    }
+
+    fn scan(&self, f: &mut dyn FnMut(usize, &BinlogOperation)) -> io::Result<()> {
+        self.for_each(0..self.len, |idx, op| {
+            f(idx, op);
+            ControlFlow::Continue(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::insert_id;
+
+    fn insert_op(id: usize) -> BinlogOperation {
+        insert_id("251108 10:00:00", id)
+    }
+
+    #[test]
+    fn test_spilled_operations_page_in_around_cursor() {
+        let mut store = OperationStore::in_memory((0..100).map(insert_op).collect()).with_limit(30).unwrap();
+        assert!(store.is_spilled());
+        let path = store.spill.as_ref().unwrap().path.clone();
+        let id = |op: &BinlogOperation| op.after_values.clone().unwrap().remove(0);
+
+        assert_eq!(id(store.operation(57).unwrap()), "57");
+        let ids: Vec<String> = store.range(8..12).unwrap().iter().map(id).collect();
+        assert_eq!(ids, vec!["8", "9", "10", "11"]);
+        store.set(57, insert_op(1000));
+
+        store.evict_around(95);
+        let loaded = store.pages.iter().filter(|page| page.get().is_some()).count();
+        assert_eq!(loaded, 0);
+        assert_eq!(id(store.operation(57).unwrap()), "1000");
+
+        store.extend((100..115).map(insert_op).collect()).unwrap();
+        let ids: Vec<String> = store.range(98..115).unwrap().iter().map(id).collect();
+        assert_eq!(ids, (98..115).map(|i| i.to_string()).collect::<Vec<_>>());
+
+        let store = store.with_limit(1000).unwrap();
+        assert!(!store.is_spilled() && !path.exists());
+        assert_eq!((store.len(), id(store.operation(57).unwrap())), (115, "1000".to_string()));
+    }
+
+    #[test]
+    fn test_unreadable_spill_file_is_an_error() {
+        let store = OperationStore::in_memory((0..100).map(insert_op).collect()).with_limit(30).unwrap();
+        std::fs::remove_file(&store.spill.as_ref().unwrap().path).unwrap();
+
+        assert!(store.try_get(57).is_err());
+        assert!(store.get(57).is_none());
+        assert!(store.range(0..100).is_err());
+        assert!(store.for_each(0..100, |_, _| ControlFlow::Continue(())).is_err());
+    }
+}
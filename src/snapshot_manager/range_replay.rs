@@ -0,0 +1,75 @@
+use duckdb::Connection;
+use crate::binlog::BinlogOperation;
+use crate::snapshot_manager::SnapshotManager;
+
+impl SnapshotManager {
+    /// Goes to `from_timestamp`, then steps one operation at a time to `to_timestamp`, calling `f`
+    /// after each step with the operation stepped over and the connection. Timestamps resolve to
+    /// positions like goto_timestamp. If `to_timestamp` is earlier, it steps backward and passes
+    /// each operation as it was logged, once it has been undone. Returns the number of steps.
+    ///
+    /// `f` is not called for the state at `from_timestamp` itself; query it before if needed.
+    pub fn for_each_between<F>(
+        &mut self,
+        from_timestamp: &str,
+        to_timestamp: &str,
+        mut f: F,
+    ) -> Result<usize, Box<dyn std::error::Error>>
+    where
+        F: FnMut(&BinlogOperation, &Connection) -> Result<(), Box<dyn std::error::Error>>,
+    {
+        let from = self.position_at_timestamp(from_timestamp)?;
+        let to = self.position_at_timestamp(to_timestamp)?;
+        self.check_continuity(from, to)?;
+        self.goto_position(from)?;
+
+        while self.get_position() != to {
+            let position = self.get_position();
+            let op_idx = if to > position {
+                self.step_forward()?;
+                position + 1
+            } else {
+                self.step_backward()?;
+                position
+            };
+            let op = self.get_operation(op_idx).ok_or("Position out of bounds")?;
+            f(op, self.get_connection())?;
+        }
+        Ok(from.abs_diff(to))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use duckdb::Connection;
+    use crate::binlog::BinlogOperation;
+    use crate::snapshot_manager::SnapshotManager;
+    use crate::test_support::{books_db, insert_id};
+
+    fn insert(id: usize) -> BinlogOperation {
+        insert_id(&format!("251108 1{}:00:00", id), id)
+    }
+
+    #[test]
+    fn test_for_each_between_visits_each_operation_in_either_direction() {
+        let conn = books_db("id INTEGER PRIMARY KEY", "(0)");
+        let mut manager = SnapshotManager::new(conn, (0..5).map(insert).collect(), 0);
+        let count = |conn: &Connection| conn.query_row("SELECT COUNT(*) FROM books", [], |row| row.get::<_, i64>(0)).unwrap();
+
+        let mut visited = Vec::new();
+        let steps = manager.for_each_between("251108 11:00:00", "251108 13:30:00", |op, conn| {
+            visited.push((op.after_values.clone().unwrap()[0].clone(), count(conn)));
+            Ok(())
+        }).unwrap();
+        assert_eq!(steps, 2);
+        assert_eq!(visited, vec![("2".to_string(), 3), ("3".to_string(), 4)]);
+
+        let mut visited = Vec::new();
+        manager.for_each_between("251108 14:00:00", "251108 12:00:00", |op, conn| {
+            visited.push((op.after_values.clone().unwrap()[0].clone(), count(conn)));
+            Ok(())
+        }).unwrap();
+        assert_eq!(visited, vec![("4".to_string(), 4), ("3".to_string(), 3)]);
+        assert_eq!(manager.get_position(), 2);
+    }
+}
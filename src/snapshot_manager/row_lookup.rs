@@ -0,0 +1,136 @@
+use crate::binlog::{BinlogOperation, OperationType};
+use crate::snapshot_manager::SnapshotManager;
+
+/// (column, SQL literal) for every column of a row, in table column order
+pub type RowValues = Vec<(String, String)>;
+
+impl SnapshotManager {
+    /// State of one row of `table` at a timestamp, or None if the row doesn't exist then.
+    /// `key` holds the row's primary key values as SQL literals, like for subscribe.
+    ///
+    /// Starts from the row as it is at the table's current position and replays only the
+    /// operations that touch it, so the snapshot doesn't move.
+    pub fn row_at(
+        &mut self,
+        table: &str,
+        key: &[&str],
+        timestamp: &str,
+    ) -> Result<Option<RowValues>, Box<dyn std::error::Error>> {
+        let target_position = self.position_at_timestamp(timestamp)?;
+        self.row_at_position(table, key, target_position)
+    }
+
+    /// Like row_at, at an operation position
+    pub fn row_at_position(
+        &mut self,
+        table: &str,
+        key: &[&str],
+        target_position: usize,
+    ) -> Result<Option<RowValues>, Box<dyn std::error::Error>> {
+        if target_position >= self.operation_count() {
+            return Err("Target position out of bounds".into());
+        }
+        let columns = self.table_columns(table)?;
+        if columns.is_empty() {
+            return Err(format!("Unknown table {}", table).into());
+        }
+        let key_columns = self.primary_key_columns(table, &columns)?;
+        if key_columns.len() != key.len() {
+            return Err(format!("{} has key columns {:?}, got {} value(s)", table, key_columns, key.len()).into());
+        }
+        let key: Vec<String> = key.iter().map(|value| value.to_string()).collect();
+
+        let mut row = self.applier.fetch_row_by_key(table, &key_columns, &key)?;
+        let position = self.get_table_position(table);
+        let touches_row = |op: &BinlogOperation| op.table_name == table;
+
+        if target_position > position {
+            self.for_each_operation(position + 1, target_position + 1, |_, op| {
+                if touches_row(op) {
+                    replay(&mut row, op, &columns, &key_columns, &key);
+                }
+                Ok(())
+            })?;
+        } else {
+            // Undone last to first, so only the operations on the table are kept to be reversed
+            let mut undone = Vec::new();
+            self.for_each_operation(target_position + 1, position + 1, |_, op| {
+                if touches_row(op) {
+                    undone.push(op.invert());
+                }
+                Ok(())
+            })?;
+            for op in undone.iter().rev() {
+                replay(&mut row, op, &columns, &key_columns, &key);
+            }
+        }
+
+        Ok(row.map(|values| columns.into_iter().zip(values).collect()))
+    }
+}
+
+/// Applies `op` to the row if one of its images has the row's key. An UPDATE that changes the
+/// key moves the row out of (or into) the looked-up key.
+fn replay(row: &mut Option<Vec<String>>, op: &BinlogOperation, columns: &[String], key_columns: &[String], key: &[String]) {
+    let has_key = |image: &Option<Vec<String>>| image.as_ref().is_some_and(|values| {
+        key_columns.iter().zip(key).all(|(key_column, expected)| {
+            op.columns.iter().position(|col| col == key_column).and_then(|i| values.get(i)) == Some(expected)
+        })
+    });
+    let before_matches = has_key(&op.before_values);
+    let after_matches = has_key(&op.after_values);
+    if !before_matches && !after_matches {
+        return;
+    }
+
+    if op.operation_type == OperationType::Delete || !after_matches {
+        *row = None;
+        return;
+    }
+
+    // Partial row images only hold some columns; the others keep their values
+    let values = row.get_or_insert_with(|| vec!["NULL".to_string(); columns.len()]);
+    if let Some(after) = &op.after_values {
+        for (col, value) in op.columns.iter().zip(after) {
+            if let Some(i) = columns.iter().position(|c| c == col) {
+                values[i] = value.clone();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::binlog::OperationType;
+    use crate::snapshot_manager::SnapshotManager;
+    use crate::test_support::{books_db, books_op};
+
+    #[test]
+    fn test_row_at_replays_only_the_row_without_moving() {
+        let conn = books_db("id INTEGER PRIMARY KEY, price INTEGER", "(1, 11), (2, 20)");
+        let operations = vec![
+            books_op(OperationType::Insert, "251108 10:00:00", None, Some(["1", "10"])),
+            books_op(OperationType::Update, "251108 11:00:00", Some(["1", "10"]), Some(["1", "11"])),
+            books_op(OperationType::Update, "251108 12:00:00", Some(["2", "20"]), Some(["2", "21"])),
+            books_op(OperationType::Update, "251108 13:00:00", Some(["1", "11"]), Some(["1", "12"])),
+            books_op(OperationType::Delete, "251108 14:00:00", Some(["1", "12"]), None),
+        ];
+        let mut manager = SnapshotManager::new(conn, operations, 1);
+
+        let price_at = |manager: &mut SnapshotManager, timestamp: &str| {
+            manager.row_at("books", &["1"], timestamp).unwrap().map(|row| row[1].1.clone())
+        };
+        assert_eq!(price_at(&mut manager, "251108 13:30:00"), Some("12".to_string()));
+        assert_eq!(price_at(&mut manager, "251108 14:00:00"), None);
+        assert_eq!(price_at(&mut manager, "251108 10:30:00"), Some("10".to_string()));
+        assert_eq!(
+            manager.row_at("books", &["2"], "251108 12:00:00").unwrap(),
+            Some(vec![("id".to_string(), "2".to_string()), ("price".to_string(), "21".to_string())])
+        );
+        assert!(manager.row_at("books", &["1", "2"], "251108 12:00:00").is_err());
+
+        assert_eq!(manager.get_position(), 1);
+        let price: i64 = manager.get_connection().query_row("SELECT price FROM books WHERE id = 1", [], |row| row.get(0)).unwrap();
+        assert_eq!(price, 11);
+    }
+}
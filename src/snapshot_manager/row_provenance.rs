@@ -0,0 +1,246 @@
+use duckdb::params;
+use std::collections::{HashMap, HashSet};
+use crate::binlog::{quote_identifier, BinlogOperation, OperationType};
+use crate::snapshot_manager::SnapshotManager;
+
+/// The operation that last touched a row, up to the current position
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowProvenance {
+    pub position: usize,
+    pub timestamp: Option<String>,
+    /// A DELETE means the row doesn't exist at the current position
+    pub operation_type: OperationType,
+}
+
+/// Table name and primary key values (SQL literals, joined with U+001F) of a row
+type RowId = (String, String);
+
+/// Positions of the operations touching each row, and the position the provenance tables are at
+pub(super) struct ProvenanceTracker {
+    touches: HashMap<RowId, Vec<usize>>,
+    op_rows: Vec<Vec<RowId>>,
+    position: usize,
+    // Primary key columns of each table with a provenance table
+    key_columns: HashMap<String, Vec<String>>,
+}
+
+impl ProvenanceTracker {
+    /// Records the rows operation `idx` touches, creating the provenance table of a table seen for
+    /// the first time. Operations are added in order.
+    fn add(&mut self, manager: &SnapshotManager, idx: usize, op: &BinlogOperation) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.key_columns.contains_key(&op.table_name) {
+            let columns = manager.table_columns(&op.table_name)?;
+            let key_columns = manager.primary_key_columns(&op.table_name, &columns)?;
+            manager.get_connection().execute_batch(&format!(
+                "CREATE OR REPLACE TEMP TABLE {} (row_key VARCHAR PRIMARY KEY, position UBIGINT, timestamp VARCHAR, operation VARCHAR);",
+                provenance_table(&op.table_name)
+            ))?;
+            self.key_columns.insert(op.table_name.clone(), key_columns);
+        }
+        let rows = row_keys(op, &self.key_columns[&op.table_name]);
+        for row in &rows {
+            self.touches.entry(row.clone()).or_default().push(idx);
+        }
+        self.op_rows.push(rows);
+        Ok(())
+    }
+
+    /// Last operation touching `row` at or before `position`
+    fn last_touch(&self, row: &RowId, position: usize) -> Option<usize> {
+        let touches = self.touches.get(row)?;
+        let end = touches.partition_point(|&idx| idx <= position);
+        end.checked_sub(1).map(|i| touches[i])
+    }
+}
+
+/// Keys of the rows an operation touches: its before image's and, if the key changed, its after
+/// image's. Partial images may lack the key on one side, and then count for the other.
+fn row_keys(op: &BinlogOperation, key_columns: &[String]) -> Vec<RowId> {
+    let key = |values: Option<&Vec<String>>, before: bool| -> Option<String> {
+        let values = values?;
+        let parts = key_columns.iter()
+            .map(|key| {
+                if before && op.row_image.missing_before().contains(key) {
+                    return None;
+                }
+                let idx = op.columns.iter().position(|c| c == key)?;
+                values.get(idx).filter(|value| *value != "NULL").map(|value| value.as_str())
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(parts.join("\u{1f}"))
+    };
+    let mut keys: Vec<RowId> = Vec::new();
+    for row_key in [key(op.before_values.as_ref(), true), key(op.after_values.as_ref(), false)].into_iter().flatten() {
+        let row = (op.table_name.clone(), row_key);
+        if !keys.contains(&row) {
+            keys.push(row);
+        }
+    }
+    keys
+}
+
+fn provenance_table(table: &str) -> String {
+    quote_identifier(&format!("{}__provenance", table))
+}
+
+impl SnapshotManager {
+    /// Starts recording, for every row the binlog touches, the position and timestamp of the last
+    /// operation applied to it, in a temporary `<table>__provenance` table per table (row_key,
+    /// position, timestamp, operation). They are kept up to date as the global position moves,
+    /// which costs a write per touched row, so tracking is off until enabled. Rows are identified
+    /// by primary key (or first column).
+    pub fn enable_provenance(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let mut tracker = ProvenanceTracker {
+            touches: HashMap::new(),
+            op_rows: Vec::with_capacity(self.operation_count()),
+            position: self.get_position(),
+            key_columns: HashMap::new(),
+        };
+        self.for_each_operation(0, self.operation_count(), |idx, op| tracker.add(self, idx, op))?;
+        let position = self.get_position();
+        let rows: Vec<&RowId> = tracker.touches.keys()
+            .filter(|row| tracker.last_touch(row, position).is_some())
+            .collect();
+        self.write_provenance(&tracker, &rows)?;
+        self.provenance = Some(tracker);
+        Ok(())
+    }
+
+    /// The operation that last touched the row of `table` with these primary key values (SQL
+    /// literals, like binlog values: `42`, `'abc'`), up to the current position. None if provenance
+    /// isn't enabled or no operation up to here touched the row, i.e. it is as in the snapshot.
+    /// Follows the global position, not per-table cursors.
+    pub fn provenance(&self, table: &str, key_values: &[&str]) -> Result<Option<RowProvenance>, Box<dyn std::error::Error>> {
+        if self.provenance.is_none() {
+            return Ok(None);
+        }
+        let mut stmt = self.get_connection().prepare(&format!(
+            "SELECT position FROM {} WHERE row_key = ?",
+            provenance_table(table)
+        ))?;
+        let mut rows = stmt.query([key_values.join("\u{1f}")])?;
+        let Some(row) = rows.next()? else {
+            return Ok(None);
+        };
+        let position: u64 = row.get(0)?;
+        let op = self.get_operation(position as usize).ok_or("Provenance position out of bounds")?;
+        Ok(Some(RowProvenance {
+            position: position as usize,
+            timestamp: op.timestamp.clone(),
+            operation_type: op.operation_type.clone(),
+        }))
+    }
+
+    /// Records the rows touched by operations appended since provenance was enabled. They come
+    /// after the current position, so the provenance tables only change once it moves onto them.
+    pub(super) fn extend_provenance(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(mut tracker) = self.provenance.take() else {
+            return Ok(());
+        };
+        let result = self.for_each_operation(tracker.op_rows.len(), self.operation_count(), |idx, op| tracker.add(self, idx, op));
+        self.provenance = Some(tracker);
+        result
+    }
+
+    /// Brings the provenance tables to the current position, rewriting the rows touched by the
+    /// operations between the position they were at and this one
+    pub(super) fn sync_provenance(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(mut tracker) = self.provenance.take() else {
+            return Ok(());
+        };
+        let (from, to) = (tracker.position, self.get_position());
+        let (lo, hi) = if to > from { (from + 1, to) } else { (to + 1, from) };
+        let mut seen = HashSet::new();
+        let rows: Vec<&RowId> = (lo..=hi)
+            .flat_map(|idx| tracker.op_rows[idx].iter())
+            .filter(|row| seen.insert(*row))
+            .collect();
+        let result = self.write_provenance(&tracker, &rows);
+        tracker.position = to;
+        self.provenance = Some(tracker);
+        result
+    }
+
+    fn write_provenance(&self, tracker: &ProvenanceTracker, rows: &[&RowId]) -> Result<(), Box<dyn std::error::Error>> {
+        let position = self.get_position();
+        let conn = self.get_connection();
+        let write = || -> Result<(), Box<dyn std::error::Error>> {
+            for row in rows {
+                let (table, row_key) = row;
+                match tracker.last_touch(row, position).and_then(|idx| Some((idx, self.get_operation(idx)?))) {
+                    Some((idx, op)) => {
+                        conn.execute(
+                            &format!("INSERT OR REPLACE INTO {} VALUES (?, ?, ?, ?)", provenance_table(table)),
+                            params![row_key, idx as u64, op.timestamp, op.operation_type.to_string()],
+                        )?;
+                    }
+                    None => {
+                        conn.execute(&format!("DELETE FROM {} WHERE row_key = ?", provenance_table(table)), [row_key])?;
+                    }
+                }
+            }
+            Ok(())
+        };
+        conn.execute_batch("BEGIN TRANSACTION;")?;
+        match write() {
+            Ok(()) => conn.execute_batch("COMMIT;")?,
+            Err(e) => {
+                conn.execute_batch("ROLLBACK;")?;
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::binlog::OperationType;
+    use crate::snapshot_manager::SnapshotManager;
+    use crate::test_support::{books_db, books_op};
+
+    #[test]
+    fn test_provenance_follows_navigation() {
+        let conn = books_db("id INTEGER PRIMARY KEY, price INTEGER", "(1, 10)");
+        let operations = vec![
+            books_op(OperationType::Insert, "251108 10:00:00", None, Some(["1", "10"])),
+            books_op(OperationType::Update, "251108 11:00:00", Some(["1", "10"]), Some(["1", "11"])),
+            books_op(OperationType::Insert, "251108 12:00:00", None, Some(["2", "20"])),
+            books_op(OperationType::Delete, "251108 13:00:00", Some(["2", "20"]), None),
+        ];
+        let mut manager = SnapshotManager::new(conn, operations, 0);
+        assert_eq!(manager.provenance("books", &["1"]).unwrap(), None);
+        manager.enable_provenance().unwrap();
+        assert_eq!(manager.provenance("books", &["1"]).unwrap().unwrap().position, 0);
+
+        manager.goto_position(2).unwrap();
+        let row1 = manager.provenance("books", &["1"]).unwrap().unwrap();
+        assert_eq!((row1.position, row1.timestamp.as_deref()), (1, Some("251108 11:00:00")));
+        assert_eq!(manager.provenance("books", &["2"]).unwrap().unwrap().position, 2);
+
+        manager.step_forward().unwrap();
+        assert_eq!(manager.provenance("books", &["2"]).unwrap().unwrap().operation_type, OperationType::Delete);
+
+        manager.goto_position(1).unwrap();
+        assert_eq!(manager.provenance("books", &["2"]).unwrap(), None);
+        assert_eq!(manager.list_tables().unwrap(), vec!["books".to_string()]);
+    }
+
+    #[test]
+    fn test_provenance_covers_appended_operations() {
+        let conn = books_db("id INTEGER PRIMARY KEY, price INTEGER", "");
+        let mut manager = SnapshotManager::new(conn, vec![books_op(OperationType::Insert, "251108 10:00:00", None, Some(["1", "10"]))], 0);
+        manager.enable_provenance().unwrap();
+
+        manager.append_operations(vec![
+            books_op(OperationType::Update, "251108 11:00:00", Some(["1", "10"]), Some(["1", "11"])),
+            books_op(OperationType::Insert, "251108 12:00:00", None, Some(["2", "20"])),
+        ]).unwrap();
+        assert_eq!(manager.provenance("books", &["1"]).unwrap().unwrap().position, 0);
+
+        manager.step_forward().unwrap();
+        manager.step_forward().unwrap();
+        assert_eq!(manager.provenance("books", &["1"]).unwrap().unwrap().position, 1);
+        assert_eq!(manager.provenance("books", &["2"]).unwrap().unwrap().position, 2);
+    }
+}
@@ -0,0 +1,270 @@
+use chrono::{DateTime, TimeDelta};
+use duckdb::types::{TimeUnit, Value};
+use serde::Serialize;
+use serde_json::{Map, Value as JsonValue};
+use crate::binlog::quote_identifier;
+use crate::operation_applier::StateStore;
+use crate::snapshot_manager::SnapshotManager;
+
+/// Differences in one table between two positions, keyed by primary key
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableDiff {
+    pub table: String,
+    /// Columns used to match rows between the two positions
+    pub key_columns: Vec<String>,
+    /// All columns of the table, in the order values appear in each row
+    pub columns: Vec<String>,
+    /// Rows present at position B but not at position A
+    pub inserted: Vec<Vec<Value>>,
+    /// Rows present at position A but not at position B
+    pub deleted: Vec<Vec<Value>>,
+    /// (row at A, row at B) for rows whose key exists at both positions but whose values differ
+    pub modified: Vec<(Vec<Value>, Vec<Value>)>,
+}
+
+impl TableDiff {
+    pub fn is_empty(&self) -> bool {
+        self.inserted.is_empty() && self.deleted.is_empty() && self.modified.is_empty()
+    }
+
+    /// The diff with every row keyed by column name, for serialising
+    pub fn to_change_set(&self) -> ChangeSet {
+        let row = |values: &Vec<Value>| -> Row {
+            self.columns.iter().cloned().zip(values.iter().map(json_value)).collect()
+        };
+        ChangeSet {
+            table: self.table.clone(),
+            key_columns: self.key_columns.clone(),
+            inserted: self.inserted.iter().map(row).collect(),
+            deleted: self.deleted.iter().map(row).collect(),
+            modified: self.modified.iter().map(|(before, after)| (row(before), row(after))).collect(),
+        }
+    }
+}
+
+/// A row as a JSON object keyed by column name
+pub type Row = Map<String, JsonValue>;
+
+/// Machine-readable form of a TableDiff, which serialises to JSON as
+/// `{"table", "key_columns", "inserted": [row], "deleted": [row], "modified": [[before, after]]}`.
+///
+/// Numbers, booleans and strings keep their JSON types; dates and timestamps are ISO 8601
+/// strings, blobs hex strings and other types their debug text.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChangeSet {
+    pub table: String,
+    pub key_columns: Vec<String>,
+    pub inserted: Vec<Row>,
+    pub deleted: Vec<Row>,
+    pub modified: Vec<(Row, Row)>,
+}
+
+impl ChangeSet {
+    pub fn is_empty(&self) -> bool {
+        self.inserted.is_empty() && self.deleted.is_empty() && self.modified.is_empty()
+    }
+
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(self)
+    }
+}
+
+impl From<&TableDiff> for ChangeSet {
+    fn from(diff: &TableDiff) -> Self {
+        diff.to_change_set()
+    }
+}
+
+pub(crate) fn json_value(value: &Value) -> JsonValue {
+    match value {
+        Value::Null => JsonValue::Null,
+        Value::Boolean(b) => (*b).into(),
+        Value::TinyInt(n) => (*n).into(),
+        Value::SmallInt(n) => (*n).into(),
+        Value::Int(n) => (*n).into(),
+        Value::BigInt(n) => (*n).into(),
+        Value::UTinyInt(n) => (*n).into(),
+        Value::USmallInt(n) => (*n).into(),
+        Value::UInt(n) => (*n).into(),
+        Value::UBigInt(n) => (*n).into(),
+        Value::Float(n) => (*n).into(),
+        Value::Double(n) => (*n).into(),
+        // Too wide or too precise for JSON numbers
+        Value::HugeInt(n) => n.to_string().into(),
+        Value::Decimal(n) => n.to_string().into(),
+        Value::Text(s) | Value::Enum(s) => s.clone().into(),
+        Value::Blob(bytes) => bytes.iter().map(|b| format!("{:02x}", b)).collect::<String>().into(),
+        Value::Date32(days) => (DateTime::UNIX_EPOCH.date_naive() + TimeDelta::days(*days as i64)).to_string().into(),
+        Value::Timestamp(unit, n) => {
+            let micros = match unit {
+                TimeUnit::Second => n * 1_000_000,
+                TimeUnit::Millisecond => n * 1_000,
+                TimeUnit::Microsecond => *n,
+                TimeUnit::Nanosecond => n / 1_000,
+            };
+            match DateTime::from_timestamp_micros(micros) {
+                Some(timestamp) => timestamp.naive_utc().format("%Y-%m-%dT%H:%M:%S%.f").to_string().into(),
+                None => format!("{:?}", value).into(),
+            }
+        }
+        Value::List(values) | Value::Array(values) => values.iter().map(json_value).collect::<Vec<_>>().into(),
+        other => format!("{:?}", other).into(),
+    }
+}
+
+impl SnapshotManager {
+    /// Compares a table between two positions.
+    ///
+    /// The table is copied to a temporary table at `position_a`, the snapshot is moved to `position_b`,
+    /// and the two are joined on the table's primary key (or its first column if it has none).
+    /// The snapshot is moved back to its previous position afterwards. See TableDiff::to_change_set
+    /// for a serialisable form.
+    pub fn diff(&mut self, position_a: usize, position_b: usize, table: &str) -> Result<TableDiff, Box<dyn std::error::Error>> {
+        self.check_continuity(position_a, position_b)?;
+        let columns = self.table_columns(table)?;
+        if columns.is_empty() {
+            return Err(format!("Table {} not found", table).into());
+        }
+        let key_columns = self.primary_key_columns(table, &columns)?;
+
+        let previous_position = self.get_position();
+        let snapshot_table = format!("{}__diff_a", table);
+
+        self.goto_position(position_a)?;
+        self.get_connection().execute_batch(&format!(
+            "CREATE OR REPLACE TEMP TABLE {} AS SELECT * FROM {};",
+            quote_identifier(&snapshot_table), quote_identifier(table)
+        ))?;
+        self.goto_position(position_b)?;
+
+        let result = self.compare_tables(&snapshot_table, table, &columns, &key_columns);
+
+        self.get_connection().execute_batch(&format!("DROP TABLE IF EXISTS {};", quote_identifier(&snapshot_table)))?;
+        self.goto_position(previous_position)?;
+
+        let (inserted, deleted, modified) = result?;
+        Ok(TableDiff {
+            table: table.to_string(),
+            key_columns,
+            columns,
+            inserted,
+            deleted,
+            modified,
+        })
+    }
+
+    /// (rows only in `table_b`, rows only in `table_a`, (row in a, row in b) for rows whose
+    /// values differ), matched on `key_columns`
+    #[allow(clippy::type_complexity)]
+    pub(super) fn compare_tables(
+        &self,
+        table_a: &str,
+        table_b: &str,
+        columns: &[String],
+        key_columns: &[String],
+    ) -> Result<(Vec<Vec<Value>>, Vec<Vec<Value>>, Vec<(Vec<Value>, Vec<Value>)>), Box<dyn std::error::Error>> {
+        let (table_a, table_b) = (quote_identifier(table_a), quote_identifier(table_b));
+        let columns: Vec<String> = columns.iter().map(|col| quote_identifier(col)).collect();
+        let key_columns: Vec<String> = key_columns.iter().map(|col| quote_identifier(col)).collect();
+        let key_match = key_columns.iter()
+            .map(|col| format!("a.{} = b.{}", col, col))
+            .collect::<Vec<_>>()
+            .join(" AND ");
+        let order_by = key_columns.join(", ");
+
+        let inserted = self.query(&format!(
+            "SELECT b.* FROM {} b WHERE NOT EXISTS (SELECT 1 FROM {} a WHERE {}) ORDER BY {}",
+            table_b, table_a, key_match, order_by
+        ))?;
+        let deleted = self.query(&format!(
+            "SELECT a.* FROM {} a WHERE NOT EXISTS (SELECT 1 FROM {} b WHERE {}) ORDER BY {}",
+            table_a, table_b, key_match, order_by
+        ))?;
+
+        let any_changed = columns.iter()
+            .map(|col| format!("a.{} IS DISTINCT FROM b.{}", col, col))
+            .collect::<Vec<_>>()
+            .join(" OR ");
+        let a_columns = columns.iter().map(|col| format!("a.{}", col)).collect::<Vec<_>>().join(", ");
+        let b_columns = columns.iter().map(|col| format!("b.{}", col)).collect::<Vec<_>>().join(", ");
+        let modified = self.query(&format!(
+            "SELECT {}, {} FROM {} a JOIN {} b ON {} WHERE {} ORDER BY {}",
+            a_columns,
+            b_columns,
+            table_a,
+            table_b,
+            key_match,
+            any_changed,
+            key_columns.iter().map(|col| format!("a.{}", col)).collect::<Vec<_>>().join(", ")
+        ))?
+            .into_iter()
+            .map(|mut row| {
+                let after = row.split_off(columns.len());
+                (row, after)
+            })
+            .collect();
+
+        Ok((inserted, deleted, modified))
+    }
+
+    pub(crate) fn table_columns(&self, table: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        self.get_connection().table_columns(table)
+    }
+
+    /// Primary key columns from the table's constraints, or the first column if it has no primary key
+    /// (parquet snapshots carry no constraints, and the first column is usually the id)
+    pub(crate) fn primary_key_columns(&self, table: &str, columns: &[String]) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let mut stmt = self.get_connection().prepare(
+            "SELECT unnest(constraint_column_names) FROM duckdb_constraints() \
+             WHERE table_name = ? AND constraint_type = 'PRIMARY KEY'"
+        )?;
+        let key_columns = stmt
+            .query_map([table], |row| row.get::<usize, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if key_columns.is_empty() {
+            Ok(columns.iter().take(1).cloned().collect())
+        } else {
+            Ok(key_columns)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binlog::OperationType;
+    use crate::test_support::{books_db, books_op};
+
+    #[test]
+    fn test_diff_reports_inserted_deleted_and_modified_rows() {
+        let conn = books_db("id INTEGER PRIMARY KEY, price INTEGER", "(1, 10), (2, 20)");
+        let operations = vec![
+            books_op(OperationType::Insert, "251108 10:00:00", None, Some(["1", "10"])),
+            books_op(OperationType::Update, "251108 10:00:00", Some(["1", "10"]), Some(["1", "15"])),
+            books_op(OperationType::Delete, "251108 10:00:00", Some(["2", "20"]), None),
+            books_op(OperationType::Insert, "251108 10:00:00", None, Some(["3", "30"])),
+        ];
+        let mut manager = SnapshotManager::new(conn, operations, 0);
+
+        let diff = manager.diff(0, 3, "books").unwrap();
+
+        assert_eq!(diff.key_columns, vec!["id".to_string()]);
+        assert_eq!(diff.inserted, vec![vec![Value::Int(3), Value::Int(30)]]);
+        assert_eq!(diff.deleted, vec![vec![Value::Int(2), Value::Int(20)]]);
+        assert_eq!(diff.modified, vec![(
+            vec![Value::Int(1), Value::Int(10)],
+            vec![Value::Int(1), Value::Int(15)],
+        )]);
+        assert_eq!(manager.get_position(), 0);
+
+        assert!(manager.diff(2, 2, "books").unwrap().is_empty());
+
+        let change_set = diff.to_change_set();
+        assert_eq!(change_set.inserted[0]["price"], 30);
+        assert_eq!(
+            change_set.to_json().unwrap(),
+            r#"{"table":"books","key_columns":["id"],"inserted":[{"id":3,"price":30}],"deleted":[{"id":2,"price":20}],"modified":[[{"id":1,"price":10},{"id":1,"price":15}]]}"#
+        );
+    }
+}
@@ -0,0 +1,149 @@
+use duckdb::arrow::record_batch::RecordBatch;
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde_json::json;
+use crate::binlog::quote_identifier;
+use crate::snapshot_manager::SnapshotManager;
+
+/// Name of the manifest written next to the table directories of an exported snapshot
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// An exported table
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportedTable {
+    pub table: String,
+    pub file: PathBuf,
+    pub rows: usize,
+    /// Position the table was exported at, which differs from the global position only in
+    /// CursorMode::Independent
+    pub position: usize,
+}
+
+/// What export_snapshot wrote
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnapshotExport {
+    pub position: usize,
+    pub timestamp: Option<String>,
+    /// In table name order
+    pub tables: Vec<ExportedTable>,
+}
+
+impl SnapshotExport {
+    fn manifest(&self, dir: &Path) -> serde_json::Value {
+        let tables: Vec<serde_json::Value> = self.tables.iter()
+            .map(|exported| json!({
+                "table": exported.table,
+                "file": exported.file.strip_prefix(dir).unwrap_or(&exported.file).to_string_lossy(),
+                "rows": exported.rows,
+                "position": exported.position,
+            }))
+            .collect();
+        json!({
+            "pensieve_version": env!("CARGO_PKG_VERSION"),
+            "position": self.position,
+            "timestamp": self.timestamp,
+            "tables": tables,
+        })
+    }
+}
+
+impl SnapshotManager {
+    /// Writes every loaded table, as of the current position, to `<dir>/<table>/snapshot.parquet`
+    /// and describes the export in `<dir>/manifest.json` (position, timestamp, tables and row counts).
+    ///
+    /// The layout is the one of a db_data directory, so an "as of" snapshot can be handed to other
+    /// tools, or loaded by Pensieve again once binlog files are added.
+    pub fn export_snapshot(&self, dir: &Path) -> Result<SnapshotExport, Box<dyn std::error::Error>> {
+        let mut tables = Vec::new();
+        for table in self.list_tables()? {
+            let table_dir = dir.join(&table);
+            fs::create_dir_all(&table_dir)?;
+            let file = table_dir.join("snapshot.parquet");
+            let rows = self.materialise(&format!("SELECT * FROM {}", quote_identifier(&table)), &file.to_string_lossy())?;
+            let position = self.get_table_position(&table);
+            tables.push(ExportedTable { table, file, rows, position });
+        }
+
+        let export = SnapshotExport {
+            position: self.get_position(),
+            timestamp: self.get_timestamp().cloned(),
+            tables,
+        };
+        fs::write(dir.join(MANIFEST_FILE), serde_json::to_string_pretty(&export.manifest(dir))?)?;
+        Ok(export)
+    }
+
+    /// The rows of a table as of the current position (its own position in
+    /// CursorMode::Independent), as Arrow record batches straight from DuckDB, to hand to
+    /// polars, DataFusion and the like without writing files. The batches use the arrow crate
+    /// DuckDB re-exports (`duckdb::arrow`). An empty table gives one empty batch with its schema.
+    pub fn to_arrow(&self, table: &str) -> Result<Vec<RecordBatch>, Box<dyn std::error::Error>> {
+        if !self.list_tables()?.iter().any(|t| t == table) {
+            return Err(format!("No table named {}", table).into());
+        }
+        let mut stmt = self.get_connection().prepare(&format!("SELECT * FROM {}", quote_identifier(table)))?;
+        let arrow = stmt.query_arrow([])?;
+        let schema = arrow.get_schema();
+        let batches: Vec<RecordBatch> = arrow.collect();
+        if batches.is_empty() {
+            return Ok(vec![RecordBatch::new_empty(schema)]);
+        }
+        Ok(batches)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use duckdb::Connection;
+    use crate::test_support::insert_id;
+
+    #[test]
+    fn test_export_snapshot_at_current_position() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE books (id INTEGER PRIMARY KEY);
+             CREATE TABLE authors (id INTEGER);
+             INSERT INTO books VALUES (1);"
+        ).unwrap();
+        let operations = vec![insert_id("251108 10:00:00", 1), insert_id("251108 11:00:00", 2), insert_id("251108 12:00:00", 3)];
+        let mut manager = SnapshotManager::new(conn, operations, 0);
+        manager.goto_position(1).unwrap();
+
+        let dir = std::env::temp_dir().join(format!("pensieve_export_{}", std::process::id()));
+        let export = manager.export_snapshot(&dir).unwrap();
+
+        assert_eq!(export.position, 1);
+        assert_eq!(export.tables.iter().map(|t| (t.table.as_str(), t.rows)).collect::<Vec<_>>(), vec![("authors", 0), ("books", 2)]);
+        let count: i64 = Connection::open_in_memory().unwrap().query_row(
+            &format!("SELECT COUNT(*) FROM read_parquet('{}')", dir.join("books").join("snapshot.parquet").display()),
+            [],
+            |row| row.get(0),
+        ).unwrap();
+        assert_eq!(count, 2);
+
+        let manifest: serde_json::Value = serde_json::from_str(&fs::read_to_string(dir.join(MANIFEST_FILE)).unwrap()).unwrap();
+        assert_eq!(manifest["position"], 1);
+        assert_eq!(manifest["timestamp"], "251108 11:00:00");
+        assert_eq!(manifest["tables"][1]["file"], Path::new("books").join("snapshot.parquet").to_string_lossy().as_ref());
+        assert_eq!(manifest["tables"][1]["rows"], 2);
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_to_arrow_returns_table_at_current_position() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE books (id INTEGER PRIMARY KEY); CREATE TABLE authors (id INTEGER); INSERT INTO books VALUES (1);").unwrap();
+        let operations = vec![insert_id("251108 10:00:00", 1), insert_id("251108 11:00:00", 2), insert_id("251108 12:00:00", 3)];
+        let mut manager = SnapshotManager::new(conn, operations, 0);
+        manager.goto_position(2).unwrap();
+
+        let batches = manager.to_arrow("books").unwrap();
+        assert_eq!(batches.iter().map(|batch| batch.num_rows()).sum::<usize>(), 3);
+        assert_eq!(batches[0].schema().field(0).name(), "id");
+        let authors = manager.to_arrow("authors").unwrap();
+        assert_eq!((authors.len(), authors[0].num_rows(), authors[0].num_columns()), (1, 0, 1));
+        assert!(manager.to_arrow("missing").is_err());
+    }
+}
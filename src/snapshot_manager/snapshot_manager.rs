@@ -1,21 +1,111 @@
 use duckdb::Connection;
-use crate::binlog::BinlogOperation;
-use crate::operation_applier::OperationApplier;
+use duckdb::types::Value;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::ControlFlow;
+use std::sync::Arc;
+use tracing::{debug, debug_span, warn};
+use crate::binlog::{BinlogFileMarker, BinlogOperation, BinlogTimestamp, TimestampOptions};
+use crate::operation_applier::{ApplyPolicy, MultiMatchPolicy, OperationApplier, RowComparator, StateStore};
+use crate::loader::redaction::Redactions;
+use crate::snapshot_manager::Bookmark;
+use crate::snapshot_manager::invariants::Invariants;
+use crate::snapshot_manager::operation_index::OperationIndex;
+use crate::snapshot_manager::operation_store::{OperationSource, OperationStore};
+use crate::snapshot_manager::row_provenance::ProvenanceTracker;
+use crate::snapshot_manager::snapshot_reader::NavigationLock;
+use crate::snapshot_manager::snapshot_rebuild::SnapshotSource;
+use crate::snapshot_manager::snapshot_stats::NavigationCounters;
+use crate::snapshot_manager::subscriptions::Subscriptions;
+use crate::snapshot_manager::throttle::OperationThrottle;
+use crate::snapshot_manager::undo_history::UndoHistory;
+use crate::snapshot_manager::watch::Watch;
+
+/// How table positions relate to the global position
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CursorMode {
+    /// Every table is always at the global position
+    #[default]
+    Locked,
+    /// Tables can be moved individually with goto_table_position, skipping other tables' operations.
+    /// Global navigation brings every table back to the global position first.
+    Independent,
+}
 
 /// Manages a database snapshot and enables time navigation through binlog operations
 pub struct SnapshotManager {
-    applier: OperationApplier,
-    operations: Vec<BinlogOperation>,
+    pub(super) applier: OperationApplier,
+    operations: OperationStore,
     current_position: usize,
+    cursor_mode: CursorMode,
+    // Positions of tables that have moved away from current_position (Independent mode only)
+    table_positions: HashMap<String, usize>,
+    // Built on the first goto_position that spans more than one operation
+    operation_index: Option<OperationIndex>,
+    // The binlog ends before the snapshot, which is the state after the final operation
+    backwards_only: bool,
+    // Forward steps since the last jump, undone exactly by step_backward
+    history: UndoHistory,
+    // Held for writing while operations are applied, so SnapshotReaders see whole positions
+    pub(super) navigation_lock: Arc<NavigationLock>,
+    // Set by enable_provenance
+    pub(super) provenance: Option<ProvenanceTracker>,
+    // Set by set_snapshot_source
+    pub(super) snapshot_source: Option<SnapshotSource>,
+    // Set by set_redactions
+    pub(super) redactions: Redactions,
+    // Set by set_decoded_columns
+    decoded_columns: Vec<(String, String)>,
+    // Set by set_timestamp_options
+    timestamp_options: TimestampOptions,
+    // Set by set_binlog_files
+    pub(super) binlog_files: Vec<BinlogFileMarker>,
+    // Set by set_refuse_binlog_gaps
+    pub(super) refuse_binlog_gaps: bool,
+    pub(super) watches: Vec<Watch>,
+    pub(super) bookmarks: BTreeMap<String, Bookmark>,
+    pub(super) counters: NavigationCounters,
+    pub(super) subscriptions: Subscriptions,
+    pub(super) invariants: Invariants,
+    // Set by set_max_operations_per_second
+    throttle: Option<OperationThrottle>,
 }
 
 impl SnapshotManager {
     pub fn new(conn: Connection, operations: Vec<BinlogOperation>, initial_position: usize) -> Self {
-        Self {
+        Self::with_operation_store(conn, OperationStore::in_memory(operations), initial_position)
+    }
+
+    /// Like new, with operations that may already be spilled to disk (see
+    /// set_max_operations_in_memory)
+    pub(crate) fn with_operation_store(conn: Connection, operations: OperationStore, initial_position: usize) -> Self {
+        let mut manager = Self {
             applier: OperationApplier::new(conn),
             operations,
             current_position: initial_position,
-        }
+            cursor_mode: CursorMode::default(),
+            table_positions: HashMap::new(),
+            operation_index: None,
+            backwards_only: false,
+            history: UndoHistory::default(),
+            navigation_lock: NavigationLock::new(initial_position),
+            provenance: None,
+            snapshot_source: None,
+            redactions: Redactions::new(),
+            decoded_columns: Vec::new(),
+            timestamp_options: TimestampOptions::default(),
+            binlog_files: Vec::new(),
+            refuse_binlog_gaps: false,
+            watches: Vec::new(),
+            bookmarks: BTreeMap::new(),
+            counters: NavigationCounters::default(),
+            subscriptions: Subscriptions::default(),
+            invariants: Invariants::default(),
+            throttle: None,
+        };
+        manager.counters.record_visit(manager.operations.get(initial_position).and_then(|op| op.timestamp.as_ref()));
+        manager.operations.evict_around(initial_position);
+        manager
     }
 
     pub fn get_position(&self) -> usize {
@@ -35,36 +125,279 @@ impl SnapshotManager {
         self.operations.len()
     }
 
+    /// Keeps at most about `max_operations` operations in memory. If there are more, they are
+    /// spilled to a temporary file and read back a page at a time as navigation reaches them,
+    /// keeping only the pages around the current position. Scans with for_each_operation (stats,
+    /// exports, ...) read the other pages one at a time and drop them again; get_operations_range
+    /// copies its whole range into memory. Pensieve spills while loading instead, see
+    /// PensieveConfig::max_operations_in_memory.
+    pub fn set_max_operations_in_memory(&mut self, max_operations: usize) -> Result<(), Box<dyn std::error::Error>> {
+        let operations = std::mem::replace(&mut self.operations, OperationStore::in_memory(Vec::new()));
+        self.operations = operations.with_limit(max_operations)?;
+        self.operations.evict_around(self.current_position);
+        Ok(())
+    }
+
+    /// Applies at most `max_operations_per_second` operations per second while navigating more
+    /// than one step (step_forward_by, goto_position, ...), so that long replays don't starve
+    /// other jobs sharing the machine. None removes the limit.
+    pub fn set_max_operations_per_second(&mut self, max_operations_per_second: Option<f64>) {
+        self.throttle = max_operations_per_second
+            .filter(|rate| *rate > 0.0)
+            .map(OperationThrottle::new);
+    }
+
+    /// Adds operations after the last one, e.g. transactions streamed by a LiveTail. The position
+    /// doesn't change.
+    pub fn append_operations(&mut self, operations: Vec<BinlogOperation>) -> Result<(), Box<dyn std::error::Error>> {
+        if operations.is_empty() {
+            return Ok(());
+        }
+        self.operations.extend(operations)?;
+        // Rebuilt with the new operations on the next long jump
+        self.operation_index = None;
+        self.operations.evict_around(self.current_position);
+        self.extend_provenance()
+    }
+
+    /// Whether operations were spilled to disk by set_max_operations_in_memory
+    pub fn operations_spilled(&self) -> bool {
+        self.operations.is_spilled()
+    }
+
+    pub fn get_apply_policy(&self) -> ApplyPolicy {
+        self.applier.get_policy()
+    }
+
+    /// Choose what happens when an operation's before-image doesn't match the current row
+    pub fn set_apply_policy(&mut self, policy: ApplyPolicy) {
+        self.applier.set_policy(policy);
+    }
+
+    pub fn get_multi_match_policy(&self) -> MultiMatchPolicy {
+        self.applier.get_multi_match_policy()
+    }
+
+    /// Choose what happens when an UPDATE's or DELETE's before-image matches several rows
+    pub fn set_multi_match_policy(&mut self, policy: MultiMatchPolicy) {
+        self.applier.set_multi_match_policy(policy);
+    }
+
+    /// Compare rows of `table` with `comparator` when deciding whether an operation applies,
+    /// see OperationApplier::set_comparator
+    pub fn set_comparator(&mut self, table: &str, comparator: impl RowComparator + 'static) {
+        self.applier.set_comparator(table, comparator);
+    }
+
+    /// Number of before-image mismatches per table since load
+    pub fn get_mismatch_counts(&self) -> &HashMap<String, usize> {
+        self.applier.get_mismatch_counts()
+    }
+
+    /// Number of UPDATEs and DELETEs per table whose before-image matched several rows since load
+    pub fn get_multi_match_counts(&self) -> &HashMap<String, usize> {
+        self.applier.get_multi_match_counts()
+    }
+
+    pub fn is_backwards_only(&self) -> bool {
+        self.backwards_only
+    }
+
+    /// Backwards-only mode is for binlogs that end before the snapshot was taken: the snapshot is
+    /// the state after the final operation and only earlier states can be explored. Stepping
+    /// forward from the final operation warns that the binlog has nothing after it.
+    /// The position is not changed.
+    pub fn set_backwards_only(&mut self, backwards_only: bool) {
+        self.backwards_only = backwards_only;
+    }
+
+    /// Redactions the tables were loaded with, applied to snapshot files the manager reads again
+    /// (rebuilds, reference dumps), so they are compared with redacted rows
+    pub fn set_redactions(&mut self, redactions: Redactions) {
+        self.redactions = redactions;
+    }
+
+    pub fn get_redactions(&self) -> &Redactions {
+        &self.redactions
+    }
+
+    /// (table, column) pairs whose values were decoded while loading (see ColumnDecoder), so that
+    /// exports meant to be applied to MySQL can refuse them
+    pub fn set_decoded_columns(&mut self, decoded_columns: Vec<(String, String)>) {
+        self.decoded_columns = decoded_columns;
+    }
+
+    pub fn get_decoded_columns(&self) -> &[(String, String)] {
+        &self.decoded_columns
+    }
+
+    /// Century and server timezone of the binlog's timestamps, used wherever they are shown or
+    /// converted to real time
+    pub fn set_timestamp_options(&mut self, timestamp_options: TimestampOptions) {
+        self.timestamp_options = timestamp_options;
+    }
+
+    pub fn get_timestamp_options(&self) -> &TimestampOptions {
+        &self.timestamp_options
+    }
+
+    /// Number of forward steps step_backward (and goto_position going back over them) can undo
+    /// exactly, by replaying what they did. Jumps (consolidated goto_position and per-table
+    /// navigation) clear them, and the oldest are forgotten once they hold too many values.
+    pub fn undo_depth(&self) -> usize {
+        self.history.len()
+    }
+
+    pub fn get_cursor_mode(&self) -> CursorMode {
+        self.cursor_mode
+    }
+
+    /// Switch cursor mode. Switching to Locked brings every table back to the global position.
+    pub fn set_cursor_mode(&mut self, mode: CursorMode) -> Result<(), Box<dyn std::error::Error>> {
+        if mode == CursorMode::Locked {
+            self.sync_table_cursors()?;
+        }
+        self.cursor_mode = mode;
+        Ok(())
+    }
+
+    /// Position of a single table. Equal to the global position unless the table was moved
+    /// independently.
+    pub fn get_table_position(&self, table: &str) -> usize {
+        self.table_positions.get(table).copied().unwrap_or(self.current_position)
+    }
+
+    /// Move a single table to `target_position`, applying only that table's operations.
+    /// Only available in CursorMode::Independent.
+    pub fn goto_table_position(&mut self, table: &str, target_position: usize) -> Result<(), Box<dyn std::error::Error>> {
+        if self.cursor_mode != CursorMode::Independent {
+            return Err("Per-table navigation requires CursorMode::Independent".into());
+        }
+        if target_position >= self.operations.len() {
+            return Err("Target position out of bounds".into());
+        }
+
+        self.move_table_cursor(table, target_position)?;
+        // The table's rows no longer match the steps recorded for the global position
+        self.history.clear();
+
+        if target_position == self.current_position {
+            self.table_positions.remove(table);
+        } else {
+            self.table_positions.insert(table.to_string(), target_position);
+        }
+        Ok(())
+    }
+
+    /// Move every independently positioned table back to the global position
+    fn sync_table_cursors(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let tables: Vec<String> = self.table_positions.keys().cloned().collect();
+        for table in tables {
+            self.move_table_cursor(&table, self.current_position)?;
+            self.table_positions.remove(&table);
+        }
+        Ok(())
+    }
+
+    fn move_table_cursor(&mut self, table: &str, target_position: usize) -> Result<(), Box<dyn std::error::Error>> {
+        let from = self.get_table_position(table);
+        let navigation_lock = Arc::clone(&self.navigation_lock);
+        let _guard = navigation_lock.write();
+
+        if target_position > from {
+            for idx in from + 1..=target_position {
+                if self.operations.operation(idx)?.table_name == table {
+                    self.complete_row_image(idx)?;
+                    let op = self.operations.operation(idx)?;
+                    let applied = self.applier.apply_operation_conditionally(op)?;
+                    self.counters.record_apply(applied);
+                    if applied {
+                        self.subscriptions.notify(op, idx, &self.operations)?;
+                    }
+                }
+            }
+        } else {
+            for idx in (target_position + 1..=from).rev() {
+                let op = self.operations.operation(idx)?;
+                if op.table_name == table {
+                    let inverted = op.invert();
+                    let applied = self.applier.apply_operation_conditionally(&inverted)?;
+                    self.counters.record_apply(applied);
+                    if applied {
+                        self.subscriptions.notify(&inverted, idx - 1, &self.operations)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
     pub fn step_forward(&mut self) -> Result<bool, Box<dyn std::error::Error>> {
+        self.sync_table_cursors()?;
         if self.current_position + 1 >= self.operations.len() {
+            if self.backwards_only {
+                warn!("Can't step forward: the binlog ends before the snapshot, at {:?}", self.get_timestamp());
+            }
             return Ok(false);
         }
 
-        let next_op = &self.operations[self.current_position + 1];
-        self.applier.apply_operation_conditionally(next_op)?;
+        let navigation_lock = Arc::clone(&self.navigation_lock);
+        let _guard = navigation_lock.write();
+        self.complete_row_image(self.current_position + 1)?;
+        let next_op = self.operations.operation(self.current_position + 1)?;
+        let (outcome, rows_changed) = self.applier.apply_operation_with_row_count(next_op)?;
+        let applied = outcome.applied();
+        self.counters.record_apply(applied);
+        if applied {
+            self.subscriptions.notify(next_op, self.current_position + 1, &self.operations)?;
+        }
 
         self.current_position += 1;
+        let executed = (applied && rows_changed > 0).then_some(next_op);
+        self.history.push(self.current_position, executed);
+        self.position_changed()?;
         Ok(true)
     }
 
     pub fn step_backward(&mut self) -> Result<bool, Box<dyn std::error::Error>> {
+        self.sync_table_cursors()?;
         if self.current_position == 0 {
             return Ok(false); // Already at the beginning
         }
 
-        let current_op = &self.operations[self.current_position];
-        let inverted = current_op.invert();
-        self.applier.apply_operation_conditionally(&inverted)?;
+        let navigation_lock = Arc::clone(&self.navigation_lock);
+        let _guard = navigation_lock.write();
+        // Undo what the forward step did if it was recorded, otherwise check the inverse against
+        // the current row
+        let (inverted, applied) = match self.history.pop(self.current_position) {
+            Some(Some(undo)) => {
+                self.applier.execute_operation(&undo)?;
+                (undo, true)
+            }
+            Some(None) => (self.operations.operation(self.current_position)?.invert(), false),
+            None => {
+                let inverted = self.operations.operation(self.current_position)?.invert();
+                let applied = self.applier.apply_operation_conditionally(&inverted)?;
+                (inverted, applied)
+            }
+        };
+        self.counters.record_apply(applied);
+        if applied {
+            self.subscriptions.notify(&inverted, self.current_position - 1, &self.operations)?;
+        }
 
         self.current_position -= 1;
+        self.position_changed()?;
         Ok(true)
     }
 
     pub fn step_forward_by(&mut self, count: usize) -> Result<usize, Box<dyn std::error::Error>> {
         let mut steps_taken = 0;
+        self.begin_throttle();
         for _ in 0..count {
             if self.step_forward()? {
                 steps_taken += 1;
+                self.throttle_operation();
             } else {
                 break;
             }
@@ -74,9 +407,11 @@ impl SnapshotManager {
 
     pub fn step_backward_by(&mut self, count: usize) -> Result<usize, Box<dyn std::error::Error>> {
         let mut steps_taken = 0;
+        self.begin_throttle();
         for _ in 0..count {
             if self.step_backward()? {
                 steps_taken += 1;
+                self.throttle_operation();
             } else {
                 break;
             }
@@ -84,10 +419,51 @@ impl SnapshotManager {
         Ok(steps_taken)
     }
 
+    /// Go to `target_position`. Jumps of more than one operation apply a single consolidated
+    /// INSERT/UPDATE/DELETE per affected row instead of replaying every operation in between,
+    /// unless the span contains an operation whose row can't be identified by its primary key, or
+    /// there are row subscriptions (which are told about every change). With a snapshot source
+    /// (see set_snapshot_source), it rebuilds from the snapshot first when that is estimated to
+    /// be cheaper. Otherwise, going back over forward steps that are all still recorded (see
+    /// undo_depth) undoes each of them.
     pub fn goto_position(&mut self, target_position: usize) -> Result<(), Box<dyn std::error::Error>> {
         if target_position >= self.operations.len() {
             return Err("Target position out of bounds".into());
         }
+        let _span = debug_span!("navigate", from = self.current_position, to = target_position).entered();
+
+        if self.rebuild_is_cheaper(target_position)? {
+            // Every table is reloaded at the normalised position
+            self.table_positions.clear();
+            self.current_position = self.rebuild_from_snapshot()?;
+            self.history.clear();
+            if target_position == self.current_position {
+                return self.position_changed();
+            }
+        }
+
+        if target_position < self.current_position && self.history.covers(self.current_position, target_position) {
+            self.step_backward_by(self.current_position - target_position)?;
+            return Ok(());
+        }
+
+        if target_position.abs_diff(self.current_position) > 1 && self.subscriptions.is_empty() {
+            self.sync_table_cursors()?;
+            if let Some(operations) = self.consolidated_operations(target_position)? {
+                let navigation_lock = Arc::clone(&self.navigation_lock);
+                let _guard = navigation_lock.write();
+                debug!("Applying {} consolidated operation(s)", operations.len());
+                self.history.clear();
+                self.begin_throttle();
+                for op in &operations {
+                    self.throttle_operation();
+                    let applied = self.applier.apply_operation_conditionally(op)?;
+                    self.counters.record_apply(applied);
+                }
+                self.current_position = target_position;
+                return self.position_changed();
+            }
+        }
 
         if target_position > self.current_position {
             let steps = target_position - self.current_position;
@@ -100,36 +476,555 @@ impl SnapshotManager {
         Ok(())
     }
 
-    /// Go to a specific timestamp (finds closest operation)
-    pub fn goto_timestamp(&mut self, target_timestamp: &str) -> Result<(), Box<dyn std::error::Error>> {
-        let mut closest_idx = 0;
-        let mut closest_diff = i64::MAX;
-
-        for (idx, op) in self.operations.iter().enumerate() {
-            if let Some(ts) = &op.timestamp {
-                if ts == target_timestamp {
-                    closest_idx = idx;
-                    break;
+    fn begin_throttle(&mut self) {
+        if let Some(throttle) = &mut self.throttle {
+            throttle.begin();
+        }
+    }
+
+    fn throttle_operation(&mut self) {
+        if let Some(throttle) = &mut self.throttle {
+            throttle.record();
+        }
+    }
+
+    /// Fills in the before values missing from a partial row image before the operation is applied
+    /// forward, and keeps them, so that stepping back over it can restore the row
+    fn complete_row_image(&mut self, idx: usize) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(completed) = self.applier.complete_row_image(self.operations.operation(idx)?)? {
+            self.operations.set(idx, completed);
+        }
+        Ok(())
+    }
+
+    /// Bookkeeping after the global position moved: navigation counters, provenance, watches and
+    /// invariants
+    fn position_changed(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.navigation_lock.set_position(self.current_position);
+        self.operations.evict_around(self.current_position);
+        self.sync_provenance()?;
+        let timestamp = self.operations.operation(self.current_position)?.timestamp.as_ref();
+        self.counters.record_visit(timestamp);
+        self.evaluate_watches()?;
+        self.check_invariants()
+    }
+
+    /// Net-effect operations between the current position and `target_position`, building the
+    /// per-row operation index on first use
+    fn consolidated_operations(&mut self, target_position: usize) -> Result<Option<Vec<BinlogOperation>>, Box<dyn std::error::Error>> {
+        if self.operation_index.is_none() {
+            let mut tables = Vec::new();
+            self.operations.for_each(0..self.operations.len(), |_, op| {
+                if !tables.contains(&op.table_name) {
+                    tables.push(op.table_name.clone());
                 }
-                // Simple string comparison for now
-                let diff = (ts.as_str().cmp(target_timestamp) as i64).abs();
-                if diff < closest_diff {
-                    closest_diff = diff;
-                    closest_idx = idx;
+                ControlFlow::Continue(())
+            })?;
+            // Rows of tables without a declared primary key can't be told apart, so their
+            // operations are left out of the index and replayed
+            let mut key_columns = HashMap::new();
+            for table in tables {
+                let keys = StateStore::primary_key_columns(self.get_connection(), &table)?;
+                if !keys.is_empty() {
+                    key_columns.insert(table, keys);
                 }
             }
+            self.operation_index = Some(OperationIndex::build(&self.operations, &key_columns)?);
         }
 
-        self.goto_position(closest_idx)
+        let index = self.operation_index.as_ref().unwrap();
+        Ok(index.consolidate(&self.operations, self.current_position, target_position)?)
+    }
+
+    /// Go to the state of the database at a specific timestamp
+    ///
+    /// Navigates to the last operation at or before `target_timestamp` (format: "YYMMDD HH:MM:SS").
+    /// If every operation is later than the target, navigates to the first operation.
+    pub fn goto_timestamp(&mut self, target_timestamp: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let target_idx = self.position_at_timestamp(target_timestamp)?;
+        self.goto_position(target_idx)
+    }
+
+    /// Position goto_timestamp would navigate to: the last operation at or before
+    /// `target_timestamp`, or the first operation if every operation is later.
+    ///
+    /// Operations stay in log order (binlog file, then end_log_pos), so when timestamps go
+    /// backwards (see ParseDiagnostics::timestamp_regressions) this is the operation just before
+    /// the first one later than the target: an operation whose clock went back counts as
+    /// happening with the ones before it. Of operations with the same timestamp, the one latest
+    /// in the log is picked.
+    pub fn position_at_timestamp(&self, target_timestamp: &str) -> Result<usize, Box<dyn std::error::Error>> {
+        let target = BinlogTimestamp::parse(target_timestamp)?;
+        let mut target_idx = 0;
+
+        self.operations.for_each(0..self.operations.len(), |idx, op| {
+            let Some(ts) = op.timestamp.as_ref().and_then(|ts| BinlogTimestamp::parse(ts).ok()) else {
+                return ControlFlow::Continue(());
+            };
+            if ts > target {
+                return ControlFlow::Break(());
+            }
+            target_idx = idx;
+            ControlFlow::Continue(())
+        })?;
+
+        Ok(target_idx)
+    }
+
+    /// First position after `after` whose operation is at or after `target`, in log order.
+    /// Operations without a timestamp are skipped.
+    pub(super) fn first_position_from(&self, after: usize, target: &BinlogTimestamp) -> Result<Option<usize>, Box<dyn std::error::Error>> {
+        let mut found = None;
+        self.operations.for_each(after + 1..self.operations.len(), |idx, op| {
+            if op.timestamp.as_ref().and_then(|ts| BinlogTimestamp::parse(ts).ok()).is_some_and(|ts| ts >= *target) {
+                found = Some(idx);
+                return ControlFlow::Break(());
+            }
+            ControlFlow::Continue(())
+        })?;
+        Ok(found)
+    }
+
+    /// Run a SQL query against the database at the current position.
+    /// Each row is returned as a Vec of DuckDB values, in the query's column order.
+    pub fn query(&self, sql: &str) -> Result<Vec<Vec<Value>>, Box<dyn std::error::Error>> {
+        query_rows(self.get_connection(), sql)
+    }
+
+    /// Writes the result of a SQL query at the current position to a parquet file with DuckDB's COPY.
+    /// Returns the number of rows written.
+    pub fn materialise(&self, sql: &str, output_path: &str) -> Result<usize, Box<dyn std::error::Error>> {
+        let sql = sql.trim().trim_end_matches(';');
+        let rows = self.get_connection().execute(
+            &format!("COPY ({}) TO '{}' (FORMAT PARQUET);", sql, output_path.replace('\'', "''")),
+            [],
+        )?;
+        Ok(rows)
+    }
+
+    /// Names of the tables loaded in the database (temporary tables excluded), in name order
+    pub fn list_tables(&self) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let mut stmt = self.get_connection().prepare(
+            "SELECT table_name FROM duckdb_tables() WHERE schema_name = 'main' AND NOT temporary ORDER BY table_name"
+        )?;
+        let tables = stmt
+            .query_map([], |row| row.get::<usize, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(tables)
     }
 
+    /// (column name, DuckDB type) for each column of a table, in column order
+    pub fn table_schema(&self, table: &str) -> Result<Vec<(String, String)>, Box<dyn std::error::Error>> {
+        let schema = self.get_connection().table_schema(table)?;
+        if schema.is_empty() {
+            return Err(format!("Table {} not found", table).into());
+        }
+        Ok(schema)
+    }
+
+    /// The operation at `index`. None if it is out of bounds, or was spilled to disk and can't be
+    /// read back (logged as a warning).
     pub fn get_operation(&self, index: usize) -> Option<&BinlogOperation> {
         self.operations.get(index)
     }
 
-    pub fn get_operations_range(&self, start: usize, end: usize) -> &[BinlogOperation] {
-        let end = end.min(self.operations.len());
-        &self.operations[start..end]
+    /// Operations `start..end`. Borrowed, unless they were spilled to disk (see
+    /// set_max_operations_in_memory) and have to be read back, all at once: scan long ranges with
+    /// for_each_operation.
+    pub fn get_operations_range(&self, start: usize, end: usize) -> Result<Cow<'_, [BinlogOperation]>, Box<dyn std::error::Error>> {
+        Ok(self.operations.range(start..end)?)
+    }
+
+    /// Calls `f` with the position and operation of each of `start..end` in order, stopping at
+    /// the first error. Spilled operations are read a page at a time and not kept in memory.
+    pub fn for_each_operation(
+        &self,
+        start: usize,
+        end: usize,
+        mut f: impl FnMut(usize, &BinlogOperation) -> Result<(), Box<dyn std::error::Error>>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut result = Ok(());
+        self.operations.for_each(start..end, |position, op| match f(position, op) {
+            Ok(()) => ControlFlow::Continue(()),
+            Err(e) => {
+                result = Err(e);
+                ControlFlow::Break(())
+            }
+        })?;
+        result
     }
 }
 
+/// Rows of a SQL query as DuckDB values, in the query's column order
+pub(super) fn query_rows(conn: &Connection, sql: &str) -> Result<Vec<Vec<Value>>, Box<dyn std::error::Error>> {
+    let mut stmt = conn.prepare(sql)?;
+    let mut rows = stmt.query([])?;
+    let column_count = rows.as_ref().map(|stmt| stmt.column_count()).unwrap_or(0);
+
+    let mut results = Vec::new();
+    while let Some(row) = rows.next()? {
+        let mut values = Vec::with_capacity(column_count);
+        for i in 0..column_count {
+            values.push(row.get::<usize, Value>(i)?);
+        }
+        results.push(values);
+    }
+
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binlog::{OperationType, RowImage};
+    use crate::operation_applier::Comparison;
+    use crate::test_support::{books_db, books_op};
+
+    fn create_test_db() -> Connection {
+        books_db("id INTEGER, price INTEGER", "(1, 10)")
+    }
+
+    fn insert_op(timestamp: &str, id: i64, price: i64) -> BinlogOperation {
+        books_op(OperationType::Insert, timestamp, None, Some([&id.to_string(), &price.to_string()]))
+    }
+
+    fn create_test_manager() -> SnapshotManager {
+        let operations = vec![
+            insert_op("251108 10:00:00", 1, 10),
+            insert_op("251108 11:00:00", 2, 20),
+            insert_op("251108 12:00:00", 3, 30),
+        ];
+        SnapshotManager::new(create_test_db(), operations, 0)
+    }
+
+    fn count_books(manager: &SnapshotManager) -> i64 {
+        manager.get_connection()
+            .query_row("SELECT COUNT(*) FROM books", [], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_goto_timestamp_picks_last_operation_at_or_before_target() {
+        let mut manager = create_test_manager();
+
+        manager.goto_timestamp("251108 11:30:00").unwrap();
+        assert_eq!(manager.get_position(), 1);
+        assert_eq!(count_books(&manager), 2);
+
+        manager.goto_timestamp("251108 12:00:00").unwrap();
+        assert_eq!(manager.get_position(), 2);
+
+        manager.goto_timestamp("251108 09:00:00").unwrap();
+        assert_eq!(manager.get_position(), 0);
+        assert_eq!(count_books(&manager), 1);
+    }
+
+    #[test]
+    fn test_position_at_timestamp_stops_at_first_later_operation() {
+        let operations = vec![
+            insert_op("251108 10:00:00", 1, 10),
+            insert_op("251108 10:05:00", 2, 20),
+            // The clock went back
+            insert_op("251108 10:03:00", 3, 30),
+            insert_op("251108 10:05:00", 4, 40),
+        ];
+        let manager = SnapshotManager::new(create_test_db(), operations, 0);
+
+        assert_eq!(manager.position_at_timestamp("251108 10:04:00").unwrap(), 0);
+        assert_eq!(manager.position_at_timestamp("251108 10:05:00").unwrap(), 3);
+    }
+
+    #[test]
+    fn test_independent_table_cursor_skips_other_tables() {
+        let conn = create_test_db();
+        conn.execute_batch("CREATE TABLE authors (id INTEGER, price INTEGER);").unwrap();
+
+        let mut author_op = insert_op("251108 10:30:00", 7, 0);
+        author_op.table_name = "authors".to_string();
+        let operations = vec![
+            insert_op("251108 10:00:00", 1, 10),
+            author_op,
+            insert_op("251108 11:00:00", 2, 20),
+        ];
+        let mut manager = SnapshotManager::new(conn, operations, 0);
+
+        assert!(manager.goto_table_position("books", 2).is_err());
+        manager.set_cursor_mode(CursorMode::Independent).unwrap();
+
+        manager.goto_table_position("books", 2).unwrap();
+        assert_eq!(manager.get_table_position("books"), 2);
+        assert_eq!(manager.get_table_position("authors"), 0);
+        assert_eq!(count_books(&manager), 2);
+        let authors: i64 = manager.get_connection()
+            .query_row("SELECT COUNT(*) FROM authors", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(authors, 0);
+
+        // Global navigation realigns every table first
+        manager.step_forward().unwrap();
+        assert_eq!(manager.get_position(), 1);
+        assert_eq!(manager.get_table_position("books"), 1);
+        assert_eq!(count_books(&manager), 1);
+    }
+
+    #[test]
+    fn test_goto_position_consolidates_span() {
+        let update = |before: i64, after: i64| BinlogOperation {
+            operation_type: OperationType::Update,
+            before_values: Some(vec!["1".to_string(), before.to_string()]),
+            after_values: Some(vec!["1".to_string(), after.to_string()]),
+            ..insert_op("251108 10:00:00", 1, after)
+        };
+        let mut operations = vec![insert_op("251108 09:00:00", 1, 10)];
+        operations.extend((10..20).map(|price| update(price, price + 1)));
+        operations.push(insert_op("251108 12:00:00", 2, 20));
+        let conn = books_db("id INTEGER PRIMARY KEY, price INTEGER", "(1, 10)");
+        let mut manager = SnapshotManager::new(conn, operations, 0);
+
+        manager.goto_position(11).unwrap();
+        let rows = manager.query("SELECT id, price FROM books ORDER BY id").unwrap();
+        assert_eq!(rows, vec![
+            vec![Value::Int(1), Value::Int(20)],
+            vec![Value::Int(2), Value::Int(20)],
+        ]);
+
+        manager.goto_position(3).unwrap();
+        let rows = manager.query("SELECT id, price FROM books ORDER BY id").unwrap();
+        assert_eq!(rows, vec![vec![Value::Int(1), Value::Int(13)]]);
+    }
+
+    #[test]
+    fn test_goto_position_replays_tables_without_primary_key() {
+        // Both rows have id 1, so keying on the first column would merge them
+        let operations = vec![
+            insert_op("251108 09:00:00", 1, 10),
+            insert_op("251108 10:00:00", 1, 20),
+            BinlogOperation {
+                operation_type: OperationType::Delete,
+                before_values: Some(vec!["1".to_string(), "10".to_string()]),
+                after_values: None,
+                ..insert_op("251108 11:00:00", 1, 10)
+            },
+            BinlogOperation {
+                operation_type: OperationType::Update,
+                before_values: Some(vec!["1".to_string(), "20".to_string()]),
+                ..insert_op("251108 12:00:00", 1, 21)
+            },
+        ];
+        let mut manager = SnapshotManager::new(create_test_db(), operations, 0);
+
+        manager.goto_position(3).unwrap();
+        let rows = manager.query("SELECT id, price FROM books").unwrap();
+        assert_eq!(rows, vec![vec![Value::Int(1), Value::Int(21)]]);
+    }
+
+    #[test]
+    fn test_max_operations_per_second_slows_bulk_navigation() {
+        let operations: Vec<_> = (1..=11).map(|id| insert_op("251108 10:00:00", id, 10)).collect();
+        let mut manager = SnapshotManager::new(create_test_db(), operations, 0);
+        manager.set_max_operations_per_second(Some(100.0));
+
+        let started = std::time::Instant::now();
+        assert_eq!(manager.step_forward_by(10).unwrap(), 10);
+        assert!(started.elapsed() >= std::time::Duration::from_millis(90), "{:?}", started.elapsed());
+
+        manager.set_max_operations_per_second(None);
+        manager.step_backward_by(10).unwrap();
+        assert_eq!(count_books(&manager), 1);
+    }
+
+    #[test]
+    fn test_list_tables_and_table_schema() {
+        let conn = create_test_db();
+        conn.execute_batch(
+            "CREATE TABLE authors (id INTEGER, name VARCHAR);
+             CREATE TEMP TABLE scratch (id INTEGER);"
+        ).unwrap();
+        let manager = SnapshotManager::new(conn, vec![insert_op("251108 10:00:00", 1, 10)], 0);
+
+        assert_eq!(manager.list_tables().unwrap(), vec!["authors".to_string(), "books".to_string()]);
+        assert_eq!(manager.table_schema("authors").unwrap(), vec![
+            ("id".to_string(), "INTEGER".to_string()),
+            ("name".to_string(), "VARCHAR".to_string()),
+        ]);
+        assert!(manager.table_schema("missing").is_err());
+    }
+
+    #[test]
+    fn test_apply_policy_on_before_image_mismatch() {
+        let stale_update = BinlogOperation {
+            operation_type: OperationType::Update,
+            before_values: Some(vec!["1".to_string(), "99".to_string()]),
+            after_values: Some(vec!["1".to_string(), "100".to_string()]),
+            ..insert_op("251108 11:00:00", 1, 100)
+        };
+        let operations = vec![insert_op("251108 10:00:00", 1, 10), stale_update];
+
+        let mut manager = SnapshotManager::new(create_test_db(), operations.clone(), 0);
+        manager.step_forward().unwrap();
+        assert_eq!(manager.get_position(), 1);
+        assert_eq!(manager.get_mismatch_counts().get("books"), Some(&1));
+
+        let mut manager = SnapshotManager::new(create_test_db(), operations, 0);
+        manager.set_apply_policy(ApplyPolicy::Error);
+        assert!(manager.step_forward().is_err());
+        assert_eq!(manager.get_position(), 0);
+        assert_eq!(manager.get_mismatch_counts().get("books"), Some(&1));
+    }
+
+    #[test]
+    fn test_multi_match_policy_on_duplicate_rows() {
+        let duplicate_update = BinlogOperation {
+            operation_type: OperationType::Update,
+            before_values: Some(vec!["1".to_string(), "10".to_string()]),
+            after_values: Some(vec!["1".to_string(), "20".to_string()]),
+            ..insert_op("251108 11:00:00", 1, 20)
+        };
+        let operations = vec![insert_op("251108 10:00:00", 2, 30), duplicate_update];
+        let with_duplicates = || {
+            let conn = create_test_db();
+            conn.execute_batch("INSERT INTO books VALUES (1, 10);").unwrap();
+            conn
+        };
+        let prices = |manager: &SnapshotManager| manager.query("SELECT price FROM books WHERE id = 1").unwrap();
+
+        let mut manager = SnapshotManager::new(with_duplicates(), operations.clone(), 0);
+        manager.step_forward().unwrap();
+        assert_eq!(prices(&manager), vec![vec![Value::Int(20)], vec![Value::Int(20)]]);
+        assert_eq!(manager.get_multi_match_counts().get("books"), Some(&1));
+
+        let mut manager = SnapshotManager::new(with_duplicates(), operations.clone(), 0);
+        manager.set_multi_match_policy(MultiMatchPolicy::Skip);
+        manager.step_forward().unwrap();
+        assert_eq!(prices(&manager), vec![vec![Value::Int(10)], vec![Value::Int(10)]]);
+        assert_eq!(manager.get_multi_match_counts().get("books"), Some(&1));
+
+        let mut manager = SnapshotManager::new(with_duplicates(), operations, 0);
+        manager.set_multi_match_policy(MultiMatchPolicy::Error);
+        assert!(manager.step_forward().is_err());
+    }
+
+    #[test]
+    fn test_comparator_decides_whether_before_image_matches() {
+        let drifted_update = BinlogOperation {
+            operation_type: OperationType::Update,
+            before_values: Some(vec!["1".to_string(), "11".to_string()]),
+            after_values: Some(vec!["1".to_string(), "12".to_string()]),
+            ..insert_op("251108 11:00:00", 1, 12)
+        };
+        let operations = vec![insert_op("251108 10:00:00", 1, 10), drifted_update];
+        let keyed_db = || {
+            books_db("id INTEGER PRIMARY KEY, price INTEGER", "(1, 10), (2, 11)")
+        };
+
+        let mut manager = SnapshotManager::new(keyed_db(), operations.clone(), 0);
+        manager.set_comparator("books", Comparison::NumericTolerant(0.5));
+        manager.step_forward().unwrap();
+        assert_eq!(manager.query("SELECT price FROM books ORDER BY id").unwrap(), vec![vec![Value::Int(10)], vec![Value::Int(11)]]);
+        assert_eq!(manager.get_mismatch_counts().get("books"), Some(&1));
+
+        let mut manager = SnapshotManager::new(keyed_db(), operations.clone(), 0);
+        manager.set_comparator("books", Comparison::NumericTolerant(1.0));
+        manager.step_forward().unwrap();
+        assert_eq!(manager.query("SELECT price FROM books ORDER BY id").unwrap(), vec![vec![Value::Int(12)], vec![Value::Int(11)]]);
+        assert!(manager.get_mismatch_counts().is_empty());
+
+        // Without a primary key, filtering on the first column could change every row with id 1
+        let mut manager = SnapshotManager::new(create_test_db(), operations, 0);
+        manager.set_comparator("books", Comparison::NumericTolerant(1.0));
+        assert!(manager.step_forward().is_err());
+    }
+
+    #[test]
+    fn test_step_back_over_partial_update_restores_row() {
+        // binlog_row_image=MINIMAL: key in the before image, changed column in the after image
+        let minimal_update = BinlogOperation {
+            operation_type: OperationType::Update,
+            columns: vec!["id".to_string(), "price".to_string()],
+            before_values: Some(vec!["1".to_string(), "NULL".to_string()]),
+            after_values: Some(vec!["1".to_string(), "15".to_string()]),
+            row_image: RowImage::Partial { missing_before: vec!["price".to_string()] },
+            ..insert_op("251108 11:00:00", 1, 15)
+        };
+        let operations = vec![insert_op("251108 10:00:00", 1, 10), minimal_update];
+        let mut manager = SnapshotManager::new(create_test_db(), operations, 0);
+
+        manager.step_forward().unwrap();
+        assert_eq!(manager.query("SELECT price FROM books").unwrap(), vec![vec![Value::Int(15)]]);
+        assert_eq!(manager.get_operation(1).unwrap().before_values, Some(vec!["1".to_string(), "10".to_string()]));
+
+        manager.step_backward().unwrap();
+        assert_eq!(manager.query("SELECT price FROM books").unwrap(), vec![vec![Value::Int(10)]]);
+    }
+
+    #[test]
+    fn test_step_back_skips_operation_skipped_forward() {
+        // The snapshot already has the update's after image, so stepping forward skips it
+        let conn = create_test_db();
+        conn.execute_batch("UPDATE books SET price = 30;").unwrap();
+        let update = BinlogOperation {
+            operation_type: OperationType::Update,
+            before_values: Some(vec!["1".to_string(), "20".to_string()]),
+            ..insert_op("251108 11:00:00", 1, 30)
+        };
+        let operations = vec![insert_op("251108 10:00:00", 2, 20), update];
+        let mut manager = SnapshotManager::new(conn, operations, 0);
+
+        manager.step_forward().unwrap();
+        assert_eq!(manager.undo_depth(), 1);
+        manager.step_backward().unwrap();
+
+        // Re-deriving the inverse would have set the price back to 20
+        assert_eq!(manager.query("SELECT price FROM books").unwrap(), vec![vec![Value::Int(30)]]);
+        assert_eq!(manager.undo_depth(), 0);
+    }
+
+    #[test]
+    fn test_goto_back_over_recorded_steps_undoes_them() {
+        // Keyed, so that the jump back could be consolidated
+        let conn = books_db("id INTEGER PRIMARY KEY, price INTEGER", "(1, 30)");
+        let update = BinlogOperation {
+            operation_type: OperationType::Update,
+            before_values: Some(vec!["1".to_string(), "20".to_string()]),
+            ..insert_op("251108 11:00:00", 1, 30)
+        };
+        let operations = vec![
+            insert_op("251108 10:00:00", 2, 20),
+            update,
+            insert_op("251108 12:00:00", 3, 40),
+        ];
+        let mut manager = SnapshotManager::new(conn, operations, 0);
+
+        manager.step_forward_by(2).unwrap();
+        assert_eq!(manager.undo_depth(), 2);
+        manager.goto_position(0).unwrap();
+
+        assert_eq!(manager.query("SELECT id, price FROM books").unwrap(), vec![vec![Value::Int(1), Value::Int(30)]]);
+        assert_eq!(manager.undo_depth(), 0);
+    }
+
+    #[test]
+    fn test_query_returns_typed_rows() {
+        let mut manager = create_test_manager();
+        manager.goto_position(2).unwrap();
+
+        let rows = manager.query("SELECT id, price FROM books ORDER BY id").unwrap();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[1], vec![Value::Int(2), Value::Int(20)]);
+    }
+
+    #[test]
+    fn test_materialise_writes_query_result_to_parquet() {
+        let mut manager = create_test_manager();
+        manager.goto_position(1).unwrap();
+        let output = std::env::temp_dir().join(format!("materialise_{}.parquet", std::process::id()));
+        let output = output.to_string_lossy().to_string();
+
+        let written = manager.materialise("SELECT id, price * 2 AS doubled FROM books ORDER BY id;", &output).unwrap();
+
+        assert_eq!(written, 2);
+        let rows = manager.query(&format!("SELECT doubled FROM read_parquet('{}')", output)).unwrap();
+        assert_eq!(rows, vec![vec![Value::Int(20)], vec![Value::Int(40)]]);
+        std::fs::remove_file(output).ok();
+    }
+}
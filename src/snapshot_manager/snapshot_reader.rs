@@ -0,0 +1,116 @@
+use duckdb::Connection;
+use duckdb::types::Value;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use crate::snapshot_manager::SnapshotManager;
+use crate::snapshot_manager::snapshot_manager::query_rows;
+
+/// Shared between a SnapshotManager and its readers: navigation holds the lock for writing while
+/// it changes the database, readers hold it for reading while they query
+#[derive(Debug, Default)]
+pub(super) struct NavigationLock {
+    lock: RwLock<()>,
+    position: AtomicUsize,
+}
+
+impl NavigationLock {
+    pub(super) fn new(position: usize) -> Arc<Self> {
+        Arc::new(Self { lock: RwLock::new(()), position: AtomicUsize::new(position) })
+    }
+
+    /// Blocks readers until the guard is dropped
+    pub(super) fn write(&self) -> RwLockWriteGuard<'_, ()> {
+        self.lock.write().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, ()> {
+        self.lock.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Call while holding the write guard
+    pub(super) fn set_position(&self, position: usize) {
+        self.position.store(position, Ordering::Release);
+    }
+
+    fn position(&self) -> usize {
+        self.position.load(Ordering::Acquire)
+    }
+}
+
+/// Position a query ran at, and its rows
+pub type RowsAtPosition = (usize, Vec<Vec<Value>>);
+
+/// Read-only view of a SnapshotManager's database for another thread, e.g. a dashboard querying
+/// the current state while the manager is navigated. It has its own connection to the same
+/// database, and its queries wait for the operation being applied, so they only ever see the
+/// state at some position, never half of a consolidated jump.
+///
+/// Queries should only read: nothing stops a statement from writing, but writes would be lost
+/// or undone by navigation.
+pub struct SnapshotReader {
+    conn: Connection,
+    lock: Arc<NavigationLock>,
+}
+
+impl SnapshotReader {
+    /// Position of the manager when the last query ran, or now if none is running
+    pub fn get_position(&self) -> usize {
+        self.lock.position()
+    }
+
+    /// Runs a SQL query at the manager's current position, returning the position with the rows
+    pub fn query(&self, sql: &str) -> Result<RowsAtPosition, Box<dyn std::error::Error>> {
+        let _guard = self.lock.read();
+        let rows = query_rows(&self.conn, sql)?;
+        Ok((self.lock.position(), rows))
+    }
+
+    /// Another reader of the same manager, for another thread
+    pub fn try_clone(&self) -> Result<SnapshotReader, Box<dyn std::error::Error>> {
+        Ok(SnapshotReader { conn: self.conn.try_clone()?, lock: Arc::clone(&self.lock) })
+    }
+}
+
+impl SnapshotManager {
+    /// A reader that can be sent to another thread to query the database while this manager
+    /// navigates. See SnapshotReader.
+    pub fn reader(&self) -> Result<SnapshotReader, Box<dyn std::error::Error>> {
+        Ok(SnapshotReader { conn: self.get_connection().try_clone()?, lock: Arc::clone(&self.navigation_lock) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use duckdb::types::Value;
+    use crate::binlog::BinlogOperation;
+    use crate::snapshot_manager::SnapshotManager;
+    use crate::test_support::{books_db, insert_id};
+
+    fn insert(id: usize) -> BinlogOperation {
+        insert_id(&format!("251108 10:00:{:02}", id), id)
+    }
+
+    #[test]
+    fn test_reader_sees_consistent_positions_while_navigating() {
+        let conn = books_db("id INTEGER PRIMARY KEY", "(0)");
+        let mut manager = SnapshotManager::new(conn, (0..40).map(insert).collect(), 0);
+        let reader = manager.reader().unwrap();
+
+        let dashboard = std::thread::spawn(move || {
+            let mut observed = Vec::new();
+            while observed.last().is_none_or(|&(position, _)| position < 39) {
+                let (position, rows) = reader.query("SELECT COUNT(*) FROM books").unwrap();
+                observed.push((position, rows[0][0].clone()));
+            }
+            observed
+        });
+        for _ in 0..5 {
+            manager.step_forward_by(3).unwrap();
+        }
+        manager.goto_position(39).unwrap();
+
+        for (position, count) in dashboard.join().unwrap() {
+            assert_eq!(count, Value::BigInt(position as i64 + 1));
+        }
+    }
+}
@@ -0,0 +1,253 @@
+use std::sync::Arc;
+use tracing::debug;
+use crate::loader::column_decoding;
+use crate::loader::column_projection::ColumnProjection;
+use crate::loader::parquet_loader;
+use crate::parser::value_decoder::ColumnDecoder;
+use crate::snapshot_manager::SnapshotManager;
+use crate::snapshot_normaliser::timestamp_normaliser::{NormalisationDirection, NormalisationReport};
+
+/// Snapshot rows reloaded in the time it takes to replay one operation. Loading is a bulk scan,
+/// replaying a statement per operation.
+const ROWS_PER_REPLAYED_OPERATION: usize = 1000;
+
+/// Where the loaded tables came from, and how they were normalised, so that the state at the
+/// normalised position can be rebuilt instead of replayed to
+pub(super) struct SnapshotSource {
+    tables: Vec<(String, Vec<String>)>,
+    projection: ColumnProjection,
+    /// Decoders of the tables' columns, reapplied after every reload
+    column_decoders: Vec<(String, String, ColumnDecoder)>,
+    /// Operations normalisation applied, in order, and whether they were inverted
+    normalisation: Vec<(usize, bool)>,
+    position: usize,
+    // Counted on the first estimate
+    row_count: Option<usize>,
+}
+
+impl SnapshotManager {
+    /// Records the snapshot files the tables were loaded from (with the projection and column
+    /// decoders they were loaded with) and how they were normalised. From then on, goto_position
+    /// reloads the tables and moves from the normalised position instead of replaying, when that
+    /// is cheaper: e.g. jumping from position 900k back to 1k near the snapshot.
+    ///
+    /// Jumps that rebuild don't notify subscriptions, so the manager never rebuilds while it has any.
+    pub fn set_snapshot_source(
+        &mut self,
+        tables: &[(String, Vec<String>)],
+        projection: &ColumnProjection,
+        column_decoders: &[(String, String, ColumnDecoder)],
+        normalisation: &NormalisationReport,
+    ) {
+        self.snapshot_source = Some(SnapshotSource {
+            tables: tables.to_vec(),
+            projection: projection.clone(),
+            column_decoders: column_decoders.iter()
+                .filter(|(table, _, _)| tables.iter().any(|(loaded, _)| loaded == table))
+                .cloned()
+                .collect(),
+            normalisation: normalisation.decisions.iter()
+                .filter(|decision| decision.applied)
+                .map(|decision| (decision.index, decision.direction == NormalisationDirection::Inverted))
+                .collect(),
+            position: normalisation.tx_zero_idx.unwrap_or(self.get_position()),
+            row_count: None,
+        });
+    }
+
+    /// Whether reloading the snapshot and moving from the normalised position to `target_position`
+    /// is estimated to cost less than moving there from the current position
+    pub(super) fn rebuild_is_cheaper(&mut self, target_position: usize) -> Result<bool, Box<dyn std::error::Error>> {
+        if !self.subscriptions.is_empty() {
+            return Ok(false);
+        }
+        let replay_cost = target_position.abs_diff(self.get_position());
+        let Some(source) = self.snapshot_source.as_mut() else {
+            return Ok(false);
+        };
+        if replay_cost <= target_position.abs_diff(source.position) {
+            return Ok(false);
+        }
+        if source.row_count.is_none() {
+            let conn = self.applier.get_connection();
+            let mut row_count = 0;
+            for (_, files) in &source.tables {
+                row_count += parquet_loader::snapshot_row_count(conn, files)?;
+            }
+            source.row_count = Some(row_count);
+        }
+        let rebuild_cost = source.row_count.unwrap_or(0) / ROWS_PER_REPLAYED_OPERATION
+            + source.normalisation.len()
+            + target_position.abs_diff(source.position);
+        debug!("Replaying {} operation(s), rebuilding costs about {}", replay_cost, rebuild_cost);
+        Ok(rebuild_cost < replay_cost)
+    }
+
+    /// Reloads every table from its snapshot files and reapplies normalisation, returning the
+    /// normalised position the database is now at
+    pub(super) fn rebuild_from_snapshot(&mut self) -> Result<usize, Box<dyn std::error::Error>> {
+        let Some(source) = self.snapshot_source.take() else {
+            return Err("No snapshot source to rebuild from".into());
+        };
+        let navigation_lock = Arc::clone(&self.navigation_lock);
+        let _guard = navigation_lock.write();
+        let result = self.reload_and_normalise(&source);
+        self.snapshot_source = Some(source);
+        let position = result?;
+        navigation_lock.set_position(position);
+        Ok(position)
+    }
+
+    /// Reloads the tables and replays normalisation in one transaction, so that a failure leaves
+    /// the database at the position it was at
+    fn reload_and_normalise(&mut self, source: &SnapshotSource) -> Result<usize, Box<dyn std::error::Error>> {
+        debug!("Rebuilding from the snapshot at position {}", source.position);
+        self.applier.get_connection().execute_batch("BEGIN TRANSACTION;")?;
+        if let Err(e) = self.reload_and_replay(source) {
+            self.applier.get_connection().execute_batch("ROLLBACK;")?;
+            return Err(e);
+        }
+        self.applier.get_connection().execute_batch("COMMIT;")?;
+        Ok(source.position)
+    }
+
+    fn reload_and_replay(&mut self, source: &SnapshotSource) -> Result<(), Box<dyn std::error::Error>> {
+        let conn = self.applier.get_connection();
+        for (table, files) in &source.tables {
+            parquet_loader::reload_table_from_files(conn, table, files, &source.projection, &self.redactions)?;
+        }
+        // As when loading: the snapshot's values are decoded after they are redacted
+        column_decoding::decode_snapshot_columns(conn, &source.column_decoders)?;
+        for &(idx, inverted) in &source.normalisation {
+            let op = self.get_operation(idx).ok_or("Normalised operation out of bounds")?;
+            let op = if inverted { op.invert() } else { op.clone() };
+            self.applier.execute_operation(&op)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use duckdb::Connection;
+    use crate::binlog::{BinlogOperation, OperationType};
+    use crate::loader::column_projection::ColumnProjection;
+    use crate::parser::value_decoder::ColumnDecoder;
+    use crate::snapshot_manager::SnapshotManager;
+    use crate::operation_applier::ApplyOutcome;
+    use crate::snapshot_normaliser::timestamp_normaliser::{NormalisationDecision, NormalisationDirection, NormalisationReport};
+    use crate::test_support::operation;
+
+    fn update(idx: i64) -> BinlogOperation {
+        let (before, after) = (idx.to_string(), (idx + 1).to_string());
+        BinlogOperation {
+            timestamp: Some(format!("251108 10:{:02}:00", idx)),
+            ..operation(OperationType::Update, "counters", &["id", "value"], Some(&["1", &before]), Some(&["1", &after]))
+        }
+    }
+
+    #[test]
+    fn test_long_jump_back_rebuilds_from_snapshot() {
+        let dir = std::env::temp_dir().join(format!("pensieve_rebuild_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("counters.parquet").to_string_lossy().to_string();
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(&format!(
+            "COPY (SELECT 1 AS id, 1 AS value) TO '{file}' (FORMAT PARQUET);
+             CREATE TABLE counters (id INTEGER PRIMARY KEY, value INTEGER); INSERT INTO counters VALUES (1, 1);"
+        )).unwrap();
+
+        // The snapshot is the state after operation 0
+        let mut manager = SnapshotManager::new(conn, (0..60).map(update).collect(), 0);
+        let report = NormalisationReport { tx_zero_idx: Some(0), ..NormalisationReport::default() };
+        manager.set_snapshot_source(&[("counters".to_string(), vec![file])], &ColumnProjection::new(), &[], &report);
+        manager.step_forward_by(50).unwrap();
+        assert!(manager.rebuild_is_cheaper(2).unwrap());
+        assert!(!manager.rebuild_is_cheaper(40).unwrap());
+
+        manager.goto_position(2).unwrap();
+        let value = |manager: &SnapshotManager| manager.get_connection()
+            .query_row("SELECT value FROM counters", [], |row| row.get::<_, i64>(0)).unwrap();
+        assert_eq!((manager.get_position(), value(&manager)), (2, 3));
+        manager.goto_position(59).unwrap();
+        assert_eq!(value(&manager), 60);
+
+        // Replaying normalisation fails after the reload: the reload is rolled back too
+        let report = NormalisationReport {
+            tx_zero_idx: Some(0),
+            decisions: vec![NormalisationDecision {
+                index: 60,
+                direction: NormalisationDirection::Forward,
+                applied: true,
+                outcome: ApplyOutcome::Applied,
+                sql: String::new(),
+            }],
+            ..NormalisationReport::default()
+        };
+        let file = dir.join("counters.parquet").to_string_lossy().to_string();
+        manager.set_snapshot_source(&[("counters".to_string(), vec![file])], &ColumnProjection::new(), &[], &report);
+        assert!(manager.rebuild_from_snapshot().is_err());
+        assert_eq!(value(&manager), 60);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_rebuild_reapplies_column_decoders() {
+        let dir = std::env::temp_dir().join(format!("pensieve_rebuild_decoded_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("counters.parquet").to_string_lossy().to_string();
+        // The snapshot file holds the encoded value, the loaded table the decoded one
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(&format!(
+            "COPY (SELECT 1 AS id, 'v1' AS value) TO '{file}' (FORMAT PARQUET);
+             CREATE TABLE counters (id INTEGER PRIMARY KEY, value VARCHAR); INSERT INTO counters VALUES (1, 'V1');"
+        )).unwrap();
+        let decoded = |idx: i64| BinlogOperation {
+            before_values: Some(vec!["1".to_string(), format!("'V{}'", idx)]),
+            after_values: Some(vec!["1".to_string(), format!("'V{}'", idx + 1)]),
+            ..update(idx)
+        };
+
+        let mut manager = SnapshotManager::new(conn, (0..60).map(decoded).collect(), 0);
+        let report = NormalisationReport { tx_zero_idx: Some(0), ..NormalisationReport::default() };
+        let decoders = vec![("counters".to_string(), "value".to_string(), ColumnDecoder::new(|text| text.to_uppercase()))];
+        manager.set_snapshot_source(&[("counters".to_string(), vec![file])], &ColumnProjection::new(), &decoders, &report);
+        manager.step_forward_by(50).unwrap();
+        assert!(manager.rebuild_is_cheaper(2).unwrap());
+
+        manager.goto_position(2).unwrap();
+        let value: String = manager.get_connection()
+            .query_row("SELECT value FROM counters", [], |row| row.get(0)).unwrap();
+        assert_eq!((manager.get_position(), value.as_str()), (2, "V3"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_rebuild_is_preferred_over_undoing_recorded_steps() {
+        let dir = std::env::temp_dir().join(format!("pensieve_rebuild_undo_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("counters.parquet").to_string_lossy().to_string();
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(&format!(
+            "COPY (SELECT 1 AS id, 1 AS value) TO '{file}' (FORMAT PARQUET);
+             CREATE TABLE counters (id INTEGER PRIMARY KEY, value INTEGER); INSERT INTO counters VALUES (1, 1);"
+        )).unwrap();
+
+        let mut manager = SnapshotManager::new(conn, (0..60).map(update).collect(), 0);
+        let report = NormalisationReport { tx_zero_idx: Some(0), ..NormalisationReport::default() };
+        manager.set_snapshot_source(&[("counters".to_string(), vec![file])], &ColumnProjection::new(), &[], &report);
+        for _ in 0..50 {
+            manager.step_forward().unwrap();
+        }
+        assert_eq!(manager.undo_depth(), 50);
+        assert!(manager.rebuild_is_cheaper(2).unwrap());
+
+        // Undoing 48 of the recorded steps would leave 2 of them; the rebuild forgets them all
+        manager.goto_position(2).unwrap();
+        assert_eq!(manager.undo_depth(), 0);
+        let value: i64 = manager.get_connection()
+            .query_row("SELECT value FROM counters", [], |row| row.get(0)).unwrap();
+        assert_eq!((manager.get_position(), value), (2, 3));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
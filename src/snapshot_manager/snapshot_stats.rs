@@ -0,0 +1,109 @@
+use crate::binlog::{quote_identifier, BinlogTimestamp};
+use crate::snapshot_manager::SnapshotManager;
+
+/// State of the snapshot at the current position, plus navigation counters since load
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnapshotStats {
+    pub position: usize,
+    pub timestamp: Option<String>,
+    /// (table, row count) for every loaded table, in table name order
+    pub table_row_counts: Vec<(String, i64)>,
+    /// Earliest and latest timestamps the snapshot has been positioned at since load
+    pub min_timestamp_visited: Option<String>,
+    pub max_timestamp_visited: Option<String>,
+    /// Operations executed against the database since load. A jump over many operations counts
+    /// one consolidated operation per affected row.
+    pub applied_operations: usize,
+    /// Operations skipped because they would not have changed the table
+    pub skipped_operations: usize,
+}
+
+/// Counters updated as the snapshot moves
+#[derive(Debug, Clone, Default)]
+pub(super) struct NavigationCounters {
+    applied: usize,
+    skipped: usize,
+    min_timestamp: Option<(BinlogTimestamp, String)>,
+    max_timestamp: Option<(BinlogTimestamp, String)>,
+}
+
+impl NavigationCounters {
+    pub(super) fn record_apply(&mut self, applied: bool) {
+        if applied {
+            self.applied += 1;
+        } else {
+            self.skipped += 1;
+        }
+    }
+
+    pub(super) fn record_visit(&mut self, timestamp: Option<&String>) {
+        let Some((raw, parsed)) = timestamp.and_then(|ts| BinlogTimestamp::parse(ts).ok().map(|parsed| (ts, parsed))) else {
+            return;
+        };
+        if self.min_timestamp.as_ref().is_none_or(|(min, _)| parsed < *min) {
+            self.min_timestamp = Some((parsed.clone(), raw.clone()));
+        }
+        if self.max_timestamp.as_ref().is_none_or(|(max, _)| parsed > *max) {
+            self.max_timestamp = Some((parsed, raw.clone()));
+        }
+    }
+}
+
+impl SnapshotManager {
+    /// Row counts per table at the current position, the range of timestamps visited, and how many
+    /// operations were applied or skipped since load. Useful to sanity-check navigation.
+    pub fn stats(&self) -> Result<SnapshotStats, Box<dyn std::error::Error>> {
+        let mut table_row_counts = Vec::new();
+        for table in self.list_tables()? {
+            let count: i64 = self.get_connection()
+                .query_row(&format!("SELECT COUNT(*) FROM {}", quote_identifier(&table)), [], |row| row.get(0))?;
+            table_row_counts.push((table, count));
+        }
+
+        Ok(SnapshotStats {
+            position: self.get_position(),
+            timestamp: self.get_timestamp().cloned(),
+            table_row_counts,
+            min_timestamp_visited: self.counters.min_timestamp.as_ref().map(|(_, raw)| raw.clone()),
+            max_timestamp_visited: self.counters.max_timestamp.as_ref().map(|(_, raw)| raw.clone()),
+            applied_operations: self.counters.applied,
+            skipped_operations: self.counters.skipped,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use duckdb::Connection;
+    use crate::test_support::insert_id;
+
+    #[test]
+    fn test_stats_track_row_counts_and_navigation() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE books (id INTEGER); INSERT INTO books VALUES (1), (2), (3);
+             CREATE TABLE authors (id INTEGER);"
+        ).unwrap();
+        let operations = vec![
+            insert_id("251108 10:00:00", 1),
+            insert_id("251108 11:00:00", 2),
+            // Already in the snapshot, so skipped
+            insert_id("251108 12:00:00", 3),
+        ];
+        let mut manager = SnapshotManager::new(conn, operations, 1);
+
+        manager.step_forward().unwrap();
+        manager.step_backward().unwrap();
+        manager.step_backward().unwrap();
+        let stats = manager.stats().unwrap();
+
+        assert_eq!(stats.position, 0);
+        // Stepping back over the skipped insert skips it too, so row 3 stays
+        assert_eq!(stats.table_row_counts, vec![("authors".to_string(), 0), ("books".to_string(), 2)]);
+        assert_eq!(stats.min_timestamp_visited.as_deref(), Some("251108 10:00:00"));
+        assert_eq!(stats.max_timestamp_visited.as_deref(), Some("251108 12:00:00"));
+        assert_eq!(stats.applied_operations, 1);
+        assert_eq!(stats.skipped_operations, 2);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,188 @@
+use std::io;
+use crate::binlog::BinlogOperation;
+use crate::snapshot_manager::SnapshotManager;
+use crate::snapshot_manager::operation_store::{OperationSource, OperationStore};
+
+/// A change applied to a subscribed row
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowChange {
+    /// Position of the snapshot once the change is applied
+    pub position: usize,
+    pub timestamp: Option<String>,
+    pub columns: Vec<String>,
+    /// Row values (SQL literals) before and after the change. None if the row didn't exist before
+    /// the change, or doesn't exist after it.
+    pub before: Option<Vec<String>>,
+    pub after: Option<Vec<String>>,
+}
+
+pub type RowChangeCallback = Box<dyn FnMut(&RowChange) + Send>;
+
+struct Subscription {
+    id: usize,
+    table: String,
+    key_columns: Vec<String>,
+    key_values: Vec<String>,
+    callback: RowChangeCallback,
+}
+
+impl Subscription {
+    fn matches(&self, op: &BinlogOperation) -> bool {
+        op.table_name == self.table
+            && [&op.before_values, &op.after_values].into_iter()
+                .flatten()
+                .any(|values| self.key_columns.iter().zip(&self.key_values).all(|(key, expected)| {
+                    op.columns.iter().position(|col| col == key).and_then(|i| values.get(i)) == Some(expected)
+                }))
+    }
+}
+
+#[derive(Default)]
+pub(super) struct Subscriptions {
+    next_id: usize,
+    entries: Vec<Subscription>,
+}
+
+impl Subscriptions {
+    pub(super) fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Calls the callback of every subscription whose row `op` touches. `op` is the operation as
+    /// applied (already inverted when stepping back), `position` the snapshot position it leads to.
+    pub(super) fn notify(&mut self, op: &BinlogOperation, position: usize, operations: &OperationStore) -> io::Result<()> {
+        for subscription in self.entries.iter_mut().filter(|subscription| subscription.matches(op)) {
+            (subscription.callback)(&RowChange {
+                position,
+                timestamp: operations.operation(position)?.timestamp.clone(),
+                columns: op.columns.clone(),
+                before: op.before_values.clone(),
+                after: op.after_values.clone(),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl SnapshotManager {
+    /// Calls `callback` whenever navigation applies an operation that touches one row of `table`,
+    /// forwards or backwards. `key` holds the row's primary key values as SQL literals (e.g. `["1"]`
+    /// or `["'abc'"]`), in primary key column order; the first column is the key if the table has
+    /// no primary key.
+    ///
+    /// While there are subscriptions, goto_position replays every operation of a jump instead of
+    /// consolidating it, so each change is reported at its own position. Returns an id for unsubscribe.
+    pub fn subscribe(
+        &mut self,
+        table: &str,
+        key: &[&str],
+        callback: impl FnMut(&RowChange) + Send + 'static,
+    ) -> Result<usize, Box<dyn std::error::Error>> {
+        let columns = self.table_columns(table)?;
+        if columns.is_empty() {
+            return Err(format!("Unknown table {}", table).into());
+        }
+        let key_columns = self.primary_key_columns(table, &columns)?;
+        if key_columns.len() != key.len() {
+            return Err(format!("{} has key columns {:?}, got {} value(s)", table, key_columns, key.len()).into());
+        }
+
+        let id = self.subscriptions.next_id;
+        self.subscriptions.next_id += 1;
+        self.subscriptions.entries.push(Subscription {
+            id,
+            table: table.to_string(),
+            key_columns,
+            key_values: key.iter().map(|value| value.to_string()).collect(),
+            callback: Box::new(callback),
+        });
+        Ok(id)
+    }
+
+    /// Removes a subscription. Returns false if there was no such subscription.
+    pub fn unsubscribe(&mut self, id: usize) -> bool {
+        let count = self.subscriptions.entries.len();
+        self.subscriptions.entries.retain(|subscription| subscription.id != id);
+        self.subscriptions.entries.len() != count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use crate::binlog::OperationType;
+    use crate::test_support::{books_db, books_op};
+
+    fn update_op(timestamp: &str, id: i64, before: i64, after: i64) -> BinlogOperation {
+        let (id, before, after) = (id.to_string(), before.to_string(), after.to_string());
+        books_op(OperationType::Update, timestamp, Some([&id, &before]), Some([&id, &after]))
+    }
+
+    #[test]
+    fn test_subscription_reports_changes_to_one_row() {
+        let conn = books_db("id INTEGER PRIMARY KEY, price INTEGER", "(1, 10), (2, 20)");
+        let operations = vec![
+            update_op("251108 10:00:00", 2, 19, 20),
+            update_op("251108 11:00:00", 1, 10, 11),
+            update_op("251108 12:00:00", 2, 20, 21),
+            update_op("251108 13:00:00", 1, 11, 12),
+        ];
+        let mut manager = SnapshotManager::new(conn, operations, 0);
+
+        let changes = Arc::new(Mutex::new(Vec::new()));
+        let recorded = changes.clone();
+        let id = manager.subscribe("books", &["1"], move |change| {
+            recorded.lock().unwrap().push((change.position, change.after.clone().unwrap()[1].clone()));
+        }).unwrap();
+
+        manager.goto_position(3).unwrap();
+        manager.step_backward().unwrap();
+        assert_eq!(*changes.lock().unwrap(), vec![
+            (1, "11".to_string()),
+            (3, "12".to_string()),
+            (2, "11".to_string()),
+        ]);
+
+        assert!(manager.subscribe("books", &["1", "2"], |_| {}).is_err());
+        assert!(manager.unsubscribe(id));
+        manager.step_forward().unwrap();
+        assert_eq!(changes.lock().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_jumps_report_each_change_instead_of_a_consolidated_one() {
+        let conn = books_db("id INTEGER PRIMARY KEY, price INTEGER", "(1, 10), (2, 20)");
+        let operations = vec![
+            update_op("251108 10:00:00", 2, 19, 20),
+            update_op("251108 11:00:00", 1, 10, 11),
+            update_op("251108 12:00:00", 1, 11, 12),
+            update_op("251108 13:00:00", 1, 12, 13),
+            update_op("251108 14:00:00", 1, 13, 14),
+        ];
+        let mut manager = SnapshotManager::new(conn, operations, 0);
+
+        let changes = Arc::new(Mutex::new(Vec::new()));
+        let recorded = changes.clone();
+        manager.subscribe("books", &["1"], move |change| {
+            recorded.lock().unwrap().push((change.position, change.after.clone().unwrap()[1].clone()));
+        }).unwrap();
+
+        manager.goto_position(4).unwrap();
+        assert_eq!(*changes.lock().unwrap(), vec![
+            (1, "11".to_string()),
+            (2, "12".to_string()),
+            (3, "13".to_string()),
+            (4, "14".to_string()),
+        ]);
+
+        changes.lock().unwrap().clear();
+        manager.goto_position(0).unwrap();
+        assert_eq!(*changes.lock().unwrap(), vec![
+            (3, "13".to_string()),
+            (2, "12".to_string()),
+            (1, "11".to_string()),
+            (0, "10".to_string()),
+        ]);
+    }
+}
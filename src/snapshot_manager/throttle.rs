@@ -0,0 +1,31 @@
+use std::time::{Duration, Instant};
+
+/// Limits how fast navigation applies operations, by sleeping whenever it gets ahead of
+/// `max_operations_per_second` since the navigation began
+#[derive(Debug, Clone)]
+pub(super) struct OperationThrottle {
+    max_operations_per_second: f64,
+    started: Instant,
+    applied: u64,
+}
+
+impl OperationThrottle {
+    pub(super) fn new(max_operations_per_second: f64) -> Self {
+        Self { max_operations_per_second, started: Instant::now(), applied: 0 }
+    }
+
+    /// Starts a navigation; time spent between navigations isn't credited to the next one
+    pub(super) fn begin(&mut self) {
+        self.started = Instant::now();
+        self.applied = 0;
+    }
+
+    /// Records an applied operation, sleeping if the rate is above the limit
+    pub(super) fn record(&mut self) {
+        self.applied += 1;
+        let due = Duration::from_secs_f64(self.applied as f64 / self.max_operations_per_second);
+        if let Some(ahead) = due.checked_sub(self.started.elapsed()) {
+            std::thread::sleep(ahead);
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use chrono::{Duration, DurationRound};
+use crate::binlog::BinlogTimestamp;
+use crate::snapshot_manager::SnapshotManager;
+
+/// Where a step to the next minute or hour ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoundaryStep {
+    /// Stepped to the operation at `position`, applying `applied` operations
+    Reached { position: usize, applied: usize },
+    /// No later operation is in a later minute (or hour), so the position didn't change
+    Exhausted,
+}
+
+impl SnapshotManager {
+    /// Steps forward to the first operation at or after the start of the minute following the
+    /// current operation's, e.g. from 10:04:31 to the first operation at 10:05:00 or later.
+    pub fn step_to_next_minute(&mut self) -> Result<BoundaryStep, Box<dyn std::error::Error>> {
+        self.step_to_next_boundary(Duration::minutes(1))
+    }
+
+    /// Like step_to_next_minute, to the start of the next hour
+    pub fn step_to_next_hour(&mut self) -> Result<BoundaryStep, Box<dyn std::error::Error>> {
+        self.step_to_next_boundary(Duration::hours(1))
+    }
+
+    fn step_to_next_boundary(&mut self, unit: Duration) -> Result<BoundaryStep, Box<dyn std::error::Error>> {
+        let Some(current) = self.get_timestamp() else {
+            return Err(format!("Operation {} has no timestamp", self.get_position()).into());
+        };
+        let current = BinlogTimestamp::parse(current)?;
+        let boundary = BinlogTimestamp::from(current.as_datetime().duration_trunc(unit)? + unit);
+        let Some(target) = self.first_position_from(self.get_position(), &boundary)? else {
+            return Ok(BoundaryStep::Exhausted);
+        };
+        let applied = self.step_forward_by(target - self.get_position())?;
+        Ok(BoundaryStep::Reached { position: self.get_position(), applied })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::snapshot_manager::SnapshotManager;
+    use super::BoundaryStep;
+    use crate::test_support::{books_db, insert_id};
+
+    #[test]
+    fn test_steps_to_the_first_operation_of_the_next_minute_and_hour() {
+        let conn = books_db("id INTEGER PRIMARY KEY", "(0)");
+        let timestamps = ["251108 10:04:31", "251108 10:04:59", "251108 10:05:00", "251108 10:05:30", "251108 10:07:10", "251108 11:00:01"];
+        let operations = timestamps.iter().enumerate().map(|(id, ts)| insert_id(ts, id)).collect();
+        let mut manager = SnapshotManager::new(conn, operations, 0);
+
+        assert_eq!(manager.step_to_next_minute().unwrap(), BoundaryStep::Reached { position: 2, applied: 2 });
+        assert_eq!(manager.get_timestamp().unwrap(), "251108 10:05:00");
+        // Minutes without operations are skipped
+        assert_eq!(manager.step_to_next_minute().unwrap(), BoundaryStep::Reached { position: 4, applied: 2 });
+        assert_eq!(manager.step_to_next_hour().unwrap(), BoundaryStep::Reached { position: 5, applied: 1 });
+        assert_eq!(manager.step_to_next_minute().unwrap(), BoundaryStep::Exhausted);
+        assert_eq!(manager.get_position(), 5);
+    }
+
+    #[test]
+    fn test_step_is_exhausted_when_later_operations_share_the_minute() {
+        let conn = books_db("id INTEGER PRIMARY KEY", "(0)");
+        let timestamps = ["251108 10:04:31", "251108 10:04:40", "251108 10:04:59"];
+        let operations = timestamps.iter().enumerate().map(|(id, ts)| insert_id(ts, id)).collect();
+        let mut manager = SnapshotManager::new(conn, operations, 0);
+
+        assert_eq!(manager.step_to_next_minute().unwrap(), BoundaryStep::Exhausted);
+        assert_eq!(manager.step_to_next_hour().unwrap(), BoundaryStep::Exhausted);
+        assert_eq!(manager.get_position(), 0);
+    }
+}
@@ -0,0 +1,102 @@
+use duckdb::arrow::array::{Array, AsArray};
+use duckdb::arrow::compute::cast;
+use duckdb::arrow::datatypes::{DataType, Float64Type, Int64Type, UInt64Type};
+use duckdb::arrow::record_batch::RecordBatch;
+use duckdb::arrow::util::display::{ArrayFormatter, FormatOptions};
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value as JsonValue};
+use crate::snapshot_manager::SnapshotManager;
+
+impl SnapshotManager {
+    /// Runs a SQL query at the current position and deserializes each row into a `T`, matching
+    /// the query's column names to T's field names (alias columns with `AS` to match them).
+    ///
+    /// Rows are read through DuckDB's Arrow output. Booleans, integers, floats, decimals and
+    /// strings become the matching serde types (decimals as f64); any other type (dates,
+    /// timestamps, blobs, lists, ...) is passed as its text, so read it into a String. NULLs are
+    /// `None` for `Option` fields.
+    pub fn query_as<T: DeserializeOwned>(&self, sql: &str) -> Result<Vec<T>, Box<dyn std::error::Error>> {
+        let mut stmt = self.get_connection().prepare(sql)?;
+        let mut rows = Vec::new();
+        for batch in stmt.query_arrow([])? {
+            for row in batch_rows(&batch)? {
+                let row_number = rows.len();
+                let row = serde_json::from_value(JsonValue::Object(row))
+                    .map_err(|e| format!("Row {} of the query doesn't fit the requested type: {}", row_number, e))?;
+                rows.push(row);
+            }
+        }
+        Ok(rows)
+    }
+}
+
+/// The rows of a record batch as JSON objects keyed by column name
+fn batch_rows(batch: &RecordBatch) -> Result<Vec<Map<String, JsonValue>>, Box<dyn std::error::Error>> {
+    let mut rows = vec![Map::new(); batch.num_rows()];
+    for (field, column) in batch.schema().fields().iter().zip(batch.columns()) {
+        for (row, value) in rows.iter_mut().zip(column_values(column.as_ref())?) {
+            row.insert(field.name().clone(), value);
+        }
+    }
+    Ok(rows)
+}
+
+/// Values of an Arrow column as JSON values, NULLs included
+fn column_values(array: &dyn Array) -> Result<Vec<JsonValue>, Box<dyn std::error::Error>> {
+    let values: Vec<JsonValue> = match array.data_type() {
+        DataType::Null => vec![JsonValue::Null; array.len()],
+        DataType::Boolean => array.as_boolean().iter().map(|v| v.into()).collect(),
+        DataType::Int8 | DataType::Int16 | DataType::Int32 | DataType::Int64 => {
+            cast(array, &DataType::Int64)?.as_primitive::<Int64Type>().iter().map(|v| v.into()).collect()
+        }
+        DataType::UInt8 | DataType::UInt16 | DataType::UInt32 | DataType::UInt64 => {
+            cast(array, &DataType::UInt64)?.as_primitive::<UInt64Type>().iter().map(|v| v.into()).collect()
+        }
+        DataType::Float16 | DataType::Float32 | DataType::Float64
+            | DataType::Decimal128(_, _) | DataType::Decimal256(_, _) => {
+            cast(array, &DataType::Float64)?.as_primitive::<Float64Type>().iter().map(|v| v.into()).collect()
+        }
+        DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View => {
+            cast(array, &DataType::Utf8)?.as_string::<i32>().iter().map(|v| v.into()).collect()
+        }
+        _ => {
+            let formatter = ArrayFormatter::try_new(array, &FormatOptions::default())?;
+            (0..array.len())
+                .map(|i| if array.is_null(i) { JsonValue::Null } else { formatter.value(i).to_string().into() })
+                .collect()
+        }
+    };
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use duckdb::Connection;
+    use serde::Deserialize;
+    use crate::snapshot_manager::SnapshotManager;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Book {
+        id: i32,
+        title: String,
+        price: f64,
+        published: Option<String>,
+    }
+
+    #[test]
+    fn test_query_as_deserializes_rows_by_column_name() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE books (id INTEGER, title VARCHAR, price DECIMAL(10,2), published DATE);
+             INSERT INTO books VALUES (1, 'Dune', 9.50, '1965-08-01'), (2, 'Emma', 4.25, NULL);"
+        ).unwrap();
+        let manager = SnapshotManager::new(conn, Vec::new(), 0);
+
+        let books: Vec<Book> = manager.query_as("SELECT title, id, price, published FROM books ORDER BY id").unwrap();
+        assert_eq!(books, vec![
+            Book { id: 1, title: "Dune".to_string(), price: 9.5, published: Some("1965-08-01".to_string()) },
+            Book { id: 2, title: "Emma".to_string(), price: 4.25, published: None },
+        ]);
+        assert!(manager.query_as::<Book>("SELECT id FROM books").is_err());
+    }
+}
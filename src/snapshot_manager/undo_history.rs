@@ -0,0 +1,80 @@
+use std::collections::VecDeque;
+use crate::binlog::BinlogOperation;
+
+/// Bytes of values kept for undoing steps; the oldest steps are forgotten beyond this
+const MAX_UNDO_BYTES: usize = 64 * 1024 * 1024;
+
+/// One forward step: the position it moved to, and the inverse of the operation it executed if
+/// that changed a row
+#[derive(Debug, Clone)]
+struct AppliedStep {
+    position: usize,
+    undo: Option<BinlogOperation>,
+}
+
+impl AppliedStep {
+    fn size(&self) -> usize {
+        self.undo.as_ref().map_or(0, |op| {
+            [&op.before_values, &op.after_values].into_iter()
+                .flatten()
+                .flatten()
+                .chain(&op.columns)
+                .map(String::len)
+                .sum()
+        })
+    }
+}
+
+/// Stack of the forward steps taken since the last jump, so that stepping back undoes exactly what
+/// stepping forward did. Operations skipped on the way forward (already applied, before-image
+/// mismatch, or a forced statement that matched no row) are skipped on the way back, instead of
+/// their inverse being checked against the current row again, which can disagree.
+///
+/// Each step keeps the inverse of the operation as executed (with its partial row image
+/// completed), so undoing it doesn't depend on the stored operation. The oldest steps are
+/// forgotten once their values take more than MAX_UNDO_BYTES.
+#[derive(Debug, Default)]
+pub(crate) struct UndoHistory {
+    steps: VecDeque<AppliedStep>,
+    bytes: usize,
+}
+
+impl UndoHistory {
+    /// Records the step to `position`, with `executed` the operation it changed a row with
+    pub(crate) fn push(&mut self, position: usize, executed: Option<&BinlogOperation>) {
+        let step = AppliedStep { position, undo: executed.map(BinlogOperation::invert) };
+        self.bytes += step.size();
+        self.steps.push_back(step);
+        while self.bytes > MAX_UNDO_BYTES && let Some(oldest) = self.steps.pop_front() {
+            self.bytes -= oldest.size();
+        }
+    }
+
+    /// The operation undoing the step to `position` (None inside if it changed nothing), if it is
+    /// the last step recorded
+    pub(crate) fn pop(&mut self, position: usize) -> Option<Option<BinlogOperation>> {
+        if self.steps.back()?.position != position {
+            self.clear();
+            return None;
+        }
+        let step = self.steps.pop_back()?;
+        self.bytes -= step.size();
+        Some(step.undo)
+    }
+
+    /// Whether the recorded steps reach from `from` back to `to`, so that going back there can
+    /// undo each of them
+    pub(crate) fn covers(&self, from: usize, to: usize) -> bool {
+        self.steps.back().is_some_and(|step| step.position == from)
+            && self.steps.len() >= from.saturating_sub(to)
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.steps.clear();
+        self.bytes = 0;
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.steps.len()
+    }
+}
@@ -0,0 +1,165 @@
+use duckdb::types::Value;
+use crate::loader::parquet_loader;
+use crate::binlog::quote_identifier;
+use crate::snapshot_manager::SnapshotManager;
+
+/// How one reconstructed table compares with a reference dump, rows matched by primary key
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableVerification {
+    pub table: String,
+    pub key_columns: Vec<String>,
+    pub columns: Vec<String>,
+    pub reference_rows: usize,
+    pub reconstructed_rows: usize,
+    /// Rows of the reference dump that Pensieve doesn't have
+    pub missing: Vec<Vec<Value>>,
+    /// Rows Pensieve has that the reference dump doesn't
+    pub extra: Vec<Vec<Value>>,
+    /// (reference row, reconstructed row) for rows whose values differ
+    pub mismatched: Vec<(Vec<Value>, Vec<Value>)>,
+}
+
+impl TableVerification {
+    pub fn is_exact(&self) -> bool {
+        self.missing.is_empty() && self.extra.is_empty() && self.mismatched.is_empty()
+    }
+
+    /// Reference rows that Pensieve reconstructed exactly. Rows sharing a key are paired with
+    /// each other, so there can be more mismatches than reference rows.
+    pub fn matching_rows(&self) -> usize {
+        self.reference_rows.saturating_sub(self.missing.len()).saturating_sub(self.mismatched.len())
+    }
+}
+
+/// Result of verify_against_reference
+#[derive(Debug, Clone, PartialEq)]
+pub struct VerificationReport {
+    /// Position the reference was compared at
+    pub position: usize,
+    pub timestamp: Option<String>,
+    pub tables: Vec<TableVerification>,
+}
+
+impl VerificationReport {
+    pub fn is_exact(&self) -> bool {
+        self.tables.iter().all(TableVerification::is_exact)
+    }
+
+    /// Share of the reference rows (and extra rows) that were reconstructed exactly, 1.0 when
+    /// there are none
+    pub fn accuracy(&self) -> f64 {
+        let matching: usize = self.tables.iter().map(TableVerification::matching_rows).sum();
+        let total: usize = self.tables.iter().map(|t| t.reference_rows + t.extra.len()).sum();
+        if total == 0 { 1.0 } else { matching as f64 / total as f64 }
+    }
+}
+
+impl SnapshotManager {
+    /// Compares the state at `timestamp` with a reference dump taken at that time, e.g. a
+    /// point-in-time restore, to measure how accurately Pensieve reconstructs it.
+    ///
+    /// `reference` holds the dump's files (parquet, CSV or JSONL) per table. They are loaded into
+    /// temporary tables with the types of Pensieve's tables, and compared on the primary key (or
+    /// first column). The snapshot is moved back to its previous position afterwards.
+    pub fn verify_against_reference(
+        &mut self,
+        timestamp: &str,
+        reference: &[(String, Vec<String>)],
+    ) -> Result<VerificationReport, Box<dyn std::error::Error>> {
+        let previous_position = self.get_position();
+        self.goto_timestamp(timestamp)?;
+        let position = self.get_position();
+        let timestamp = self.get_timestamp().cloned();
+
+        let tables = reference.iter()
+            .map(|(table, files)| self.verify_table(table, files))
+            .collect::<Result<Vec<_>, _>>();
+        self.goto_position(previous_position)?;
+        Ok(VerificationReport { position, timestamp, tables: tables? })
+    }
+
+    fn verify_table(&self, table: &str, files: &[String]) -> Result<TableVerification, Box<dyn std::error::Error>> {
+        let columns = self.table_columns(table)?;
+        if columns.is_empty() {
+            return Err(format!("Table {} not found", table).into());
+        }
+        let key_columns = self.primary_key_columns(table, &columns)?;
+
+        let reference_table = format!("{}__reference", table);
+        self.get_connection().execute_batch(&format!(
+            "CREATE OR REPLACE TEMP TABLE {} AS SELECT * FROM {} LIMIT 0;",
+            quote_identifier(&reference_table), quote_identifier(table)
+        ))?;
+        let compare = || -> Result<_, Box<dyn std::error::Error>> {
+            let reference_rows = parquet_loader::insert_from_snapshot_files(self.get_connection(), &reference_table, &columns, files, table, &self.redactions)?;
+            let (extra, missing, mismatched) = self.compare_tables(&reference_table, table, &columns, &key_columns)?;
+            Ok((reference_rows, extra, missing, mismatched))
+        };
+        let result = compare();
+        self.get_connection().execute_batch(&format!("DROP TABLE IF EXISTS {};", quote_identifier(&reference_table)))?;
+        let (reference_rows, extra, missing, mismatched) = result?;
+
+        let reconstructed_rows: i64 = self.get_connection()
+            .query_row(&format!("SELECT COUNT(*) FROM {}", quote_identifier(table)), [], |row| row.get(0))?;
+        Ok(TableVerification {
+            table: table.to_string(),
+            key_columns,
+            columns,
+            reference_rows,
+            reconstructed_rows: reconstructed_rows as usize,
+            missing,
+            extra,
+            mismatched,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binlog::OperationType;
+    use crate::test_support::{books_db, books_op};
+
+    #[test]
+    fn test_verify_against_reference_dump() {
+        let conn = books_db("id INTEGER PRIMARY KEY, price INTEGER", "(1, 10), (2, 20)");
+        let operations = vec![
+            books_op(OperationType::Insert, "251108 10:00:00", None, Some(["2", "20"])),
+            books_op(OperationType::Update, "251108 11:00:00", Some(["1", "10"]), Some(["1", "11"])),
+            books_op(OperationType::Insert, "251108 12:00:00", None, Some(["3", "30"])),
+        ];
+        let mut manager = SnapshotManager::new(conn, operations, 0);
+
+        // Taken at 11:30: row 1 at 12 instead of 11, row 4 that the binlog never inserted
+        let dump = std::env::temp_dir().join(format!("pensieve_reference_{}.csv", std::process::id()));
+        std::fs::write(&dump, "price,id\n12,1\n20,2\n40,4\n").unwrap();
+        let reference = vec![("books".to_string(), vec![dump.to_string_lossy().to_string()])];
+
+        let report = manager.verify_against_reference("251108 11:30:00", &reference).unwrap();
+
+        assert_eq!(report.position, 1);
+        let books = &report.tables[0];
+        assert_eq!((books.reference_rows, books.reconstructed_rows), (3, 2));
+        assert_eq!(books.missing, vec![vec![Value::Int(4), Value::Int(40)]]);
+        assert!(books.extra.is_empty());
+        assert_eq!(books.mismatched, vec![(vec![Value::Int(1), Value::Int(12)], vec![Value::Int(1), Value::Int(11)])]);
+        assert_eq!(books.matching_rows(), 1);
+        assert!(!report.is_exact());
+        assert!((report.accuracy() - 1.0 / 3.0).abs() < 1e-9);
+        assert_eq!(manager.get_position(), 0);
+
+        // Reference rows 1 and 1 against reconstructed 1 and 1 give four mismatched pairs
+        let row = |price: i32| vec![Value::Int(1), Value::Int(price)];
+        let duplicated = TableVerification {
+            reference_rows: 2,
+            reconstructed_rows: 2,
+            missing: Vec::new(),
+            extra: Vec::new(),
+            mismatched: vec![(row(12), row(11)), (row(12), row(13)), (row(14), row(11)), (row(14), row(13))],
+            ..books.clone()
+        };
+        assert_eq!(duplicated.matching_rows(), 0);
+
+        std::fs::remove_file(dump).ok();
+    }
+}
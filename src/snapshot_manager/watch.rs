@@ -0,0 +1,117 @@
+use duckdb::types::Value;
+use crate::snapshot_manager::SnapshotManager;
+
+/// Value of a watch expression at one position
+#[derive(Debug, Clone, PartialEq)]
+pub struct WatchSample {
+    pub position: usize,
+    pub timestamp: Option<String>,
+    pub value: Value,
+}
+
+/// A SQL expression re-evaluated every time the snapshot moves
+#[derive(Debug, Clone)]
+pub struct Watch {
+    pub name: String,
+    pub sql: String,
+    pub samples: Vec<WatchSample>,
+}
+
+impl SnapshotManager {
+    /// Registers a watch: `sql` is evaluated now and after every step or goto, and its value
+    /// (first column of the first row, NULL if there are no rows) is recorded with the position
+    /// and timestamp. Registering a name again replaces the previous watch.
+    pub fn add_watch(&mut self, name: &str, sql: &str) -> Result<(), Box<dyn std::error::Error>> {
+        self.remove_watch(name);
+        let sample = self.evaluate_watch(sql)?;
+        self.watches.push(Watch {
+            name: name.to_string(),
+            sql: sql.to_string(),
+            samples: vec![sample],
+        });
+        Ok(())
+    }
+
+    /// Removes a watch and its recorded samples. Returns false if there was no such watch.
+    pub fn remove_watch(&mut self, name: &str) -> bool {
+        let count = self.watches.len();
+        self.watches.retain(|watch| watch.name != name);
+        self.watches.len() != count
+    }
+
+    pub fn get_watches(&self) -> &[Watch] {
+        &self.watches
+    }
+
+    /// Recorded samples of a watch, in the order they were taken
+    pub fn get_watch_series(&self, name: &str) -> Option<&[WatchSample]> {
+        self.watches.iter()
+            .find(|watch| watch.name == name)
+            .map(|watch| watch.samples.as_slice())
+    }
+
+    /// Records a sample of every watch at the current position.
+    /// Nothing is recorded for a watch whose last sample is already at this position.
+    pub(super) fn evaluate_watches(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        for i in 0..self.watches.len() {
+            let last_position = self.watches[i].samples.last().map(|sample| sample.position);
+            if last_position == Some(self.get_position()) {
+                continue;
+            }
+            let sample = self.evaluate_watch(&self.watches[i].sql)?;
+            self.watches[i].samples.push(sample);
+        }
+        Ok(())
+    }
+
+    fn evaluate_watch(&self, sql: &str) -> Result<WatchSample, Box<dyn std::error::Error>> {
+        let value = self.query(sql)?
+            .into_iter()
+            .next()
+            .and_then(|row| row.into_iter().next())
+            .unwrap_or(Value::Null);
+
+        Ok(WatchSample {
+            position: self.get_position(),
+            timestamp: self.get_timestamp().cloned(),
+            value,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{books_db, insert_id};
+
+    #[test]
+    fn test_watch_records_value_after_each_move() {
+        let conn = books_db("id INTEGER", "(1)");
+        let operations = vec![
+            insert_id("251108 10:00:00", 1),
+            insert_id("251108 11:00:00", 2),
+            insert_id("251108 12:00:00", 3),
+        ];
+        let mut manager = SnapshotManager::new(conn, operations, 0);
+
+        manager.add_watch("books", "SELECT COUNT(*) FROM books").unwrap();
+        manager.step_forward().unwrap();
+        manager.goto_position(2).unwrap();
+        manager.step_backward().unwrap();
+
+        let series: Vec<(usize, Value)> = manager.get_watch_series("books").unwrap()
+            .iter()
+            .map(|sample| (sample.position, sample.value.clone()))
+            .collect();
+        assert_eq!(series, vec![
+            (0, Value::BigInt(1)),
+            (1, Value::BigInt(2)),
+            (2, Value::BigInt(3)),
+            (1, Value::BigInt(2)),
+        ]);
+        assert_eq!(manager.get_watch_series("books").unwrap()[1].timestamp.as_deref(), Some("251108 11:00:00"));
+
+        assert!(manager.remove_watch("books"));
+        assert!(manager.get_watch_series("books").is_none());
+    }
+}
\ No newline at end of file
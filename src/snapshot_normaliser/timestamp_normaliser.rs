@@ -1,6 +1,155 @@
 use duckdb::Connection;
+use std::fs::File;
+use std::io::{BufWriter, Write};
 use crate::binlog::{BinlogOperation, BinlogTimestamp};
-use crate::operation_applier::OperationApplier;
+use crate::operation_applier::{ApplyOutcome, OperationApplier};
+use crate::pensieve::CancelToken;
+use crate::snapshot_manager::operation_store::OperationSource;
+use tracing::{debug, info};
+
+/// Whether an operation is applied as-is or inverted during normalisation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NormalisationDirection {
+    /// Operations up to and including transaction zero are applied forward
+    Forward,
+    /// Operations after transaction zero are inverted
+    Inverted,
+}
+
+impl std::fmt::Display for NormalisationDirection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NormalisationDirection::Forward => write!(f, "forward"),
+            NormalisationDirection::Inverted => write!(f, "inverted"),
+        }
+    }
+}
+
+/// What the normaliser did (or would do) with one operation
+#[derive(Debug, Clone)]
+pub struct NormalisationDecision {
+    /// Index of the operation in the operation stream
+    pub index: usize,
+    pub direction: NormalisationDirection,
+    /// False if the operation was skipped because it would have no effect
+    pub applied: bool,
+    /// Why the operation was applied or skipped
+    pub outcome: ApplyOutcome,
+    /// SQL generated for the operation (already inverted for Inverted operations)
+    pub sql: String,
+}
+
+/// What normalize does when no operations fall in the window around the snapshot timestamp
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EmptyWindowPolicy {
+    /// Skip normalisation and anchor to the last operation
+    #[default]
+    AnchorToLast,
+    /// Double the window until it contains operations, failing with NoOperationsInWindow once
+    /// it is wider than `max_window_hours`
+    Expand { max_window_hours: i64 },
+    /// Fail with NoOperationsInWindow
+    Fail,
+}
+
+/// Which operation in the window normalize anchors the snapshot to (transaction zero)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AnchorStrategy {
+    /// The middle operation of the window
+    #[default]
+    Midpoint,
+    /// The first operation of the window, for snapshots taken right after the estimate
+    EarliestInWindow,
+    /// The last operation of the window, for snapshots taken right before the estimate
+    LatestInWindow,
+    /// The operation closest in time to the snapshot timestamp, for snapshots whose time is known.
+    /// Of operations equally close, the latest.
+    ExactTimestamp,
+}
+
+/// Returned by normalize when no operations fall in the window and the EmptyWindowPolicy
+/// doesn't allow anchoring anyway
+#[derive(Debug, Clone, PartialEq)]
+pub struct NoOperationsInWindow {
+    pub snapshot_timestamp: String,
+    /// Widest window searched, in hours either side of the snapshot timestamp
+    pub window_hours: i64,
+}
+
+impl std::fmt::Display for NoOperationsInWindow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "No operations within {} hour(s) of {}", self.window_hours, self.snapshot_timestamp)
+    }
+}
+
+impl std::error::Error for NoOperationsInWindow {}
+
+/// How a snapshot was (or would be) anchored: every decision of TimestampNormaliser::normalize
+/// or normalize_dry_run, in the order they were made
+#[derive(Debug, Clone, Default)]
+pub struct NormalisationReport {
+    /// The position normalisation anchored to, or None if no operations fell in the window
+    pub tx_zero_idx: Option<usize>,
+    /// Window searched around the snapshot timestamp, in hours either side. Larger than requested
+    /// if EmptyWindowPolicy::Expand widened it.
+    pub window_hours: i64,
+    pub decisions: Vec<NormalisationDecision>,
+}
+
+/// Result of TimestampNormaliser::normalize_dry_run
+pub type DryRunReport = NormalisationReport;
+
+impl NormalisationReport {
+    pub fn applied_count(&self) -> usize {
+        self.decisions.iter().filter(|d| d.applied).count()
+    }
+
+    pub fn skipped_count(&self) -> usize {
+        self.decisions.len() - self.applied_count()
+    }
+
+    /// Writes the SQL that normalisation would execute, in execution order, to a file.
+    /// Skipped operations are included as comments.
+    pub fn write_sql_script(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "-- Normalisation script (transaction zero: {:?})", self.tx_zero_idx)?;
+
+        for decision in &self.decisions {
+            if decision.applied {
+                writeln!(writer, "-- #{} {}", decision.index, decision.direction)?;
+                writeln!(writer, "{}", decision.sql)?;
+            } else {
+                writeln!(writer, "-- #{} {} (skipped, {}): {}", decision.index, decision.direction, decision.outcome, decision.sql)?;
+            }
+        }
+
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Writes the decision log as tab-separated values with a header line:
+    /// index, direction, applied, reason and SQL of every decision, in the order they were made
+    pub fn write_decision_log(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "index\tdirection\tapplied\treason\tsql")?;
+        for decision in &self.decisions {
+            writeln!(
+                writer,
+                "{}\t{}\t{}\t{}\t{}",
+                decision.index,
+                decision.direction,
+                decision.applied,
+                decision.outcome,
+                decision.sql.replace(['\t', '\n'], " "),
+            )?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Connection, operations, normalized position index and decision report returned by normalize
+pub type NormalisedSnapshot = (Connection, Vec<BinlogOperation>, usize, NormalisationReport);
 
 /// Normalizes a database snapshot to a specific timestamp using binlog operations
 pub struct TimestampNormaliser {
@@ -29,7 +178,7 @@ impl TimestampNormaliser {
     /// Check the TimestampNormaliser documentation for an explanation of the normalisation algorithm.
     ///
     /// This function arbitrarily chooses the midpoint of the transactions in the window and normalises
-    /// the snapshot to this point. This lets us identify exactly which transactions come immediately
+    /// the snapshot to this point (see normalize_with_anchor for other choices). This lets us identify exactly which transactions come immediately
     /// before and after the snapshot.
     ///
     /// # Arguments
@@ -39,17 +188,160 @@ impl TimestampNormaliser {
     /// * `window_hours` - Size of window to search around snapshot (e.g., 1 hour)
     /// 
     /// # Returns
-    /// A tuple of (Connection, operations, normalized position index)
+    /// A tuple of (Connection, operations, normalized position index, report of every decision)
     pub fn normalize(
         conn: Connection,
         operations: Vec<BinlogOperation>,
         snapshot_timestamp: &str,
         window_hours: i64,
-    ) -> Result<(Connection, Vec<BinlogOperation>, usize), Box<dyn std::error::Error>> {
-        let mut applier = OperationApplier::new(conn);
+    ) -> Result<NormalisedSnapshot, Box<dyn std::error::Error>> {
+        Self::normalize_with_policy(conn, operations, snapshot_timestamp, window_hours, EmptyWindowPolicy::default())
+    }
+
+    /// Like normalize, with a choice of what to do when no operations fall in the window.
+    /// Returns a NoOperationsInWindow error if the policy doesn't find any.
+    pub fn normalize_with_policy(
+        conn: Connection,
+        operations: Vec<BinlogOperation>,
+        snapshot_timestamp: &str,
+        window_hours: i64,
+        policy: EmptyWindowPolicy,
+    ) -> Result<NormalisedSnapshot, Box<dyn std::error::Error>> {
+        Self::normalize_with_anchor(conn, operations, snapshot_timestamp, window_hours, policy, AnchorStrategy::default())
+    }
+
+    /// Like normalize_with_policy, with a choice of which operation in the window to anchor to.
+    /// The midpoint is wrong when the snapshot took much longer to finish than to start (or the
+    /// other way round) around the estimated timestamp.
+    pub fn normalize_with_anchor(
+        conn: Connection,
+        operations: Vec<BinlogOperation>,
+        snapshot_timestamp: &str,
+        window_hours: i64,
+        policy: EmptyWindowPolicy,
+        anchor: AnchorStrategy,
+    ) -> Result<NormalisedSnapshot, Box<dyn std::error::Error>> {
+        Self::normalize_with_applier(OperationApplier::new(conn), operations, snapshot_timestamp, window_hours, policy, anchor)
+    }
+
+    /// Like normalize_with_anchor, applying the window's operations with a configured applier,
+    /// e.g. one using DuckDB's Appender for INSERTs
+    pub fn normalize_with_applier(
+        applier: OperationApplier,
+        operations: Vec<BinlogOperation>,
+        snapshot_timestamp: &str,
+        window_hours: i64,
+        policy: EmptyWindowPolicy,
+        anchor: AnchorStrategy,
+    ) -> Result<NormalisedSnapshot, Box<dyn std::error::Error>> {
+        let (conn, tx_zero_idx, report) = Self::normalize_source(applier, operations.as_slice(), snapshot_timestamp, window_hours, policy, anchor, &CancelToken::new())?;
+        Ok((conn, operations, tx_zero_idx, report))
+    }
+
+    /// Like normalize_with_applier, reading the operations from a slice or an OperationStore,
+    /// which may have spilled them to disk. Only the window's operations are read by index.
+    /// Stops with a LoadCancelled error once `cancel` is cancelled.
+    pub(crate) fn normalize_source<O: OperationSource + ?Sized>(
+        mut applier: OperationApplier,
+        operations: &O,
+        snapshot_timestamp: &str,
+        window_hours: i64,
+        policy: EmptyWindowPolicy,
+        anchor: AnchorStrategy,
+        cancel: &CancelToken,
+    ) -> Result<(Connection, usize, NormalisationReport), Box<dyn std::error::Error>> {
+
+        info!("Normalising to timestamp {}", snapshot_timestamp);
+        
+        let (window_ops, window_hours) = Self::resolve_window(operations, snapshot_timestamp, window_hours, policy)?;
+
+        if window_ops.is_empty() {
+            info!("No operations found in window, skipping normalisation");
+            let tx_zero_idx = operations.operation_count().saturating_sub(1);
+            let report = NormalisationReport { window_hours, ..NormalisationReport::default() };
+            return Ok((applier.into_connection(), tx_zero_idx, report));
+        }
+        info!("Found {} operations in {}-hour window around snapshot", window_ops.len(), window_hours * 2);
+
+        let tx_zero_idx = Self::select_anchor(operations, &window_ops, snapshot_timestamp, anchor)?;
+        info!("Selected transaction zero at index {} (timestamp: {:?})", tx_zero_idx, operations.operation(tx_zero_idx)?.timestamp);
         
-        println!("Normalizing to timestamp: {}", snapshot_timestamp);
+        let decisions = Self::apply_window(&mut applier, operations, &window_ops, tx_zero_idx, cancel)?;
+
+        let count = |direction: NormalisationDirection, applied: bool| decisions.iter()
+            .filter(|d| d.direction == direction && d.applied == applied)
+            .count();
+        info!("Applied {} operations, skipped {}",
+              count(NormalisationDirection::Forward, true), count(NormalisationDirection::Forward, false));
+        info!("Applied {} inverted operations, skipped {}",
+              count(NormalisationDirection::Inverted, true), count(NormalisationDirection::Inverted, false));
         
+        let conn = applier.into_connection();
+        Ok((conn, tx_zero_idx, NormalisationReport { tx_zero_idx: Some(tx_zero_idx), window_hours, decisions }))
+    }
+
+    /// Works out what normalize would do without changing the snapshot.
+    ///
+    /// The normalisation runs inside a transaction that is rolled back afterwards, so every
+    /// apply/skip decision is exactly the one normalize would make (later decisions depend on
+    /// the effect of earlier ones).
+    ///
+    /// # Returns
+    /// The unchanged Connection and a report of every decision, in the order they were made
+    pub fn normalize_dry_run(
+        conn: Connection,
+        operations: &[BinlogOperation],
+        snapshot_timestamp: &str,
+        window_hours: i64,
+    ) -> Result<(Connection, DryRunReport), Box<dyn std::error::Error>> {
+        let window_ops = Self::find_window_operations(operations, snapshot_timestamp, window_hours)?;
+        if window_ops.is_empty() {
+            return Ok((conn, DryRunReport { tx_zero_idx: None, window_hours, decisions: Vec::new() }));
+        }
+        let tx_zero_idx = Self::select_anchor(operations, &window_ops, snapshot_timestamp, AnchorStrategy::default())?;
+
+        let mut applier = OperationApplier::new(conn);
+        applier.get_connection().execute_batch("BEGIN TRANSACTION;")?;
+        let decisions = Self::apply_window(&mut applier, operations, &window_ops, tx_zero_idx, &CancelToken::new());
+        applier.get_connection().execute_batch("ROLLBACK;")?;
+
+        Ok((applier.into_connection(), DryRunReport { tx_zero_idx: Some(tx_zero_idx), window_hours, decisions: decisions? }))
+    }
+
+    /// Window operations and the window's size in hours, after applying the EmptyWindowPolicy.
+    /// The operations are only empty under EmptyWindowPolicy::AnchorToLast.
+    fn resolve_window<O: OperationSource + ?Sized>(
+        operations: &O,
+        snapshot_timestamp: &str,
+        window_hours: i64,
+        policy: EmptyWindowPolicy,
+    ) -> Result<(Vec<usize>, i64), Box<dyn std::error::Error>> {
+        let mut window_hours = window_hours;
+        let mut window_ops = Self::find_window_operations(operations, snapshot_timestamp, window_hours)?;
+
+        if let EmptyWindowPolicy::Expand { max_window_hours } = policy {
+            while window_ops.is_empty() && window_hours < max_window_hours {
+                window_hours = (window_hours * 2).clamp(1, max_window_hours);
+                info!("No operations found in window, widening to {} hours", window_hours * 2);
+                window_ops = Self::find_window_operations(operations, snapshot_timestamp, window_hours)?;
+            }
+        }
+
+        if window_ops.is_empty() && policy != EmptyWindowPolicy::AnchorToLast {
+            return Err(Box::new(NoOperationsInWindow {
+                snapshot_timestamp: snapshot_timestamp.to_string(),
+                window_hours,
+            }));
+        }
+        Ok((window_ops, window_hours))
+    }
+
+    /// Indices of operations whose timestamps fall within `window_hours` of the snapshot timestamp
+    fn find_window_operations<O: OperationSource + ?Sized>(
+        operations: &O,
+        snapshot_timestamp: &str,
+        window_hours: i64,
+    ) -> Result<Vec<usize>, Box<dyn std::error::Error>> {
         let snapshot_ts = BinlogTimestamp::parse(snapshot_timestamp)
             .map_err(|e| format!("Failed to parse snapshot timestamp: {}", e))?;
         
@@ -57,72 +349,199 @@ impl TimestampNormaliser {
         let ts_lower = snapshot_ts.subtract_hours(window_hours);
         let ts_upper = snapshot_ts.add_hours(window_hours);
         
-        println!("Window range: {} to {}", ts_lower, ts_upper);
+        debug!("Window range: {} to {}", ts_lower, ts_upper);
         
-        // Find operations within window
-        let window_ops: Vec<usize> = operations.iter()
-            .enumerate()
-            .filter(|(_, op)| {
-                if let Some(ts_str) = &op.timestamp {
-                    if let Ok(op_ts) = BinlogTimestamp::parse(ts_str) {
-                        return op_ts >= ts_lower && op_ts <= ts_upper;
+        let mut window_ops = Vec::new();
+        operations.scan(&mut |idx, op| {
+            if let Some(ts_str) = &op.timestamp
+                && let Ok(op_ts) = BinlogTimestamp::parse(ts_str)
+                && op_ts >= ts_lower && op_ts <= ts_upper {
+                window_ops.push(idx);
+            }
+        })?;
+        Ok(window_ops)
+    }
+
+    /// Index of transaction zero among the (non-empty) window operations
+    fn select_anchor<O: OperationSource + ?Sized>(
+        operations: &O,
+        window_ops: &[usize],
+        snapshot_timestamp: &str,
+        anchor: AnchorStrategy,
+    ) -> Result<usize, Box<dyn std::error::Error>> {
+        let idx = match anchor {
+            AnchorStrategy::Midpoint => window_ops[window_ops.len() / 2],
+            AnchorStrategy::EarliestInWindow => window_ops[0],
+            AnchorStrategy::LatestInWindow => window_ops[window_ops.len() - 1],
+            AnchorStrategy::ExactTimestamp => {
+                let snapshot_ts = BinlogTimestamp::parse(snapshot_timestamp)?;
+                let mut closest = (window_ops[0], i64::MAX);
+                for &idx in window_ops {
+                    // Window operations all have parseable timestamps
+                    let Some(op_ts) = operations.operation(idx)?.timestamp.as_ref().and_then(|ts| BinlogTimestamp::parse(ts).ok()) else {
+                        continue;
+                    };
+                    let distance = (*op_ts.as_datetime() - *snapshot_ts.as_datetime()).num_seconds().abs();
+                    if distance <= closest.1 {
+                        closest = (idx, distance);
                     }
                 }
-                false
-            })
-            .map(|(idx, _)| idx)
-            .collect();
+                closest.0
+            }
+        };
+        debug!("{:?} anchor of {} window operation(s): index {}", anchor, window_ops.len(), idx);
+        Ok(idx)
+    }
 
-        if window_ops.is_empty() {
-            println!("No operations found in window. Skipping normalization");
-            let tx_zero_idx = if operations.is_empty() { 0 } else { operations.len() - 1 };
-            return Ok((applier.into_connection(), operations, tx_zero_idx));
-        }
-        println!("Found {} operations in {}-hour window around snapshot", window_ops.len(), window_hours * 2);
+    /// Applies window operations up to and including tx_zero, then inverts the ones after it
+    /// (latest first), recording what happened to each. `cancel` is checked between chunks of
+    /// CancelToken::CHECK_INTERVAL operations.
+    fn apply_window<O: OperationSource + ?Sized>(
+        applier: &mut OperationApplier,
+        operations: &O,
+        window_ops: &[usize],
+        tx_zero_idx: usize,
+        cancel: &CancelToken,
+    ) -> Result<Vec<NormalisationDecision>, Box<dyn std::error::Error>> {
+        let mut decisions = Vec::with_capacity(window_ops.len());
 
-        let tx_zero_idx = window_ops[window_ops.len() / 2];
-        println!("Selected transaction zero at index {} (timestamp: {:?})", 
-                 tx_zero_idx, operations[tx_zero_idx].timestamp);
-        
-        // Apply operations BEFORE and INCLUDING tx_zero (forward)
-        println!("\n=== Phase 1: Applying operations forward (up to tx_zero) ===");
-        let mut applied_forward = 0;
-        let mut skipped_forward = 0;
-        
-        for idx in window_ops.iter().filter(|&&i| i <= tx_zero_idx) {
-            if applier.apply_operation_conditionally(&operations[*idx])? {
-                applied_forward += 1;
-            } else {
-                skipped_forward += 1;
-            }
-        }
-        
-        println!("Applied {} operations, skipped {}", applied_forward, skipped_forward);
-        
-        // Apply operations AFTER tx_zero (inverted)
-        println!("\n=== Phase 2: Applying operations inverted (after tx_zero) ===");
-        let mut applied_inverted = 0;
-        let mut skipped_inverted = 0;
-
-        let mut after_indices: Vec<usize> = window_ops.iter()
-            .filter(|&&i| i > tx_zero_idx)
-            .copied()
-            .collect();
-        after_indices.reverse();
-
-        for idx in after_indices {
-            let inverted = operations[idx].invert();
-            if applier.apply_operation_conditionally(&inverted)? {
-                applied_inverted += 1;
-            } else {
-                skipped_inverted += 1;
+        // Consecutive INSERTs are applied in batches (see OperationApplier::apply_operations)
+        let forward: Vec<usize> = window_ops.iter().copied().filter(|&i| i <= tx_zero_idx).collect();
+        let forward_ops: Vec<&BinlogOperation> = forward.iter().map(|&idx| operations.operation(idx)).collect::<Result<_, _>>()?;
+        let inverted: Vec<usize> = window_ops.iter().rev().copied().filter(|&i| i > tx_zero_idx).collect();
+        let inverted_ops: Vec<BinlogOperation> = inverted.iter().map(|&idx| operations.operation(idx).map(BinlogOperation::invert)).collect::<Result<_, _>>()?;
+        let inverted_refs: Vec<&BinlogOperation> = inverted_ops.iter().collect();
+
+        for (direction, indices, ops) in [
+            (NormalisationDirection::Forward, forward, forward_ops),
+            (NormalisationDirection::Inverted, inverted, inverted_refs),
+        ] {
+            for (indices, ops) in indices.chunks(CancelToken::CHECK_INTERVAL).zip(ops.chunks(CancelToken::CHECK_INTERVAL)) {
+                cancel.check()?;
+                let sql: Vec<String> = ops.iter().map(|op| applier.generate_sql(op)).collect();
+                let outcomes = applier.apply_operations(ops)?;
+                for ((&index, sql), outcome) in indices.iter().zip(sql).zip(outcomes) {
+                    decisions.push(NormalisationDecision {
+                        index,
+                        direction,
+                        applied: outcome.applied(),
+                        outcome,
+                        sql,
+                    });
+                }
             }
         }
 
-        println!("Applied {} inverted operations, skipped {}", applied_inverted, skipped_inverted);
-        println!("\n=== Snapshot normalized to position {} ===", tx_zero_idx);
-        
-        let conn = applier.into_connection();
-        Ok((conn, operations, tx_zero_idx))
+        Ok(decisions)
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{books_db, insert_id};
+
+    fn count_books(conn: &Connection) -> i64 {
+        conn.query_row("SELECT COUNT(*) FROM books", [], |row| row.get(0)).unwrap()
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_dry_run_reports_decisions_without_changing_snapshot() {
+        let conn = books_db("id INTEGER", "(1)");
+        let operations = vec![
+            insert_id("251108 10:00:00", 1),
+            insert_id("251108 10:10:00", 2),
+            insert_id("251108 10:20:00", 3),
+        ];
+
+        let (conn, report) = TimestampNormaliser::normalize_dry_run(conn, &operations, "251108 10:10:00", 1).unwrap();
+
+        assert_eq!(report.tx_zero_idx, Some(1));
+        assert_eq!(report.decisions.len(), 3);
+        // Row 1 already exists, row 2 is inserted, row 3 was never inserted so its inverse is skipped
+        let applied: Vec<bool> = report.decisions.iter().map(|d| d.applied).collect();
+        assert_eq!(applied, vec![false, true, false]);
+        assert_eq!(report.decisions[2].direction, NormalisationDirection::Inverted);
+        assert_eq!(report.decisions[0].outcome, ApplyOutcome::AlreadyApplied);
+        assert_eq!(report.decisions[1].sql, r#"INSERT INTO "books" ("id") VALUES (2);"#);
+        assert_eq!(count_books(&conn), 1);
+
+        let script_path = std::env::temp_dir().join(format!("dry_run_{}.sql", std::process::id()));
+        report.write_sql_script(script_path.to_str().unwrap()).unwrap();
+        let script = std::fs::read_to_string(&script_path).unwrap();
+        assert!(script.contains(r#"INSERT INTO "books" ("id") VALUES (2);"#));
+        assert!(script.contains("(skipped, already applied)"));
+        std::fs::remove_file(script_path).ok();
+    }
+
+    #[test]
+    fn test_normalize_returns_decision_log() {
+        let conn = books_db("id INTEGER", "(1)");
+        let operations = vec![
+            insert_id("251108 10:00:00", 1),
+            insert_id("251108 10:10:00", 2),
+            insert_id("251108 10:20:00", 3),
+        ];
+
+        let (conn, _, tx_zero_idx, report) = TimestampNormaliser::normalize(conn, operations, "251108 10:10:00", 1).unwrap();
+
+        assert_eq!(report.tx_zero_idx, Some(tx_zero_idx));
+        assert_eq!(report.applied_count(), 1);
+        assert_eq!(count_books(&conn), 2);
+
+        let log_path = std::env::temp_dir().join(format!("normalisation_log_{}.tsv", std::process::id()));
+        report.write_decision_log(log_path.to_str().unwrap()).unwrap();
+        let log = std::fs::read_to_string(&log_path).unwrap();
+        assert_eq!(log.lines().collect::<Vec<_>>(), vec![
+            "index\tdirection\tapplied\treason\tsql",
+            "0\tforward\tfalse\talready applied\tINSERT INTO \"books\" (\"id\") VALUES (1);",
+            "1\tforward\ttrue\tapplied\tINSERT INTO \"books\" (\"id\") VALUES (2);",
+            "2\tinverted\tfalse\talready applied\tDELETE FROM \"books\" WHERE \"id\" = 3;",
+        ]);
+        std::fs::remove_file(log_path).ok();
+    }
+
+    #[test]
+    fn test_empty_window_expands_or_fails() {
+        let conn = books_db("id INTEGER", "");
+        let operations = vec![
+            insert_id("251108 10:00:00", 1),
+            insert_id("251108 20:00:00", 2),
+        ];
+
+        let error = TimestampNormaliser::normalize_with_policy(
+            conn, operations.clone(), "251108 15:00:00", 1, EmptyWindowPolicy::Expand { max_window_hours: 4 },
+        ).err().unwrap();
+        let error = error.downcast_ref::<NoOperationsInWindow>().unwrap();
+        assert_eq!(error.window_hours, 4);
+
+        let conn = books_db("id INTEGER", "");
+        let (conn, _, tx_zero_idx, report) = TimestampNormaliser::normalize_with_policy(
+            conn, operations, "251108 15:00:00", 1, EmptyWindowPolicy::Expand { max_window_hours: 24 },
+        ).unwrap();
+        assert_eq!(report.window_hours, 8);
+        assert_eq!(tx_zero_idx, 1);
+        assert_eq!(count_books(&conn), 2);
+    }
+
+    #[test]
+    fn test_anchor_strategy_selects_transaction_zero() {
+        let operations = vec![
+            insert_id("251108 10:00:00", 1),
+            insert_id("251108 10:10:00", 2),
+            insert_id("251108 10:20:00", 3),
+            insert_id("251108 10:40:00", 4),
+        ];
+        let anchor = |strategy: AnchorStrategy| {
+            let conn = books_db("id INTEGER", "");
+            let (conn, _, tx_zero_idx, _) = TimestampNormaliser::normalize_with_anchor(
+                conn, operations.clone(), "251108 10:14:00", 1, EmptyWindowPolicy::default(), strategy,
+            ).unwrap();
+            (tx_zero_idx, count_books(&conn))
+        };
+
+        assert_eq!(anchor(AnchorStrategy::Midpoint), (2, 3));
+        assert_eq!(anchor(AnchorStrategy::EarliestInWindow), (0, 1));
+        assert_eq!(anchor(AnchorStrategy::LatestInWindow), (3, 4));
+        assert_eq!(anchor(AnchorStrategy::ExactTimestamp), (1, 2));
+    }
+}
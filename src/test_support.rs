@@ -0,0 +1,54 @@
+//! Fixtures shared by the unit tests: row operations and in-memory databases holding a `books` table
+
+use duckdb::Connection;
+use crate::binlog::{BinlogOperation, OperationType, RowImage, TransactionInfo};
+
+/// Operation on `main.<table>` with full row images, without a timestamp, position or transaction
+pub(crate) fn operation(
+    operation_type: OperationType,
+    table: &str,
+    columns: &[&str],
+    before: Option<&[&str]>,
+    after: Option<&[&str]>,
+) -> BinlogOperation {
+    let values = |values: &[&str]| values.iter().map(|value| value.to_string()).collect();
+    BinlogOperation {
+        timestamp: None,
+        position: None,
+        operation_type,
+        table_name: table.to_string(),
+        database: "main".to_string(),
+        columns: columns.iter().map(|column| column.to_string()).collect(),
+        before_values: before.map(values),
+        after_values: after.map(values),
+        row_image: RowImage::Full,
+        transaction: TransactionInfo::default(),
+    }
+}
+
+/// Operation on `books (id, price)` at `timestamp`
+pub(crate) fn books_op(operation_type: OperationType, timestamp: &str, before: Option<[&str; 2]>, after: Option<[&str; 2]>) -> BinlogOperation {
+    BinlogOperation {
+        timestamp: Some(timestamp.to_string()),
+        ..operation(operation_type, "books", &["id", "price"], before.as_ref().map(|v| &v[..]), after.as_ref().map(|v| &v[..]))
+    }
+}
+
+/// INSERT of `id` into `books (id)` at `timestamp`
+pub(crate) fn insert_id(timestamp: &str, id: impl ToString) -> BinlogOperation {
+    BinlogOperation {
+        timestamp: Some(timestamp.to_string()),
+        ..operation(OperationType::Insert, "books", &["id"], None, Some(&[&id.to_string()]))
+    }
+}
+
+/// In-memory database with `CREATE TABLE books (<columns>)` holding `rows`, e.g.
+/// `books_db("id INTEGER PRIMARY KEY, price INTEGER", "(1, 10), (2, 20)")`
+pub(crate) fn books_db(columns: &str, rows: &str) -> Connection {
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch(&format!("CREATE TABLE books ({});", columns)).unwrap();
+    if !rows.is_empty() {
+        conn.execute_batch(&format!("INSERT INTO books VALUES {};", rows)).unwrap();
+    }
+    conn
+}